@@ -0,0 +1,581 @@
+use std::cell::{Cell, RefCell};
+use std::ops::RangeInclusive;
+use std::time::Duration;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::game::{draw, Board, Move, MoveGenerator, MoveType};
+use super::eval::{EvalWeights, CHECK_BONUS, MOBILITY_WEIGHT};
+use super::rng::Rng;
+use super::time::{self, TimeSource};
+
+/// How far a search has got, reported while it runs
+#[derive(Debug, Clone, Copy)]
+pub struct SearchProgress {
+    /// The depth being searched
+    pub depth: u32,
+    /// Positions visited so far, over all depths
+    pub nodes: u64,
+    pub elapsed: Duration,
+    /// Best move and its score from the last depth searched to the end,
+    /// from the side to move's point of view
+    pub best: Option<(Move, i32)>,
+}
+
+impl SearchProgress {
+    pub fn nodes_per_second(&self) -> f64 {
+        self.nodes as f64 / self.elapsed.as_secs_f64().max(1e-9)
+    }
+}
+
+/// Score for the side to move when it's checkmated on the board. Being
+/// mated `n` plies into the search scores `n` more, and mating the
+/// negation, so the engine goes for the quickest mate and the slowest loss.
+pub const MATE_SCORE: i32 = 20000;
+/// Scores at least this far from 0 are forced mates rather than material
+pub const MATE_THRESHOLD: i32 = 19000;
+
+/// The moves to mate a score stands for: positive when the side it's from
+/// mates, negative when it gets mated, and None when it isn't a mate score
+pub fn mate_in(score: i32) -> Option<i32> {
+    if score.abs() < MATE_THRESHOLD {
+        return None;
+    }
+    let moves = (MATE_SCORE - score.abs() + 1) / 2;
+    Some(if score > 0 { moves } else { -moves })
+}
+
+/// Which shallow-depth prunings the search makes. Both are on by
+/// default; turning one off, e.g. for one side of an engine match, shows
+/// what it's worth.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pruning {
+    /// Skip quiet moves near the leaves when the position is so far below
+    /// alpha that no quiet move could bring it back
+    pub futility: bool,
+    /// Cut off near the leaves when the position is so far above beta that
+    /// the opponent can't get it back down (static null move pruning)
+    pub reverse_futility: bool,
+}
+
+impl Default for Pruning {
+    fn default() -> Self {
+        Pruning { futility: true, reverse_futility: true }
+    }
+}
+
+// How far below alpha a position must stand, by remaining depth, for its
+// quiet moves to be skipped. The static score leaves out mobility, which
+// the margins allow for.
+const FUTILITY_MARGINS: [i32; 3] = [0, 200, 450];
+// How far above beta a position must stand, per ply of remaining depth, to
+// cut off without a search, up to this depth
+const REVERSE_FUTILITY_MARGIN: i32 = 120;
+const REVERSE_FUTILITY_DEPTH: u32 = 3;
+
+/// How the engine picks its move
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Selection {
+    /// The best move the search finds
+    #[default]
+    Best,
+    /// Any of the few best moves, and now and then one giving away about a
+    /// pawn, so that a beginner can win
+    Beginner,
+    /// Any legal move, without searching
+    Random,
+}
+
+// The beginner level picks among this many of the best moves...
+const BEGINNER_CHOICES: usize = 5;
+// ...leaving out any that are worse than the best by more than this
+const BEGINNER_MARGIN: i32 = 300;
+// One beginner move in this many gives away about a pawn, if one can
+const BEGINNER_BLUNDER_ODDS: usize = 6;
+// How much worse than the best move a move giving away a pawn scores
+const PAWN_BLUNDER: RangeInclusive<i32> = 60..=200;
+
+/// Moves at the start of a game during which playing programs may vary
+/// the engine's choice, see `with_variety`
+pub const OPENING_VARIETY_MOVES: u32 = 8;
+// Moves scoring within this many centipawns of the best count as equal
+// for opening variety
+const VARIETY_MARGIN: i32 = 15;
+
+pub struct ChessAI {
+    pub depth: u32,
+    // Random centipawns added to each root move's score, to weaken play
+    pub noise: i32,
+    /// Longest a search may take; it then plays the best move of the
+    /// deepest depth it finished. None searches to the full depth, as does
+    /// an engine with no time source.
+    pub time_limit: Option<Duration>,
+    /// The clock searches are timed by, the system's by default. Without
+    /// one, searches report no elapsed time.
+    pub time_source: Option<TimeSource>,
+    pub selection: Selection,
+    /// Up to this move number the engine picks at random among the moves
+    /// scoring about as well as the best, so games don't all repeat. 0
+    /// always plays the first best move found.
+    pub variety: u32,
+    /// Seeds the random choices afresh for each position searched, so the
+    /// same seed plays the same moves in the same positions. None seeds
+    /// them from the clock, or where there's no system clock from 0.
+    pub seed: Option<u64>,
+    pub pruning: Pruning,
+    /// The piece values and square tables the evaluation uses
+    pub weights: EvalWeights,
+    rng: RefCell<Rng>,
+    // Quiet moves that last caused a cutoff at each ply, tried early in
+    // the other positions at that ply
+    killers: RefCell<Vec<[Option<Move>; 2]>>,
+    // Keys of the game's positions before the one searched, oldest first,
+    // so going back to one of them is scored as a draw
+    history: RefCell<Vec<u64>>,
+    // Positions visited by the last search
+    nodes: Cell<u64>,
+    // When the running search has to stop, by the time source, and
+    // whether it has
+    deadline: Cell<Option<u64>>,
+    stopped: Cell<bool>,
+}
+
+// Positions searched between looks at the clock
+const CLOCK_CHECK_INTERVAL: u64 = 1024;
+
+// The random choices start from the clock, where there is one to read
+fn initial_rng() -> Rng {
+    #[cfg(feature = "native")]
+    return Rng::from_time();
+    #[cfg(not(feature = "native"))]
+    return Rng::new(0);
+}
+
+impl ChessAI {
+    pub fn new(depth: u32) -> Self {
+        ChessAI {
+            depth,
+            noise: 0,
+            time_limit: None,
+            time_source: time::default_source(),
+            selection: Selection::Best,
+            variety: 0,
+            seed: None,
+            pruning: Pruning::default(),
+            weights: EvalWeights::default(),
+            rng: RefCell::new(initial_rng()),
+            killers: RefCell::new(Vec::new()),
+            history: RefCell::new(Vec::new()),
+            nodes: Cell::new(0),
+            deadline: Cell::new(None),
+            stopped: Cell::new(false),
+        }
+    }
+
+    /// Makes the engine pick among moves scoring within roughly `noise`
+    /// centipawns of the best, so it plays noticeably worse
+    pub fn with_noise(mut self, noise: i32) -> Self {
+        self.noise = noise;
+        self
+    }
+
+    /// Makes the engine pick its moves another way than the best it finds
+    pub fn with_selection(mut self, selection: Selection) -> Self {
+        self.selection = selection;
+        self
+    }
+
+    /// Makes the engine vary its choice among equally good moves up to
+    /// move number `moves`
+    pub fn with_variety(mut self, moves: u32) -> Self {
+        self.variety = moves;
+        self
+    }
+
+    /// Makes the engine's random choices reproducible
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Makes the search prune only as `pruning` allows
+    pub fn with_pruning(mut self, pruning: Pruning) -> Self {
+        self.pruning = pruning;
+        self
+    }
+
+    /// Makes the engine evaluate with other piece values and square tables
+    pub fn with_weights(mut self, weights: EvalWeights) -> Self {
+        self.weights = weights;
+        self
+    }
+
+    /// Makes searches stop after `limit`, however deep they've got
+    pub fn with_time_limit(mut self, limit: Duration) -> Self {
+        self.time_limit = Some(limit);
+        self
+    }
+
+    /// Makes the engine time its searches by `source`, such as a browser's
+    /// clock where there's no system clock to read
+    pub fn with_time_source(mut self, source: TimeSource) -> Self {
+        self.time_source = Some(source);
+        self
+    }
+
+    /// Makes the engine score a return to a position of the game so far
+    /// as a draw, as for `set_history`
+    pub fn with_history(self, initial: &Board, moves: &[Move]) -> Self {
+        self.set_history(initial, moves);
+        self
+    }
+
+    /// Tells the engine the game's `moves` from `initial`, so that it
+    /// doesn't shuffle back into earlier positions when it's winning, and
+    /// does when that saves a lost game. Searches after this should be of
+    /// the position the moves lead to.
+    pub fn set_history(&self, initial: &Board, moves: &[Move]) {
+        let mut keys = draw::position_keys(initial, moves);
+        keys.pop();
+        *self.history.borrow_mut() = keys;
+    }
+
+    pub fn get_best_move(&self, board: &Board) -> Option<Move> {
+        self.search(board).map(|(mv, _)| mv)
+    }
+
+    /// Best move and its score in centipawns from the side to move's point of view
+    pub fn search(&self, board: &Board) -> Option<(Move, i32)> {
+        self.reseed(board);
+        self.killers.borrow_mut().clear();
+        if self.selection != Selection::Best {
+            self.nodes.set(0);
+            return self.pick_loosely(board);
+        }
+        // A time limit needs the depths one at a time, to fall back on
+        if self.time_limit.is_some() {
+            return self.search_with_progress(board, |_| {});
+        }
+        self.nodes.set(0);
+        self.search_depth(board, self.depth, None, || {})
+    }
+
+    /// Searches like `search`, but one depth at a time up to the engine's,
+    /// calling `on_progress` after each root move and each finished depth.
+    /// The shallower searches cost a little extra time; the move found is
+    /// the same, unless the time limit cuts the search short.
+    pub fn search_with_progress(&self, board: &Board, mut on_progress: impl FnMut(&SearchProgress)) -> Option<(Move, i32)> {
+        self.nodes.set(0);
+        self.stopped.set(false);
+        self.reseed(board);
+        self.killers.borrow_mut().clear();
+        let now = || self.time_source.map_or(0, |source| source());
+        let started = now();
+        let elapsed = || Duration::from_millis(now().saturating_sub(started));
+        if self.selection != Selection::Best {
+            let best = self.pick_loosely(board);
+            on_progress(&SearchProgress { depth: self.depth, nodes: self.nodes.get(), elapsed: elapsed(), best });
+            return best;
+        }
+        let mut progress = SearchProgress { depth: 1, nodes: 0, elapsed: Duration::ZERO, best: None };
+        for depth in 1..=self.depth {
+            if depth > 1 {
+                // The first depth always finishes, so there's a move to play
+                let limit = self.time_limit.filter(|_| self.time_source.is_some());
+                if limit.is_some_and(|limit| elapsed() >= limit) {
+                    break;
+                }
+                self.deadline.set(limit.map(|limit| started + limit.as_millis() as u64));
+            }
+            progress.depth = depth;
+            // The last depth's best move goes first
+            let previous = progress.best.map(|(mv, _)| mv);
+            let best = self.search_depth(board, depth, previous, || {
+                progress.nodes = self.nodes.get();
+                progress.elapsed = elapsed();
+                on_progress(&progress);
+            });
+            if self.stopped.get() {
+                // An unfinished depth may not have looked at the best move
+                break;
+            }
+            progress.best = best;
+            progress.nodes = self.nodes.get();
+            progress.elapsed = elapsed();
+            on_progress(&progress);
+        }
+        self.deadline.set(None);
+        self.stopped.set(false);
+        progress.best
+    }
+
+    // With a seed, starts the random choices for `board` from it and the
+    // position, so they don't depend on what was searched before
+    fn reseed(&self, board: &Board) {
+        if let Some(seed) = self.seed {
+            *self.rng.borrow_mut() = Rng::new(seed ^ board.zobrist_hash());
+        }
+    }
+
+    // A move for the beginner and random levels, with its score (0 for a
+    // random move, which isn't searched)
+    fn pick_loosely(&self, board: &Board) -> Option<(Move, i32)> {
+        let moves = MoveGenerator::generate_all_legal_moves(board);
+        if moves.is_empty() {
+            return None;
+        }
+        if self.selection == Selection::Random {
+            let index = self.rng.borrow_mut().below(moves.len());
+            return Some((moves[index], 0));
+        }
+
+        let mut scored: Vec<(Move, i32)> = moves.iter().map(|mv| (*mv, self.score_move(board, mv))).collect();
+        scored.sort_by_key(|&(_, score)| -score);
+        let best = scored[0].1;
+        let mut rng = self.rng.borrow_mut();
+        let blunders: Vec<(Move, i32)> =
+            scored.iter().copied().filter(|&(_, score)| PAWN_BLUNDER.contains(&(best - score))).collect();
+        if !blunders.is_empty() && rng.below(BEGINNER_BLUNDER_ODDS) == 0 {
+            return Some(blunders[rng.below(blunders.len())]);
+        }
+        let choices = scored
+            .iter()
+            .take(BEGINNER_CHOICES)
+            .take_while(|&&(_, score)| best - score <= BEGINNER_MARGIN)
+            .count();
+        Some(scored[rng.below(choices)])
+    }
+
+    // Whether the search is past its deadline, looking at the clock every
+    // so many positions
+    fn out_of_time(&self) -> bool {
+        if self.stopped.get() {
+            return true;
+        }
+        let Some(deadline) = self.deadline.get() else {
+            return false;
+        };
+        let checking = self.nodes.get().is_multiple_of(CLOCK_CHECK_INTERVAL);
+        if checking && self.time_source.is_some_and(|source| source() >= deadline) {
+            self.stopped.set(true);
+        }
+        self.stopped.get()
+    }
+
+    // The root of the search at `depth`, trying `hash_move` first if it's
+    // given, and calling `on_move` after each move
+    fn search_depth(
+        &self,
+        board: &Board,
+        depth: u32,
+        hash_move: Option<Move>,
+        mut on_move: impl FnMut(),
+    ) -> Option<(Move, i32)> {
+        let mut best_move = None;
+        let mut best_score = -30000;
+        let alpha = -30000;
+        let beta = 30000;
+
+        // Positions on the current search path, used to detect repetitions
+        let mut path = vec![board.zobrist_hash()];
+        // Every move's score, to pick among the best for opening variety
+        let vary = board.fullmove_number <= self.variety;
+        let mut scored = Vec::new();
+
+        for mv in MoveGenerator::picker(board, hash_move, [None; 2]) {
+            let mut new_board = board.clone();
+            new_board.make_move(&mv);
+
+            let mut score = -self.minimax(&new_board, depth - 1, -beta, -alpha, &mut path);
+            score += self.rng.borrow_mut().spread(self.noise);
+
+            if score > best_score {
+                best_score = score;
+                best_move = Some(mv);
+            }
+            if vary {
+                scored.push((mv, score));
+            }
+            on_move();
+        }
+
+        if vary && !self.stopped.get() {
+            let equal: Vec<(Move, i32)> =
+                scored.into_iter().filter(|&(_, score)| best_score - score <= VARIETY_MARGIN).collect();
+            if !equal.is_empty() {
+                return Some(equal[self.rng.borrow_mut().below(equal.len())]);
+            }
+        }
+        best_move.map(|mv| (mv, best_score))
+    }
+
+    /// Score of playing `mv`, on the same scale as the score from `search`
+    pub fn score_move(&self, board: &Board, mv: &Move) -> i32 {
+        let mut new_board = board.clone();
+        new_board.make_move(mv);
+        let mut path = vec![board.zobrist_hash()];
+        -self.minimax(&new_board, self.depth - 1, -30000, 30000, &mut path)
+    }
+
+    /// Number of positions searched by the last call to `search`
+    pub fn nodes(&self) -> u64 {
+        self.nodes.get()
+    }
+
+    fn minimax(&self, board: &Board, depth: u32, mut alpha: i32, mut beta: i32, path: &mut Vec<u64>) -> i32 {
+        self.nodes.set(self.nodes.get() + 1);
+        if self.out_of_time() {
+            // Thrown away with the rest of the unfinished depth
+            return 0;
+        }
+        let key = board.zobrist_hash();
+
+        // A repetition of any position on the path or in the game since the
+        // last irreversible move is scored as a draw, so the engine neither throws away wins by
+        // repeating nor misses a repetition that would save a lost position
+        if self.is_repetition(board, key, path) {
+            return 0;
+        }
+
+        // Plies from the root, which mate scores count
+        let ply = path.len();
+
+        // A side with no moves is mated or stalemated, whatever else holds
+        let no_moves = |board: &Board| {
+            if MoveGenerator::is_in_check(board, board.current_player) {
+                -MATE_SCORE + ply as i32
+            } else {
+                0 // Stalemate
+            }
+        };
+
+        if depth == 0 {
+            // The leaves need every move anyway, for the mobility score
+            let all_moves = MoveGenerator::generate_all_legal_moves(board);
+            if all_moves.is_empty() {
+                return no_moves(board);
+            }
+            if MoveGenerator::is_insufficient_material(board) || board.is_fifty_move_draw() {
+                return 0;
+            }
+            return self.evaluate(board, all_moves.len());
+        }
+
+        // Without mating material on the board there's no mate to find
+        if MoveGenerator::is_insufficient_material(board) {
+            return 0;
+        }
+        if board.is_fifty_move_draw() {
+            let has_moves = MoveGenerator::picker(board, None, [None; 2]).next().is_some();
+            return if has_moves { 0 } else { no_moves(board) };
+        }
+
+        // Mate-distance pruning: no line from here does better than mating
+        // next move or worse than being mated now, so when a quicker mate
+        // is already known elsewhere there's nothing to search for
+        alpha = alpha.max(-MATE_SCORE + ply as i32);
+        beta = beta.min(MATE_SCORE - ply as i32 - 1);
+        if alpha >= beta {
+            return alpha;
+        }
+
+        // The shallow prunings go by the static score, and are unsafe in
+        // check or with a mate score to keep
+        let in_check = MoveGenerator::is_in_check(board, board.current_player);
+        let static_score = (!in_check).then(|| self.evaluate(board, 0));
+        if let Some(score) = static_score.filter(|_| self.pruning.reverse_futility) {
+            let margin = REVERSE_FUTILITY_MARGIN * depth as i32;
+            if depth <= REVERSE_FUTILITY_DEPTH && beta.abs() < MATE_THRESHOLD && score - margin >= beta {
+                return score - margin;
+            }
+        }
+        // What the quiet moves are assumed to score at best when they're skipped
+        let futile = static_score
+            .filter(|_| self.pruning.futility && alpha.abs() < MATE_THRESHOLD)
+            .and_then(|score| FUTILITY_MARGINS.get(depth as usize).map(|margin| score + margin))
+            .filter(|&best_quiet| best_quiet <= alpha);
+
+        // Moves come lazily, best candidates first, so a cutoff skips
+        // checking the legality of the rest
+        let killers = self.killers.borrow().get(ply).copied().unwrap_or_default();
+        let mut best_score = -30000;
+        let mut legal_moves = 0;
+
+        path.push(key);
+        for mv in MoveGenerator::picker(board, None, killers) {
+            legal_moves += 1;
+            let mut new_board = board.clone();
+            new_board.make_move(&mv);
+
+            // Checks are kept, as they may change more than the score shows
+            if let Some(best_quiet) = futile {
+                let quiet = mv.captured.is_none() && !matches!(mv.move_type, MoveType::Promotion(_));
+                if quiet && !MoveGenerator::is_in_check(&new_board, new_board.current_player) {
+                    best_score = best_score.max(best_quiet);
+                    continue;
+                }
+            }
+
+            let score = -self.minimax(&new_board, depth - 1, -beta, -alpha, path);
+
+            best_score = best_score.max(score);
+            alpha = alpha.max(score);
+
+            if alpha >= beta {
+                self.store_killer(ply, mv);
+                break; // Beta cutoff
+            }
+        }
+        path.pop();
+
+        if legal_moves == 0 {
+            return no_moves(board);
+        }
+        best_score
+    }
+
+    // Remembers a quiet move that caused a cutoff at `ply`, as the first of
+    // that ply's two killers
+    fn store_killer(&self, ply: usize, mv: Move) {
+        if mv.captured.is_some() || matches!(mv.move_type, MoveType::Promotion(_)) {
+            return;
+        }
+        let mut killers = self.killers.borrow_mut();
+        if killers.len() <= ply {
+            killers.resize(ply + 1, [None; 2]);
+        }
+        let slots = &mut killers[ply];
+        if slots[0] != Some(mv) {
+            slots[1] = slots[0];
+            slots[0] = Some(mv);
+        }
+    }
+
+    fn is_repetition(&self, board: &Board, key: u64, path: &[u64]) -> bool {
+        // Only positions reached since the last capture or pawn move can recur
+        let history = self.history.borrow();
+        path.iter()
+            .rev()
+            .chain(history.iter().rev())
+            .take(board.halfmove_clock as usize)
+            .any(|&previous| previous == key)
+    }
+
+    fn evaluate(&self, board: &Board, mobility: usize) -> i32 {
+        // Material evaluation, square by square
+        let mut score = self.weights.material(board);
+
+        // Mobility bonus, using the legal move count the search already generated
+        score += mobility as i32 * MOBILITY_WEIGHT;
+
+        // Check bonus
+        if MoveGenerator::is_in_check(board, board.current_player.opposite()) {
+            score += CHECK_BONUS;
+        }
+
+        score
+    }
+}
@@ -0,0 +1,247 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::engine::MATE_THRESHOLD;
+use crate::game::{Board, Color, PieceType, Position};
+
+/// Every kind of piece, in the order of `PieceWeights`' fields
+pub const PIECE_TYPES: [PieceType; 6] =
+    [PieceType::Pawn, PieceType::Knight, PieceType::Bishop, PieceType::Rook, PieceType::Queen, PieceType::King];
+
+/// Centipawns for each legal move of the side to move
+pub const MOBILITY_WEIGHT: i32 = 2;
+/// Centipawns for having the other side's king in check
+pub const CHECK_BONUS: i32 = 50;
+
+// The most a position can have in legal moves, for the largest mobility score
+const MAX_LEGAL_MOVES: i32 = 218;
+// No single weight may go past this, so that sums of them can't overflow
+const MAX_WEIGHT: i32 = 100_000;
+
+/// One weight, or table of weights, for each kind of piece
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PieceWeights<T> {
+    pub pawn: T,
+    pub knight: T,
+    pub bishop: T,
+    pub rook: T,
+    pub queen: T,
+    pub king: T,
+}
+
+impl<T> PieceWeights<T> {
+    pub fn get(&self, piece_type: PieceType) -> &T {
+        match piece_type {
+            PieceType::Pawn => &self.pawn,
+            PieceType::Knight => &self.knight,
+            PieceType::Bishop => &self.bishop,
+            PieceType::Rook => &self.rook,
+            PieceType::Queen => &self.queen,
+            PieceType::King => &self.king,
+        }
+    }
+
+    pub fn get_mut(&mut self, piece_type: PieceType) -> &mut T {
+        match piece_type {
+            PieceType::Pawn => &mut self.pawn,
+            PieceType::Knight => &mut self.knight,
+            PieceType::Bishop => &mut self.bishop,
+            PieceType::Rook => &mut self.rook,
+            PieceType::Queen => &mut self.queen,
+            PieceType::King => &mut self.king,
+        }
+    }
+}
+
+/// The weights of the evaluation's material terms: what each piece is
+/// worth, and how much more or less on each square. Serializable, so that
+/// tuned or experimental sets can be loaded from a file; a file leaving out
+/// the values or the tables keeps the defaults for them.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct EvalWeights {
+    /// Centipawns for each piece
+    pub values: PieceWeights<i32>,
+    /// Bonus for a piece on each square, 64 to a table, indexed
+    /// `row * 8 + col` with row 0 the piece's own back rank
+    pub squares: PieceWeights<Vec<i32>>,
+}
+
+impl EvalWeights {
+    /// Where a `color` piece on `pos` looks in its square table
+    pub fn square_index(pos: Position, color: Color) -> usize {
+        let row = if color == Color::White { 7 - pos.row } else { pos.row };
+        row * 8 + pos.col
+    }
+
+    /// Checks that every square table has its 64 squares, and that no
+    /// position can score as much as a mate, which a file of weights may not
+    pub fn validate(&self) -> Result<(), String> {
+        for piece_type in PIECE_TYPES {
+            let name = format!("{:?}", piece_type).to_lowercase();
+            let squares = self.squares.get(piece_type);
+            if squares.len() != 64 {
+                return Err(format!("the {} square table has {} entries instead of 64", name, squares.len()));
+            }
+            let weights = squares.iter().chain([self.values.get(piece_type)]);
+            if let Some(weight) = weights.into_iter().find(|weight| weight.abs() > MAX_WEIGHT) {
+                return Err(format!("the {} weight {} is outside -{} to {}", name, weight, MAX_WEIGHT, MAX_WEIGHT));
+            }
+        }
+
+        // Each side has one king, so their values cancel out. Beyond that,
+        // the side to move can have at most fifteen pieces on their best
+        // squares against fifteen of the other side's on their worst.
+        let scores = |piece_type| {
+            let value = *self.values.get(piece_type) as i64;
+            self.squares.get(piece_type).iter().map(move |&bonus| value + bonus as i64)
+        };
+        let others = || PIECE_TYPES.into_iter().filter(|&piece_type| piece_type != PieceType::King);
+        let best = others().flat_map(scores).max().unwrap_or(0).max(0);
+        let worst = others().flat_map(scores).min().unwrap_or(0).min(0);
+        let king = self.squares.get(PieceType::King).iter().map(|&bonus| bonus as i64);
+        let kings = king.clone().max().unwrap_or(0) - king.min().unwrap_or(0);
+        let positional = (MAX_LEGAL_MOVES * MOBILITY_WEIGHT + CHECK_BONUS) as i64;
+        let largest = 15 * (best - worst) + kings + positional;
+        if largest >= MATE_THRESHOLD as i64 {
+            return Err(format!(
+                "a position could score {} centipawns with these weights, which reads as a mate; \
+                 keep it under {}",
+                largest, MATE_THRESHOLD
+            ));
+        }
+        Ok(())
+    }
+
+    /// What a `color` piece of `piece_type` on `pos` is worth, its square included
+    pub fn piece_score(&self, piece_type: PieceType, pos: Position, color: Color) -> i32 {
+        self.values.get(piece_type) + self.squares.get(piece_type)[Self::square_index(pos, color)]
+    }
+
+    /// The side to move's pieces less the other side's
+    pub fn material(&self, board: &Board) -> i32 {
+        board
+            .pieces()
+            .map(|(pos, piece)| {
+                let score = self.piece_score(piece.piece_type, pos, piece.color);
+                if piece.color == board.current_player { score } else { -score }
+            })
+            .sum()
+    }
+}
+
+impl Default for EvalWeights {
+    fn default() -> Self {
+        let values = PieceWeights {
+            pawn: PieceType::Pawn.value(),
+            knight: PieceType::Knight.value(),
+            bishop: PieceType::Bishop.value(),
+            rook: PieceType::Rook.value(),
+            queen: PieceType::Queen.value(),
+            king: PieceType::King.value(),
+        };
+        let squares = PieceWeights {
+            pawn: vec![
+                0,  0,  0,  0,  0,  0,  0,  0,
+                50, 50, 50, 50, 50, 50, 50, 50,
+                10, 10, 20, 30, 30, 20, 10, 10,
+                5,  5, 10, 25, 25, 10,  5,  5,
+                0,  0,  0, 20, 20,  0,  0,  0,
+                5, -5,-10,  0,  0,-10, -5,  5,
+                5, 10, 10,-20,-20, 10, 10,  5,
+                0,  0,  0,  0,  0,  0,  0,  0,
+            ],
+            knight: vec![
+                -50,-40,-30,-30,-30,-30,-40,-50,
+                -40,-20,  0,  0,  0,  0,-20,-40,
+                -30,  0, 10, 15, 15, 10,  0,-30,
+                -30,  5, 15, 20, 20, 15,  5,-30,
+                -30,  0, 15, 20, 20, 15,  0,-30,
+                -30,  5, 10, 15, 15, 10,  5,-30,
+                -40,-20,  0,  5,  5,  0,-20,-40,
+                -50,-40,-30,-30,-30,-30,-40,-50,
+            ],
+            bishop: vec![
+                -20,-10,-10,-10,-10,-10,-10,-20,
+                -10,  0,  0,  0,  0,  0,  0,-10,
+                -10,  0,  5, 10, 10,  5,  0,-10,
+                -10,  5,  5, 10, 10,  5,  5,-10,
+                -10,  0, 10, 10, 10, 10,  0,-10,
+                -10, 10, 10, 10, 10, 10, 10,-10,
+                -10,  5,  0,  0,  0,  0,  5,-10,
+                -20,-10,-10,-10,-10,-10,-10,-20,
+            ],
+            rook: vec![
+                0,  0,  0,  0,  0,  0,  0,  0,
+                5, 10, 10, 10, 10, 10, 10,  5,
+                -5,  0,  0,  0,  0,  0,  0, -5,
+                -5,  0,  0,  0,  0,  0,  0, -5,
+                -5,  0,  0,  0,  0,  0,  0, -5,
+                -5,  0,  0,  0,  0,  0,  0, -5,
+                -5,  0,  0,  0,  0,  0,  0, -5,
+                0,  0,  0,  5,  5,  0,  0,  0,
+            ],
+            queen: vec![
+                -20,-10,-10, -5, -5,-10,-10,-20,
+                -10,  0,  0,  0,  0,  0,  0,-10,
+                -10,  0,  5,  5,  5,  5,  0,-10,
+                -5,  0,  5,  5,  5,  5,  0, -5,
+                0,  0,  5,  5,  5,  5,  0, -5,
+                -10,  5,  5,  5,  5,  5,  0,-10,
+                -10,  0,  5,  0,  0,  0,  0,-10,
+                -20,-10,-10, -5, -5,-10,-10,-20,
+            ],
+            king: vec![
+                -30,-40,-40,-50,-50,-40,-40,-30,
+                -30,-40,-40,-50,-50,-40,-40,-30,
+                -30,-40,-40,-50,-50,-40,-40,-30,
+                -30,-40,-40,-50,-50,-40,-40,-30,
+                -20,-30,-30,-40,-40,-30,-30,-20,
+                -10,-20,-20,-20,-20,-20,-20,-10,
+                20, 20,  0,  0,  0,  0, 20, 20,
+                20, 30, 10,  0,  0, 10, 30, 20,
+            ],
+        };
+        EvalWeights { values, squares }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::pieces::Piece;
+
+    #[test]
+    fn keeps_every_score_short_of_a_mate() {
+        assert_eq!(EvalWeights::default().validate(), Ok(()));
+        let with_queen = |queen| {
+            let defaults = EvalWeights::default();
+            EvalWeights { values: PieceWeights { queen, ..defaults.values }, ..defaults }
+        };
+        assert!(with_queen(25_000).validate().is_err());
+        assert!(with_queen(2_000_000_000).validate().is_err());
+        assert!(with_queen(-25_000).validate().is_err());
+
+        // The largest queen allowed, by bisection
+        let (mut low, mut high) = (900, 25_000);
+        while high - low > 1 {
+            let middle = (low + high) / 2;
+            if with_queen(middle).validate().is_ok() { low = middle } else { high = middle }
+        }
+        assert!(low > 1000, "{}", low);
+
+        // Fifteen of them on their best squares still score short of a mate
+        let weights = with_queen(low);
+        let mut board = Board::empty();
+        let squares = (0..64).map(|index| Position::new(index / 8, index % 8));
+        for pos in squares.take(15) {
+            board.set_piece(pos, Some(Piece::new(PieceType::Queen, Color::White)));
+        }
+        board.set_piece(Position::new(7, 6), Some(Piece::new(PieceType::King, Color::White)));
+        board.set_piece(Position::new(5, 0), Some(Piece::new(PieceType::King, Color::Black)));
+        let material = weights.material(&board);
+        assert!(material > 15 * 1000 && material < MATE_THRESHOLD, "{}", material);
+    }
+}
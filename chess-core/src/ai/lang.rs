@@ -0,0 +1,1073 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use super::engine::ChessAI;
+use super::eval::EvalWeights;
+use super::rng::Rng;
+use crate::game::pieces::{Color, Piece, PieceType, Position};
+use crate::game::san;
+use crate::game::{Board, Move, MoveGenerator, MoveType};
+
+// A script that runs longer than this many steps is stopped, so a loop
+// that never ends can't hang the game
+const MAX_STEPS: u64 = 10_000_000;
+// How deep script functions may call each other
+const MAX_CALL_DEPTH: usize = 100;
+// The longest list or string a script may make
+const MAX_LENGTH: usize = 100_000;
+
+/// The deepest a bot may search, from its code or its rules
+pub const MAX_SEARCH_DEPTH: u32 = 6;
+
+// The built-in functions and how many arguments each takes
+const BUILTINS: &[(&str, usize)] = &[
+    ("legal_moves", 1),
+    ("make_move", 2),
+    ("evaluate", 1),
+    ("search", 2),
+    ("turn", 1),
+    ("in_check", 1),
+    ("is_checkmate", 1),
+    ("is_stalemate", 1),
+    ("piece_at", 2),
+    ("fen", 1),
+    ("move_number", 1),
+    ("uci", 1),
+    ("san", 2),
+    ("from_square", 1),
+    ("to_square", 1),
+    ("piece", 1),
+    ("captured", 1),
+    ("promotion", 1),
+    ("is_castle", 1),
+    ("value", 1),
+    ("len", 1),
+    ("push", 2),
+    ("range", 2),
+    ("random", 1),
+    ("abs", 1),
+    ("min", 2),
+    ("max", 2),
+    ("str", 1),
+];
+
+/// A mistake in a script, found when it's read or when it runs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScriptError {
+    /// The line of the script it's on, or 0 if it's about the whole script
+    pub line: usize,
+    pub message: String,
+}
+
+impl ScriptError {
+    fn new(line: usize, message: impl Into<String>) -> Self {
+        ScriptError { line, message: message.into() }
+    }
+}
+
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.line == 0 {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "line {}: {}", self.line, self.message)
+        }
+    }
+}
+
+impl std::error::Error for ScriptError {}
+
+/// A value in a script
+#[derive(Debug, Clone)]
+pub enum Value {
+    Nil,
+    Bool(bool),
+    Int(i64),
+    Str(String),
+    List(Vec<Value>),
+    Board(Box<Board>),
+    Move(Move),
+}
+
+impl Value {
+    fn type_name(&self) -> &'static str {
+        match self {
+            Value::Nil => "nil",
+            Value::Bool(_) => "bool",
+            Value::Int(_) => "int",
+            Value::Str(_) => "string",
+            Value::List(_) => "list",
+            Value::Board(_) => "board",
+            Value::Move(_) => "move",
+        }
+    }
+}
+
+impl PartialEq for Value {
+    fn eq(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::Nil, Value::Nil) => true,
+            (Value::Bool(a), Value::Bool(b)) => a == b,
+            (Value::Int(a), Value::Int(b)) => a == b,
+            (Value::Str(a), Value::Str(b)) => a == b,
+            (Value::List(a), Value::List(b)) => a == b,
+            (Value::Board(a), Value::Board(b)) => a.to_fen() == b.to_fen(),
+            (Value::Move(a), Value::Move(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Nil => write!(f, "nil"),
+            Value::Bool(value) => write!(f, "{}", value),
+            Value::Int(value) => write!(f, "{}", value),
+            Value::Str(value) => write!(f, "{}", value),
+            Value::List(items) => {
+                let items: Vec<String> = items.iter().map(Value::to_string).collect();
+                write!(f, "[{}]", items.join(", "))
+            }
+            Value::Board(board) => write!(f, "{}", board.to_fen()),
+            Value::Move(mv) => write!(f, "{}", mv.to_uci()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Int(i64),
+    Str(String),
+    // Keywords and punctuation, as written
+    Symbol(&'static str),
+    End,
+}
+
+const KEYWORDS: &[&str] =
+    &["let", "fn", "if", "else", "while", "for", "in", "return", "break", "continue", "true", "false", "nil"];
+
+// Longest first, so that "==" isn't read as two "="
+const SYMBOLS: &[&str] = &[
+    "==", "!=", "<=", ">=", "&&", "||", "(", ")", "{", "}", "[", "]", ",", ";", "=", "<", ">", "+", "-", "*", "/", "%",
+    "!",
+];
+
+// The tokens of `source`, each with the line it's on
+fn tokenize(source: &str) -> Result<Vec<(Token, usize)>, ScriptError> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut line = 1;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c == '\n' {
+            line += 1;
+            i += 1;
+        } else if c.is_whitespace() {
+            i += 1;
+        } else if c == '/' && chars.get(i + 1) == Some(&'/') {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+        } else if c.is_ascii_digit() {
+            let start = i;
+            while i < chars.len() && chars[i].is_ascii_digit() {
+                i += 1;
+            }
+            let text: String = chars[start..i].iter().collect();
+            let value = text.parse().map_err(|_| ScriptError::new(line, format!("number {} is too big", text)))?;
+            tokens.push((Token::Int(value), line));
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            let word: String = chars[start..i].iter().collect();
+            let token = match KEYWORDS.iter().find(|&&keyword| keyword == word) {
+                Some(keyword) => Token::Symbol(keyword),
+                None => Token::Ident(word),
+            };
+            tokens.push((token, line));
+        } else if c == '"' {
+            let start_line = line;
+            let mut text = String::new();
+            i += 1;
+            loop {
+                match chars.get(i) {
+                    None => return Err(ScriptError::new(start_line, "string never ends")),
+                    Some('"') => break,
+                    Some('\n') => {
+                        line += 1;
+                        text.push('\n');
+                    }
+                    Some(&c) => text.push(c),
+                }
+                i += 1;
+            }
+            i += 1;
+            tokens.push((Token::Str(text), start_line));
+        } else {
+            let rest: String = chars[i..chars.len().min(i + 2)].iter().collect();
+            let symbol = SYMBOLS
+                .iter()
+                .find(|symbol| rest.starts_with(*symbol))
+                .ok_or_else(|| ScriptError::new(line, format!("unexpected '{}'", c)))?;
+            tokens.push((Token::Symbol(symbol), line));
+            i += symbol.len();
+        }
+    }
+    tokens.push((Token::End, line));
+    Ok(tokens)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    And,
+    Or,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum UnaryOp {
+    Neg,
+    Not,
+}
+
+#[derive(Debug)]
+enum ExprKind {
+    Literal(Value),
+    List(Vec<Expr>),
+    Var(String),
+    Call(String, Vec<Expr>),
+    Index(Box<Expr>, Box<Expr>),
+    Unary(UnaryOp, Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+}
+
+#[derive(Debug)]
+struct Expr {
+    kind: ExprKind,
+    line: usize,
+}
+
+#[derive(Debug)]
+enum Stmt {
+    Let(String, Expr),
+    Assign(String, Expr, usize),
+    If(Expr, Vec<Stmt>, Vec<Stmt>),
+    While(Expr, Vec<Stmt>),
+    For(String, Expr, Vec<Stmt>),
+    Return(Option<Expr>),
+    Break,
+    Continue,
+    Expr(Expr),
+}
+
+#[derive(Debug)]
+struct Function {
+    params: Vec<String>,
+    body: Vec<Stmt>,
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    next: usize,
+    // How many loops the statement being read is inside
+    loops: usize,
+    // Every call made, to check once all the functions are known
+    calls: Vec<(String, usize, usize)>,
+}
+
+impl Parser {
+    fn peek(&self) -> &Token {
+        &self.tokens[self.next].0
+    }
+
+    fn line(&self) -> usize {
+        self.tokens[self.next].1
+    }
+
+    fn advance(&mut self) -> Token {
+        let token = self.tokens[self.next].0.clone();
+        if token != Token::End {
+            self.next += 1;
+        }
+        token
+    }
+
+    fn at(&self, symbol: &str) -> bool {
+        matches!(self.peek(), Token::Symbol(found) if *found == symbol)
+    }
+
+    fn eat(&mut self, symbol: &str) -> bool {
+        let found = self.at(symbol);
+        if found {
+            self.advance();
+        }
+        found
+    }
+
+    fn expect(&mut self, symbol: &str) -> Result<(), ScriptError> {
+        if self.eat(symbol) {
+            Ok(())
+        } else {
+            Err(self.unexpected(&format!("'{}'", symbol)))
+        }
+    }
+
+    fn unexpected(&self, wanted: &str) -> ScriptError {
+        let found = match self.peek() {
+            Token::Ident(name) => format!("'{}'", name),
+            Token::Int(value) => value.to_string(),
+            Token::Str(_) => "a string".to_string(),
+            Token::Symbol(symbol) => format!("'{}'", symbol),
+            Token::End => "the end of the script".to_string(),
+        };
+        ScriptError::new(self.line(), format!("expected {}, found {}", wanted, found))
+    }
+
+    fn ident(&mut self) -> Result<String, ScriptError> {
+        match self.peek() {
+            Token::Ident(name) => {
+                let name = name.clone();
+                self.advance();
+                Ok(name)
+            }
+            _ => Err(self.unexpected("a name")),
+        }
+    }
+
+    fn block(&mut self) -> Result<Vec<Stmt>, ScriptError> {
+        self.expect("{")?;
+        let mut stmts = Vec::new();
+        while !self.eat("}") {
+            if *self.peek() == Token::End {
+                return Err(self.unexpected("'}'"));
+            }
+            stmts.push(self.statement()?);
+        }
+        Ok(stmts)
+    }
+
+    fn statement(&mut self) -> Result<Stmt, ScriptError> {
+        let line = self.line();
+        if self.eat("let") {
+            let name = self.ident()?;
+            self.expect("=")?;
+            let value = self.expression()?;
+            self.expect(";")?;
+            Ok(Stmt::Let(name, value))
+        } else if self.eat("if") {
+            self.if_rest()
+        } else if self.eat("while") {
+            let condition = self.expression()?;
+            self.loops += 1;
+            let body = self.block();
+            self.loops -= 1;
+            Ok(Stmt::While(condition, body?))
+        } else if self.eat("for") {
+            let name = self.ident()?;
+            self.expect("in")?;
+            let list = self.expression()?;
+            self.loops += 1;
+            let body = self.block();
+            self.loops -= 1;
+            Ok(Stmt::For(name, list, body?))
+        } else if self.eat("return") {
+            let value = if self.at(";") { None } else { Some(self.expression()?) };
+            self.expect(";")?;
+            Ok(Stmt::Return(value))
+        } else if self.at("break") || self.at("continue") {
+            let stmt = if self.eat("break") { Stmt::Break } else { self.advance(); Stmt::Continue };
+            if self.loops == 0 {
+                return Err(ScriptError::new(line, "break and continue only go in a loop"));
+            }
+            self.expect(";")?;
+            Ok(stmt)
+        } else if self.at("fn") {
+            Err(ScriptError::new(line, "functions are only defined at the top of the script"))
+        } else {
+            let expr = self.expression()?;
+            if self.eat("=") {
+                let ExprKind::Var(name) = expr.kind else {
+                    return Err(ScriptError::new(line, "only a variable can be assigned to"));
+                };
+                let value = self.expression()?;
+                self.expect(";")?;
+                return Ok(Stmt::Assign(name, value, line));
+            }
+            self.expect(";")?;
+            Ok(Stmt::Expr(expr))
+        }
+    }
+
+    // An if statement after its "if"
+    fn if_rest(&mut self) -> Result<Stmt, ScriptError> {
+        let condition = self.expression()?;
+        let then = self.block()?;
+        let otherwise = if !self.eat("else") {
+            Vec::new()
+        } else if self.eat("if") {
+            vec![self.if_rest()?]
+        } else {
+            self.block()?
+        };
+        Ok(Stmt::If(condition, then, otherwise))
+    }
+
+    fn expression(&mut self) -> Result<Expr, ScriptError> {
+        self.binary(0)
+    }
+
+    // Operators by how tightly they bind, loosest first
+    const PRECEDENCE: &'static [&'static [(&'static str, BinaryOp)]] = &[
+        &[("||", BinaryOp::Or)],
+        &[("&&", BinaryOp::And)],
+        &[("==", BinaryOp::Eq), ("!=", BinaryOp::Ne)],
+        &[("<", BinaryOp::Lt), ("<=", BinaryOp::Le), (">", BinaryOp::Gt), (">=", BinaryOp::Ge)],
+        &[("+", BinaryOp::Add), ("-", BinaryOp::Sub)],
+        &[("*", BinaryOp::Mul), ("/", BinaryOp::Div), ("%", BinaryOp::Rem)],
+    ];
+
+    fn binary(&mut self, level: usize) -> Result<Expr, ScriptError> {
+        let Some(operators) = Self::PRECEDENCE.get(level) else {
+            return self.unary();
+        };
+        let mut left = self.binary(level + 1)?;
+        while let Some(&(_, op)) = operators.iter().find(|(symbol, _)| self.at(symbol)) {
+            let line = self.line();
+            self.advance();
+            let right = self.binary(level + 1)?;
+            left = Expr { kind: ExprKind::Binary(op, Box::new(left), Box::new(right)), line };
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expr, ScriptError> {
+        let line = self.line();
+        let op = if self.eat("-") {
+            UnaryOp::Neg
+        } else if self.eat("!") {
+            UnaryOp::Not
+        } else {
+            return self.postfix();
+        };
+        let operand = self.unary()?;
+        Ok(Expr { kind: ExprKind::Unary(op, Box::new(operand)), line })
+    }
+
+    fn postfix(&mut self) -> Result<Expr, ScriptError> {
+        let mut expr = self.primary()?;
+        while self.at("[") {
+            let line = self.line();
+            self.advance();
+            let index = self.expression()?;
+            self.expect("]")?;
+            expr = Expr { kind: ExprKind::Index(Box::new(expr), Box::new(index)), line };
+        }
+        Ok(expr)
+    }
+
+    fn primary(&mut self) -> Result<Expr, ScriptError> {
+        let line = self.line();
+        let kind = match self.peek().clone() {
+            Token::Int(value) => {
+                self.advance();
+                ExprKind::Literal(Value::Int(value))
+            }
+            Token::Str(text) => {
+                self.advance();
+                ExprKind::Literal(Value::Str(text))
+            }
+            Token::Ident(name) => {
+                self.advance();
+                if self.eat("(") {
+                    let args = self.list_rest(")")?;
+                    self.calls.push((name.clone(), args.len(), line));
+                    ExprKind::Call(name, args)
+                } else {
+                    ExprKind::Var(name)
+                }
+            }
+            Token::Symbol("true") => {
+                self.advance();
+                ExprKind::Literal(Value::Bool(true))
+            }
+            Token::Symbol("false") => {
+                self.advance();
+                ExprKind::Literal(Value::Bool(false))
+            }
+            Token::Symbol("nil") => {
+                self.advance();
+                ExprKind::Literal(Value::Nil)
+            }
+            Token::Symbol("(") => {
+                self.advance();
+                let expr = self.expression()?;
+                self.expect(")")?;
+                return Ok(expr);
+            }
+            Token::Symbol("[") => {
+                self.advance();
+                ExprKind::List(self.list_rest("]")?)
+            }
+            _ => return Err(self.unexpected("a value")),
+        };
+        Ok(Expr { kind, line })
+    }
+
+    // Comma-separated expressions up to the closing `end`
+    fn list_rest(&mut self, end: &str) -> Result<Vec<Expr>, ScriptError> {
+        let mut items = Vec::new();
+        while !self.eat(end) {
+            items.push(self.expression()?);
+            if !self.at(end) {
+                self.expect(",")?;
+            }
+        }
+        Ok(items)
+    }
+}
+
+/// A bot's script: functions, and variables set up before any of them
+/// runs. The language is small: `let` and assignment, `if`/`else`,
+/// `while`, `for x in list`, `return`, `break` and `continue`, integers,
+/// strings, lists, `true`, `false` and `nil`, with built-in functions for
+/// the board and its moves.
+#[derive(Debug)]
+pub struct Program {
+    globals: Vec<Stmt>,
+    functions: HashMap<String, Function>,
+}
+
+impl Program {
+    pub fn parse(source: &str) -> Result<Program, ScriptError> {
+        let mut parser = Parser { tokens: tokenize(source)?, next: 0, loops: 0, calls: Vec::new() };
+        let mut globals = Vec::new();
+        let mut functions = HashMap::new();
+        while *parser.peek() != Token::End {
+            let line = parser.line();
+            if parser.eat("fn") {
+                let name = parser.ident()?;
+                if BUILTINS.iter().any(|&(builtin, _)| builtin == name) {
+                    return Err(ScriptError::new(line, format!("{} is a built-in function", name)));
+                }
+                parser.expect("(")?;
+                let mut params = Vec::new();
+                while !parser.eat(")") {
+                    params.push(parser.ident()?);
+                    if !parser.at(")") {
+                        parser.expect(",")?;
+                    }
+                }
+                let body = parser.block()?;
+                if functions.insert(name.clone(), Function { params, body }).is_some() {
+                    return Err(ScriptError::new(line, format!("fn {} is defined twice", name)));
+                }
+            } else if parser.at("let") {
+                globals.push(parser.statement()?);
+            } else {
+                return Err(parser.unexpected("fn or let"));
+            }
+        }
+
+        for (name, args, line) in &parser.calls {
+            let params = match functions.get(name) {
+                Some(function) => function.params.len(),
+                None => match BUILTINS.iter().find(|&&(builtin, _)| builtin == name) {
+                    Some(&(_, params)) => params,
+                    None => return Err(ScriptError::new(*line, format!("no function called {}", name))),
+                },
+            };
+            if params != *args {
+                return Err(ScriptError::new(*line, format!("{} takes {} arguments, not {}", name, params, args)));
+            }
+        }
+        Ok(Program { globals, functions })
+    }
+
+    /// How many parameters the script's function `name` takes, if it has one
+    pub fn arity(&self, name: &str) -> Option<usize> {
+        self.functions.get(name).map(|function| function.params.len())
+    }
+
+    /// Runs the script's function `name`. `seed` seeds `random`.
+    pub fn call(&self, name: &str, args: Vec<Value>, seed: u64) -> Result<Value, ScriptError> {
+        let mut interpreter =
+            Interpreter { program: self, globals: HashMap::new(), rng: Rng::new(seed), steps: 0, depth: 0 };
+        let mut scopes = vec![HashMap::new()];
+        for stmt in &self.globals {
+            interpreter.exec(stmt, &mut scopes)?;
+        }
+        interpreter.globals = scopes.pop().unwrap_or_default();
+        interpreter.call(name, args, 0)
+    }
+}
+
+// How a statement finished
+enum Flow {
+    Next,
+    Return(Value),
+    Break,
+    Continue,
+}
+
+struct Interpreter<'a> {
+    program: &'a Program,
+    globals: HashMap<String, Value>,
+    rng: Rng,
+    steps: u64,
+    depth: usize,
+}
+
+// The variables of the blocks being run in a function, innermost last
+type Scopes = Vec<HashMap<String, Value>>;
+
+impl Interpreter<'_> {
+    fn step(&mut self, line: usize) -> Result<(), ScriptError> {
+        self.steps += 1;
+        if self.steps > MAX_STEPS {
+            return Err(ScriptError::new(line, "the script ran too long"));
+        }
+        Ok(())
+    }
+
+    fn call(&mut self, name: &str, args: Vec<Value>, line: usize) -> Result<Value, ScriptError> {
+        let Some(function) = self.program.functions.get(name) else {
+            let value = builtin(name, args, &mut self.rng).map_err(|message| ScriptError::new(line, message))?;
+            // Making a list takes a step for each item
+            if let Value::List(items) = &value {
+                self.steps += items.len() as u64;
+                self.step(line)?;
+            }
+            return Ok(value);
+        };
+        if function.params.len() != args.len() {
+            return Err(ScriptError::new(line, format!("{} takes {} arguments", name, function.params.len())));
+        }
+        if self.depth >= MAX_CALL_DEPTH {
+            return Err(ScriptError::new(line, "functions call each other too deeply"));
+        }
+        self.depth += 1;
+        let mut scopes = vec![function.params.iter().cloned().zip(args).collect()];
+        let flow = self.exec_block(&function.body, &mut scopes);
+        self.depth -= 1;
+        match flow? {
+            Flow::Return(value) => Ok(value),
+            _ => Ok(Value::Nil),
+        }
+    }
+
+    fn exec_block(&mut self, stmts: &[Stmt], scopes: &mut Scopes) -> Result<Flow, ScriptError> {
+        scopes.push(HashMap::new());
+        let mut flow = Ok(Flow::Next);
+        for stmt in stmts {
+            flow = self.exec(stmt, scopes);
+            if !matches!(flow, Ok(Flow::Next)) {
+                break;
+            }
+        }
+        scopes.pop();
+        flow
+    }
+
+    fn exec(&mut self, stmt: &Stmt, scopes: &mut Scopes) -> Result<Flow, ScriptError> {
+        match stmt {
+            Stmt::Let(name, value) => {
+                let value = self.eval(value, scopes)?;
+                // There's always a scope: the function's, or the globals'
+                if let Some(scope) = scopes.last_mut() {
+                    scope.insert(name.clone(), value);
+                }
+            }
+            Stmt::Assign(name, value, line) => {
+                let value = self.eval(value, scopes)?;
+                let slot = scopes
+                    .iter_mut()
+                    .rev()
+                    .find_map(|scope| scope.get_mut(name))
+                    .or_else(|| self.globals.get_mut(name))
+                    .ok_or_else(|| {
+                        ScriptError::new(*line, format!("{} isn't declared; use let {} = ...", name, name))
+                    })?;
+                *slot = value;
+            }
+            Stmt::If(condition, then, otherwise) => {
+                let branch = if self.condition(condition, scopes)? { then } else { otherwise };
+                return self.exec_block(branch, scopes);
+            }
+            Stmt::While(condition, body) => {
+                while self.condition(condition, scopes)? {
+                    match self.exec_block(body, scopes)? {
+                        Flow::Break => break,
+                        Flow::Return(value) => return Ok(Flow::Return(value)),
+                        Flow::Next | Flow::Continue => {}
+                    }
+                }
+            }
+            Stmt::For(name, list, body) => {
+                let items = match self.eval(list, scopes)? {
+                    Value::List(items) => items,
+                    other => {
+                        let message = format!("for needs a list, not {}", other.type_name());
+                        return Err(ScriptError::new(list.line, message));
+                    }
+                };
+                for item in items {
+                    self.step(list.line)?;
+                    scopes.push(HashMap::from([(name.clone(), item)]));
+                    let flow = self.exec_block(body, scopes);
+                    scopes.pop();
+                    match flow? {
+                        Flow::Break => break,
+                        Flow::Return(value) => return Ok(Flow::Return(value)),
+                        Flow::Next | Flow::Continue => {}
+                    }
+                }
+            }
+            Stmt::Return(value) => {
+                let value = match value {
+                    Some(value) => self.eval(value, scopes)?,
+                    None => Value::Nil,
+                };
+                return Ok(Flow::Return(value));
+            }
+            Stmt::Break => return Ok(Flow::Break),
+            Stmt::Continue => return Ok(Flow::Continue),
+            Stmt::Expr(expr) => {
+                self.eval(expr, scopes)?;
+            }
+        }
+        Ok(Flow::Next)
+    }
+
+    fn condition(&mut self, expr: &Expr, scopes: &mut Scopes) -> Result<bool, ScriptError> {
+        match self.eval(expr, scopes)? {
+            Value::Bool(value) => Ok(value),
+            other => {
+                let message = format!("a condition must be true or false, not {}", other.type_name());
+                Err(ScriptError::new(expr.line, message))
+            }
+        }
+    }
+
+    fn eval(&mut self, expr: &Expr, scopes: &mut Scopes) -> Result<Value, ScriptError> {
+        self.step(expr.line)?;
+        let line = expr.line;
+        match &expr.kind {
+            ExprKind::Literal(value) => Ok(value.clone()),
+            ExprKind::List(items) => {
+                Ok(Value::List(items.iter().map(|item| self.eval(item, scopes)).collect::<Result<_, _>>()?))
+            }
+            ExprKind::Var(name) => scopes
+                .iter()
+                .rev()
+                .find_map(|scope| scope.get(name))
+                .or_else(|| self.globals.get(name))
+                .cloned()
+                .ok_or_else(|| ScriptError::new(line, format!("no variable called {}", name))),
+            ExprKind::Call(name, args) => {
+                let args = args.iter().map(|arg| self.eval(arg, scopes)).collect::<Result<_, _>>()?;
+                self.call(name, args, line)
+            }
+            ExprKind::Index(target, index) => match (self.eval(target, scopes)?, self.eval(index, scopes)?) {
+                (Value::List(items), Value::Int(index)) => usize::try_from(index)
+                    .ok()
+                    .and_then(|index| items.get(index).cloned())
+                    .ok_or_else(|| {
+                        ScriptError::new(line, format!("index {} is outside a list of {}", index, items.len()))
+                    }),
+                (target, index) => Err(ScriptError::new(
+                    line,
+                    format!("can't index a {} with a {}", target.type_name(), index.type_name()),
+                )),
+            },
+            ExprKind::Unary(op, operand) => match (op, self.eval(operand, scopes)?) {
+                (UnaryOp::Neg, Value::Int(value)) => Ok(Value::Int(value.wrapping_neg())),
+                (UnaryOp::Not, Value::Bool(value)) => Ok(Value::Bool(!value)),
+                (UnaryOp::Neg, other) => Err(ScriptError::new(line, format!("can't negate a {}", other.type_name()))),
+                (UnaryOp::Not, other) => {
+                    Err(ScriptError::new(line, format!("! needs true or false, not {}", other.type_name())))
+                }
+            },
+            ExprKind::Binary(BinaryOp::And, left, right) => {
+                Ok(Value::Bool(self.condition(left, scopes)? && self.condition(right, scopes)?))
+            }
+            ExprKind::Binary(BinaryOp::Or, left, right) => {
+                Ok(Value::Bool(self.condition(left, scopes)? || self.condition(right, scopes)?))
+            }
+            ExprKind::Binary(op, left, right) => {
+                let (left, right) = (self.eval(left, scopes)?, self.eval(right, scopes)?);
+                binary(*op, left, right).map_err(|message| ScriptError::new(line, message))
+            }
+        }
+    }
+}
+
+// Refuses a list or string longer than MAX_LENGTH before it's made
+fn check_length(length: usize) -> Result<(), String> {
+    if length > MAX_LENGTH {
+        return Err(format!("lists and strings can't be longer than {}", MAX_LENGTH));
+    }
+    Ok(())
+}
+
+fn binary(op: BinaryOp, left: Value, right: Value) -> Result<Value, String> {
+    let value = match (op, &left, &right) {
+        (BinaryOp::Eq, _, _) => Value::Bool(left == right),
+        (BinaryOp::Ne, _, _) => Value::Bool(left != right),
+        (BinaryOp::Add, Value::Str(a), b) => {
+            let text = format!("{}{}", a, b);
+            check_length(text.chars().count())?;
+            Value::Str(text)
+        }
+        (BinaryOp::Add, Value::List(a), Value::List(b)) => {
+            check_length(a.len() + b.len())?;
+            Value::List(a.iter().chain(b).cloned().collect())
+        }
+        (_, Value::Int(a), Value::Int(b)) => {
+            let (a, b) = (*a, *b);
+            match op {
+                BinaryOp::Add => Value::Int(a.wrapping_add(b)),
+                BinaryOp::Sub => Value::Int(a.wrapping_sub(b)),
+                BinaryOp::Mul => Value::Int(a.wrapping_mul(b)),
+                BinaryOp::Div | BinaryOp::Rem if b == 0 => return Err("division by zero".to_string()),
+                BinaryOp::Div => Value::Int(a.wrapping_div(b)),
+                BinaryOp::Rem => Value::Int(a.wrapping_rem(b)),
+                BinaryOp::Lt => Value::Bool(a < b),
+                BinaryOp::Le => Value::Bool(a <= b),
+                BinaryOp::Gt => Value::Bool(a > b),
+                BinaryOp::Ge => Value::Bool(a >= b),
+                BinaryOp::Eq | BinaryOp::Ne | BinaryOp::And | BinaryOp::Or => unreachable!("handled above"),
+            }
+        }
+        (BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge, Value::Str(a), Value::Str(b)) => {
+            Value::Bool(match op {
+                BinaryOp::Lt => a < b,
+                BinaryOp::Le => a <= b,
+                BinaryOp::Gt => a > b,
+                _ => a >= b,
+            })
+        }
+        _ => {
+            return Err(format!("can't apply {:?} to a {} and a {}", op, left.type_name(), right.type_name()));
+        }
+    };
+    Ok(value)
+}
+
+// A piece as its FEN letter, White's in capitals
+fn piece_letter(piece: Piece) -> String {
+    let letter = match piece.piece_type {
+        PieceType::Pawn => 'p',
+        PieceType::Knight => 'n',
+        PieceType::Bishop => 'b',
+        PieceType::Rook => 'r',
+        PieceType::Queen => 'q',
+        PieceType::King => 'k',
+    };
+    match piece.color {
+        Color::White => letter.to_ascii_uppercase().to_string(),
+        Color::Black => letter.to_string(),
+    }
+}
+
+fn builtin(name: &str, args: Vec<Value>, rng: &mut Rng) -> Result<Value, String> {
+    let wrong = |args: &[Value]| {
+        let types: Vec<&str> = args.iter().map(Value::type_name).collect();
+        format!("{} can't take ({})", name, types.join(", "))
+    };
+    let value = match (name, args.as_slice()) {
+        ("legal_moves", [Value::Board(board)]) => {
+            Value::List(MoveGenerator::generate_all_legal_moves(board).into_iter().map(Value::Move).collect())
+        }
+        ("make_move", [Value::Board(board), Value::Move(mv)]) => {
+            if !MoveGenerator::is_legal(board, mv) {
+                return Err(format!("{} isn't legal here", mv.to_uci()));
+            }
+            let mut next = board.clone();
+            next.make_move(mv);
+            Value::Board(next)
+        }
+        ("evaluate", [Value::Board(board)]) => Value::Int(EvalWeights::default().material(board) as i64),
+        ("search", [Value::Board(board), Value::Int(depth)]) => {
+            let engine = ChessAI::new((*depth).clamp(1, MAX_SEARCH_DEPTH as i64) as u32);
+            engine.get_best_move(board).map_or(Value::Nil, Value::Move)
+        }
+        ("turn", [Value::Board(board)]) => Value::Str(
+            match board.current_player {
+                Color::White => "white",
+                Color::Black => "black",
+            }
+            .to_string(),
+        ),
+        ("in_check", [Value::Board(board)]) => Value::Bool(MoveGenerator::is_in_check(board, board.current_player)),
+        ("is_checkmate", [Value::Board(board)]) => {
+            Value::Bool(MoveGenerator::is_checkmate(board, board.current_player))
+        }
+        ("is_stalemate", [Value::Board(board)]) => {
+            Value::Bool(MoveGenerator::is_stalemate(board, board.current_player))
+        }
+        ("piece_at", [Value::Board(board), Value::Str(square)]) => {
+            let pos = Position::from_algebraic(square).ok_or_else(|| format!("{} isn't a square", square))?;
+            board.get_piece(pos).map_or(Value::Nil, |piece| Value::Str(piece_letter(piece)))
+        }
+        ("fen", [Value::Board(board)]) => Value::Str(board.to_fen()),
+        ("move_number", [Value::Board(board)]) => Value::Int(board.fullmove_number as i64),
+        ("uci", [Value::Move(mv)]) => Value::Str(mv.to_uci()),
+        ("san", [Value::Board(board), Value::Move(mv)]) => Value::Str(san::to_san(board, mv)),
+        ("from_square", [Value::Move(mv)]) => Value::Str(mv.from.to_algebraic()),
+        ("to_square", [Value::Move(mv)]) => Value::Str(mv.to.to_algebraic()),
+        ("piece", [Value::Move(mv)]) => Value::Str(piece_letter(mv.piece)),
+        ("captured", [Value::Move(mv)]) => mv.captured.map_or(Value::Nil, |piece| Value::Str(piece_letter(piece))),
+        ("promotion", [Value::Move(mv)]) => match mv.move_type {
+            MoveType::Promotion(piece_type) => Value::Str(piece_letter(Piece::new(piece_type, mv.piece.color))),
+            _ => Value::Nil,
+        },
+        ("is_castle", [Value::Move(mv)]) => Value::Bool(mv.move_type == MoveType::Castle),
+        ("value", [Value::Str(letter)]) => {
+            let piece_type = match letter.to_ascii_lowercase().as_str() {
+                "p" => PieceType::Pawn,
+                "n" => PieceType::Knight,
+                "b" => PieceType::Bishop,
+                "r" => PieceType::Rook,
+                "q" => PieceType::Queen,
+                "k" => PieceType::King,
+                _ => return Err(format!("{} isn't a piece letter", letter)),
+            };
+            Value::Int(piece_type.value() as i64)
+        }
+        ("len", [Value::List(items)]) => Value::Int(items.len() as i64),
+        ("len", [Value::Str(text)]) => Value::Int(text.chars().count() as i64),
+        ("push", [Value::List(items), item]) => {
+            check_length(items.len() + 1)?;
+            let mut items = items.clone();
+            items.push(item.clone());
+            Value::List(items)
+        }
+        ("range", [Value::Int(start), Value::Int(end)]) => {
+            check_length((*end as i128 - *start as i128).clamp(0, MAX_LENGTH as i128 + 1) as usize)?;
+            Value::List((*start..*end).map(Value::Int).collect())
+        }
+        ("random", [Value::Int(n)]) if *n > 0 => Value::Int(rng.below(*n as usize) as i64),
+        ("random", [Value::Int(_)]) => return Err("random needs a number above 0".to_string()),
+        ("abs", [Value::Int(n)]) => Value::Int(n.wrapping_abs()),
+        ("min", [Value::Int(a), Value::Int(b)]) => Value::Int(*a.min(b)),
+        ("max", [Value::Int(a), Value::Int(b)]) => Value::Int(*a.max(b)),
+        ("str", [value]) => Value::Str(value.to_string()),
+        _ => return Err(wrong(&args)),
+    };
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(source: &str) -> Result<Value, ScriptError> {
+        Program::parse(source)?.call("main", Vec::new(), 1)
+    }
+
+    #[test]
+    fn runs_the_language() {
+        let source = r#"
+            let LIMIT = 10;
+
+            // Sums of the odd numbers below LIMIT, in a list
+            fn main() {
+                let sums = [];
+                let total = 0;
+                for n in range(0, LIMIT) {
+                    if n % 2 == 0 {
+                        continue;
+                    } else if n > 7 {
+                        break;
+                    }
+                    total = total + n;
+                    sums = push(sums, total);
+                }
+                let i = 0;
+                while i < 3 && !(i == 5) {
+                    i = i + 1;
+                }
+                return [sums, i, fact(5), "n=" + str(-3 * 2), len(sums) >= 4 || false];
+            }
+
+            fn fact(n) {
+                if n <= 1 {
+                    return 1;
+                }
+                return n * fact(n - 1);
+            }
+        "#;
+        assert_eq!(run(source).unwrap().to_string(), "[[1, 4, 9, 16], 3, 120, n=-6, true]");
+        // Integers wrap around rather than overflow
+        let wrapping = "fn main() { let min = -9223372036854775807 - 1; return [-min, min - 1, abs(min)]; }";
+        let min = i64::MIN;
+        assert_eq!(run(wrapping).unwrap().to_string(), format!("[{}, {}, {}]", min, i64::MAX, min));
+    }
+
+    #[test]
+    fn reports_mistakes_with_their_line() {
+        let error = |source: &str| run(source).unwrap_err();
+        assert_eq!(error("fn main() {\n  return 1 +;\n}"), ScriptError::new(2, "expected a value, found ';'"));
+        assert_eq!(error("fn main() {\n  return nope(1);\n}").line, 2);
+        assert_eq!(error("fn main() {\n  x = 1;\n}").message, "x isn't declared; use let x = ...");
+        assert_eq!(error("fn main() {\n  break;\n}").message, "break and continue only go in a loop");
+        assert_eq!(error("fn len(x) {}").message, "len is a built-in function");
+        let condition = error("fn main() {\n\n  if 1 { }\n}");
+        assert_eq!(condition, ScriptError::new(3, "a condition must be true or false, not int"));
+        assert_eq!(error("fn main() { return [1][2]; }").message, "index 2 is outside a list of 1");
+        assert_eq!(error("fn main() { return 1 / 0; }").message, "division by zero");
+        assert_eq!(error("fn main() { while true { } }").message, "the script ran too long");
+        assert_eq!(error("fn main() { return main(); }").message, "functions call each other too deeply");
+        let too_long = "lists and strings can't be longer than 100000";
+        assert_eq!(error("fn main() { return range(0, 9223372036854775807); }").message, too_long);
+        assert_eq!(error("fn main() { return range(-9223372036854775807 - 1, 0); }").message, too_long);
+        let doubling = "fn main() { let text = \"ab\"; while true { text = text + text; } }";
+        assert_eq!(error(doubling).message, too_long);
+        let growing = "fn main() { let list = range(0, 100000); while true { list = push(list, 1); } }";
+        assert_eq!(error(growing).message, too_long);
+    }
+
+    #[test]
+    fn plays_with_the_board() {
+        // White mates with Qh7 or Qf7... only Qxf7 is mate here
+        let board = Board::from_fen("r1bqkbnr/pppp1ppp/2n5/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 4 4").unwrap();
+        let source = r#"
+            fn choose(board) {
+                for mv in legal_moves(board) {
+                    if is_checkmate(make_move(board, mv)) {
+                        return mv;
+                    }
+                }
+                return nil;
+            }
+        "#;
+        let program = Program::parse(source).unwrap();
+        assert_eq!(program.arity("choose"), Some(1));
+        let mv = program.call("choose", vec![Value::Board(Box::new(board.clone()))], 1).unwrap();
+        assert_eq!(mv.to_string(), "h5f7");
+
+        let source = r#"
+            fn main(board) {
+                let mv = search(board, 2);
+                return [turn(board), piece_at(board, "c4"), captured(mv), san(board, mv), evaluate(board) > 0];
+            }
+        "#;
+        let value = Program::parse(source).unwrap().call("main", vec![Value::Board(Box::new(board))], 1).unwrap();
+        assert_eq!(value.to_string(), "[white, B, p, Qxf7#, false]");
+    }
+}
@@ -0,0 +1,12 @@
+#[cfg(feature = "native")]
+pub mod bench;
+pub mod engine;
+pub mod eval;
+pub mod lang;
+pub mod rng;
+pub mod script;
+pub mod time;
+pub mod training;
+pub mod tune;
+
+pub use engine::{mate_in, ChessAI, Pruning, SearchProgress, Selection, MATE_SCORE, MATE_THRESHOLD, OPENING_VARIETY_MOVES};
@@ -0,0 +1,236 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::engine::ChessAI;
+use super::lang::{Program, ScriptError, Value, MAX_SEARCH_DEPTH};
+use super::rng::Rng;
+use crate::game::fen::STARTING_FEN;
+use crate::game::{Board, Color, Move, MoveGenerator, MoveType, PieceType};
+
+/// One way a scripted bot narrows down its legal moves
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum Rule {
+    /// Moves that checkmate
+    Mate,
+    /// Captures of the most valuable piece that can be taken
+    Capture,
+    /// Moves that give check
+    Check,
+    Castle,
+    Promote,
+    /// Knight and bishop moves off their own back rank
+    Develop,
+    /// Pawn moves
+    Advance,
+    /// Moves to the four center squares
+    Center,
+    /// The engine's best move, searching this many plies, up to
+    /// MAX_SEARCH_DEPTH
+    Search(u32),
+    /// Any legal move
+    Random,
+}
+
+impl Rule {
+    // The moves among `moves` this rule picks on `board`
+    fn apply(self, board: &Board, moves: &[Move]) -> Vec<Move> {
+        let after = |mv: &Move| {
+            let mut next = board.clone();
+            next.make_move(mv);
+            next
+        };
+        let opponent = board.current_player.opposite();
+        let keep = |test: &dyn Fn(&Move) -> bool| moves.iter().copied().filter(|mv| test(mv)).collect();
+        match self {
+            Rule::Mate => keep(&|mv| MoveGenerator::is_checkmate(&after(mv), opponent)),
+            Rule::Capture => {
+                let best = moves.iter().filter_map(|mv| mv.captured).map(|piece| piece.piece_type.value()).max();
+                keep(&|mv| mv.captured.map(|piece| piece.piece_type.value()) == best && best.is_some())
+            }
+            Rule::Check => keep(&|mv| MoveGenerator::is_in_check(&after(mv), opponent)),
+            Rule::Castle => keep(&|mv| mv.move_type == MoveType::Castle),
+            Rule::Promote => keep(&|mv| matches!(mv.move_type, MoveType::Promotion(_))),
+            Rule::Develop => {
+                let back_rank = if board.current_player == Color::White { 7 } else { 0 };
+                keep(&|mv| {
+                    matches!(mv.piece.piece_type, PieceType::Knight | PieceType::Bishop) && mv.from.row == back_rank
+                })
+            }
+            Rule::Advance => keep(&|mv| mv.piece.piece_type == PieceType::Pawn),
+            Rule::Center => keep(&|mv| (3..=4).contains(&mv.to.row) && (3..=4).contains(&mv.to.col)),
+            Rule::Search(depth) => {
+                ChessAI::new(depth.clamp(1, MAX_SEARCH_DEPTH)).get_best_move(board).into_iter().collect()
+            }
+            Rule::Random => moves.to_vec(),
+        }
+    }
+}
+
+/// A bot written as a script rather than built in: lines of UCI moves to
+/// open with, then, if it has one, its `choose(board)` function in the
+/// [`Program`] language, then rules tried in order each move. The first
+/// rule that leaves any legal moves picks one of them at random; when none
+/// does, any legal move will do.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct BotScript {
+    pub name: String,
+    /// Followed while the game's moves so far start the line
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub openings: Vec<Vec<String>>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub rules: Vec<Rule>,
+    /// A script defining `fn choose(board)`, which returns the move to
+    /// play, or nil to leave it to the rules
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub code: Option<String>,
+}
+
+impl BotScript {
+    /// Checks that every opening line can be played from the starting
+    /// position, and that the code reads and defines `choose(board)`,
+    /// which a script file may not
+    pub fn validate(&self) -> Result<(), String> {
+        for (index, line) in self.openings.iter().enumerate() {
+            let mut board = Board::new();
+            for uci in line {
+                let mv = MoveGenerator::find_uci_move(&board, uci)
+                    .ok_or_else(|| format!("opening {} has the illegal move {}", index + 1, uci))?;
+                board.make_move(&mv);
+            }
+        }
+        if let Some(code) = &self.code {
+            let program = Program::parse(code).map_err(|error| format!("code {}", error))?;
+            if program.arity("choose") != Some(1) {
+                return Err("code must define fn choose(board)".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    /// The bot's move on `board`, reached by playing `played` from
+    /// `initial`, or None if it has none. The openings are only followed
+    /// in games from the standard starting position. If the code fails,
+    /// the error comes back and [`BotScript::choose_by_rules`] can pick the
+    /// move instead.
+    pub fn choose(
+        &self,
+        initial: &Board,
+        board: &Board,
+        played: &[Move],
+        seed: u64,
+    ) -> Result<Option<Move>, ScriptError> {
+        let moves = MoveGenerator::generate_all_legal_moves(board);
+        if moves.is_empty() {
+            return Ok(None);
+        }
+        let mut rng = Rng::new(seed);
+        let book: Vec<Move> = if initial.to_fen() == STARTING_FEN {
+            self.openings
+                .iter()
+                .filter(|line| {
+                    line.len() > played.len() && line.iter().zip(played).all(|(uci, mv)| *uci == mv.to_uci())
+                })
+                .filter_map(|line| MoveGenerator::find_uci_move(board, &line[played.len()]))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        if !book.is_empty() {
+            return Ok(Some(book[rng.below(book.len())]));
+        }
+        if let Some(code) = &self.code {
+            let program = Program::parse(code)?;
+            let message = match program.call("choose", vec![Value::Board(Box::new(board.clone()))], rng.next_u64())? {
+                Value::Move(mv) if moves.contains(&mv) => return Ok(Some(mv)),
+                Value::Move(mv) => format!("choose returned {}, which isn't legal", mv.to_uci()),
+                Value::Nil => return Ok(self.choose_by_rules(board, seed)),
+                other => format!("choose returned {}, not a move", other),
+            };
+            return Err(ScriptError { line: 0, message });
+        }
+        Ok(self.choose_by_rules(board, seed))
+    }
+
+    /// The move the bot's rules pick on `board`, leaving out its openings
+    /// and code, or None if it has none
+    pub fn choose_by_rules(&self, board: &Board, seed: u64) -> Option<Move> {
+        let moves = MoveGenerator::generate_all_legal_moves(board);
+        if moves.is_empty() {
+            return None;
+        }
+        let mut rng = Rng::new(seed);
+        let choices = self
+            .rules
+            .iter()
+            .map(|rule| rule.apply(board, &moves))
+            .find(|choices| !choices.is_empty())
+            .unwrap_or(moves);
+        Some(choices[rng.below(choices.len())])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bot(code: Option<&str>) -> BotScript {
+        BotScript {
+            name: "Test".to_string(),
+            openings: vec![vec!["e2e4".to_string(), "e7e5".to_string(), "g1f3".to_string()]],
+            rules: vec![Rule::Advance],
+            code: code.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn follows_the_book_only_from_the_standard_start() {
+        let bot = bot(None);
+        let start = Board::new();
+        assert_eq!(bot.choose(&start, &start, &[], 1).unwrap().unwrap().to_uci(), "e2e4");
+
+        // The same position after the same moves, but reached from a FEN
+        // with the counters moved on, so it isn't the game's start
+        let initial = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 5").unwrap();
+        for seed in 0..20 {
+            let mv = bot.choose(&initial, &initial, &[], seed).unwrap().unwrap();
+            assert_eq!(mv.piece.piece_type, PieceType::Pawn, "{}", mv.to_uci());
+        }
+    }
+
+    #[test]
+    fn plays_the_codes_move_and_falls_back_to_the_rules() {
+        let board = Board::from_fen("4k3/8/8/8/8/8/3q4/3QK3 w - - 0 1").unwrap();
+        let capture = bot(Some("fn choose(board) { return search(board, 2); }"));
+        assert_eq!(capture.choose(&Board::new(), &board, &[], 1).unwrap().unwrap().to_uci(), "d1d2");
+
+        let no_opinion = bot(Some("fn choose(board) { return nil; }"));
+        assert!(no_opinion.choose(&Board::new(), &board, &[], 1).unwrap().is_some());
+
+        let failing = bot(Some("fn choose(board) {\n  return legal_moves(board)[99];\n}"));
+        let error = failing.choose(&Board::new(), &board, &[], 1).unwrap_err();
+        assert_eq!(error.line, 2);
+        assert!(failing.choose_by_rules(&board, 1).is_some());
+
+        // A search deeper than a bot may go is cut short rather than left to hang
+        let deep = BotScript { rules: vec![Rule::Search(40)], ..bot(None) };
+        assert_eq!(deep.choose_by_rules(&board, 1).unwrap().to_uci(), "d1d2");
+
+        let wrong_type = bot(Some("fn choose(board) { return 3; }"));
+        let error = wrong_type.choose(&Board::new(), &board, &[], 1).unwrap_err();
+        assert_eq!(error.to_string(), "choose returned 3, not a move");
+    }
+
+    #[test]
+    fn rejects_bad_openings_and_code() {
+        assert_eq!(bot(None).validate(), Ok(()));
+        let mut illegal = bot(None);
+        illegal.openings = vec![vec!["e2e5".to_string()]];
+        assert_eq!(illegal.validate(), Err("opening 1 has the illegal move e2e5".to_string()));
+        assert_eq!(bot(Some("fn pick(board) { }")).validate(), Err("code must define fn choose(board)".to_string()));
+        let unreadable = bot(Some("fn choose(board) {\n  let = 1;\n}"));
+        assert_eq!(unreadable.validate(), Err("code line 2: expected a name, found '='".to_string()));
+    }
+}
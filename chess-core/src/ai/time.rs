@@ -0,0 +1,26 @@
+#[cfg(feature = "native")]
+use std::sync::OnceLock;
+#[cfg(feature = "native")]
+use std::time::Instant;
+
+/// Milliseconds since some fixed moment. The engine reads the time through
+/// one of these rather than the system clock, so that it can run where
+/// there is none, as in WebAssembly in a browser, with the host's clock
+/// passed in instead.
+pub type TimeSource = fn() -> u64;
+
+/// The system's monotonic clock, counting from its first reading
+#[cfg(feature = "native")]
+pub fn system_time() -> u64 {
+    static START: OnceLock<Instant> = OnceLock::new();
+    START.get_or_init(Instant::now).elapsed().as_millis() as u64
+}
+
+/// The clock searches are timed by unless given another: the system's,
+/// where there is one
+pub fn default_source() -> Option<TimeSource> {
+    #[cfg(feature = "native")]
+    return Some(system_time);
+    #[cfg(not(feature = "native"))]
+    return None;
+}
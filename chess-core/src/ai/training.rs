@@ -0,0 +1,132 @@
+use std::fmt;
+
+use super::eval::PIECE_TYPES;
+use crate::game::board::CastlingRights;
+use crate::game::{Board, Color, Position};
+use crate::game::pieces::Piece;
+
+/// Bytes each position takes in a training data file
+pub const RECORD_SIZE: usize = 40;
+
+/// A position from a game, with the engine's score for it and the game's
+/// result: what the evaluation is tuned or trained on
+#[derive(Clone)]
+pub struct TrainingPosition {
+    pub board: Board,
+    /// The search score in centipawns, from the side to move's point of view
+    pub score: i32,
+    /// White's share of the points: 1 for a win, 0.5 for a draw, 0 for a loss
+    pub result: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TrainingDataError {
+    /// The data ends partway through a position
+    Truncated,
+    /// The position with this index has a byte no position could have
+    InvalidRecord(usize),
+}
+
+impl fmt::Display for TrainingDataError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TrainingDataError::Truncated => write!(f, "the data ends partway through a position"),
+            TrainingDataError::InvalidRecord(index) => write!(f, "position {} is invalid", index + 1),
+        }
+    }
+}
+
+impl std::error::Error for TrainingDataError {}
+
+// A piece as a nibble: 0 for none, 1 to 6 for White's pawn to king and 9
+// to 14 for Black's
+fn piece_code(piece: Option<Piece>) -> u8 {
+    piece.map_or(0, |piece| {
+        let kind = PIECE_TYPES.iter().position(|&piece_type| piece_type == piece.piece_type).unwrap_or(0) as u8;
+        let color = if piece.color == Color::White { 0 } else { 8 };
+        color + kind + 1
+    })
+}
+
+fn piece_from_code(code: u8) -> Result<Option<Piece>, ()> {
+    let color = if code & 8 == 0 { Color::White } else { Color::Black };
+    match code & 7 {
+        0 if code == 0 => Ok(None),
+        kind @ 1..=6 => Ok(Some(Piece::new(PIECE_TYPES[kind as usize - 1], color))),
+        _ => Err(()),
+    }
+}
+
+impl TrainingPosition {
+    /// Appends the position's `RECORD_SIZE` bytes to `out`: the squares two
+    /// to a byte, then the side to move and castling rights, the en passant
+    /// square (255 for none), the halfmove clock, the fullmove number, the
+    /// score and the result (0, 1 or 2 half-points for White), numbers
+    /// little-endian
+    pub fn encode(&self, out: &mut Vec<u8>) {
+        let board = &self.board;
+        let squares: Vec<Option<Piece>> = board.squares().map(|(_, piece)| piece).collect();
+        out.extend(squares.chunks(2).map(|pair| piece_code(pair[0]) | piece_code(pair[1]) << 4));
+
+        let rights = &board.castling_rights;
+        let flags = [board.current_player == Color::Black, rights.white_kingside, rights.white_queenside]
+            .into_iter()
+            .chain([rights.black_kingside, rights.black_queenside])
+            .enumerate()
+            .fold(0, |flags, (bit, set)| flags | (set as u8) << bit);
+        out.push(flags);
+        out.push(board.en_passant_target.map_or(255, |pos| (pos.row * 8 + pos.col) as u8));
+        out.push(board.halfmove_clock.min(255) as u8);
+        out.extend((board.fullmove_number.min(u16::MAX as u32) as u16).to_le_bytes());
+        out.extend((self.score.clamp(i16::MIN as i32, i16::MAX as i32) as i16).to_le_bytes());
+        out.push((self.result * 2.0).round() as u8);
+    }
+
+    /// Reads back the positions `encode` wrote
+    pub fn decode_all(data: &[u8]) -> Result<Vec<TrainingPosition>, TrainingDataError> {
+        if !data.len().is_multiple_of(RECORD_SIZE) {
+            return Err(TrainingDataError::Truncated);
+        }
+        data.chunks(RECORD_SIZE)
+            .enumerate()
+            .map(|(index, record)| Self::decode(record).ok_or(TrainingDataError::InvalidRecord(index)))
+            .collect()
+    }
+
+    fn decode(record: &[u8]) -> Option<TrainingPosition> {
+        let mut board = Board::empty();
+        for (index, &byte) in record[..32].iter().enumerate() {
+            for (half, code) in [byte & 15, byte >> 4].into_iter().enumerate() {
+                let square = index * 2 + half;
+                board.set_piece(Position::new(square / 8, square % 8), piece_from_code(code).ok()?);
+            }
+        }
+
+        let flags = record[32];
+        if flags >> 5 != 0 {
+            return None;
+        }
+        let flag = |bit: u8| flags & (1 << bit) != 0;
+        board.current_player = if flag(0) { Color::Black } else { Color::White };
+        board.castling_rights = CastlingRights {
+            white_kingside: flag(1),
+            white_queenside: flag(2),
+            black_kingside: flag(3),
+            black_queenside: flag(4),
+        };
+        board.en_passant_target = match record[33] {
+            255 => None,
+            square @ 0..64 => Some(Position::new(square as usize / 8, square as usize % 8)),
+            _ => return None,
+        };
+        board.halfmove_clock = u32::from(record[34]);
+        board.fullmove_number = u32::from(u16::from_le_bytes([record[35], record[36]]));
+
+        let score = i32::from(i16::from_le_bytes([record[37], record[38]]));
+        let result = match record[39] {
+            half_points @ 0..=2 => f64::from(half_points) / 2.0,
+            _ => return None,
+        };
+        Some(TrainingPosition { board, score, result })
+    }
+}
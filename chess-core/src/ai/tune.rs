@@ -0,0 +1,198 @@
+use super::eval::{EvalWeights, CHECK_BONUS, MOBILITY_WEIGHT, PIECE_TYPES};
+use crate::game::{Board, Color, MoveGenerator};
+
+// The weights tuned: a value for each piece, then each piece's 64 squares
+const PARAMETERS: usize = 6 + 6 * 64;
+
+// Adam's step size, in centipawns, and how fast it forgets old gradients
+const LEARNING_RATE: f64 = 1.0;
+const BETA1: f64 = 0.9;
+const BETA2: f64 = 0.999;
+
+/// A position with the result of the game it came from
+pub struct LabeledPosition {
+    pub board: Board,
+    /// White's share of the points: 1 for a win, 0.5 for a draw, 0 for a loss
+    pub result: f64,
+}
+
+/// White's share of the points for a game result written "1-0", "0-1" or
+/// "1/2-1/2", or as a number such as "0.5"
+pub fn parse_result(text: &str) -> Option<f64> {
+    match text {
+        "1-0" => Some(1.0),
+        "0-1" => Some(0.0),
+        "1/2-1/2" => Some(0.5),
+        _ => text.parse().ok().filter(|result| (0.0..=1.0).contains(result)),
+    }
+}
+
+// A position as the tuner sees it: its score from White's side is the sum
+// of the weights it counts, each once per White piece and minus once per
+// Black piece, plus the terms that aren't tuned
+struct Sample {
+    counts: Vec<(usize, f64)>,
+    fixed: f64,
+    result: f64,
+}
+
+/// Fits the evaluation's piece values and square tables to game results,
+/// Texel style: the score of each position, squashed into an expected
+/// result, should come as close as it can to the result of its game.
+/// Since the score is a sum of weights, the error's gradient is cheap, and
+/// the weights follow it down a step at a time.
+pub struct Tuner {
+    samples: Vec<Sample>,
+    weights: Vec<f64>,
+    // How steeply scores turn into expected results
+    scale: f64,
+    // Adam's running averages of the gradient and its square
+    moment: Vec<f64>,
+    velocity: Vec<f64>,
+    steps: i32,
+}
+
+impl Tuner {
+    /// A tuner starting from `weights`. Positions in which the side to move
+    /// has no moves are left out, as no score stands for them.
+    pub fn new(positions: &[LabeledPosition], weights: &EvalWeights) -> Self {
+        let samples = positions.iter().filter_map(sample).collect();
+        let mut flat = vec![0.0; PARAMETERS];
+        for (index, &piece_type) in PIECE_TYPES.iter().enumerate() {
+            flat[index] = f64::from(*weights.values.get(piece_type));
+            for (square, &bonus) in weights.squares.get(piece_type).iter().enumerate() {
+                flat[square_parameter(index, square)] = f64::from(bonus);
+            }
+        }
+        let mut tuner = Tuner {
+            samples,
+            weights: flat,
+            scale: 1.0,
+            moment: vec![0.0; PARAMETERS],
+            velocity: vec![0.0; PARAMETERS],
+            steps: 0,
+        };
+        tuner.fit_scale();
+        tuner
+    }
+
+    /// Positions the tuner learns from
+    pub fn positions(&self) -> usize {
+        self.samples.len()
+    }
+
+    /// The scale turning scores into expected results, fitted to the
+    /// starting weights so that tuning changes the weights, not the scale
+    pub fn scale(&self) -> f64 {
+        self.scale
+    }
+
+    /// Mean squared difference between the positions' expected and actual results
+    pub fn error(&self) -> f64 {
+        self.error_at(self.scale)
+    }
+
+    fn error_at(&self, scale: f64) -> f64 {
+        let total: f64 = self
+            .samples
+            .iter()
+            .map(|sample| (sample.result - expected(self.score(sample), scale)).powi(2))
+            .sum();
+        total / self.samples.len().max(1) as f64
+    }
+
+    fn score(&self, sample: &Sample) -> f64 {
+        sample.fixed + sample.counts.iter().map(|&(parameter, count)| self.weights[parameter] * count).sum::<f64>()
+    }
+
+    // The error is lowest at a single scale, found by narrowing the range
+    fn fit_scale(&mut self) {
+        let (mut low, mut high) = (0.01, 10.0);
+        while high - low > 0.001 {
+            let third = (high - low) / 3.0;
+            if self.error_at(low + third) < self.error_at(high - third) {
+                high -= third;
+            } else {
+                low += third;
+            }
+        }
+        self.scale = (low + high) / 2.0;
+    }
+
+    /// Moves every weight a step down the error's gradient
+    pub fn step(&mut self) {
+        let mut gradient = vec![0.0; PARAMETERS];
+        // d expected / d score, less the constant factors, which Adam
+        // scales away
+        for sample in &self.samples {
+            let expected = expected(self.score(sample), self.scale);
+            let slope = (expected - sample.result) * expected * (1.0 - expected);
+            for &(parameter, count) in &sample.counts {
+                gradient[parameter] += slope * count;
+            }
+        }
+
+        self.steps += 1;
+        let moment_correction = 1.0 - BETA1.powi(self.steps);
+        let velocity_correction = 1.0 - BETA2.powi(self.steps);
+        for (parameter, g) in gradient.into_iter().enumerate() {
+            self.moment[parameter] = BETA1 * self.moment[parameter] + (1.0 - BETA1) * g;
+            self.velocity[parameter] = BETA2 * self.velocity[parameter] + (1.0 - BETA2) * g * g;
+            let moment = self.moment[parameter] / moment_correction;
+            let velocity = self.velocity[parameter] / velocity_correction;
+            self.weights[parameter] -= LEARNING_RATE * moment / (velocity.sqrt() + 1e-8);
+        }
+    }
+
+    /// The weights reached so far, rounded to whole centipawns
+    pub fn weights(&self) -> EvalWeights {
+        let mut weights = EvalWeights::default();
+        for (index, &piece_type) in PIECE_TYPES.iter().enumerate() {
+            *weights.values.get_mut(piece_type) = self.weights[index].round() as i32;
+            let table = weights.squares.get_mut(piece_type);
+            for (square, bonus) in table.iter_mut().enumerate() {
+                *bonus = self.weights[square_parameter(index, square)].round() as i32;
+            }
+        }
+        weights
+    }
+}
+
+fn square_parameter(piece_index: usize, square: usize) -> usize {
+    6 + piece_index * 64 + square
+}
+
+// The expected result for White of a position White scores `score`
+fn expected(score: f64, scale: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-scale * score / 400.0))
+}
+
+// The weights `position` counts, and its untuned terms, as the engine's
+// evaluation at the leaves has them but from White's side
+fn sample(position: &LabeledPosition) -> Option<Sample> {
+    let board = &position.board;
+    let mobility = MoveGenerator::generate_all_legal_moves(board).len();
+    if mobility == 0 {
+        return None;
+    }
+    let side = |color: Color| if color == Color::White { 1.0 } else { -1.0 };
+
+    let mut counts: Vec<(usize, f64)> = Vec::new();
+    let mut add = |parameter: usize, count: f64| match counts.iter_mut().find(|(p, _)| *p == parameter) {
+        Some((_, total)) => *total += count,
+        None => counts.push((parameter, count)),
+    };
+    for (pos, piece) in board.pieces() {
+        let index = PIECE_TYPES.iter().position(|&piece_type| piece_type == piece.piece_type).unwrap_or(0);
+        add(index, side(piece.color));
+        add(square_parameter(index, EvalWeights::square_index(pos, piece.color)), side(piece.color));
+    }
+    // Equal numbers of a piece on both sides cancel out
+    counts.retain(|&(_, count)| count != 0.0);
+
+    let mut fixed = mobility as i32 * MOBILITY_WEIGHT;
+    if MoveGenerator::is_in_check(board, board.current_player.opposite()) {
+        fixed += CHECK_BONUS;
+    }
+    Some(Sample { counts, fixed: side(board.current_player) * f64::from(fixed), result: position.result })
+}
@@ -0,0 +1,69 @@
+use super::magic::{bit, square, Bitboard};
+use super::pieces::{Color, Position};
+
+const KNIGHT_OFFSETS: [(i32, i32); 8] = [(-2, -1), (-2, 1), (-1, -2), (-1, 2), (1, -2), (1, 2), (2, -1), (2, 1)];
+const KING_OFFSETS: [(i32, i32); 8] = [(-1, -1), (-1, 0), (-1, 1), (0, -1), (0, 1), (1, -1), (1, 0), (1, 1)];
+// White pawns capture towards row 0, Black's towards row 7
+const WHITE_PAWN_OFFSETS: [(i32, i32); 2] = [(-1, -1), (-1, 1)];
+const BLACK_PAWN_OFFSETS: [(i32, i32); 2] = [(1, -1), (1, 1)];
+
+// The squares `offsets` away from each square, leaving out those off the
+// board. A const fn, so the tables are built at compile time.
+const fn leaper_table(offsets: &[(i32, i32)]) -> [Bitboard; 64] {
+    let mut table = [0; 64];
+    let mut square = 0;
+    while square < 64 {
+        let mut i = 0;
+        while i < offsets.len() {
+            let row = (square / 8) as i32 + offsets[i].0;
+            let col = (square % 8) as i32 + offsets[i].1;
+            if row >= 0 && row < 8 && col >= 0 && col < 8 {
+                table[square] |= 1 << (row * 8 + col);
+            }
+            i += 1;
+        }
+        square += 1;
+    }
+    table
+}
+
+static KNIGHT_ATTACKS: [Bitboard; 64] = leaper_table(&KNIGHT_OFFSETS);
+static KING_ATTACKS: [Bitboard; 64] = leaper_table(&KING_OFFSETS);
+static PAWN_ATTACKS: [[Bitboard; 64]; 2] = [leaper_table(&WHITE_PAWN_OFFSETS), leaper_table(&BLACK_PAWN_OFFSETS)];
+
+/// The squares a knight on `from` attacks
+pub fn knight_attacks(from: Position) -> Bitboard {
+    KNIGHT_ATTACKS[square(from)]
+}
+
+/// The squares a king on `from` attacks, not counting castling
+pub fn king_attacks(from: Position) -> Bitboard {
+    KING_ATTACKS[square(from)]
+}
+
+/// The squares strictly between `a` and `b` when they share a rank, file
+/// or diagonal, and none otherwise
+pub fn between(a: Position, b: Position) -> Bitboard {
+    let (rows, cols) = (b.row as i32 - a.row as i32, b.col as i32 - a.col as i32);
+    if a == b || (rows != 0 && cols != 0 && rows.abs() != cols.abs()) {
+        return 0;
+    }
+    let (row_dir, col_dir) = (rows.signum(), cols.signum());
+    let mut squares = 0;
+    let (mut row, mut col) = (a.row as i32 + row_dir, a.col as i32 + col_dir);
+    while (row, col) != (b.row as i32, b.col as i32) {
+        squares |= bit(Position::new(row as usize, col as usize));
+        row += row_dir;
+        col += col_dir;
+    }
+    squares
+}
+
+/// The squares a `color` pawn on `from` attacks, diagonally forward
+pub fn pawn_attacks(color: Color, from: Position) -> Bitboard {
+    let side = match color {
+        Color::White => 0,
+        Color::Black => 1,
+    };
+    PAWN_ATTACKS[side][square(from)]
+}
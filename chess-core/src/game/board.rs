@@ -1,9 +1,26 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::magic::{self, Bitboard};
 use super::pieces::{Color, Piece, PieceType, Position};
 use super::moves::{Move, MoveType};
+use super::zobrist;
+
+/// Number of halfmoves without a capture or pawn move that draws the game
+pub const FIFTY_MOVE_LIMIT: u32 = 100;
 
+/// A position, serialized as its FEN
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(into = "String", try_from = "String"))]
 pub struct Board {
-    pub squares: [[Option<Piece>; 8]; 8],
+    // The piece on each square, by row and column. Only `set_piece` writes
+    // to it, keeping the bitboards below in step.
+    squares: [[Option<Piece>; 8]; 8],
+    // The squares of each kind of piece, in `PieceType` order, and of each
+    // color's pieces, White's first
+    piece_bits: [Bitboard; 6],
+    color_bits: [Bitboard; 2],
     pub current_player: Color,
     pub en_passant_target: Option<Position>,
     pub castling_rights: CastlingRights,
@@ -12,6 +29,7 @@ pub struct Board {
 }
 
 #[derive(Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct CastlingRights {
     pub white_kingside: bool,
     pub white_queenside: bool,
@@ -28,48 +46,61 @@ impl CastlingRights {
             black_queenside: true,
         }
     }
+
+    pub fn none() -> Self {
+        CastlingRights {
+            white_kingside: false,
+            white_queenside: false,
+            black_kingside: false,
+            black_queenside: false,
+        }
+    }
+}
+
+impl Default for CastlingRights {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Board {
     pub fn new() -> Self {
-        let mut board = Board {
+        let mut board = Board { castling_rights: CastlingRights::new(), ..Board::empty() };
+        board.setup_initial_position();
+        board
+    }
+
+    /// A board with no pieces on it, White to move, without castling rights
+    pub fn empty() -> Self {
+        Board {
             squares: [[None; 8]; 8],
+            piece_bits: [0; 6],
+            color_bits: [0; 2],
             current_player: Color::White,
             en_passant_target: None,
-            castling_rights: CastlingRights::new(),
+            castling_rights: CastlingRights::none(),
             halfmove_clock: 0,
             fullmove_number: 1,
-        };
-        board.setup_initial_position();
-        board
+        }
     }
 
     fn setup_initial_position(&mut self) {
-        // Place pawns
-        for col in 0..8 {
-            self.squares[1][col] = Some(Piece::new(PieceType::Pawn, Color::Black));
-            self.squares[6][col] = Some(Piece::new(PieceType::Pawn, Color::White));
+        const BACK_RANK: [PieceType; 8] = [
+            PieceType::Rook,
+            PieceType::Knight,
+            PieceType::Bishop,
+            PieceType::Queen,
+            PieceType::King,
+            PieceType::Bishop,
+            PieceType::Knight,
+            PieceType::Rook,
+        ];
+        for (col, piece_type) in BACK_RANK.into_iter().enumerate() {
+            self.set_piece(Position::new(0, col), Some(Piece::new(piece_type, Color::Black)));
+            self.set_piece(Position::new(1, col), Some(Piece::new(PieceType::Pawn, Color::Black)));
+            self.set_piece(Position::new(6, col), Some(Piece::new(PieceType::Pawn, Color::White)));
+            self.set_piece(Position::new(7, col), Some(Piece::new(piece_type, Color::White)));
         }
-
-        // Place black pieces
-        self.squares[0][0] = Some(Piece::new(PieceType::Rook, Color::Black));
-        self.squares[0][1] = Some(Piece::new(PieceType::Knight, Color::Black));
-        self.squares[0][2] = Some(Piece::new(PieceType::Bishop, Color::Black));
-        self.squares[0][3] = Some(Piece::new(PieceType::Queen, Color::Black));
-        self.squares[0][4] = Some(Piece::new(PieceType::King, Color::Black));
-        self.squares[0][5] = Some(Piece::new(PieceType::Bishop, Color::Black));
-        self.squares[0][6] = Some(Piece::new(PieceType::Knight, Color::Black));
-        self.squares[0][7] = Some(Piece::new(PieceType::Rook, Color::Black));
-
-        // Place white pieces
-        self.squares[7][0] = Some(Piece::new(PieceType::Rook, Color::White));
-        self.squares[7][1] = Some(Piece::new(PieceType::Knight, Color::White));
-        self.squares[7][2] = Some(Piece::new(PieceType::Bishop, Color::White));
-        self.squares[7][3] = Some(Piece::new(PieceType::Queen, Color::White));
-        self.squares[7][4] = Some(Piece::new(PieceType::King, Color::White));
-        self.squares[7][5] = Some(Piece::new(PieceType::Bishop, Color::White));
-        self.squares[7][6] = Some(Piece::new(PieceType::Knight, Color::White));
-        self.squares[7][7] = Some(Piece::new(PieceType::Rook, Color::White));
     }
 
     pub fn get_piece(&self, pos: Position) -> Option<Piece> {
@@ -81,9 +112,34 @@ impl Board {
     }
 
     pub fn set_piece(&mut self, pos: Position, piece: Option<Piece>) {
-        if pos.is_valid() {
-            self.squares[pos.row][pos.col] = piece;
+        if !pos.is_valid() {
+            return;
+        }
+        let bit = magic::bit(pos);
+        if let Some(old) = self.squares[pos.row][pos.col] {
+            self.piece_bits[old.piece_type as usize] &= !bit;
+            self.color_bits[old.color as usize] &= !bit;
         }
+        if let Some(new) = piece {
+            self.piece_bits[new.piece_type as usize] |= bit;
+            self.color_bits[new.color as usize] |= bit;
+        }
+        self.squares[pos.row][pos.col] = piece;
+    }
+
+    /// The squares with a piece on them
+    pub fn occupancy(&self) -> Bitboard {
+        self.color_bits[0] | self.color_bits[1]
+    }
+
+    /// The squares with a piece of `color` on them
+    pub fn color_occupancy(&self, color: Color) -> Bitboard {
+        self.color_bits[color as usize]
+    }
+
+    /// The squares with a `color` piece of `piece_type` on them
+    pub fn piece_occupancy(&self, piece_type: PieceType, color: Color) -> Bitboard {
+        self.piece_bits[piece_type as usize] & self.color_bits[color as usize]
     }
 
     pub fn make_move(&mut self, mv: &Move) -> bool {
@@ -169,6 +225,17 @@ impl Board {
             }
         }
 
+        // Capturing a rook on its home square also removes that castling right
+        if mv.captured.is_some_and(|captured| captured.piece_type == PieceType::Rook) {
+            match (mv.to.row, mv.to.col) {
+                (7, 0) => self.castling_rights.white_queenside = false,
+                (7, 7) => self.castling_rights.white_kingside = false,
+                (0, 0) => self.castling_rights.black_queenside = false,
+                (0, 7) => self.castling_rights.black_kingside = false,
+                _ => {}
+            }
+        }
+
         // Update move counters
         if self.current_player == Color::Black {
             self.fullmove_number += 1;
@@ -180,31 +247,39 @@ impl Board {
         true
     }
 
+    pub fn zobrist_hash(&self) -> u64 {
+        zobrist::hash(self)
+    }
+
+    pub fn is_fifty_move_draw(&self) -> bool {
+        self.halfmove_clock >= FIFTY_MOVE_LIMIT
+    }
+
+    /// Iterates over all 64 squares, rank 8 to rank 1, with their occupants
+    pub fn squares(&self) -> impl Iterator<Item = (Position, Option<Piece>)> + '_ {
+        (0..64).map(move |index| {
+            let pos = Position::new(index / 8, index % 8);
+            (pos, self.squares[pos.row][pos.col])
+        })
+    }
+
+    /// Iterates over the occupied squares and the pieces standing on them,
+    /// rank 8 to rank 1
+    pub fn pieces(&self) -> impl Iterator<Item = (Position, Piece)> + '_ {
+        self.pieces_on(self.occupancy())
+    }
+
+    // The pieces on the `bits` squares, which must all be occupied
+    fn pieces_on(&self, bits: Bitboard) -> impl Iterator<Item = (Position, Piece)> + '_ {
+        magic::squares(bits).filter_map(move |pos| self.squares[pos.row][pos.col].map(|piece| (pos, piece)))
+    }
+
     pub fn find_king(&self, color: Color) -> Option<Position> {
-        for row in 0..8 {
-            for col in 0..8 {
-                if let Some(piece) = self.squares[row][col] {
-                    if piece.color == color && piece.piece_type == PieceType::King {
-                        return Some(Position::new(row, col));
-                    }
-                }
-            }
-        }
-        None
+        magic::squares(self.piece_occupancy(PieceType::King, color)).next()
     }
 
     pub fn get_all_pieces(&self, color: Color) -> Vec<(Position, Piece)> {
-        let mut pieces = Vec::new();
-        for row in 0..8 {
-            for col in 0..8 {
-                if let Some(piece) = self.squares[row][col] {
-                    if piece.color == color {
-                        pieces.push((Position::new(row, col), piece));
-                    }
-                }
-            }
-        }
-        pieces
+        self.pieces_on(self.color_occupancy(color)).collect()
     }
 }
 
@@ -0,0 +1,220 @@
+use std::fmt;
+use std::str::FromStr;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::pieces::Color;
+
+/// A clock under this many milliseconds is running low
+pub const LOW_TIME_MS: u64 = 20_000;
+
+/// How the per-move time of a time control is given
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum ClockMode {
+    /// Fischer increment: added after every move
+    #[default]
+    Increment,
+    /// Simple (US) delay: the clock waits this long each move before running
+    Delay,
+    /// Bronstein delay: time used is given back after the move, up to this much
+    Bronstein,
+}
+
+impl ClockMode {
+    pub fn next(self) -> ClockMode {
+        match self {
+            ClockMode::Increment => ClockMode::Delay,
+            ClockMode::Delay => ClockMode::Bronstein,
+            ClockMode::Bronstein => ClockMode::Increment,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ClockMode::Increment => "increment",
+            ClockMode::Delay => "delay",
+            ClockMode::Bronstein => "Bronstein delay",
+        }
+    }
+}
+
+/// Starting time for each side and the time each move earns
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TimeControl {
+    pub initial_ms: u64,
+    /// The increment, or the delay in the delay modes
+    pub increment_ms: u64,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub mode: ClockMode,
+}
+
+impl TimeControl {
+    pub const fn new(minutes: u64, increment_seconds: u64) -> Self {
+        TimeControl {
+            initial_ms: minutes * 60_000,
+            increment_ms: increment_seconds * 1000,
+            mode: ClockMode::Increment,
+        }
+    }
+
+    pub const fn delay(minutes: u64, delay_seconds: u64) -> Self {
+        TimeControl { mode: ClockMode::Delay, ..TimeControl::new(minutes, delay_seconds) }
+    }
+
+    pub const fn bronstein(minutes: u64, delay_seconds: u64) -> Self {
+        TimeControl { mode: ClockMode::Bronstein, ..TimeControl::new(minutes, delay_seconds) }
+    }
+
+    /// Speed category by estimated game length (initial time plus 40
+    /// increments), with the same boundaries as Lichess. A delay counts
+    /// like an increment, since it's at most that much per move.
+    pub fn category(&self) -> &'static str {
+        let estimate_ms = self.initial_ms + 40 * self.increment_ms;
+        match estimate_ms / 1000 {
+            0..180 => "Bullet",
+            180..480 => "Blitz",
+            480..1500 => "Rapid",
+            _ => "Classical",
+        }
+    }
+}
+
+impl fmt::Display for TimeControl {
+    /// The usual "minutes+seconds" notation, e.g. "3+2", with "d" or "b"
+    /// for delays: "5 d3", "5 b3"
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let minutes = self.initial_ms / 60_000;
+        let seconds = self.increment_ms / 1000;
+        match self.mode {
+            ClockMode::Increment => write!(f, "{}+{}", minutes, seconds),
+            ClockMode::Delay => write!(f, "{} d{}", minutes, seconds),
+            ClockMode::Bronstein => write!(f, "{} b{}", minutes, seconds),
+        }
+    }
+}
+
+/// Text that isn't a time control in the notation `Display` writes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimeControlError(pub String);
+
+impl fmt::Display for TimeControlError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid time control \"{}\" (expected e.g. 3+2, or 5 d3 / 5 b3 for delays)", self.0)
+    }
+}
+
+impl std::error::Error for TimeControlError {}
+
+impl FromStr for TimeControl {
+    type Err = TimeControlError;
+
+    /// Parses "3+2", "5 d3" or "5 b3"
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let error = || TimeControlError(text.to_string());
+        let (minutes, mode, seconds) = match text.trim().split_once('+') {
+            Some((minutes, seconds)) => (minutes, ClockMode::Increment, seconds),
+            None => {
+                let (minutes, delay) = text.trim().split_once(' ').ok_or_else(error)?;
+                let delay = delay.trim();
+                match delay.get(..1) {
+                    Some("d") => (minutes, ClockMode::Delay, &delay[1..]),
+                    Some("b") => (minutes, ClockMode::Bronstein, &delay[1..]),
+                    _ => return Err(error()),
+                }
+            }
+        };
+        let minutes: u64 = minutes.trim().parse().map_err(|_| error())?;
+        let seconds: u64 = seconds.trim().parse().map_err(|_| error())?;
+        if minutes == 0 {
+            return Err(error());
+        }
+        Ok(TimeControl { mode, ..TimeControl::new(minutes, seconds) })
+    }
+}
+
+/// Remaining time for both sides of a timed game. The clock doesn't measure
+/// time itself; callers charge it with the time a side spent thinking.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Clock {
+    pub control: TimeControl,
+    pub white_ms: u64,
+    pub black_ms: u64,
+    /// Time spent so far on the move being played, for the delay modes
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub turn_ms: u64,
+}
+
+impl Clock {
+    pub fn new(control: TimeControl) -> Self {
+        Clock {
+            control,
+            white_ms: control.initial_ms,
+            black_ms: control.initial_ms,
+            turn_ms: 0,
+        }
+    }
+
+    pub fn remaining(&self, color: Color) -> u64 {
+        match color {
+            Color::White => self.white_ms,
+            Color::Black => self.black_ms,
+        }
+    }
+
+    /// Gives one side a different amount of time, e.g. for time odds
+    pub fn set_remaining(&mut self, color: Color, millis: u64) {
+        *self.remaining_mut(color) = millis;
+    }
+
+    fn remaining_mut(&mut self, color: Color) -> &mut u64 {
+        match color {
+            Color::White => &mut self.white_ms,
+            Color::Black => &mut self.black_ms,
+        }
+    }
+
+    /// Charges `color`, the side to move, for `elapsed_ms` more of its
+    /// move. Returns true if its flag fell.
+    pub fn charge(&mut self, color: Color, elapsed_ms: u64) -> bool {
+        let spent = self.turn_ms;
+        self.turn_ms += elapsed_ms;
+        let charged = match self.control.mode {
+            ClockMode::Delay => {
+                let delay = self.control.increment_ms;
+                self.turn_ms.saturating_sub(delay) - spent.saturating_sub(delay)
+            }
+            ClockMode::Increment | ClockMode::Bronstein => elapsed_ms,
+        };
+        let remaining = self.remaining_mut(color);
+        *remaining = remaining.saturating_sub(charged);
+        *remaining == 0
+    }
+
+    /// Credits `color` for the move it just completed and starts a new move
+    pub fn complete_move(&mut self, color: Color) {
+        let credit = match self.control.mode {
+            ClockMode::Increment => self.control.increment_ms,
+            ClockMode::Delay => 0,
+            ClockMode::Bronstein => self.turn_ms.min(self.control.increment_ms),
+        };
+        *self.remaining_mut(color) += credit;
+        self.turn_ms = 0;
+    }
+
+    /// What's left of the delay on the move being played, in the simple delay mode
+    pub fn delay_remaining(&self) -> u64 {
+        match self.control.mode {
+            ClockMode::Delay => self.control.increment_ms.saturating_sub(self.turn_ms),
+            ClockMode::Increment | ClockMode::Bronstein => 0,
+        }
+    }
+
+    pub fn is_flagged(&self, color: Color) -> bool {
+        self.remaining(color) == 0
+    }
+}
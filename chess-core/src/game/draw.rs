@@ -0,0 +1,145 @@
+use super::board::{Board, FIFTY_MOVE_LIMIT};
+use super::moves::Move;
+
+/// Half-moves without a capture or pawn move after which the game is drawn
+/// without a claim (FIDE Article 9.6.2)
+pub const SEVENTY_FIVE_MOVE_LIMIT: u32 = 150;
+
+/// A draw the player to move may claim (FIDE Article 9.2 and 9.3)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DrawClaim {
+    /// The position has occurred three times
+    ThreefoldRepetition,
+    /// Fifty moves by each side without a capture or pawn move
+    FiftyMoves,
+}
+
+impl DrawClaim {
+    pub fn label(self) -> &'static str {
+        match self {
+            DrawClaim::ThreefoldRepetition => "threefold repetition",
+            DrawClaim::FiftyMoves => "the fifty-move rule",
+        }
+    }
+}
+
+/// A draw that ends the game without anyone claiming it (FIDE Article 9.6)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AutomaticDraw {
+    FivefoldRepetition,
+    SeventyFiveMoves,
+}
+
+/// How many times the position after `moves` from `initial` has occurred,
+/// itself included. Positions count as the same with the same pieces on
+/// the same squares, the same side to move and the same castling and en
+/// passant captures possible.
+pub fn repetitions(initial: &Board, moves: &[Move]) -> usize {
+    let keys = position_keys(initial, moves);
+    let last = keys[keys.len() - 1];
+    keys.iter().filter(|&&key| key == last).count()
+}
+
+/// The Zobrist keys of the positions after `moves` from `initial` that can
+/// still come back, oldest first and ending with the current one: those
+/// since the last capture or pawn move
+pub fn position_keys(initial: &Board, moves: &[Move]) -> Vec<u64> {
+    let mut board = initial.clone();
+    let mut keys = vec![board.zobrist_hash()];
+    for mv in moves {
+        board.make_move(mv);
+        // A capture or pawn move means nothing before it can come back
+        if board.halfmove_clock == 0 {
+            keys.clear();
+        }
+        keys.push(board.zobrist_hash());
+    }
+    keys
+}
+
+/// The draw the side to move can claim after `moves` from `initial`, if any
+pub fn claimable(initial: &Board, moves: &[Move]) -> Option<DrawClaim> {
+    if repetitions(initial, moves) >= 3 {
+        return Some(DrawClaim::ThreefoldRepetition);
+    }
+    let mut board = initial.clone();
+    for mv in moves {
+        board.make_move(mv);
+    }
+    (board.halfmove_clock >= FIFTY_MOVE_LIMIT).then_some(DrawClaim::FiftyMoves)
+}
+
+/// Whether the game is already drawn after `moves` from `initial`, claim
+/// or no claim
+pub fn automatic(initial: &Board, moves: &[Move]) -> Option<AutomaticDraw> {
+    if repetitions(initial, moves) >= 5 {
+        return Some(AutomaticDraw::FivefoldRepetition);
+    }
+    let mut board = initial.clone();
+    for mv in moves {
+        board.make_move(mv);
+    }
+    (board.halfmove_clock >= SEVENTY_FIVE_MOVE_LIMIT).then_some(AutomaticDraw::SeventyFiveMoves)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::MoveGenerator;
+
+    // The moves in UCI coordinates played from `initial`
+    fn play(initial: &Board, ucis: &str) -> Vec<Move> {
+        let mut board = initial.clone();
+        ucis.split_whitespace()
+            .map(|uci| {
+                let mv = MoveGenerator::find_uci_move(&board, uci).unwrap();
+                board.make_move(&mv);
+                mv
+            })
+            .collect()
+    }
+
+    const KNIGHT_SHUFFLE: &str = "g1f3 g8f6 f3g1 f6g8";
+
+    #[test]
+    fn third_repetition_can_be_claimed() {
+        let initial = Board::new();
+        let moves = play(&initial, &[KNIGHT_SHUFFLE; 2].join(" "));
+        assert_eq!(repetitions(&initial, &moves), 3);
+        assert_eq!(claimable(&initial, &moves), Some(DrawClaim::ThreefoldRepetition));
+        assert_eq!(claimable(&initial, &moves[..7]), None);
+        assert_eq!(automatic(&initial, &moves), None);
+    }
+
+    #[test]
+    fn fifth_repetition_draws_without_a_claim() {
+        let initial = Board::new();
+        let moves = play(&initial, &[KNIGHT_SHUFFLE; 4].join(" "));
+        assert_eq!(repetitions(&initial, &moves), 5);
+        assert_eq!(automatic(&initial, &moves), Some(AutomaticDraw::FivefoldRepetition));
+        assert_eq!(automatic(&initial, &moves[..15]), None);
+    }
+
+    #[test]
+    fn lost_castling_rights_make_a_different_position() {
+        let initial = Board::from_fen("4k3/8/8/8/8/8/8/R3K3 w Q - 0 1").unwrap();
+        let moves = play(&initial, "e1e2 e8e7 e2e1 e7e8");
+        assert_eq!(repetitions(&initial, &moves), 1);
+        let moves = play(&initial, "e1e2 e8e7 e2e1 e7e8 e1e2 e8e7 e2e1 e7e8");
+        assert_eq!(repetitions(&initial, &moves), 2);
+    }
+
+    #[test]
+    fn fifty_and_seventy_five_move_rules() {
+        let fifty = Board::from_fen("4k3/8/8/8/8/8/4P3/R3K3 w - - 99 80").unwrap();
+        assert_eq!(claimable(&fifty, &[]), None);
+        assert_eq!(claimable(&fifty, &play(&fifty, "a1a2")), Some(DrawClaim::FiftyMoves));
+        assert_eq!(automatic(&fifty, &play(&fifty, "a1a2")), None);
+        // A pawn move starts the count again
+        assert_eq!(claimable(&fifty, &play(&fifty, "e2e3")), None);
+
+        let seventy_five = Board::from_fen("4k3/8/8/8/8/8/4P3/R3K3 w - - 149 105").unwrap();
+        assert_eq!(automatic(&seventy_five, &[]), None);
+        assert_eq!(automatic(&seventy_five, &play(&seventy_five, "a1a2")), Some(AutomaticDraw::SeventyFiveMoves));
+    }
+}
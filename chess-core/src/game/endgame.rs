@@ -0,0 +1,68 @@
+use super::board::Board;
+use super::pieces::Color;
+
+/// What the player has to make of an endgame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EndgameGoal {
+    /// Checkmate the other side
+    Win,
+    /// Reach a draw by the rules: stalemate, a lack of mating material or
+    /// the fifty-move rule
+    Draw,
+}
+
+/// A classic endgame position to practise against the engine
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Endgame {
+    pub name: &'static str,
+    pub fen: &'static str,
+    /// The side the player takes
+    pub player: Color,
+    pub goal: EndgameGoal,
+}
+
+impl Endgame {
+    pub fn board(&self) -> Board {
+        Board::from_fen(self.fen).expect("endgame positions are valid")
+    }
+}
+
+/// The endgames to practise, simplest first
+pub const ENDGAMES: &[Endgame] = &[
+    Endgame {
+        name: "King and queen against king",
+        fen: "8/8/8/4k3/8/8/8/3QK3 w - - 0 1",
+        player: Color::White,
+        goal: EndgameGoal::Win,
+    },
+    Endgame {
+        name: "King and rook against king",
+        fen: "8/8/8/4k3/8/8/8/4K2R w - - 0 1",
+        player: Color::White,
+        goal: EndgameGoal::Win,
+    },
+    Endgame {
+        name: "King and pawn: the king in front",
+        fen: "4k3/8/4K3/4P3/8/8/8/8 w - - 0 1",
+        player: Color::White,
+        goal: EndgameGoal::Win,
+    },
+    Endgame {
+        name: "King and pawn: the rule of the square",
+        fen: "8/8/8/8/6k1/8/P7/7K w - - 0 1",
+        player: Color::White,
+        goal: EndgameGoal::Win,
+    },
+    Endgame {
+        name: "Lucena position",
+        fen: "1K1k4/1P6/8/8/8/8/r7/2R5 w - - 0 1",
+        player: Color::White,
+        goal: EndgameGoal::Win,
+    },
+    Endgame {
+        name: "Philidor position",
+        fen: "4k3/R7/6r1/3KP3/8/8/8/8 w - - 0 1",
+        player: Color::Black,
+        goal: EndgameGoal::Draw,
+    },
+];
@@ -0,0 +1,149 @@
+use std::fmt;
+
+use super::board::Board;
+use super::fen::FenError;
+use super::moves::Move;
+use super::san::{self, SanError};
+
+/// One line of an Extended Position Description file: a position and its
+/// operations, e.g. `bm Qxf7+; id "WAC.001";`
+#[derive(Clone)]
+pub struct EpdRecord {
+    pub board: Board,
+    pub operations: Vec<(String, Vec<String>)>,
+}
+
+impl EpdRecord {
+    pub fn operands(&self, opcode: &str) -> Option<&[String]> {
+        self.operations
+            .iter()
+            .find(|(name, _)| name == opcode)
+            .map(|(_, operands)| operands.as_slice())
+    }
+
+    pub fn id(&self) -> Option<&str> {
+        self.operands("id")?.first().map(String::as_str)
+    }
+
+    /// Moves listed under `bm`, the best moves for the side to move
+    pub fn best_moves(&self) -> Result<Vec<Move>, SanError> {
+        self.moves("bm")
+    }
+
+    /// Moves listed under `am`, which the side to move should avoid
+    pub fn avoid_moves(&self) -> Result<Vec<Move>, SanError> {
+        self.moves("am")
+    }
+
+    fn moves(&self, opcode: &str) -> Result<Vec<Move>, SanError> {
+        self.operands(opcode)
+            .unwrap_or_default()
+            .iter()
+            .map(|text| san::parse_san(&self.board, text))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EpdError {
+    InvalidFen { line: usize, error: FenError },
+    UnterminatedString { line: usize },
+}
+
+impl fmt::Display for EpdError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EpdError::InvalidFen { line, error } => write!(f, "line {}: {}", line, error),
+            EpdError::UnterminatedString { line } => write!(f, "line {}: unterminated string", line),
+        }
+    }
+}
+
+impl std::error::Error for EpdError {}
+
+/// Splits operations into words, keeping quoted strings (which may contain
+/// spaces and semicolons) whole. `None` marks the end of an operation.
+fn split_operations(text: &str) -> Option<Vec<Option<String>>> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            ';' => tokens.push(None),
+            '"' => {
+                let mut word = String::new();
+                loop {
+                    match chars.next()? {
+                        '\\' => word.extend(chars.next()),
+                        '"' => break,
+                        c => word.push(c),
+                    }
+                }
+                tokens.push(Some(word));
+            }
+            c if c.is_whitespace() => {}
+            c => {
+                let mut word = c.to_string();
+                while let Some(c) = chars.next_if(|&c| !c.is_whitespace() && c != ';') {
+                    word.push(c);
+                }
+                tokens.push(Some(word));
+            }
+        }
+    }
+
+    Some(tokens)
+}
+
+fn parse_line(line: &str, number: usize) -> Result<EpdRecord, EpdError> {
+    // The first four fields are the FEN without its move counters
+    let mut rest = line;
+    let mut position = Vec::new();
+    for _ in 0..4 {
+        rest = rest.trim_start();
+        let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        position.push(&rest[..end]);
+        rest = &rest[end..];
+    }
+    let board = Board::from_fen(&position.join(" "))
+        .map_err(|error| EpdError::InvalidFen { line: number, error })?;
+
+    let tokens = split_operations(rest).ok_or(EpdError::UnterminatedString { line: number })?;
+
+    let mut operations = Vec::new();
+    let mut current: Vec<String> = Vec::new();
+    for token in tokens {
+        match token {
+            Some(word) => current.push(word),
+            None if !current.is_empty() => {
+                let opcode = current.remove(0);
+                operations.push((opcode, std::mem::take(&mut current)));
+            }
+            None => {}
+        }
+    }
+    if !current.is_empty() {
+        let opcode = current.remove(0);
+        operations.push((opcode, current));
+    }
+
+    let mut record = EpdRecord { board, operations };
+    // Apply the optional move counters so they show up in FEN output
+    if let Some(clock) = record.operands("hmvc").and_then(|ops| ops.first()?.parse().ok()) {
+        record.board.halfmove_clock = clock;
+    }
+    if let Some(number) = record.operands("fmvn").and_then(|ops| ops.first()?.parse().ok()) {
+        record.board.fullmove_number = number;
+    }
+    Ok(record)
+}
+
+/// Parses an EPD file, skipping blank lines and `#` comments
+pub fn parse_epd(text: &str) -> Result<Vec<EpdRecord>, EpdError> {
+    text.lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(number, line)| parse_line(line, number))
+        .collect()
+}
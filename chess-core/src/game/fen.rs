@@ -0,0 +1,331 @@
+use std::fmt;
+
+use super::board::{Board, CastlingRights};
+use super::pieces::{Color, Piece, PieceType, Position};
+use super::rules::MoveGenerator;
+
+pub const STARTING_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FenError {
+    WrongFieldCount,
+    InvalidPlacement,
+    InvalidSideToMove,
+    InvalidCastling,
+    InvalidEnPassant,
+    InvalidCounter,
+    MissingKing,
+    PawnOnBackRank,
+    OpponentInCheck,
+}
+
+impl fmt::Display for FenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let message = match self {
+            FenError::WrongFieldCount => "FEN must have 4 to 6 space-separated fields",
+            FenError::InvalidPlacement => "invalid piece placement",
+            FenError::InvalidSideToMove => "side to move must be 'w' or 'b'",
+            FenError::InvalidCastling => "invalid castling rights",
+            FenError::InvalidEnPassant => "invalid en passant square",
+            FenError::InvalidCounter => "invalid halfmove clock or fullmove number",
+            FenError::MissingKing => "each side must have exactly one king",
+            FenError::PawnOnBackRank => "pawns can't stand on the first or eighth rank",
+            FenError::OpponentInCheck => "the side not to move is in check",
+        };
+        write!(f, "{}", message)
+    }
+}
+
+impl std::error::Error for FenError {}
+
+fn piece_from_char(c: char) -> Option<Piece> {
+    let color = if c.is_ascii_uppercase() { Color::White } else { Color::Black };
+    let piece_type = match c.to_ascii_lowercase() {
+        'p' => PieceType::Pawn,
+        'n' => PieceType::Knight,
+        'b' => PieceType::Bishop,
+        'r' => PieceType::Rook,
+        'q' => PieceType::Queen,
+        'k' => PieceType::King,
+        _ => return None,
+    };
+    Some(Piece::new(piece_type, color))
+}
+
+fn piece_to_char(piece: Piece) -> char {
+    let c = match piece.piece_type {
+        PieceType::Pawn => 'p',
+        PieceType::Knight => 'n',
+        PieceType::Bishop => 'b',
+        PieceType::Rook => 'r',
+        PieceType::Queen => 'q',
+        PieceType::King => 'k',
+    };
+    match piece.color {
+        Color::White => c.to_ascii_uppercase(),
+        Color::Black => c,
+    }
+}
+
+impl Board {
+    /// Parses a position in Forsyth-Edwards Notation. The halfmove clock and
+    /// fullmove number may be omitted, as they are in EPD records. Positions
+    /// that can't arise in a game are refused, except that castling rights
+    /// whose king or rook has left its square are dropped.
+    pub fn from_fen(fen: &str) -> Result<Board, FenError> {
+        let fields: Vec<&str> = fen.split_whitespace().collect();
+        if fields.len() < 4 || fields.len() > 6 {
+            return Err(FenError::WrongFieldCount);
+        }
+
+        let mut board = Board::empty();
+        let ranks: Vec<&str> = fields[0].split('/').collect();
+        if ranks.len() != 8 {
+            return Err(FenError::InvalidPlacement);
+        }
+        for (row, rank) in ranks.iter().enumerate() {
+            let mut col = 0;
+            for c in rank.chars() {
+                if let Some(skip) = c.to_digit(10) {
+                    col += skip as usize;
+                } else {
+                    let piece = piece_from_char(c).ok_or(FenError::InvalidPlacement)?;
+                    if col >= 8 {
+                        return Err(FenError::InvalidPlacement);
+                    }
+                    board.set_piece(Position::new(row, col), Some(piece));
+                    col += 1;
+                }
+                if col > 8 {
+                    return Err(FenError::InvalidPlacement);
+                }
+            }
+            if col != 8 {
+                return Err(FenError::InvalidPlacement);
+            }
+        }
+
+        let current_player = match fields[1] {
+            "w" => Color::White,
+            "b" => Color::Black,
+            _ => return Err(FenError::InvalidSideToMove),
+        };
+
+        let mut castling_rights = CastlingRights::none();
+        if fields[2] != "-" {
+            for c in fields[2].chars() {
+                match c {
+                    'K' => castling_rights.white_kingside = true,
+                    'Q' => castling_rights.white_queenside = true,
+                    'k' => castling_rights.black_kingside = true,
+                    'q' => castling_rights.black_queenside = true,
+                    _ => return Err(FenError::InvalidCastling),
+                }
+            }
+        }
+
+        let en_passant_target = match fields[3] {
+            "-" => None,
+            square => {
+                let pos = Position::from_algebraic(square).ok_or(FenError::InvalidEnPassant)?;
+                // Behind a pawn that just moved two squares: the sixth
+                // rank when White is to move, the third when Black is
+                let row = if current_player == Color::White { 2 } else { 5 };
+                if pos.row != row {
+                    return Err(FenError::InvalidEnPassant);
+                }
+                Some(pos)
+            }
+        };
+
+        let halfmove_clock = match fields.get(4) {
+            Some(field) => field.parse().map_err(|_| FenError::InvalidCounter)?,
+            None => 0,
+        };
+        let fullmove_number = match fields.get(5) {
+            Some(field) => field.parse().map_err(|_| FenError::InvalidCounter)?,
+            None => 1,
+        };
+
+        board.current_player = current_player;
+        board.en_passant_target = en_passant_target;
+        board.castling_rights = castling_rights;
+        board.halfmove_clock = halfmove_clock;
+        board.fullmove_number = fullmove_number;
+
+        for color in [Color::White, Color::Black] {
+            let kings = board
+                .pieces()
+                .filter(|(_, piece)| piece.color == color && piece.piece_type == PieceType::King)
+                .count();
+            if kings != 1 {
+                return Err(FenError::MissingKing);
+            }
+        }
+        if board.pieces().any(|(pos, piece)| piece.piece_type == PieceType::Pawn && (pos.row == 0 || pos.row == 7)) {
+            return Err(FenError::PawnOnBackRank);
+        }
+        if MoveGenerator::is_in_check(&board, current_player.opposite()) {
+            return Err(FenError::OpponentInCheck);
+        }
+
+        // A right to castle needs the king and that rook on their squares
+        let home = |row, col, piece_type, color| {
+            board.get_piece(Position::new(row, col)) == Some(Piece::new(piece_type, color))
+        };
+        let white_king = home(7, 4, PieceType::King, Color::White);
+        let black_king = home(0, 4, PieceType::King, Color::Black);
+        let possible = CastlingRights {
+            white_kingside: white_king && home(7, 7, PieceType::Rook, Color::White),
+            white_queenside: white_king && home(7, 0, PieceType::Rook, Color::White),
+            black_kingside: black_king && home(0, 7, PieceType::Rook, Color::Black),
+            black_queenside: black_king && home(0, 0, PieceType::Rook, Color::Black),
+        };
+        let rights = &mut board.castling_rights;
+        rights.white_kingside &= possible.white_kingside;
+        rights.white_queenside &= possible.white_queenside;
+        rights.black_kingside &= possible.black_kingside;
+        rights.black_queenside &= possible.black_queenside;
+
+        Ok(board)
+    }
+
+    pub fn to_fen(&self) -> String {
+        let mut placement = String::new();
+        for row in 0..8 {
+            let mut empty = 0;
+            for col in 0..8 {
+                match self.get_piece(Position::new(row, col)) {
+                    Some(piece) => {
+                        if empty > 0 {
+                            placement.push_str(&empty.to_string());
+                            empty = 0;
+                        }
+                        placement.push(piece_to_char(piece));
+                    }
+                    None => empty += 1,
+                }
+            }
+            if empty > 0 {
+                placement.push_str(&empty.to_string());
+            }
+            if row < 7 {
+                placement.push('/');
+            }
+        }
+
+        let side = match self.current_player {
+            Color::White => "w",
+            Color::Black => "b",
+        };
+
+        let mut castling = String::new();
+        if self.castling_rights.white_kingside {
+            castling.push('K');
+        }
+        if self.castling_rights.white_queenside {
+            castling.push('Q');
+        }
+        if self.castling_rights.black_kingside {
+            castling.push('k');
+        }
+        if self.castling_rights.black_queenside {
+            castling.push('q');
+        }
+        if castling.is_empty() {
+            castling.push('-');
+        }
+
+        let en_passant = self
+            .en_passant_target
+            .map_or_else(|| "-".to_string(), |pos| pos.to_algebraic());
+
+        format!(
+            "{} {} {} {} {} {}",
+            placement, side, castling, en_passant, self.halfmove_clock, self.fullmove_number
+        )
+    }
+}
+
+// Boards are serialized as their FEN
+#[cfg(feature = "serde")]
+impl From<Board> for String {
+    fn from(board: Board) -> String {
+        board.to_fen()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<String> for Board {
+    type Error = FenError;
+
+    fn try_from(fen: String) -> Result<Board, FenError> {
+        Board::from_fen(&fen)
+    }
+}
+
+/// A text diagram of the position, White at the bottom, followed by the
+/// FEN fields after the placement:
+///
+/// ```text
+/// 8  r n b q k b n r
+/// ...
+/// 1  R N B Q K B N R
+///    a b c d e f g h
+/// w KQkq - 0 1
+/// ```
+impl fmt::Display for Board {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for row in 0..8 {
+            write!(f, "{} ", 8 - row)?;
+            for col in 0..8 {
+                write!(f, " {}", self.get_piece(Position::new(row, col)).map_or('.', piece_to_char))?;
+            }
+            writeln!(f)?;
+        }
+        writeln!(f, "   a b c d e f g h")?;
+        let fen = self.to_fen();
+        write!(f, "{}", fen.split_once(' ').map_or("", |(_, fields)| fields))
+    }
+}
+
+// The diagram too, on lines of its own, so that a board in a failed
+// assertion can be read at a glance
+impl fmt::Debug for Board {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f)?;
+        fmt::Display::fmt(self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_the_starting_position() {
+        assert_eq!(Board::from_fen(STARTING_FEN).unwrap().to_fen(), STARTING_FEN);
+    }
+
+    #[test]
+    fn drops_castling_rights_without_their_king_or_rook() {
+        let board = Board::from_fen("4k3/8/8/8/8/8/8/4K3 w KQkq - 0 1").unwrap();
+        assert_eq!(board.to_fen(), "4k3/8/8/8/8/8/8/4K3 w - - 0 1");
+        assert_eq!(MoveGenerator::generate_all_legal_moves(&board).len(), 5);
+
+        // Only the rights whose rook is still home are kept
+        let board = Board::from_fen("r3k3/8/8/8/8/8/8/4K2R w KQkq - 0 1").unwrap();
+        assert_eq!(board.to_fen(), "r3k3/8/8/8/8/8/8/4K2R w Kq - 0 1");
+    }
+
+    #[test]
+    fn refuses_positions_that_cant_arise() {
+        let error = |fen| Board::from_fen(fen).unwrap_err();
+        assert_eq!(error("4k3/8/8/8/8/8/8/4R1K1 w - - 0 1"), FenError::OpponentInCheck);
+        assert_eq!(error("4k3/8/8/8/4P3/8/8/4K3 w - e3 0 1"), FenError::InvalidEnPassant);
+        assert_eq!(error("4k3/8/8/8/3pP3/8/8/4K3 b - e6 0 1"), FenError::InvalidEnPassant);
+        assert_eq!(error("4k2P/8/8/8/8/8/8/4K3 w - - 0 1"), FenError::PawnOnBackRank);
+        assert_eq!(error("4k3/8/8/8/8/8/8/p3K3 w - - 0 1"), FenError::PawnOnBackRank);
+        assert!(Board::from_fen("4k3/8/8/8/3pP3/8/8/4K3 b - e3 0 1").is_ok());
+    }
+}
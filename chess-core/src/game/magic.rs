@@ -0,0 +1,190 @@
+use std::sync::OnceLock;
+
+use super::pieces::Position;
+
+/// A set of squares, one bit each, numbered `row * 8 + col` as in `Position`
+pub type Bitboard = u64;
+
+const ROOK_DIRECTIONS: [(i32, i32); 4] = [(-1, 0), (1, 0), (0, -1), (0, 1)];
+const BISHOP_DIRECTIONS: [(i32, i32); 4] = [(-1, -1), (-1, 1), (1, -1), (1, 1)];
+
+pub fn square(pos: Position) -> usize {
+    pos.row * 8 + pos.col
+}
+
+/// The bitboard of just `pos`
+pub fn bit(pos: Position) -> Bitboard {
+    1 << square(pos)
+}
+
+pub fn position(square: usize) -> Position {
+    Position::new(square / 8, square % 8)
+}
+
+/// The squares of a bitboard, lowest first
+pub fn squares(mut bits: Bitboard) -> impl Iterator<Item = Position> {
+    std::iter::from_fn(move || {
+        if bits == 0 {
+            return None;
+        }
+        let square = bits.trailing_zeros() as usize;
+        bits &= bits - 1;
+        Some(position(square))
+    })
+}
+
+/// The squares a rook on `from` attacks with the pieces on `occupied` in
+/// the way: up to and including the first piece in each direction
+pub fn rook_attacks(from: Position, occupied: Bitboard) -> Bitboard {
+    let tables = tables();
+    tables.rook[square(from)].attacks(&tables.attacks, occupied)
+}
+
+/// The squares a bishop on `from` attacks, as for `rook_attacks`
+pub fn bishop_attacks(from: Position, occupied: Bitboard) -> Bitboard {
+    let tables = tables();
+    tables.bishop[square(from)].attacks(&tables.attacks, occupied)
+}
+
+pub fn queen_attacks(from: Position, occupied: Bitboard) -> Bitboard {
+    rook_attacks(from, occupied) | bishop_attacks(from, occupied)
+}
+
+// Where one square's attacks sit in the shared table. Multiplying the
+// pieces on the mask by the magic number gathers them into the top bits,
+// which index the attacks for that arrangement.
+#[derive(Clone, Copy, Default)]
+struct Magic {
+    mask: Bitboard,
+    magic: u64,
+    shift: u32,
+    offset: usize,
+}
+
+impl Magic {
+    fn index(&self, occupied: Bitboard) -> usize {
+        self.offset + ((occupied & self.mask).wrapping_mul(self.magic) >> self.shift) as usize
+    }
+
+    fn attacks(&self, table: &[Bitboard], occupied: Bitboard) -> Bitboard {
+        table[self.index(occupied)]
+    }
+}
+
+struct Tables {
+    rook: [Magic; 64],
+    bishop: [Magic; 64],
+    // Every square's attacks for every arrangement of its mask, rooks' first
+    attacks: Vec<Bitboard>,
+}
+
+// Numbers that gather every arrangement of each square's blockers into
+// its own entry, or one leaving the same attacks. They were found by
+// trying random numbers with few bits set, which work more often.
+const ROOK_MAGICS: [u64; 64] = [
+    0x0080_00C0_0011_8020, 0x0440_04C0_1001_2002, 0x0200_112A_0080_40A0, 0x0080_0410_0008_0080,
+    0x8100_0500_0208_0010, 0x2100_0400_0802_0500, 0x1100_0100_5200_0094, 0x1200_0201_4021_0084,
+    0x0440_8028_4000_8000, 0x6080_4000_4120_1000, 0x1100_8010_0080_2000, 0x4902_0022_0241_6830,
+    0x0001_0004_10C8_0102, 0x0062_0008_0200_0C70, 0x0041_0001_0002_0024, 0x4281_8008_C080_0100,
+    0x0004_8880_0040_0021, 0x0011_8080_4000_200E, 0x0010_0080_200A_5080, 0x0050_0080_0800_8330,
+    0x0470_8080_0402_0800, 0x1081_0100_2208_0400, 0x4480_0B01_0002_0004, 0x0802_1A00_1400_4083,
+    0x0118_8202_0020_4100, 0x8480_4008_8020_0084, 0x0030_0801_2000_2401, 0x0106_0012_0008_C122,
+    0x4021_1C00_8008_0080, 0x4002_0002_0008_910C, 0x0825_0104_0012_5008, 0x4020_0402_0000_8841,
+    0x0004_4010_8080_0020, 0x0000_2002_4040_1008, 0x8008_4011_0100_2008, 0x1000_8010_0080_6800,
+    0x1001_8004_0080_0800, 0xC022_0080_F280_0400, 0x1000_1081_2400_0802, 0x1040_8405_8200_0241,
+    0x0002_8620_4000_8000, 0x5400_8062_0102_0042, 0x0000_4020_0101_0011, 0x0C20_2100_D001_0029,
+    0x0202_00E0_9026_0008, 0x3082_0010_0482_0008, 0x0002_0118_1084_0002, 0x0000_3145_0882_0004,
+    0x1C80_4102_0080_2200, 0x8C04_4000_2002_8480, 0x5007_2000_D041_0100, 0x0000_2010_0100_8900,
+    0xA104_0080_0C08_0080, 0x0000_8004_0002_0080, 0x0041_0004_0200_0100, 0x0000_C401_0280_6200,
+    0x2008_2100_4018_8001, 0x0020_4192_0108_8062, 0x0015_A000_1840_1101, 0x0010_1824_3001_0021,
+    0x0803_0004_0800_900B, 0x4802_0010_0D08_0482, 0x2020_4810_0203_2284, 0x0040_0040_8C09_00A6,
+];
+const BISHOP_MAGICS: [u64; 64] = [
+    0x0024_2004_0108_2101, 0x0004_0404_2442_1500, 0x00C1_0202_8100_4900, 0x000A_0A02_0856_0002,
+    0x0090_8820_0308_0802, 0x000A_8610_C008_0020, 0x0000_8801_0820_0082, 0x4800_2108_0804_0220,
+    0x4020_0411_8218_1100, 0xA180_2008_0983_1040, 0x0021_388A_1042_0028, 0x2104_0C04_0084_0030,
+    0x04C0_8A02_1000_1100, 0x0900_8190_1008_4620, 0x0000_0104_1024_1400, 0x0020_0100_4504_2000,
+    0x2050_0124_0450_0C00, 0x0010_2003_0438_0080, 0x0218_0001_8800_4480, 0x4481_0228_0341_0000,
+    0x0481_0060_9040_0000, 0x0082_0031_0882_2010, 0x8201_0440_C802_1008, 0x0000_2443_0128_0202,
+    0x2006_2004_5A08_1006, 0x0401_8810_1012_0820, 0x004A_0806_0244_8400, 0x200A_0020_0801_8020,
+    0x0405_0010_0100_4000, 0x4058_0080_0110_0498, 0x0006_0200_0C00_8200, 0x0020_4100_0206_8248,
+    0x810B_1828_0020_2001, 0x3808_040A_4210_2200, 0x0410_2804_0008_0824, 0x0040_0200_8008_0180,
+    0x0804_4404_0008_4100, 0x2802_0041_000A_0080, 0x0818_0608_8524_0180, 0x0001_0210_8020_2416,
+    0x0112_1202_4280_A020, 0x0024_8404_2A10_2000, 0x4044_1C00_4800_4401, 0x0440_0220_1100_A800,
+    0x1019_0811_00C3_0400, 0x90A0_0400_8A04_8020, 0x5204_0832_0404_4448, 0x5411_4801_0108_0046,
+    0x0000_4402_2010_1620, 0x0222_0242_0804_0000, 0x0110_0114_0104_0240, 0x0024_0000_4202_0024,
+    0x0100_2130_2202_0000, 0x000A_0820_2802_8441, 0x0020_0410_1200_4200, 0x0008_0604_0042_0620,
+    0x0304_4820_8420_2000, 0x0620_0044_1201_1008, 0x1001_5021_0108_0900, 0x0010_0100_0084_0450,
+    0x4502_C080_2021_4500, 0x0040_0042_0802_0420, 0x2020_1010_1000_8480, 0x10A4_0130_0401_0441,
+];
+
+// Filled on first use, from the rays
+fn tables() -> &'static Tables {
+    static TABLES: OnceLock<Tables> = OnceLock::new();
+    TABLES.get_or_init(|| {
+        let mut attacks = Vec::new();
+        let rook = fill(&ROOK_DIRECTIONS, &ROOK_MAGICS, &mut attacks);
+        let bishop = fill(&BISHOP_DIRECTIONS, &BISHOP_MAGICS, &mut attacks);
+        Tables { rook, bishop, attacks }
+    })
+}
+
+// The attacks of a piece on `square` sliding in `directions`, walking the
+// rays: what the tables are filled from
+fn slide(square: usize, occupied: Bitboard, directions: &[(i32, i32)]) -> Bitboard {
+    let mut attacks = 0;
+    for &(row_dir, col_dir) in directions {
+        let mut row = (square / 8) as i32 + row_dir;
+        let mut col = (square % 8) as i32 + col_dir;
+        while (0..8).contains(&row) && (0..8).contains(&col) {
+            let bit = 1 << (row * 8 + col);
+            attacks |= bit;
+            if occupied & bit != 0 {
+                break;
+            }
+            row += row_dir;
+            col += col_dir;
+        }
+    }
+    attacks
+}
+
+// The squares whose pieces can block a slider on `square`: its rays, less
+// the last square of each, which is attacked whether it's occupied or not
+fn blocker_mask(square: usize, directions: &[(i32, i32)]) -> Bitboard {
+    let mut mask = 0;
+    for &(row_dir, col_dir) in directions {
+        let mut row = (square / 8) as i32 + row_dir;
+        let mut col = (square % 8) as i32 + col_dir;
+        while (0..8).contains(&(row + row_dir)) && (0..8).contains(&(col + col_dir)) {
+            mask |= 1 << (row * 8 + col);
+            row += row_dir;
+            col += col_dir;
+        }
+    }
+    mask
+}
+
+fn fill(directions: &[(i32, i32)], numbers: &[u64; 64], table: &mut Vec<Bitboard>) -> [Magic; 64] {
+    let mut magics = [Magic::default(); 64];
+    for (square, magic) in magics.iter_mut().enumerate() {
+        let mask = blocker_mask(square, directions);
+        let bits = mask.count_ones();
+        *magic = Magic { mask, magic: numbers[square], shift: 64 - bits, offset: table.len() };
+        table.resize(table.len() + (1 << bits), 0);
+
+        // Every arrangement of pieces on the mask, by the carry-rippler trick
+        let mut occupied: Bitboard = 0;
+        loop {
+            let attacks = slide(square, occupied, directions);
+            let entry = &mut table[magic.index(occupied)];
+            debug_assert!(*entry == 0 || *entry == attacks, "bad magic number for square {}", square);
+            *entry = attacks;
+            occupied = occupied.wrapping_sub(mask) & mask;
+            if occupied == 0 {
+                break;
+            }
+        }
+    }
+    magics
+}
@@ -0,0 +1,143 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::pieces::Position;
+
+/// The colors squares and arrows can be marked in, as used by the
+/// `[%csl]` and `[%cal]` PGN comment commands
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum MarkColor {
+    Green,
+    Red,
+    Yellow,
+    Blue,
+}
+
+impl MarkColor {
+    pub const ALL: [MarkColor; 4] = [MarkColor::Green, MarkColor::Red, MarkColor::Yellow, MarkColor::Blue];
+
+    pub fn letter(self) -> char {
+        match self {
+            MarkColor::Green => 'G',
+            MarkColor::Red => 'R',
+            MarkColor::Yellow => 'Y',
+            MarkColor::Blue => 'B',
+        }
+    }
+
+    pub fn from_letter(letter: char) -> Option<MarkColor> {
+        MarkColor::ALL.into_iter().find(|color| color.letter() == letter)
+    }
+
+    /// The color after this one, or None after the last
+    fn next(self) -> Option<MarkColor> {
+        let index = MarkColor::ALL.iter().position(|&color| color == self)?;
+        MarkColor::ALL.get(index + 1).copied()
+    }
+}
+
+/// Marked squares and arrows drawn on a position
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct Marks {
+    pub squares: Vec<(Position, MarkColor)>,
+    pub arrows: Vec<(Position, Position, MarkColor)>,
+}
+
+impl Marks {
+    pub fn is_empty(&self) -> bool {
+        self.squares.is_empty() && self.arrows.is_empty()
+    }
+
+    pub fn square(&self, pos: Position) -> Option<MarkColor> {
+        self.squares.iter().find(|&&(square, _)| square == pos).map(|&(_, color)| color)
+    }
+
+    /// Marks `pos` green, or moves its mark on to the next color, removing
+    /// it after the last
+    pub fn cycle_square(&mut self, pos: Position) {
+        match self.squares.iter().position(|&(square, _)| square == pos) {
+            Some(index) => match self.squares[index].1.next() {
+                Some(color) => self.squares[index].1 = color,
+                None => {
+                    self.squares.remove(index);
+                }
+            },
+            None => self.squares.push((pos, MarkColor::Green)),
+        }
+    }
+
+    /// Like `cycle_square`, for the arrow from `from` to `to`
+    pub fn cycle_arrow(&mut self, from: Position, to: Position) {
+        match self.arrows.iter().position(|&(start, end, _)| (start, end) == (from, to)) {
+            Some(index) => match self.arrows[index].2.next() {
+                Some(color) => self.arrows[index].2 = color,
+                None => {
+                    self.arrows.remove(index);
+                }
+            },
+            None => self.arrows.push((from, to, MarkColor::Green)),
+        }
+    }
+
+    /// The marks as PGN comment commands: "[%csl Gd4,Re5][%cal Ge2e4]"
+    pub fn to_commands(&self) -> String {
+        let mut commands = String::new();
+        if !self.squares.is_empty() {
+            let squares: Vec<String> = self
+                .squares
+                .iter()
+                .map(|&(pos, color)| format!("{}{}", color.letter(), pos.to_algebraic()))
+                .collect();
+            commands.push_str(&format!("[%csl {}]", squares.join(",")));
+        }
+        if !self.arrows.is_empty() {
+            let arrows: Vec<String> = self
+                .arrows
+                .iter()
+                .map(|&(from, to, color)| format!("{}{}{}", color.letter(), from.to_algebraic(), to.to_algebraic()))
+                .collect();
+            commands.push_str(&format!("[%cal {}]", arrows.join(",")));
+        }
+        commands
+    }
+
+    /// Takes the `[%csl]` and `[%cal]` commands out of a PGN comment,
+    /// returning the marks they hold and the rest of the comment. Marks
+    /// that can't be read are dropped.
+    pub fn extract(comment: &str) -> (Marks, String) {
+        let mut marks = Marks::default();
+        let mut rest = String::new();
+        let mut remaining = comment;
+
+        while let Some(start) = remaining.find("[%") {
+            let Some(len) = remaining[start..].find(']') else {
+                break;
+            };
+            rest.push_str(&remaining[..start]);
+            let command = &remaining[start + 2..start + len];
+            let (name, args) = command.split_once(char::is_whitespace).unwrap_or((command, ""));
+            let items = args.split(',').map(str::trim);
+            match name {
+                "csl" => marks.squares.extend(items.filter_map(|item| {
+                    let color = MarkColor::from_letter(item.chars().next()?)?;
+                    Some((Position::from_algebraic(item.get(1..)?)?, color))
+                })),
+                "cal" => marks.arrows.extend(items.filter_map(|item| {
+                    let color = MarkColor::from_letter(item.chars().next()?)?;
+                    let from = Position::from_algebraic(item.get(1..3)?)?;
+                    let to = Position::from_algebraic(item.get(3..)?)?;
+                    Some((from, to, color))
+                })),
+                // Other commands, like [%clk] and [%eval], stay in the text
+                _ => rest.push_str(&remaining[start..=start + len]),
+            }
+            remaining = &remaining[start + len + 1..];
+        }
+        rest.push_str(remaining);
+
+        (marks, rest.split_whitespace().collect::<Vec<_>>().join(" "))
+    }
+}
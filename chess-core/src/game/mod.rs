@@ -0,0 +1,32 @@
+pub mod attacks;
+pub mod board;
+pub mod clock;
+pub mod draw;
+pub mod eco;
+pub mod endgame;
+pub mod epd;
+pub mod fen;
+pub mod magic;
+pub mod marks;
+pub mod pieces;
+pub mod puzzle;
+pub mod moves;
+pub mod odds;
+pub mod perft;
+pub mod pgn;
+pub mod picker;
+pub mod record;
+pub mod rules;
+pub mod san;
+pub mod state;
+pub mod tree;
+pub mod zobrist;
+
+pub use board::Board;
+pub use clock::{Clock, ClockMode, TimeControl};
+pub use pieces::{Color, PieceType, Position};
+pub use moves::{Move, MoveType};
+pub use record::Game;
+pub use picker::MovePicker;
+pub use rules::MoveGenerator;
+pub use state::{GameEvent, GameOverReason, GameState};
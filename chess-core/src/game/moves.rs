@@ -1,6 +1,11 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
 use super::pieces::{Piece, PieceType, Position};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum MoveType {
     Normal,
     Capture,
@@ -9,7 +14,8 @@ pub enum MoveType {
     Promotion(PieceType),
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Move {
     pub from: Position,
     pub to: Position,
@@ -44,7 +50,7 @@ impl Move {
         self
     }
 
-    pub fn to_algebraic(&self) -> String {
+    pub fn to_algebraic(self) -> String {
         let piece_symbol = match self.piece.piece_type {
             PieceType::Pawn => "",
             PieceType::Knight => "N",
@@ -87,4 +93,16 @@ impl Move {
             }
         }
     }
+
+    /// Long algebraic coordinate notation as used by UCI and the Lichess API, e.g. "e2e4" or "e7e8q"
+    pub fn to_uci(self) -> String {
+        let promotion = match self.move_type {
+            MoveType::Promotion(PieceType::Knight) => "n",
+            MoveType::Promotion(PieceType::Bishop) => "b",
+            MoveType::Promotion(PieceType::Rook) => "r",
+            MoveType::Promotion(_) => "q",
+            _ => "",
+        };
+        format!("{}{}{}", self.from.to_algebraic(), self.to.to_algebraic(), promotion)
+    }
 }
@@ -0,0 +1,72 @@
+use super::board::Board;
+use super::pieces::{Color, Position};
+
+/// Material a stronger player gives up before the game, as in classic
+/// odds games: the piece is simply missing from the starting position
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MaterialOdds {
+    #[default]
+    None,
+    /// The f-pawn
+    Pawn,
+    /// The queen's knight
+    Knight,
+    /// The queen's rook, along with queenside castling
+    Rook,
+    Queen,
+}
+
+impl MaterialOdds {
+    pub const ALL: [MaterialOdds; 5] = [
+        MaterialOdds::None,
+        MaterialOdds::Pawn,
+        MaterialOdds::Knight,
+        MaterialOdds::Rook,
+        MaterialOdds::Queen,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            MaterialOdds::None => "None",
+            MaterialOdds::Pawn => "Pawn odds",
+            MaterialOdds::Knight => "Knight odds",
+            MaterialOdds::Rook => "Rook odds",
+            MaterialOdds::Queen => "Queen odds",
+        }
+    }
+
+    /// Column of the removed piece on the giver's first rank (or second,
+    /// for the pawn)
+    fn column(self) -> Option<usize> {
+        match self {
+            MaterialOdds::None => None,
+            MaterialOdds::Pawn => Some(5),
+            MaterialOdds::Knight => Some(1),
+            MaterialOdds::Rook => Some(0),
+            MaterialOdds::Queen => Some(3),
+        }
+    }
+
+    /// The standard starting position with `giver`'s odds piece removed
+    pub fn starting_board(self, giver: Color) -> Board {
+        let mut board = Board::new();
+        let Some(col) = self.column() else {
+            return board;
+        };
+        let row = match (giver, self) {
+            (Color::White, MaterialOdds::Pawn) => 6,
+            (Color::White, _) => 7,
+            (Color::Black, MaterialOdds::Pawn) => 1,
+            (Color::Black, _) => 0,
+        };
+        board.set_piece(Position::new(row, col), None);
+
+        if self == MaterialOdds::Rook {
+            match giver {
+                Color::White => board.castling_rights.white_queenside = false,
+                Color::Black => board.castling_rights.black_queenside = false,
+            }
+        }
+        board
+    }
+}
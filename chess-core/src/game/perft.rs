@@ -0,0 +1,110 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+
+use super::board::Board;
+use super::moves::Move;
+use super::rules::MoveGenerator;
+
+/// Counts the leaf nodes of the legal move tree to the given depth, for
+/// checking the move generator against known results
+pub fn perft(board: &Board, depth: u32) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+
+    let moves = MoveGenerator::generate_all_legal_moves(board);
+    if depth == 1 {
+        return moves.len() as u64;
+    }
+
+    moves
+        .iter()
+        .map(|mv| {
+            let mut new_board = board.clone();
+            new_board.make_move(mv);
+            perft(&new_board, depth - 1)
+        })
+        .sum()
+}
+
+/// The leaf count under each legal move of `board`, in the order they're
+/// generated: perft's total split by root move, to narrow a wrong count
+/// down to the move it comes from. The root moves are shared out among
+/// `jobs` threads, where there are threads to start.
+pub fn divide(board: &Board, depth: u32, jobs: usize) -> Vec<(Move, u64)> {
+    if depth == 0 {
+        return Vec::new();
+    }
+    let moves = MoveGenerator::generate_all_legal_moves(board);
+    let count = |mv: &Move| {
+        let mut new_board = board.clone();
+        new_board.make_move(mv);
+        perft(&new_board, depth - 1)
+    };
+    if jobs <= 1 || !cfg!(feature = "native") {
+        return moves.into_iter().map(|mv| (mv, count(&mv))).collect();
+    }
+    let next = AtomicUsize::new(0);
+    let (sender, receiver) = mpsc::channel();
+    let mut counts = vec![0; moves.len()];
+
+    thread::scope(|scope| {
+        for _ in 0..jobs.clamp(1, moves.len().max(1)) {
+            let sender = sender.clone();
+            let (next, moves, count) = (&next, &moves, &count);
+            scope.spawn(move || loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                let Some(mv) = moves.get(index) else {
+                    break;
+                };
+                if sender.send((index, count(mv))).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(sender);
+
+        for (index, count) in receiver {
+            counts[index] = count;
+        }
+    });
+
+    moves.into_iter().zip(counts).collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Known leaf counts from the Chess Programming Wiki's perft results,
+    // at depths a debug build gets through in a few seconds
+    const POSITIONS: [(&str, u32, u64); 6] = [
+        ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", 4, 197_281),
+        ("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", 3, 97_862),
+        ("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", 4, 43_238),
+        ("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1", 3, 9_467),
+        ("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8", 3, 62_379),
+        ("r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10", 3, 89_890),
+    ];
+
+    #[test]
+    fn perft_matches_known_counts() {
+        for (fen, depth, nodes) in POSITIONS {
+            let board = Board::from_fen(fen).unwrap();
+            assert_eq!(perft(&board, depth), nodes, "perft({}) of {}", depth, fen);
+        }
+    }
+
+    #[test]
+    fn divide_adds_up_to_perft() {
+        let (fen, depth, nodes) = POSITIONS[1];
+        let board = Board::from_fen(fen).unwrap();
+        for jobs in [1, 4] {
+            let counts = divide(&board, depth, jobs);
+            assert_eq!(counts.len(), 48);
+            assert_eq!(counts.iter().map(|(_, count)| count).sum::<u64>(), nodes);
+        }
+    }
+}
@@ -0,0 +1,452 @@
+use std::fmt;
+use std::iter::Peekable;
+use std::str::Chars;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::board::Board;
+use super::eco;
+use super::fen::{FenError, STARTING_FEN};
+use super::marks::Marks;
+use super::moves::Move;
+use super::pieces::Color;
+use super::rules::MoveGenerator;
+use super::san::{self, SanError};
+use super::tree::{MoveTree, NodeId};
+
+/// A game read from PGN: its tag pairs, starting position and mainline
+/// moves with their comments and NAGs. Variations are skipped.
+#[derive(Clone)]
+pub struct PgnGame {
+    pub tags: Vec<(String, String)>,
+    pub initial_board: Board,
+    pub moves: Vec<Move>,
+    /// `annotations[i]` goes with the i-th move; may be shorter than `moves`
+    pub annotations: Vec<MoveAnnotation>,
+    pub result: String,
+}
+
+impl PgnGame {
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The position after all mainline moves
+    pub fn final_board(&self) -> Board {
+        let mut board = self.initial_board.clone();
+        for mv in &self.moves {
+            board.make_move(mv);
+        }
+        board
+    }
+
+    /// Checks that each mainline move can be played in turn, which those
+    /// of a game read from JSON, rather than PGN text, may not
+    pub fn validate(&self) -> Result<(), PgnError> {
+        let mut board = self.initial_board.clone();
+        for (index, mv) in self.moves.iter().enumerate() {
+            if !MoveGenerator::generate_all_legal_moves(&board).contains(mv) {
+                return Err(PgnError::IllegalMove { ply: index + 1, uci: mv.to_uci() });
+            }
+            board.make_move(mv);
+        }
+        Ok(())
+    }
+}
+
+/// Extra text written after a move: a numeric annotation glyph (2 for "?",
+/// 4 for "??", ...), a comment and the squares and arrows marked on the
+/// position it leads to
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct MoveAnnotation {
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub nag: Option<u8>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub comment: Option<String>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Marks::is_empty"))]
+    pub marks: Marks,
+}
+
+impl MoveAnnotation {
+    pub fn is_empty(&self) -> bool {
+        self.nag.is_none() && self.comment.is_none() && self.marks.is_empty()
+    }
+}
+
+/// The NAGs judging a move, with the symbols they're usually shown as
+pub const MOVE_NAGS: [(u8, &str); 6] = [(1, "!"), (2, "?"), (3, "!!"), (4, "??"), (5, "!?"), (6, "?!")];
+
+/// How a NAG is shown next to a move: "!?" for the move judgements, "$n" otherwise
+pub fn nag_symbol(nag: u8) -> String {
+    match MOVE_NAGS.iter().find(|&&(number, _)| number == nag) {
+        Some((_, symbol)) => symbol.to_string(),
+        None => format!("${}", nag),
+    }
+}
+
+const LINE_WIDTH: usize = 80;
+
+impl PgnGame {
+    /// Writes the game as PGN with its own annotations. Moves are always
+    /// written in English SAN.
+    pub fn to_pgn(&self) -> String {
+        self.to_annotated_pgn(&self.annotations)
+    }
+
+    /// Writes the game as PGN with `annotations[i]` after the i-th move
+    /// instead of the game's own
+    pub fn to_annotated_pgn(&self, annotations: &[MoveAnnotation]) -> String {
+        let mut tree = MoveTree::new();
+        let mut node = MoveTree::ROOT;
+        for (index, &mv) in self.moves.iter().enumerate() {
+            node = tree.add(node, mv);
+            if let Some(annotation) = annotations.get(index) {
+                *tree.annotation_mut(node) = annotation.clone();
+            }
+        }
+        self.write_pgn(&tree)
+    }
+
+    /// Writes the game as PGN with the variations and annotations in `tree`,
+    /// whose main line should be the game's moves. Variations go in
+    /// parentheses after the moves they replace.
+    pub fn to_pgn_with_variations(&self, tree: &MoveTree) -> String {
+        self.write_pgn(tree)
+    }
+
+    fn write_pgn(&self, tree: &MoveTree) -> String {
+        let mut pgn = String::new();
+
+        let mut tags = self.tags.clone();
+        let start_fen = self.initial_board.to_fen();
+        if start_fen != STARTING_FEN && !tags.iter().any(|(name, _)| name == "FEN") {
+            tags.push(("SetUp".to_string(), "1".to_string()));
+            tags.push(("FEN".to_string(), start_fen));
+        }
+        if !tags.iter().any(|(name, _)| name == "ECO") {
+            if let Some(opening) = eco::classify(&self.initial_board, &self.moves) {
+                tags.push(("ECO".to_string(), opening.eco.to_string()));
+                tags.push(("Opening".to_string(), opening.name.to_string()));
+            }
+        }
+        match tags.iter_mut().find(|(name, _)| name == "Result") {
+            Some((_, value)) => *value = self.result.clone(),
+            None => tags.push(("Result".to_string(), self.result.clone())),
+        }
+        for (name, value) in &tags {
+            let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+            pgn.push_str(&format!("[{} \"{}\"]\n", name, value));
+        }
+        pgn.push('\n');
+
+        let mut tokens = Vec::new();
+        write_line(tree, &mut tokens, MoveTree::ROOT, self.initial_board.clone(), true);
+        tokens.push(self.result.clone());
+
+        let mut line_length = 0;
+        for token in tokens {
+            if line_length > 0 && line_length + 1 + token.len() > LINE_WIDTH {
+                pgn.push('\n');
+                line_length = 0;
+            } else if line_length > 0 {
+                pgn.push(' ');
+                line_length += 1;
+            }
+            line_length += token.len();
+            pgn.push_str(&token);
+        }
+        pgn.push('\n');
+
+        pgn
+    }
+}
+
+/// Appends the moves of `tree` from `node`, at `board`, along its main
+/// continuations, with each move's annotation and variations after it.
+/// `needs_number` asks for a number before Black's first move, as at the
+/// start or after a comment or variation.
+fn write_line(tree: &MoveTree, tokens: &mut Vec<String>, mut node: NodeId, mut board: Board, mut needs_number: bool) {
+    while let Some(next) = tree.main_child(node) {
+        let mv = *tree.mv(next).expect("only the root has no move");
+        push_move(tokens, &board, &mv, needs_number);
+        needs_number = push_annotation(tokens, tree.annotation(next));
+
+        for &variation in &tree.children(node)[1..] {
+            let alternative = *tree.mv(variation).expect("only the root has no move");
+            let mut moves = Vec::new();
+            push_move(&mut moves, &board, &alternative, true);
+            let mut after = board.clone();
+            after.make_move(&alternative);
+            let after_comment = push_annotation(&mut moves, tree.annotation(variation));
+            write_line(tree, &mut moves, variation, after, after_comment);
+
+            moves[0].insert(0, '(');
+            moves.last_mut().expect("a variation has a move").push(')');
+            tokens.extend(moves);
+            needs_number = true;
+        }
+
+        board.make_move(&mv);
+        node = next;
+    }
+}
+
+/// Appends a move's NAG and comment, with its marks as commands at the
+/// start of the comment. Returns whether there was a comment.
+fn push_annotation(tokens: &mut Vec<String>, annotation: &MoveAnnotation) -> bool {
+    if let Some(nag) = annotation.nag {
+        tokens.push(format!("${}", nag));
+    }
+    let commands = annotation.marks.to_commands();
+    let comment = match &annotation.comment {
+        Some(text) if commands.is_empty() => text.clone(),
+        Some(text) => format!("{} {}", commands, text),
+        None if commands.is_empty() => return false,
+        None => commands,
+    };
+    // Braces can't be escaped inside PGN comments
+    tokens.push(format!("{{{}}}", comment.replace('}', ")")));
+    true
+}
+
+fn push_move(tokens: &mut Vec<String>, board: &Board, mv: &Move, needs_number: bool) {
+    match board.current_player {
+        Color::White => tokens.push(format!("{}.", board.fullmove_number)),
+        Color::Black if needs_number => tokens.push(format!("{}...", board.fullmove_number)),
+        Color::Black => {}
+    }
+    tokens.push(san::to_san(board, mv));
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PgnError {
+    UnterminatedTag,
+    InvalidFen(FenError),
+    /// A move that doesn't parse or isn't legal, with its 1-based ply number
+    InvalidMove { ply: usize, error: SanError },
+    /// A move that can't be played in its position, with its 1-based ply
+    /// number
+    IllegalMove { ply: usize, uci: String },
+}
+
+impl fmt::Display for PgnError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PgnError::UnterminatedTag => write!(f, "unterminated tag pair"),
+            PgnError::InvalidFen(err) => write!(f, "invalid FEN tag: {}", err),
+            PgnError::InvalidMove { ply, error } => write!(f, "ply {}: {}", ply, error),
+            PgnError::IllegalMove { ply, uci } => write!(f, "ply {}: {} isn't a legal move there", ply, uci),
+        }
+    }
+}
+
+impl std::error::Error for PgnError {}
+
+enum Token {
+    Tag(String, String),
+    Move(String),
+    Comment(String),
+    Nag(u8),
+    Result(String),
+}
+
+const RESULTS: [&str; 4] = ["1-0", "0-1", "1/2-1/2", "*"];
+
+fn is_symbol_end(c: char) -> bool {
+    c.is_whitespace() || "[]{}();".contains(c)
+}
+
+fn read_tag(chars: &mut Peekable<Chars>) -> Result<Token, PgnError> {
+    let mut name = String::new();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() || c == '"' || c == ']' {
+            break;
+        }
+        name.push(c);
+        chars.next();
+    }
+
+    let mut value = String::new();
+    while chars.peek().is_some_and(|c| c.is_whitespace()) {
+        chars.next();
+    }
+    if chars.next_if_eq(&'"').is_some() {
+        loop {
+            match chars.next() {
+                Some('\\') => value.extend(chars.next()),
+                Some('"') => break,
+                Some(c) => value.push(c),
+                None => return Err(PgnError::UnterminatedTag),
+            }
+        }
+    }
+
+    if chars.any(|c| c == ']') {
+        Ok(Token::Tag(name, value))
+    } else {
+        Err(PgnError::UnterminatedTag)
+    }
+}
+
+fn skip_variation(chars: &mut Peekable<Chars>) {
+    let mut depth = 1;
+    while let Some(c) = chars.next() {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return;
+                }
+            }
+            '{' => {
+                for c in chars.by_ref() {
+                    if c == '}' {
+                        break;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, PgnError> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '[' => tokens.push(read_tag(&mut chars)?),
+            '{' => {
+                let comment: String = chars.by_ref().take_while(|&c| c != '}').collect();
+                // Comments can span lines
+                let comment = comment.split_whitespace().collect::<Vec<_>>().join(" ");
+                if !comment.is_empty() {
+                    tokens.push(Token::Comment(comment));
+                }
+            }
+            ';' | '%' => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            }
+            '(' => skip_variation(&mut chars),
+            c if c.is_whitespace() || c == ']' || c == ')' || c == '}' => {}
+            c => {
+                let mut symbol = c.to_string();
+                while let Some(c) = chars.next_if(|&c| !is_symbol_end(c)) {
+                    symbol.push(c);
+                }
+
+                if RESULTS.contains(&symbol.as_str()) {
+                    tokens.push(Token::Result(symbol));
+                } else if let Some(number) = symbol.strip_prefix('$') {
+                    tokens.extend(number.parse().ok().map(Token::Nag));
+                } else {
+                    // Drop move numbers, including ones glued to the move ("1.e4")
+                    let san = symbol.trim_start_matches(|c: char| c.is_ascii_digit() || c == '.');
+                    // and split off a judgement glued to its end ("e4!?")
+                    let suffix_start = san.trim_end_matches(['!', '?']).len();
+                    let (san, suffix) = san.split_at(suffix_start);
+                    if !san.is_empty() {
+                        tokens.push(Token::Move(san.to_string()));
+                    }
+                    if let Some(&(nag, _)) = MOVE_NAGS.iter().find(|&&(_, glyph)| glyph == suffix) {
+                        tokens.push(Token::Nag(nag));
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+fn finish_game(
+    tags: &mut Vec<(String, String)>,
+    sans: &mut Vec<String>,
+    annotations: &mut Vec<MoveAnnotation>,
+    result: String,
+) -> Result<PgnGame, PgnError> {
+    let tags = std::mem::take(tags);
+    let sans = std::mem::take(sans);
+    let mut annotations = std::mem::take(annotations);
+    while annotations.last().is_some_and(MoveAnnotation::is_empty) {
+        annotations.pop();
+    }
+
+    let initial_board = match tags.iter().find(|(name, _)| name == "FEN") {
+        Some((_, fen)) => Board::from_fen(fen).map_err(PgnError::InvalidFen)?,
+        None => Board::new(),
+    };
+
+    let mut board = initial_board.clone();
+    let mut moves = Vec::with_capacity(sans.len());
+    for (index, text) in sans.iter().enumerate() {
+        let mv = san::parse_san(&board, text)
+            .map_err(|error| PgnError::InvalidMove { ply: index + 1, error })?;
+        board.make_move(&mv);
+        moves.push(mv);
+    }
+
+    Ok(PgnGame { tags, initial_board, moves, annotations, result })
+}
+
+/// Parses every game in a PGN file. A game ends at its result token, or
+/// where the next game's tags begin if the result is missing. Comments
+/// before the first move are dropped, as are all but the first NAG of a move.
+pub fn parse_pgn(text: &str) -> Result<Vec<PgnGame>, PgnError> {
+    let mut games = Vec::new();
+    let mut tags = Vec::new();
+    let mut sans = Vec::new();
+    let mut annotations: Vec<MoveAnnotation> = Vec::new();
+
+    for token in tokenize(text)? {
+        match token {
+            Token::Tag(name, value) => {
+                if !sans.is_empty() {
+                    games.push(finish_game(&mut tags, &mut sans, &mut annotations, "*".to_string())?);
+                }
+                tags.push((name, value));
+            }
+            Token::Move(san) => {
+                sans.push(san);
+                annotations.push(MoveAnnotation::default());
+            }
+            Token::Comment(text) => {
+                let (marks, text) = Marks::extract(&text);
+                if let Some(annotation) = annotations.last_mut() {
+                    annotation.marks.squares.extend(marks.squares);
+                    annotation.marks.arrows.extend(marks.arrows);
+                    if !text.is_empty() {
+                        annotation.comment = Some(match annotation.comment.take() {
+                            Some(comment) => format!("{} {}", comment, text),
+                            None => text,
+                        });
+                    }
+                }
+            }
+            Token::Nag(nag) => {
+                if let Some(annotation) = annotations.last_mut() {
+                    annotation.nag.get_or_insert(nag);
+                }
+            }
+            Token::Result(result) => games.push(finish_game(&mut tags, &mut sans, &mut annotations, result)?),
+        }
+    }
+
+    if !tags.is_empty() || !sans.is_empty() {
+        games.push(finish_game(&mut tags, &mut sans, &mut annotations, "*".to_string())?);
+    }
+
+    Ok(games)
+}
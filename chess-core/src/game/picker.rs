@@ -0,0 +1,160 @@
+use super::board::Board;
+use super::moves::{Move, MoveType};
+use super::rules::MoveGenerator;
+
+// Where a picker is in handing out its moves
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Stage {
+    HashMove,
+    Captures,
+    Killers,
+    Quiets,
+    Done,
+}
+
+/// Hands out the legal moves of a position in the order a search wants to
+/// try them: the hash move, then captures and promotions with the most
+/// valuable victims first, then the killer moves, then the quiet moves.
+/// Each move is checked for legality only when its turn comes, so a search
+/// that cuts off early never pays for checking the rest.
+pub struct MovePicker<'a> {
+    board: &'a Board,
+    stage: Stage,
+    hash_move: Option<Move>,
+    killers: [Option<Move>; 2],
+    // The moves of the current stage still to hand out, the next last
+    pending: Vec<Move>,
+    // Quiet moves, held back while the captures go first
+    quiets: Vec<Move>,
+}
+
+impl<'a> MovePicker<'a> {
+    /// A picker for the side to move on `board`. `hash_move` and `killers`
+    /// are moves that did well before, from this position or a sibling;
+    /// they're skipped if they aren't legal here.
+    pub fn new(board: &'a Board, hash_move: Option<Move>, killers: [Option<Move>; 2]) -> Self {
+        MovePicker { board, stage: Stage::HashMove, hash_move, killers, pending: Vec::new(), quiets: Vec::new() }
+    }
+
+    // Captures and promotions: the moves that change the material
+    fn is_noisy(mv: &Move) -> bool {
+        mv.captured.is_some() || matches!(mv.move_type, MoveType::Promotion(_))
+    }
+
+    // Whether `mv` was already handed out by an earlier stage
+    fn tried_early(&self, mv: &Move) -> bool {
+        (self.stage >= Stage::Captures && self.hash_move == Some(*mv))
+            || (self.stage >= Stage::Quiets && self.killers.contains(&Some(*mv)))
+    }
+
+    fn advance(&mut self) {
+        self.stage = match self.stage {
+            Stage::HashMove => {
+                let moves = MoveGenerator::generate_candidate_moves(self.board);
+                let (mut captures, quiets): (Vec<Move>, Vec<Move>) = moves.into_iter().partition(Self::is_noisy);
+                // Most valuable victim first, taken by the least valuable
+                // attacker; reversed, since moves are popped off the end
+                captures.sort_by_key(|mv| {
+                    let victim = mv.captured.map_or(0, |captured| captured.value());
+                    (victim, -mv.piece.value())
+                });
+                self.pending = captures;
+                self.quiets = quiets;
+                Stage::Captures
+            }
+            Stage::Captures => {
+                // Killers are only tried if they're quiet moves here
+                self.pending = self
+                    .killers
+                    .iter()
+                    .rev()
+                    .flatten()
+                    .filter(|killer| self.quiets.contains(killer))
+                    .copied()
+                    .collect();
+                Stage::Killers
+            }
+            Stage::Killers => {
+                self.pending = std::mem::take(&mut self.quiets);
+                self.pending.reverse();
+                Stage::Quiets
+            }
+            Stage::Quiets | Stage::Done => Stage::Done,
+        };
+    }
+}
+
+impl Iterator for MovePicker<'_> {
+    type Item = Move;
+
+    fn next(&mut self) -> Option<Move> {
+        loop {
+            if self.stage == Stage::HashMove {
+                let hash_move = self.hash_move;
+                self.advance();
+                // Checked against the generated moves, since it may come
+                // from another position
+                if let Some(mv) = hash_move.filter(|mv| self.pending.contains(mv) || self.quiets.contains(mv)) {
+                    if MoveGenerator::is_legal(self.board, &mv) {
+                        return Some(mv);
+                    }
+                }
+                continue;
+            }
+            match self.pending.pop() {
+                Some(mv) => {
+                    if !self.tried_early(&mv) && MoveGenerator::is_legal(self.board, &mv) {
+                        return Some(mv);
+                    }
+                }
+                None if self.stage == Stage::Done => return None,
+                None => self.advance(),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::pieces::{Color, Piece, PieceType, Position};
+
+    const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+
+    fn sorted(mut moves: Vec<Move>) -> Vec<String> {
+        let mut ucis: Vec<String> = moves.drain(..).map(Move::to_uci).collect();
+        ucis.sort();
+        ucis
+    }
+
+    #[test]
+    fn hands_out_each_legal_move_once() {
+        // The last is in check, where only evasions are generated
+        for fen in [KIWIPETE, "rnbqkbnr/ppp2ppp/8/1B1pp3/4P3/8/PPPP1PPP/RNBQK1NR b KQkq - 1 3"] {
+            let board = Board::from_fen(fen).unwrap();
+            let legal = MoveGenerator::generate_all_legal_moves(&board);
+            let killers = [legal.first().copied(), legal.last().copied()];
+            let picked: Vec<Move> = MovePicker::new(&board, legal.get(5).copied(), killers).collect();
+            assert_eq!(sorted(picked), sorted(legal), "{}", fen);
+        }
+    }
+
+    #[test]
+    fn orders_hash_move_captures_killers_then_quiets() {
+        let board = Board::from_fen(KIWIPETE).unwrap();
+        let find = |uci| MoveGenerator::find_uci_move(&board, uci).unwrap();
+        let (hash_move, killer) = (find("a2a3"), find("g2g3"));
+        // Not a move here, so it's skipped
+        let stray = Move::new(Position::new(0, 0), Position::new(1, 1), Piece::new(PieceType::Rook, Color::White));
+        let picked: Vec<Move> = MovePicker::new(&board, Some(hash_move), [Some(killer), Some(stray)]).collect();
+
+        assert_eq!(picked[0], hash_move);
+        let captures = picked.iter().skip(1).take_while(|mv| mv.captured.is_some()).count();
+        assert_eq!(captures, 8);
+        let victims: Vec<i32> =
+            picked[1..=captures].iter().filter_map(|mv| mv.captured.map(|piece| piece.value())).collect();
+        assert!(victims.windows(2).all(|pair| pair[0] >= pair[1]), "{:?}", victims);
+        assert_eq!(picked[1 + captures], killer);
+        assert!(picked[2 + captures..].iter().all(|mv| mv.captured.is_none() && *mv != hash_move));
+    }
+}
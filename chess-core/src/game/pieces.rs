@@ -1,6 +1,11 @@
 use std::fmt;
 
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum Color {
     White,
     Black,
@@ -16,6 +21,8 @@ impl Color {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum PieceType {
     Pawn,
     Knight,
@@ -85,6 +92,7 @@ impl PieceType {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Piece {
     pub piece_type: PieceType,
     pub color: Color,
@@ -111,6 +119,9 @@ impl fmt::Display for Piece {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+// Serialized as a square name such as "e4"
+#[cfg_attr(feature = "serde", serde(into = "String", try_from = "String"))]
 pub struct Position {
     pub row: usize,
     pub col: usize,
@@ -125,25 +136,35 @@ impl Position {
         self.row < 8 && self.col < 8
     }
 
-    pub fn to_algebraic(&self) -> String {
+    pub fn to_algebraic(self) -> String {
         let file = (b'a' + self.col as u8) as char;
         let rank = (b'1' + (7 - self.row) as u8) as char;
         format!("{}{}", file, rank)
     }
 
-    #[allow(dead_code)]
     pub fn from_algebraic(s: &str) -> Option<Self> {
         let bytes = s.as_bytes();
         if bytes.len() != 2 {
             return None;
         }
-        let col = (bytes[0] as i32 - 'a' as i32) as usize;
-        let row = 7 - (bytes[1] as i32 - '1' as i32) as usize;
-        let pos = Position::new(row, col);
-        if pos.is_valid() {
-            Some(pos)
-        } else {
-            None
+        let (file, rank) = (bytes[0], bytes[1]);
+        if !(b'a'..=b'h').contains(&file) || !(b'1'..=b'8').contains(&rank) {
+            return None;
         }
+        Some(Position::new(7 - (rank - b'1') as usize, (file - b'a') as usize))
+    }
+}
+
+impl From<Position> for String {
+    fn from(pos: Position) -> String {
+        pos.to_algebraic()
+    }
+}
+
+impl TryFrom<String> for Position {
+    type Error = String;
+
+    fn try_from(square: String) -> Result<Self, Self::Error> {
+        Position::from_algebraic(&square).ok_or_else(|| format!("invalid square: {}", square))
     }
 }
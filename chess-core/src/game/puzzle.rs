@@ -0,0 +1,241 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use super::board::Board;
+use super::fen::FenError;
+use super::moves::Move;
+use super::pieces::Color;
+use super::rules::MoveGenerator;
+
+/// A tactics position and the line that solves it: the solver's moves
+/// alternating with the opponent's replies, starting with the solver's
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Puzzle {
+    pub id: String,
+    pub name: String,
+    /// The starting position, before any setup move
+    pub board: Board,
+    /// The opponent's move that sets the puzzle, as in the Lichess database
+    pub setup: Option<Move>,
+    pub solution: Vec<Move>,
+    /// Whether the solution ends in checkmate
+    pub mates: bool,
+    pub rating: Option<u32>,
+    pub themes: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PuzzleError {
+    InvalidFen(FenError),
+    /// A move of the solution that isn't legal where it's played
+    IllegalMove { ply: usize, uci: String },
+    EmptySolution,
+    /// A database row without enough fields
+    MissingField(&'static str),
+    InvalidRating(String),
+}
+
+impl fmt::Display for PuzzleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PuzzleError::InvalidFen(error) => write!(f, "{}", error),
+            PuzzleError::IllegalMove { ply, uci } => write!(f, "move {} ({}) is illegal", ply + 1, uci),
+            PuzzleError::EmptySolution => write!(f, "no solution moves"),
+            PuzzleError::MissingField(field) => write!(f, "missing {}", field),
+            PuzzleError::InvalidRating(text) => write!(f, "invalid rating \"{}\"", text),
+        }
+    }
+}
+
+impl std::error::Error for PuzzleError {}
+
+impl Puzzle {
+    /// A puzzle from its position and its solution in space-separated UCI
+    pub fn new(id: &str, name: &str, fen: &str, moves: &str) -> Result<Puzzle, PuzzleError> {
+        let (board, solution, mates) = replay(fen, moves)?;
+        if solution.is_empty() {
+            return Err(PuzzleError::EmptySolution);
+        }
+        Ok(Puzzle {
+            id: id.to_string(),
+            name: name.to_string(),
+            board,
+            setup: None,
+            solution,
+            mates,
+            rating: None,
+            themes: Vec::new(),
+        })
+    }
+
+    /// A row of the Lichess puzzle database CSV: `PuzzleId,FEN,Moves,Rating,
+    /// RatingDeviation,Popularity,NbPlays,Themes,GameUrl,OpeningTags`. The
+    /// first move is the opponent's, setting the puzzle.
+    pub fn from_lichess(row: &str) -> Result<Puzzle, PuzzleError> {
+        let fields = LichessRow::parse(row)?;
+        let (board, mut line, mates) = replay(fields.fen, fields.moves)?;
+        if line.len() < 2 {
+            return Err(PuzzleError::EmptySolution);
+        }
+        Ok(Puzzle {
+            id: fields.id.to_string(),
+            name: format!("Lichess puzzle {}", fields.id),
+            board,
+            setup: Some(line.remove(0)),
+            solution: line,
+            mates,
+            rating: fields.rating,
+            themes: fields.themes.iter().map(|theme| theme.to_string()).collect(),
+        })
+    }
+
+    /// The position the solver has to move in
+    pub fn position(&self) -> Board {
+        let mut board = self.board.clone();
+        if let Some(setup) = &self.setup {
+            board.make_move(setup);
+        }
+        board
+    }
+
+    pub fn solver(&self) -> Color {
+        match self.setup {
+            Some(_) => self.board.current_player.opposite(),
+            None => self.board.current_player,
+        }
+    }
+
+    /// How many moves the solver has to find
+    pub fn moves_to_find(&self) -> usize {
+        self.solution.len().div_ceil(2)
+    }
+
+    /// Whether `mv`, played as move `ply` of the solution in `board`, solves
+    /// the puzzle as well as the solution's own move. Any mate will do.
+    pub fn accepts(&self, ply: usize, board: &Board, mv: &Move) -> bool {
+        if self.solution.get(ply).is_some_and(|expected| expected.to_uci() == mv.to_uci()) {
+            return true;
+        }
+        let mut after = board.clone();
+        after.make_move(mv);
+        MoveGenerator::is_checkmate(&after, after.current_player)
+    }
+}
+
+/// Plays `moves` from `fen`, returning the starting position, the moves and
+/// whether they end in checkmate
+fn replay(fen: &str, moves: &str) -> Result<(Board, Vec<Move>, bool), PuzzleError> {
+    let board = Board::from_fen(fen).map_err(PuzzleError::InvalidFen)?;
+    let mut position = board.clone();
+    let mut line = Vec::new();
+    for (ply, uci) in moves.split_whitespace().enumerate() {
+        let mv = MoveGenerator::find_uci_move(&position, uci)
+            .ok_or_else(|| PuzzleError::IllegalMove { ply, uci: uci.to_string() })?;
+        position.make_move(&mv);
+        line.push(mv);
+    }
+    let mates = MoveGenerator::is_checkmate(&position, position.current_player);
+    Ok((board, line, mates))
+}
+
+/// The fields of a Lichess database row that puzzles are chosen and set up by
+pub struct LichessRow<'a> {
+    pub id: &'a str,
+    pub fen: &'a str,
+    pub moves: &'a str,
+    pub rating: Option<u32>,
+    pub themes: Vec<&'a str>,
+}
+
+impl<'a> LichessRow<'a> {
+    /// Splits a row without setting up its position, which is much cheaper
+    /// when filtering a whole database
+    pub fn parse(row: &'a str) -> Result<LichessRow<'a>, PuzzleError> {
+        let mut fields = row.trim_end().split(',');
+        let mut field = |name| fields.next().ok_or(PuzzleError::MissingField(name));
+        let id = field("PuzzleId")?;
+        let fen = field("FEN")?;
+        let moves = field("Moves")?;
+        let rating = field("Rating")?;
+        let rating = match rating {
+            "" => None,
+            text => Some(text.parse().map_err(|_| PuzzleError::InvalidRating(text.to_string()))?),
+        };
+        // RatingDeviation, Popularity and NbPlays come before the themes
+        for name in ["RatingDeviation", "Popularity", "NbPlays"] {
+            field(name)?;
+        }
+        let themes = field("Themes")?.split_whitespace().collect();
+        Ok(LichessRow { id, fen, moves, rating, themes })
+    }
+
+    /// Whether this is the header row naming the columns
+    pub fn is_header(row: &str) -> bool {
+        row.starts_with("PuzzleId,")
+    }
+}
+
+/// Which puzzles of a database to use: those with any of `themes`, if
+/// there are some, rated within the limits given
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PuzzleFilter {
+    pub themes: Vec<String>,
+    pub min_rating: Option<u32>,
+    pub max_rating: Option<u32>,
+}
+
+impl PuzzleFilter {
+    pub fn matches(&self, row: &LichessRow) -> bool {
+        let themed = self.themes.is_empty() || self.themes.iter().any(|theme| row.themes.contains(&theme.as_str()));
+        let rated = match row.rating {
+            Some(rating) => {
+                self.min_rating.is_none_or(|min| rating >= min) && self.max_rating.is_none_or(|max| rating <= max)
+            }
+            None => self.min_rating.is_none() && self.max_rating.is_none(),
+        };
+        themed && rated
+    }
+}
+
+// Name, rating, position and solution of the built-in puzzles, easiest first
+const PUZZLES: &[(&str, &str, u32, &str, &str)] = &[
+    ("back-rank", "Back-rank mate", 600, "6k1/5ppp/8/8/8/8/5PPP/3R2K1 w - - 0 1", "d1d8"),
+    ("fools-mate", "Fool's mate", 650, "rnbqkbnr/pppp1ppp/8/4p3/6P1/5P2/PPPPP2P/RNBQKBNR b KQkq - 0 2", "d8h4"),
+    (
+        "scholars-mate",
+        "Scholar's mate",
+        700,
+        "r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 4 4",
+        "h5f7",
+    ),
+    ("smothered-mate", "Smothered mate", 900, "6rk/6pp/8/6N1/8/8/8/6K1 w - - 0 1", "g5f7"),
+    (
+        "queen-sacrifice",
+        "Queen sacrifice on the back rank",
+        1100,
+        "r5k1/1b3ppp/8/8/8/8/Q4PPP/R5K1 w - - 0 1",
+        "a2a8 b7a8 a1a8",
+    ),
+    ("philidors-legacy", "Philidor's legacy", 1300, "4r2k/6pp/7N/8/2Q5/8/8/6K1 w - - 0 1", "c4g8 e8g8 h6f7"),
+    ("smothered-black", "Smothered mate for Black", 1350, "6k1/5ppp/8/2q5/8/7n/6PP/4R2K b - - 0 1", "c5g1 e1g1 h3f2"),
+    (
+        "queen-and-knight",
+        "Queen and knight",
+        1600,
+        "k7/p1p5/1pN5/8/8/8/8/1Q4K1 w - - 0 1",
+        "b1b5 a8b7 c6a5 b7a8 b5e8",
+    ),
+];
+
+/// The puzzles that come with the game
+pub fn builtin() -> Vec<Puzzle> {
+    PUZZLES
+        .iter()
+        .map(|&(id, name, rating, fen, moves)| Puzzle {
+            rating: Some(rating),
+            ..Puzzle::new(id, name, fen, moves).expect("built-in puzzles are valid")
+        })
+        .collect()
+}
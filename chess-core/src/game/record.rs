@@ -0,0 +1,93 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::board::Board;
+use super::moves::Move;
+use super::pgn::{MoveAnnotation, PgnGame};
+
+/// A whole game: its tag pairs, starting position, the moves played and the
+/// result. Serializes to the JSON that games are exported as.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Game {
+    pub tags: Vec<(String, String)>,
+    pub initial_board: Board,
+    pub moves: Vec<Move>,
+    /// Comments and NAGs on the moves, by ply
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Vec::is_empty"))]
+    pub annotations: Vec<MoveAnnotation>,
+    /// "1-0", "0-1", "1/2-1/2", or "*" while the game is in progress
+    pub result: String,
+}
+
+impl Game {
+    pub fn new(initial_board: Board) -> Self {
+        Game {
+            tags: Vec::new(),
+            initial_board,
+            moves: Vec::new(),
+            annotations: Vec::new(),
+            result: "*".to_string(),
+        }
+    }
+
+    /// The position after all moves
+    pub fn board(&self) -> Board {
+        let mut board = self.initial_board.clone();
+        for mv in &self.moves {
+            board.make_move(mv);
+        }
+        board
+    }
+}
+
+impl From<PgnGame> for Game {
+    fn from(game: PgnGame) -> Self {
+        Game {
+            tags: game.tags,
+            initial_board: game.initial_board,
+            moves: game.moves,
+            annotations: game.annotations,
+            result: game.result,
+        }
+    }
+}
+
+impl From<Game> for PgnGame {
+    fn from(game: Game) -> Self {
+        PgnGame {
+            tags: game.tags,
+            initial_board: game.initial_board,
+            moves: game.moves,
+            annotations: game.annotations,
+            result: game.result,
+        }
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use super::*;
+    use crate::game::pgn::PgnError;
+    use crate::game::MoveGenerator;
+
+    #[test]
+    fn finds_an_illegal_move_in_a_json_game() {
+        let mut game = Game::new(Board::new());
+        for uci in ["e2e4", "e7e5", "g1f3"] {
+            let mv = MoveGenerator::find_uci_move(&game.board(), uci).unwrap();
+            game.moves.push(mv);
+        }
+        let json = serde_json::to_string(&game).unwrap();
+        let read: Game = serde_json::from_str(&json).unwrap();
+        assert_eq!(PgnGame::from(read).validate(), Ok(()));
+
+        // Black's pawn sent on to e4, where White's stands
+        let mut value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        value["moves"][1]["to"] = "e4".into();
+        let edited: Game = serde_json::from_value(value).unwrap();
+        let error = PgnGame::from(edited).validate().unwrap_err();
+        assert_eq!(error, PgnError::IllegalMove { ply: 2, uci: "e7e4".to_string() });
+        assert_eq!(error.to_string(), "ply 2: e7e4 isn't a legal move there");
+    }
+}
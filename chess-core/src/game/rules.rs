@@ -0,0 +1,550 @@
+use super::attacks;
+use super::board::Board;
+use super::magic::{self, squares, Bitboard};
+use super::moves::{Move, MoveType};
+use super::picker::MovePicker;
+use super::pieces::{Color, Piece, PieceType, Position};
+
+pub struct MoveGenerator;
+
+impl MoveGenerator {
+    pub fn generate_legal_moves(board: &Board, pos: Position) -> Vec<Move> {
+        let piece = match board.get_piece(pos) {
+            Some(p) if p.color == board.current_player => p,
+            _ => return Vec::new(),
+        };
+        Self::legal_moves_from(board, pos, piece, Self::move_targets(board))
+    }
+
+    pub fn generate_all_legal_moves(board: &Board) -> Vec<Move> {
+        let targets = Self::move_targets(board);
+        let mut moves = Vec::new();
+        for (pos, piece) in board.get_all_pieces(board.current_player) {
+            moves.extend(Self::legal_moves_from(board, pos, piece, targets));
+        }
+        moves
+    }
+
+    // The legal moves of `piece` on `pos`, given the side to move's `move_targets`
+    fn legal_moves_from(board: &Board, pos: Position, piece: Piece, targets: Bitboard) -> Vec<Move> {
+        let mut moves = Self::generate_pseudo_legal_moves(board, pos, piece, targets);
+
+        // Filter out moves that would leave the king in check
+        moves.retain(|mv| !Self::would_be_in_check(board, mv));
+
+        moves
+    }
+
+    /// The squares the side to move's pieces other than the king may move
+    /// to: any, or in check only those that might get out of it, which are
+    /// the checking piece's and those between it and the king. In double
+    /// check there are none, since only the king can move.
+    pub fn move_targets(board: &Board) -> Bitboard {
+        let color = board.current_player;
+        let Some(king) = board.find_king(color) else {
+            return !0;
+        };
+        match Self::attackers(board, king, color.opposite()).as_slice() {
+            [] => !0,
+            &[checker] => magic::bit(checker) | attacks::between(king, checker),
+            _ => 0,
+        }
+    }
+
+    /// The legal moves for the side to move, handed out one at a time in a
+    /// good order to search them in; see `MovePicker`
+    pub fn picker(board: &Board, hash_move: Option<Move>, killers: [Option<Move>; 2]) -> MovePicker<'_> {
+        MovePicker::new(board, hash_move, killers)
+    }
+
+    /// Whether `mv`, one of the moves the pieces can make, doesn't leave
+    /// the mover's king in check
+    pub fn is_legal(board: &Board, mv: &Move) -> bool {
+        !Self::would_be_in_check(board, mv)
+    }
+
+    // Every move of the side to move that might be legal: those that get
+    // out of check when it's in check, otherwise all its pieces can make
+    pub(super) fn generate_candidate_moves(board: &Board) -> Vec<Move> {
+        let targets = Self::move_targets(board);
+        board
+            .get_all_pieces(board.current_player)
+            .into_iter()
+            .flat_map(|(pos, piece)| Self::generate_pseudo_legal_moves(board, pos, piece, targets))
+            .collect()
+    }
+
+    /// Finds the legal move matching a UCI coordinate string such as "e2e4" or "e7e8q"
+    pub fn find_uci_move(board: &Board, uci: &str) -> Option<Move> {
+        if uci.len() < 4 || uci.len() > 5 || !uci.is_ascii() {
+            return None;
+        }
+        let from = Position::from_algebraic(&uci[0..2])?;
+        let to = Position::from_algebraic(&uci[2..4])?;
+        let promotion = match uci.get(4..5) {
+            None => None,
+            Some("q") => Some(PieceType::Queen),
+            Some("r") => Some(PieceType::Rook),
+            Some("b") => Some(PieceType::Bishop),
+            Some("n") => Some(PieceType::Knight),
+            Some(_) => return None,
+        };
+
+        Self::generate_legal_moves(board, from)
+            .into_iter()
+            .find(|mv| {
+                mv.to == to
+                    && match mv.move_type {
+                        MoveType::Promotion(piece_type) => promotion == Some(piece_type),
+                        _ => promotion.is_none(),
+                    }
+            })
+    }
+
+    // The moves `piece` on `pos` can make, whether or not they leave its
+    // king in check. Pieces other than the king only move to the `targets`
+    // squares, except for en passant captures, which take a pawn elsewhere.
+    fn generate_pseudo_legal_moves(board: &Board, pos: Position, piece: Piece, targets: Bitboard) -> Vec<Move> {
+        match piece.piece_type {
+            PieceType::Pawn => {
+                let mut moves = Self::generate_pawn_moves(board, pos, piece);
+                moves.retain(|mv| mv.move_type == MoveType::EnPassant || targets & magic::bit(mv.to) != 0);
+                moves
+            }
+            PieceType::Knight => Self::generate_moves_to(board, pos, piece, attacks::knight_attacks(pos) & targets),
+            PieceType::Bishop => {
+                Self::generate_moves_to(board, pos, piece, magic::bishop_attacks(pos, board.occupancy()) & targets)
+            }
+            PieceType::Rook => {
+                Self::generate_moves_to(board, pos, piece, magic::rook_attacks(pos, board.occupancy()) & targets)
+            }
+            PieceType::Queen => {
+                Self::generate_moves_to(board, pos, piece, magic::queen_attacks(pos, board.occupancy()) & targets)
+            }
+            PieceType::King => Self::generate_king_moves(board, pos, piece),
+        }
+    }
+
+    fn generate_pawn_moves(board: &Board, pos: Position, piece: Piece) -> Vec<Move> {
+        let mut moves = Vec::new();
+        let direction: i32 = if piece.color == Color::White { -1 } else { 1 };
+        let start_row = if piece.color == Color::White { 6 } else { 1 };
+        let promotion_row = if piece.color == Color::White { 0 } else { 7 };
+
+        // Forward move
+        let new_row = (pos.row as i32 + direction) as usize;
+        if new_row < 8 {
+            let forward_pos = Position::new(new_row, pos.col);
+            if board.get_piece(forward_pos).is_none() {
+                if new_row == promotion_row {
+                    // Promotion
+                    for promo_type in [PieceType::Queen, PieceType::Rook, PieceType::Bishop, PieceType::Knight] {
+                        moves.push(Move::new(pos, forward_pos, piece).with_type(MoveType::Promotion(promo_type)));
+                    }
+                } else {
+                    moves.push(Move::new(pos, forward_pos, piece));
+                }
+
+                // Double move from start
+                if pos.row == start_row {
+                    let double_row = (pos.row as i32 + direction * 2) as usize;
+                    let double_pos = Position::new(double_row, pos.col);
+                    if board.get_piece(double_pos).is_none() {
+                        moves.push(Move::new(pos, double_pos, piece));
+                    }
+                }
+            }
+        }
+
+        // Captures
+        for capture_pos in squares(attacks::pawn_attacks(piece.color, pos)) {
+            // Normal capture
+            if let Some(target) = board.get_piece(capture_pos) {
+                if target.color != piece.color {
+                    if capture_pos.row == promotion_row {
+                        for promo_type in [PieceType::Queen, PieceType::Rook, PieceType::Bishop, PieceType::Knight] {
+                            moves.push(Move::with_capture(pos, capture_pos, piece, target)
+                                .with_type(MoveType::Promotion(promo_type)));
+                        }
+                    } else {
+                        moves.push(Move::with_capture(pos, capture_pos, piece, target));
+                    }
+                }
+            }
+
+            // En passant
+            if Some(capture_pos) == board.en_passant_target {
+                let captured_pawn = Piece::new(PieceType::Pawn, piece.color.opposite());
+                moves.push(Move::with_capture(pos, capture_pos, piece, captured_pawn)
+                    .with_type(MoveType::EnPassant));
+            }
+        }
+
+        moves
+    }
+
+    // Moves to the `targets` squares not held by the mover's own pieces
+    fn generate_moves_to(board: &Board, pos: Position, piece: Piece, targets: Bitboard) -> Vec<Move> {
+        squares(targets & !board.color_occupancy(piece.color))
+            .map(|target| match board.get_piece(target) {
+                Some(captured) => Move::with_capture(pos, target, piece, captured),
+                None => Move::new(pos, target, piece),
+            })
+            .collect()
+    }
+
+    fn generate_king_moves(board: &Board, pos: Position, piece: Piece) -> Vec<Move> {
+        // Normal king moves
+        let mut moves = Self::generate_moves_to(board, pos, piece, attacks::king_attacks(pos));
+
+        // Castling
+        let (kingside, queenside, row) = match piece.color {
+            Color::White => (
+                board.castling_rights.white_kingside,
+                board.castling_rights.white_queenside,
+                7,
+            ),
+            Color::Black => (
+                board.castling_rights.black_kingside,
+                board.castling_rights.black_queenside,
+                0,
+            ),
+        };
+
+        if !Self::is_square_attacked(board, pos, piece.color.opposite()) {
+            // Kingside castling
+            if kingside && pos.col == 4 {
+                let f1 = Position::new(row, 5);
+                let g1 = Position::new(row, 6);
+                if board.get_piece(f1).is_none()
+                    && board.get_piece(g1).is_none()
+                    && !Self::is_square_attacked(board, f1, piece.color.opposite())
+                {
+                    moves.push(Move::new(pos, g1, piece).with_type(MoveType::Castle));
+                }
+            }
+
+            // Queenside castling
+            if queenside && pos.col == 4 {
+                let d1 = Position::new(row, 3);
+                let c1 = Position::new(row, 2);
+                let b1 = Position::new(row, 1);
+                if board.get_piece(d1).is_none()
+                    && board.get_piece(c1).is_none()
+                    && board.get_piece(b1).is_none()
+                    && !Self::is_square_attacked(board, d1, piece.color.opposite())
+                {
+                    moves.push(Move::new(pos, c1, piece).with_type(MoveType::Castle));
+                }
+            }
+        }
+
+        moves
+    }
+
+    fn would_be_in_check(board: &Board, mv: &Move) -> bool {
+        let mut test_board = board.clone();
+        test_board.make_move(mv);
+
+        // Switch back to check the original player's king
+        let king_color = mv.piece.color;
+        if let Some(king_pos) = test_board.find_king(king_color) {
+            Self::is_square_attacked(&test_board, king_pos, king_color.opposite())
+        } else {
+            true // King not found, invalid position
+        }
+    }
+
+    pub fn is_square_attacked(board: &Board, pos: Position, by_color: Color) -> bool {
+        let mut attacked = false;
+        Self::scan_attackers(board, pos, by_color, |_| {
+            attacked = true;
+            true
+        });
+        attacked
+    }
+
+    /// Squares of the `by_color` pieces attacking `pos`, e.g. the pieces
+    /// giving check
+    pub fn attackers(board: &Board, pos: Position, by_color: Color) -> Vec<Position> {
+        let mut attackers = Vec::new();
+        Self::scan_attackers(board, pos, by_color, |from| {
+            attackers.push(from);
+            false
+        });
+        attackers
+    }
+
+    /// Calls `found` with each attacker of `pos`, until it returns true
+    fn scan_attackers(board: &Board, pos: Position, by_color: Color, mut found: impl FnMut(Position) -> bool) {
+        // Each kind of piece attacks pos from the squares the same piece on
+        // pos would attack, except that a pawn attacks from those a pawn of
+        // the other color on pos would
+        let occupied = board.occupancy();
+        let pieces = |piece_type| board.piece_occupancy(piece_type, by_color);
+        let queens = pieces(PieceType::Queen);
+        let attackers = [
+            attacks::pawn_attacks(by_color.opposite(), pos) & pieces(PieceType::Pawn),
+            attacks::knight_attacks(pos) & pieces(PieceType::Knight),
+            magic::bishop_attacks(pos, occupied) & (pieces(PieceType::Bishop) | queens),
+            magic::rook_attacks(pos, occupied) & (pieces(PieceType::Rook) | queens),
+            attacks::king_attacks(pos) & pieces(PieceType::King),
+        ];
+        for from in attackers.into_iter().flat_map(squares) {
+            if found(from) {
+                return;
+            }
+        }
+    }
+
+    pub fn is_in_check(board: &Board, color: Color) -> bool {
+        if let Some(king_pos) = board.find_king(color) {
+            Self::is_square_attacked(board, king_pos, color.opposite())
+        } else {
+            false
+        }
+    }
+
+    pub fn is_checkmate(board: &Board, color: Color) -> bool {
+        if !Self::is_in_check(board, color) {
+            return false;
+        }
+        Self::has_no_legal_moves(board, color)
+    }
+
+    pub fn is_stalemate(board: &Board, color: Color) -> bool {
+        if Self::is_in_check(board, color) {
+            return false;
+        }
+        Self::has_no_legal_moves(board, color)
+    }
+
+    fn has_no_legal_moves(board: &Board, color: Color) -> bool {
+        board
+            .pieces()
+            .filter(|(_, piece)| piece.color == color)
+            .all(|(pos, _)| Self::generate_legal_moves(board, pos).is_empty())
+    }
+
+    pub fn is_insufficient_material(board: &Board) -> bool {
+        let mut white_pieces = Vec::new();
+        let mut black_pieces = Vec::new();
+
+        for (_, piece) in board.pieces() {
+            match piece.color {
+                Color::White => white_pieces.push(piece.piece_type),
+                Color::Black => black_pieces.push(piece.piece_type),
+            }
+        }
+
+        // King vs King
+        if white_pieces.len() == 1 && black_pieces.len() == 1 {
+            return true;
+        }
+
+        // King and Bishop vs King
+        // King and Knight vs King
+        if (white_pieces.len() == 1 && black_pieces.len() == 2)
+            || (white_pieces.len() == 2 && black_pieces.len() == 1)
+        {
+            let pieces = if white_pieces.len() == 2 { &white_pieces } else { &black_pieces };
+            return pieces.contains(&PieceType::Bishop) || pieces.contains(&PieceType::Knight);
+        }
+
+        false
+    }
+
+    /// Whether `color` could checkmate with the most helpful play from the
+    /// opponent. Pieces of the opponent's can help by hemming its king in.
+    pub fn has_mating_material(board: &Board, color: Color) -> bool {
+        let mut own = Vec::new();
+        let mut theirs = Vec::new();
+        for (pos, piece) in board.pieces() {
+            if piece.piece_type == PieceType::King {
+                continue;
+            }
+            if piece.color == color {
+                own.push((pos, piece.piece_type));
+            } else {
+                theirs.push((pos, piece.piece_type));
+            }
+        }
+
+        let count = |piece_type| own.iter().filter(|(_, t)| *t == piece_type).count();
+        let (knights, bishops) = (count(PieceType::Knight), count(PieceType::Bishop));
+        if knights + bishops < own.len() {
+            // A pawn, rook or queen
+            return true;
+        }
+
+        match (knights, bishops) {
+            (0, 0) => false,
+            // A lone knight mates only against a king with a blocker beside it
+            (1, 0) => theirs.iter().any(|(_, t)| *t != PieceType::Queen),
+            // Bishops that all share a square color need an enemy knight or
+            // pawn, or a bishop on the other color, to block the king
+            (0, _) => {
+                let mut bishop_shades = own
+                    .iter()
+                    .chain(&theirs)
+                    .filter(|(_, t)| *t == PieceType::Bishop)
+                    .map(|(pos, _)| (pos.row + pos.col) % 2);
+                let first = bishop_shades.next();
+                theirs.iter().any(|(_, t)| matches!(t, PieceType::Knight | PieceType::Pawn))
+                    || bishop_shades.any(|shade| Some(shade) != first)
+            }
+            _ => true,
+        }
+    }
+
+    /// Whether no sequence of legal moves can end in checkmate, which
+    /// draws the game (FIDE Article 5.2.2): neither side has the material
+    /// to mate, or the kings are all that can move and a locked pawn wall
+    /// keeps them from ever taking a pawn
+    pub fn is_dead_position(board: &Board) -> bool {
+        if !Self::has_mating_material(board, Color::White) && !Self::has_mating_material(board, Color::Black) {
+            return true;
+        }
+        Self::is_locked_pawn_wall(board)
+    }
+
+    // Kings and pawns only, every pawn blocked head-on by another with
+    // nothing to capture, and no king able to walk to a pawn it could take.
+    // The pawns then never move again, and kings alone can't give check.
+    fn is_locked_pawn_wall(board: &Board) -> bool {
+        if board.en_passant_target.is_some() {
+            return false;
+        }
+        let piece_at = |row: i32, col: i32| {
+            if (0..8).contains(&row) && (0..8).contains(&col) {
+                board.get_piece(Position::new(row as usize, col as usize))
+            } else {
+                None
+            }
+        };
+        let forward = |color: Color| if color == Color::White { -1 } else { 1 };
+        let attacked_by_pawn = |row: i32, col: i32, by: Color| {
+            [-1, 1].iter().any(|side| {
+                piece_at(row - forward(by), col + side)
+                    .is_some_and(|piece| piece.color == by && piece.piece_type == PieceType::Pawn)
+            })
+        };
+
+        let mut pawns = 0;
+        for (pos, piece) in board.pieces() {
+            match piece.piece_type {
+                PieceType::King => continue,
+                PieceType::Pawn => pawns += 1,
+                _ => return false,
+            }
+            let (row, col) = (pos.row as i32 + forward(piece.color), pos.col as i32);
+            if !piece_at(row, col).is_some_and(|blocker| blocker.piece_type == PieceType::Pawn) {
+                return false;
+            }
+            if [-1, 1].iter().any(|side| piece_at(row, col + side).is_some_and(|target| target.color != piece.color)) {
+                return false;
+            }
+        }
+        if pawns == 0 {
+            return false;
+        }
+
+        // Every square each king could ever reach, ignoring the other king
+        for color in [Color::White, Color::Black] {
+            let Some(king) = board.find_king(color) else {
+                return false;
+            };
+            let mut reached = [[false; 8]; 8];
+            reached[king.row][king.col] = true;
+            let mut stack = vec![king];
+            while let Some(from) = stack.pop() {
+                for to in squares(attacks::king_attacks(from)) {
+                    if reached[to.row][to.col] {
+                        continue;
+                    }
+                    let (row, col) = (to.row as i32, to.col as i32);
+                    if let Some(piece) = piece_at(row, col).filter(|piece| piece.piece_type == PieceType::Pawn) {
+                        if piece.color != color && !attacked_by_pawn(row, col, piece.color) {
+                            return false;
+                        }
+                        continue;
+                    }
+                    if attacked_by_pawn(row, col, color.opposite()) {
+                        continue;
+                    }
+                    reached[to.row][to.col] = true;
+                    stack.push(to);
+                }
+            }
+        }
+        true
+    }
+
+    /// The winner when `flagged` runs out of time: its opponent, unless the
+    /// opponent couldn't possibly mate, which makes it a draw (FIDE Laws 6.9)
+    pub fn timeout_winner(board: &Board, flagged: Color) -> Option<Color> {
+        let opponent = flagged.opposite();
+        Self::has_mating_material(board, opponent).then_some(opponent)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn board(fen: &str) -> Board {
+        Board::from_fen(fen).unwrap()
+    }
+
+    #[test]
+    fn lone_minor_pieces_are_insufficient_material() {
+        let insufficient = [
+            "4k3/8/8/8/8/8/8/4K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/2B1K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/1N2K3 b - - 0 1",
+        ];
+        for fen in insufficient {
+            assert!(MoveGenerator::is_insufficient_material(&board(fen)), "{}", fen);
+        }
+        let enough = [
+            "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/R3K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/1NN1K3 w - - 0 1",
+        ];
+        for fen in enough {
+            assert!(!MoveGenerator::is_insufficient_material(&board(fen)), "{}", fen);
+        }
+    }
+
+    #[test]
+    fn mating_material_depends_on_what_can_block() {
+        // Bishops on squares of one color can never mate
+        assert!(MoveGenerator::is_dead_position(&board("4k3/8/8/8/8/8/3b4/2B1K3 w - - 0 1")));
+        // On both colors, the opponent's bishop can block its own king in
+        assert!(!MoveGenerator::is_dead_position(&board("4k3/8/8/8/8/8/2b5/2B1K3 w - - 0 1")));
+        // A knight mates against a king with a pawn beside it, not a queen
+        let knight_vs_pawn = board("4k3/4p3/8/8/8/8/8/1N2K3 w - - 0 1");
+        assert!(MoveGenerator::has_mating_material(&knight_vs_pawn, Color::White));
+        let knight_vs_queen = board("4k3/4q3/8/8/8/8/8/1N2K3 w - - 0 1");
+        assert!(!MoveGenerator::has_mating_material(&knight_vs_queen, Color::White));
+        assert_eq!(MoveGenerator::timeout_winner(&knight_vs_queen, Color::Black), None);
+        assert_eq!(MoveGenerator::timeout_winner(&knight_vs_queen, Color::White), Some(Color::Black));
+    }
+
+    #[test]
+    fn locked_pawn_walls_are_dead() {
+        assert!(MoveGenerator::is_dead_position(&board("4k3/8/8/p1p1p1p1/P1P1P1P1/8/8/4K3 w - - 0 1")));
+        // The g-pawns aren't blocked, so they can still promote
+        assert!(!MoveGenerator::is_dead_position(&board("4k3/8/8/p1p1p3/P1P1P1P1/8/8/4K3 w - - 0 1")));
+    }
+
+    #[test]
+    fn mate_and_stalemate() {
+        let fools_mate = board("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3");
+        assert!(MoveGenerator::is_checkmate(&fools_mate, Color::White));
+        assert!(!MoveGenerator::is_stalemate(&fools_mate, Color::White));
+        let stalemate = board("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1");
+        assert!(MoveGenerator::is_stalemate(&stalemate, Color::Black));
+        assert!(!MoveGenerator::is_checkmate(&stalemate, Color::Black));
+    }
+}
@@ -0,0 +1,348 @@
+use std::fmt;
+
+use super::board::Board;
+use super::moves::{Move, MoveType};
+use super::pieces::{PieceType, Position};
+use super::rules::MoveGenerator;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SanError {
+    Malformed(String),
+    Illegal(String),
+    Ambiguous(String),
+}
+
+impl fmt::Display for SanError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SanError::Malformed(san) => write!(f, "cannot parse move '{}'", san),
+            SanError::Illegal(san) => write!(f, "illegal move '{}'", san),
+            SanError::Ambiguous(san) => write!(f, "ambiguous move '{}'", san),
+        }
+    }
+}
+
+impl std::error::Error for SanError {}
+
+fn piece_letter(piece_type: PieceType) -> Option<char> {
+    match piece_type {
+        PieceType::Pawn => None,
+        PieceType::Knight => Some('N'),
+        PieceType::Bishop => Some('B'),
+        PieceType::Rook => Some('R'),
+        PieceType::Queen => Some('Q'),
+        PieceType::King => Some('K'),
+    }
+}
+
+fn piece_from_letter(letter: char) -> Option<PieceType> {
+    match letter {
+        'N' => Some(PieceType::Knight),
+        'B' => Some(PieceType::Bishop),
+        'R' => Some(PieceType::Rook),
+        'Q' => Some(PieceType::Queen),
+        'K' => Some(PieceType::King),
+        _ => None,
+    }
+}
+
+/// Languages with their own SAN piece letters. Pawns have no letter in any
+/// of them, and castling is always written "O-O".
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Language {
+    #[default]
+    English,
+    German,
+    French,
+    Spanish,
+    Dutch,
+}
+
+impl Language {
+    pub const ALL: [Language; 5] = [
+        Language::English,
+        Language::German,
+        Language::French,
+        Language::Spanish,
+        Language::Dutch,
+    ];
+
+    /// Looks a language up by its ISO 639-1 code, e.g. "de"
+    pub fn from_code(code: &str) -> Option<Language> {
+        Self::ALL
+            .into_iter()
+            .find(|language| language.code().eq_ignore_ascii_case(code))
+    }
+
+    pub fn code(self) -> &'static str {
+        match self {
+            Language::English => "en",
+            Language::German => "de",
+            Language::French => "fr",
+            Language::Spanish => "es",
+            Language::Dutch => "nl",
+        }
+    }
+
+    /// Letters for the knight, bishop, rook, queen and king, in that order
+    fn piece_letters(self) -> [char; 5] {
+        match self {
+            Language::English => ['N', 'B', 'R', 'Q', 'K'],
+            Language::German => ['S', 'L', 'T', 'D', 'K'],
+            Language::French => ['C', 'F', 'T', 'D', 'R'],
+            Language::Spanish => ['C', 'A', 'T', 'D', 'R'],
+            Language::Dutch => ['P', 'L', 'T', 'D', 'K'],
+        }
+    }
+
+    fn translate(san: &str, from: [char; 5], to: [char; 5]) -> String {
+        san.chars()
+            .map(|c| from.iter().position(|&letter| letter == c).map_or(c, |index| to[index]))
+            .collect()
+    }
+
+    /// Rewrites English SAN with this language's piece letters
+    pub fn localize(self, san: &str) -> String {
+        Self::translate(san, Language::English.piece_letters(), self.piece_letters())
+    }
+
+    /// Rewrites SAN in this language with English piece letters
+    pub fn delocalize(self, san: &str) -> String {
+        Self::translate(san, self.piece_letters(), Language::English.piece_letters())
+    }
+}
+
+/// Formats a legal move in Standard Algebraic Notation, including
+/// disambiguation and the check or mate suffix. `board` is the position
+/// before the move.
+pub fn to_san(board: &Board, mv: &Move) -> String {
+    let mut san = String::new();
+
+    match mv.move_type {
+        MoveType::Castle => {
+            san.push_str(if mv.to.col > mv.from.col { "O-O" } else { "O-O-O" });
+        }
+        _ => {
+            match piece_letter(mv.piece.piece_type) {
+                Some(letter) => {
+                    san.push(letter);
+                    san.push_str(&disambiguation(board, mv));
+                }
+                None if mv.captured.is_some() => san.push((b'a' + mv.from.col as u8) as char),
+                None => {}
+            }
+            if mv.captured.is_some() {
+                san.push('x');
+            }
+            san.push_str(&mv.to.to_algebraic());
+            if let MoveType::Promotion(promoted_to) = mv.move_type {
+                san.push('=');
+                san.extend(piece_letter(promoted_to));
+            }
+        }
+    }
+
+    let mut after = board.clone();
+    after.make_move(mv);
+    if MoveGenerator::is_in_check(&after, after.current_player) {
+        let mated = MoveGenerator::generate_all_legal_moves(&after).is_empty();
+        san.push(if mated { '#' } else { '+' });
+    }
+
+    san
+}
+
+fn disambiguation(board: &Board, mv: &Move) -> String {
+    let rivals: Vec<Position> = MoveGenerator::generate_all_legal_moves(board)
+        .into_iter()
+        .filter(|other| {
+            other.piece.piece_type == mv.piece.piece_type && other.to == mv.to && other.from != mv.from
+        })
+        .map(|other| other.from)
+        .collect();
+
+    if rivals.is_empty() {
+        return String::new();
+    }
+
+    let square = mv.from.to_algebraic();
+    if rivals.iter().all(|from| from.col != mv.from.col) {
+        square[0..1].to_string()
+    } else if rivals.iter().all(|from| from.row != mv.from.row) {
+        square[1..2].to_string()
+    } else {
+        square
+    }
+}
+
+/// Finds the legal move described by a SAN string. Check, mate and
+/// annotation suffixes are ignored, and "0-0" is accepted for castling.
+pub fn parse_san(board: &Board, san: &str) -> Result<Move, SanError> {
+    let text = san.trim().trim_end_matches(['+', '#', '!', '?']);
+    let malformed = || SanError::Malformed(san.to_string());
+    let legal_moves = MoveGenerator::generate_all_legal_moves(board);
+
+    if matches!(text, "O-O" | "0-0" | "O-O-O" | "0-0-0") {
+        let kingside = text.len() == 3;
+        return legal_moves
+            .into_iter()
+            .find(|mv| mv.move_type == MoveType::Castle && (mv.to.col > mv.from.col) == kingside)
+            .ok_or_else(|| SanError::Illegal(san.to_string()));
+    }
+
+    let mut chars: Vec<char> = text.chars().collect();
+
+    let promotion = match chars.iter().rposition(|&c| c == '=') {
+        Some(index) => {
+            let piece = chars.get(index + 1).copied().and_then(piece_from_letter).ok_or_else(malformed)?;
+            chars.truncate(index);
+            Some(piece)
+        }
+        // Also accept the "e8Q" form
+        None => match chars.last().copied().and_then(piece_from_letter) {
+            Some(piece) if chars.len() >= 3 && chars[chars.len() - 2].is_ascii_digit() => {
+                chars.pop();
+                Some(piece)
+            }
+            _ => None,
+        },
+    };
+
+    let piece_type = match chars.first().copied().and_then(piece_from_letter) {
+        Some(piece) => {
+            chars.remove(0);
+            piece
+        }
+        None => PieceType::Pawn,
+    };
+
+    chars.retain(|&c| c != 'x' && c != ':' && c != '-');
+    if chars.len() < 2 {
+        return Err(malformed());
+    }
+    let target: String = chars[chars.len() - 2..].iter().collect();
+    let to = Position::from_algebraic(&target).ok_or_else(malformed)?;
+    let qualifiers = &chars[..chars.len() - 2];
+
+    let mut from_col = None;
+    let mut from_row = None;
+    for &c in qualifiers {
+        match c {
+            'a'..='h' => from_col = Some(c as usize - 'a' as usize),
+            '1'..='8' => from_row = Some(7 - (c as usize - '1' as usize)),
+            _ => return Err(malformed()),
+        }
+    }
+
+    let candidates: Vec<Move> = legal_moves
+        .into_iter()
+        .filter(|mv| {
+            mv.piece.piece_type == piece_type
+                && mv.to == to
+                && from_col.is_none_or(|col| mv.from.col == col)
+                && from_row.is_none_or(|row| mv.from.row == row)
+                && match mv.move_type {
+                    MoveType::Promotion(promoted_to) => promotion == Some(promoted_to),
+                    _ => promotion.is_none(),
+                }
+        })
+        .collect();
+
+    match candidates.as_slice() {
+        [mv] => Ok(*mv),
+        [] => Err(SanError::Illegal(san.to_string())),
+        _ => Err(SanError::Ambiguous(san.to_string())),
+    }
+}
+
+/// Like `parse_san`, for moves written with `language`'s piece letters
+pub fn parse_localized_san(board: &Board, san: &str, language: Language) -> Result<Move, SanError> {
+    parse_san(board, &language.delocalize(san)).map_err(|err| match err {
+        SanError::Malformed(_) => SanError::Malformed(san.to_string()),
+        SanError::Illegal(_) => SanError::Illegal(san.to_string()),
+        SanError::Ambiguous(_) => SanError::Ambiguous(san.to_string()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn board(fen: &str) -> Board {
+        Board::from_fen(fen).unwrap()
+    }
+
+    fn san_of(fen: &str, uci: &str) -> String {
+        let board = board(fen);
+        to_san(&board, &MoveGenerator::find_uci_move(&board, uci).unwrap())
+    }
+
+    #[test]
+    fn every_move_round_trips() {
+        let fens = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        ];
+        for fen in fens {
+            let root = board(fen);
+            for mv in MoveGenerator::generate_all_legal_moves(&root) {
+                let mut after = root.clone();
+                after.make_move(&mv);
+                for position in [&root, &after] {
+                    for mv in MoveGenerator::generate_all_legal_moves(position) {
+                        let san = to_san(position, &mv);
+                        assert_eq!(parse_san(position, &san), Ok(mv), "{} in {}", san, position.to_fen());
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn formats_special_moves() {
+        let kiwipete = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        assert_eq!(san_of(kiwipete, "e1g1"), "O-O");
+        assert_eq!(san_of(kiwipete, "e1c1"), "O-O-O");
+        assert_eq!(san_of("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "e5d6"), "exd6");
+        assert_eq!(san_of("7k/P7/8/8/8/8/8/K7 w - - 0 1", "a7a8n"), "a8=N");
+        assert_eq!(san_of("7k/P7/8/8/8/8/8/K7 w - - 0 1", "a7a8q"), "a8=Q+");
+        let fools_mate = "rnbqkbnr/pppp1ppp/8/4p3/6P1/5P2/PPPPP2P/RNBQKBNR b KQkq g3 0 2";
+        assert_eq!(san_of(fools_mate, "d8h4"), "Qh4#");
+    }
+
+    #[test]
+    fn disambiguates_by_file_then_rank_then_square() {
+        assert_eq!(san_of("k7/8/8/8/8/8/K7/R6R w - - 0 1", "a1d1"), "Rad1");
+        assert_eq!(san_of("7k/8/8/8/R7/8/8/R3K3 w - - 0 1", "a4a2"), "R4a2");
+        assert_eq!(san_of("8/8/1k6/8/4Q2Q/8/8/K6Q w - - 0 1", "h4e1"), "Qh4e1");
+    }
+
+    #[test]
+    fn localized_moves_round_trip() {
+        let board = board("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+        for language in Language::ALL {
+            for mv in MoveGenerator::generate_all_legal_moves(&board) {
+                let san = language.localize(&to_san(&board, &mv));
+                assert_eq!(parse_localized_san(&board, &san, language), Ok(mv), "{} in {:?}", san, language);
+            }
+        }
+        assert_eq!(Language::German.localize("Nxe5+"), "Sxe5+");
+        assert_eq!(Language::French.localize("Kf1"), "Rf1");
+        assert_eq!(Language::French.delocalize("Rf1"), "Kf1");
+    }
+
+    #[test]
+    fn parses_alternative_forms_and_rejects_bad_ones() {
+        let kiwipete = board("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+        assert_eq!(parse_san(&kiwipete, "0-0").unwrap().move_type, MoveType::Castle);
+        let promotion = board("7k/P7/8/8/8/8/8/K7 w - - 0 1");
+        assert_eq!(parse_san(&promotion, "a8Q").unwrap().move_type, MoveType::Promotion(PieceType::Queen));
+
+        let rooks = board("k7/8/8/8/8/8/K7/R6R w - - 0 1");
+        assert_eq!(parse_san(&rooks, "Rd1"), Err(SanError::Ambiguous("Rd1".to_string())));
+        assert_eq!(parse_san(&rooks, "Rd2"), Err(SanError::Illegal("Rd2".to_string())));
+        assert_eq!(parse_san(&rooks, "R"), Err(SanError::Malformed("R".to_string())));
+    }
+}
@@ -0,0 +1,568 @@
+use std::fmt;
+
+use super::board::Board;
+use super::clock::{Clock, LOW_TIME_MS};
+use super::draw::{self, AutomaticDraw, DrawClaim};
+use super::moves::{Move, MoveType};
+use super::pieces::{Color, Piece, PieceType};
+use super::record::Game;
+use super::rules::MoveGenerator;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameOverReason {
+    Checkmate(Color), // Winner
+    Stalemate,
+    InsufficientMaterial,
+    // No moves left could lead to mate, e.g. behind a locked pawn wall
+    DeadPosition,
+    // Claimed by the side to move
+    DrawClaimed(DrawClaim),
+    // One side offered a draw and the other took it
+    DrawAgreed,
+    FivefoldRepetition,
+    SeventyFiveMoveRule,
+    // The side that gave up
+    Resignation(Color),
+    // The winner is None when the other side couldn't have mated
+    Timeout { flagged: Color, winner: Option<Color> },
+    // Result reported by a server for endings the board can't show (resignation, flag, abort)
+    Remote { winner: Option<Color>, reason: &'static str },
+    // A finished game opened to look through, from the archive or a file
+    Review { winner: Option<Color>, archived: bool },
+}
+
+impl GameOverReason {
+    /// The result in PGN notation
+    pub fn result(&self) -> &'static str {
+        match self {
+            GameOverReason::Checkmate(Color::White)
+            | GameOverReason::Resignation(Color::Black)
+            | GameOverReason::Timeout { winner: Some(Color::White), .. }
+            | GameOverReason::Remote { winner: Some(Color::White), .. }
+            | GameOverReason::Review { winner: Some(Color::White), .. } => "1-0",
+            GameOverReason::Checkmate(Color::Black)
+            | GameOverReason::Resignation(Color::White)
+            | GameOverReason::Timeout { winner: Some(Color::Black), .. }
+            | GameOverReason::Remote { winner: Some(Color::Black), .. }
+            | GameOverReason::Review { winner: Some(Color::Black), .. } => "0-1",
+            _ => "1/2-1/2",
+        }
+    }
+
+    /// How the game ended, in words
+    pub fn describe(&self) -> String {
+        let winner = |winner: &Option<Color>| match winner {
+            Some(color) => format!("{} wins", side_name(*color)),
+            None => "draw".to_string(),
+        };
+        match self {
+            GameOverReason::Checkmate(winner) => format!("{} wins by checkmate", side_name(*winner)),
+            GameOverReason::Stalemate => "draw by stalemate".to_string(),
+            GameOverReason::InsufficientMaterial => "draw, neither side can mate".to_string(),
+            GameOverReason::DeadPosition => "draw, no moves can lead to mate".to_string(),
+            GameOverReason::DrawClaimed(claim) => format!("draw claimed by {}", claim.label()),
+            GameOverReason::DrawAgreed => "draw by agreement".to_string(),
+            GameOverReason::FivefoldRepetition => "draw by fivefold repetition".to_string(),
+            GameOverReason::SeventyFiveMoveRule => "draw by the seventy-five-move rule".to_string(),
+            GameOverReason::Resignation(loser) => {
+                format!("{} resigns; {} wins", side_name(*loser), side_name(loser.opposite()))
+            }
+            GameOverReason::Timeout { flagged, winner: won } => {
+                format!("{} ran out of time; {}", side_name(*flagged), winner(won))
+            }
+            GameOverReason::Remote { winner: won, reason } => format!("{}; {}", reason, winner(won)),
+            GameOverReason::Review { winner: won, .. } => winner(won),
+        }
+    }
+}
+
+fn side_name(color: Color) -> &'static str {
+    match color {
+        Color::White => "White",
+        Color::Black => "Black",
+    }
+}
+
+/// Something that happened in a game. `GameState` hands them to its
+/// subscribers as they happen, for the screen, sounds, logs or a connection
+/// to react to, so that whoever plays the moves needn't tell each of them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameEvent {
+    /// A move was played. Events about its capture, promotion or check
+    /// follow it.
+    MovePlayed(Move),
+    /// The piece the move just played took
+    Captured(Piece),
+    /// The move just played turned a pawn into `piece_type`
+    Promotion { color: Color, piece_type: PieceType },
+    /// The side to move is in check, or mated
+    Check(Color),
+    /// `color`'s clock went under `LOW_TIME_MS`, leaving `remaining_ms`
+    Clock { color: Color, remaining_ms: u64 },
+    GameEnded(GameOverReason),
+}
+
+/// Why `GameState` turned down a move or a request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlayError {
+    GameOver,
+    IllegalMove,
+    /// Claiming a draw the rules don't allow yet
+    NoDrawToClaim,
+    /// Accepting a draw the other side hasn't offered
+    NoDrawOffer,
+}
+
+impl fmt::Display for PlayError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let message = match self {
+            PlayError::GameOver => "the game is already over",
+            PlayError::IllegalMove => "the move isn't legal here",
+            PlayError::NoDrawToClaim => "there's no draw to claim",
+            PlayError::NoDrawOffer => "no draw has been offered",
+        };
+        write!(f, "{}", message)
+    }
+}
+
+impl std::error::Error for PlayError {}
+
+/// Called by `GameState` with each of its events as it happens
+pub type Subscriber = Box<dyn FnMut(&GameEvent) + Send>;
+
+/// A game being played: the position and the moves that led to it, the
+/// pieces taken, the clock, a draw on offer and how the game ended. It keeps
+/// the rules of playing a game, so that any front end, or none, can drive
+/// one.
+pub struct GameState {
+    initial: Board,
+    board: Board,
+    moves: Vec<Move>,
+    // The position before each move, for taking it back
+    previous: Vec<Board>,
+    captured_white: Vec<PieceType>,
+    captured_black: Vec<PieceType>,
+    clock: Option<Clock>,
+    draw_offer: Option<Color>,
+    result: Option<GameOverReason>,
+    subscribers: Vec<Subscriber>,
+}
+
+impl GameState {
+    pub fn new(initial: Board) -> Self {
+        GameState {
+            board: initial.clone(),
+            initial,
+            moves: Vec::new(),
+            previous: Vec::new(),
+            captured_white: Vec::new(),
+            captured_black: Vec::new(),
+            clock: None,
+            draw_offer: None,
+            result: None,
+            subscribers: Vec::new(),
+        }
+    }
+
+    pub fn with_clock(mut self, clock: Option<Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// The position the game started from
+    pub fn initial(&self) -> &Board {
+        &self.initial
+    }
+
+    /// The position now
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn moves(&self) -> &[Move] {
+        &self.moves
+    }
+
+    pub fn last_move(&self) -> Option<&Move> {
+        self.moves.last()
+    }
+
+    /// The `color` pieces taken so far, in the order they fell
+    pub fn captured(&self, color: Color) -> &[PieceType] {
+        match color {
+            Color::White => &self.captured_white,
+            Color::Black => &self.captured_black,
+        }
+    }
+
+    pub fn clock(&self) -> Option<&Clock> {
+        self.clock.as_ref()
+    }
+
+    pub fn clock_mut(&mut self) -> Option<&mut Clock> {
+        self.clock.as_mut()
+    }
+
+    /// How the game ended, or None while it goes on
+    pub fn result(&self) -> Option<GameOverReason> {
+        self.result
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.result.is_some()
+    }
+
+    /// Plays `mv` for the side to move. Moving lets a draw offered by the
+    /// other side lapse.
+    pub fn play(&mut self, mv: Move) -> Result<(), PlayError> {
+        if self.is_over() {
+            return Err(PlayError::GameOver);
+        }
+        if !MoveGenerator::generate_legal_moves(&self.board, mv.from).contains(&mv) {
+            return Err(PlayError::IllegalMove);
+        }
+
+        if let Some(captured) = mv.captured {
+            match captured.color {
+                Color::White => self.captured_white.push(captured.piece_type),
+                Color::Black => self.captured_black.push(captured.piece_type),
+            }
+        }
+        if self.draw_offer == Some(mv.piece.color.opposite()) {
+            self.draw_offer = None;
+        }
+        self.previous.push(self.board.clone());
+        self.board.make_move(&mv);
+        self.moves.push(mv);
+
+        self.emit(GameEvent::MovePlayed(mv));
+        if let Some(captured) = mv.captured {
+            self.emit(GameEvent::Captured(captured));
+        }
+        if let MoveType::Promotion(piece_type) = mv.move_type {
+            self.emit(GameEvent::Promotion { color: mv.piece.color, piece_type });
+        }
+        let side = self.board.current_player;
+        if MoveGenerator::is_in_check(&self.board, side) {
+            self.emit(GameEvent::Check(side));
+        }
+        Ok(())
+    }
+
+    /// Takes back the last move, and with it the game's result and any draw
+    /// on offer. The clock keeps the time it has.
+    pub fn undo(&mut self) -> Option<Move> {
+        let mv = self.moves.pop()?;
+        self.board = self.previous.pop().unwrap_or_else(|| self.initial.clone());
+        if let Some(captured) = mv.captured {
+            match captured.color {
+                Color::White => self.captured_white.pop(),
+                Color::Black => self.captured_black.pop(),
+            };
+        }
+        self.draw_offer = None;
+        self.result = None;
+        Some(mv)
+    }
+
+    /// Gives `color` the move without a move being played, as in exercises
+    /// where one side moves again and again. No en passant capture carries
+    /// over.
+    pub fn set_side_to_move(&mut self, color: Color) {
+        self.board.current_player = color;
+        self.board.en_passant_target = None;
+    }
+
+    /// Ends the game if the position on the board ends it: checkmate,
+    /// stalemate, no mate possible, fivefold repetition or seventy-five
+    /// moves without a capture or pawn move
+    pub fn adjudicate(&mut self) -> Option<GameOverReason> {
+        if self.is_over() {
+            return None;
+        }
+        let board = &self.board;
+        let reason = if MoveGenerator::is_checkmate(board, board.current_player) {
+            GameOverReason::Checkmate(board.current_player.opposite())
+        } else if MoveGenerator::is_stalemate(board, board.current_player) {
+            GameOverReason::Stalemate
+        } else if MoveGenerator::is_insufficient_material(board) {
+            GameOverReason::InsufficientMaterial
+        } else if MoveGenerator::is_dead_position(board) {
+            GameOverReason::DeadPosition
+        } else {
+            match draw::automatic(&self.initial, &self.moves)? {
+                AutomaticDraw::FivefoldRepetition => GameOverReason::FivefoldRepetition,
+                AutomaticDraw::SeventyFiveMoves => GameOverReason::SeventyFiveMoveRule,
+            }
+        };
+        self.end(reason);
+        self.result
+    }
+
+    /// Ends the game for a reason beyond the position on the board: a
+    /// resignation, a result from elsewhere, a finished game being reviewed
+    pub fn end(&mut self, reason: GameOverReason) {
+        self.result = Some(reason);
+        self.draw_offer = None;
+        self.emit(GameEvent::GameEnded(reason));
+    }
+
+    pub fn resign(&mut self, loser: Color) -> Result<(), PlayError> {
+        if self.is_over() {
+            return Err(PlayError::GameOver);
+        }
+        self.end(GameOverReason::Resignation(loser));
+        Ok(())
+    }
+
+    /// Charges the side to move for `elapsed_ms` on the clock, ending the
+    /// game if its flag falls. Returns whether it did.
+    pub fn charge_clock(&mut self, elapsed_ms: u64) -> bool {
+        let mover = self.board.current_player;
+        let Some(clock) = self.clock.as_mut().filter(|_| self.result.is_none()) else {
+            return false;
+        };
+        let before = clock.remaining(mover);
+        let flagged = clock.charge(mover, elapsed_ms);
+        let remaining_ms = clock.remaining(mover);
+        if before >= LOW_TIME_MS && remaining_ms < LOW_TIME_MS {
+            self.emit(GameEvent::Clock { color: mover, remaining_ms });
+        }
+        if !flagged {
+            return false;
+        }
+        let winner = MoveGenerator::timeout_winner(&self.board, mover);
+        self.end(GameOverReason::Timeout { flagged: mover, winner });
+        true
+    }
+
+    /// How many times the position now has occurred
+    pub fn repetitions(&self) -> usize {
+        draw::repetitions(&self.initial, &self.moves)
+    }
+
+    /// The draw the side to move could claim now, if any
+    pub fn claimable_draw(&self) -> Option<DrawClaim> {
+        if self.is_over() {
+            return None;
+        }
+        draw::claimable(&self.initial, &self.moves)
+    }
+
+    /// Claims a draw by threefold repetition or the fifty-move rule for the
+    /// side to move, if the rules allow one
+    pub fn claim_draw(&mut self) -> Result<DrawClaim, PlayError> {
+        if self.is_over() {
+            return Err(PlayError::GameOver);
+        }
+        let claim = self.claimable_draw().ok_or(PlayError::NoDrawToClaim)?;
+        self.end(GameOverReason::DrawClaimed(claim));
+        Ok(claim)
+    }
+
+    /// The side offering a draw, if one is
+    pub fn draw_offer(&self) -> Option<Color> {
+        self.draw_offer
+    }
+
+    /// Offers the other side a draw, which stands until they take it,
+    /// turn it down or move
+    pub fn offer_draw(&mut self, color: Color) -> Result<(), PlayError> {
+        if self.is_over() {
+            return Err(PlayError::GameOver);
+        }
+        self.draw_offer = Some(color);
+        Ok(())
+    }
+
+    /// Takes the draw offered to `color`
+    pub fn accept_draw(&mut self, color: Color) -> Result<(), PlayError> {
+        if self.is_over() {
+            return Err(PlayError::GameOver);
+        }
+        if self.draw_offer != Some(color.opposite()) {
+            return Err(PlayError::NoDrawOffer);
+        }
+        self.end(GameOverReason::DrawAgreed);
+        Ok(())
+    }
+
+    pub fn decline_draw(&mut self) {
+        self.draw_offer = None;
+    }
+
+    /// Calls `subscriber` with each event from now on, after the
+    /// subscribers before it
+    pub fn subscribe(&mut self, subscriber: Subscriber) {
+        self.subscribers.push(subscriber);
+    }
+
+    fn emit(&mut self, event: GameEvent) {
+        for subscriber in &mut self.subscribers {
+            subscriber(&event);
+        }
+    }
+
+    /// The game as a record to save or export, with its moves and result
+    /// and no tags
+    pub fn to_record(&self) -> Game {
+        let mut game = Game::new(self.initial.clone());
+        game.moves = self.moves.clone();
+        game.result = self.result.map_or("*", |reason| reason.result()).to_string();
+        game
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::game::clock::TimeControl;
+    use crate::game::pieces::Position;
+
+    type Events = Arc<Mutex<Vec<GameEvent>>>;
+
+    // Keeps the events the game has from now on
+    fn subscribe(game: &mut GameState) -> Events {
+        let events = Events::default();
+        let sink = Arc::clone(&events);
+        game.subscribe(Box::new(move |event| sink.lock().unwrap().push(*event)));
+        events
+    }
+
+    // The events kept since the last call
+    fn take(events: &Events) -> Vec<GameEvent> {
+        std::mem::take(&mut *events.lock().unwrap())
+    }
+
+    fn from_fen(fen: &str) -> GameState {
+        GameState::new(Board::from_fen(fen).unwrap())
+    }
+
+    // Plays the moves in UCI coordinates, which must all be legal
+    fn play(game: &mut GameState, ucis: &str) {
+        for uci in ucis.split_whitespace() {
+            let mv = MoveGenerator::find_uci_move(game.board(), uci).unwrap();
+            game.play(mv).unwrap();
+        }
+    }
+
+    #[test]
+    fn plays_and_takes_back_moves() {
+        let mut game = GameState::new(Board::new());
+        play(&mut game, "e2e4 d7d5");
+        let events = subscribe(&mut game);
+        let capture = MoveGenerator::find_uci_move(game.board(), "e4d5").unwrap();
+        game.play(capture).unwrap();
+
+        assert_eq!(game.moves().len(), 3);
+        assert_eq!(game.captured(Color::Black), [PieceType::Pawn]);
+        assert_eq!(
+            take(&events),
+            [GameEvent::MovePlayed(capture), GameEvent::Captured(Piece::new(PieceType::Pawn, Color::Black))]
+        );
+        let illegal = Move::new(Position::new(0, 0), Position::new(4, 0), Piece::new(PieceType::Rook, Color::Black));
+        assert_eq!(game.play(illegal), Err(PlayError::IllegalMove));
+
+        assert_eq!(game.undo(), Some(capture));
+        assert!(game.captured(Color::Black).is_empty());
+        game.undo();
+        game.undo();
+        assert_eq!(game.board().to_fen(), game.initial().to_fen());
+        assert_eq!(game.undo(), None);
+    }
+
+    #[test]
+    fn checkmate_ends_the_game_until_taken_back() {
+        let mut game = GameState::new(Board::new());
+        let events = subscribe(&mut game);
+        play(&mut game, "f2f3 e7e5 g2g4 d8h4");
+        assert_eq!(take(&events).last(), Some(&GameEvent::Check(Color::White)));
+
+        assert_eq!(game.adjudicate(), Some(GameOverReason::Checkmate(Color::Black)));
+        assert_eq!(game.to_record().result, "0-1");
+        assert_eq!(take(&events), [GameEvent::GameEnded(GameOverReason::Checkmate(Color::Black))]);
+        let mv = MoveGenerator::generate_all_legal_moves(&Board::new())[0];
+        assert_eq!(game.play(mv), Err(PlayError::GameOver));
+        assert_eq!(game.adjudicate(), None);
+
+        game.undo();
+        assert!(!game.is_over());
+        assert_eq!(game.adjudicate(), None);
+    }
+
+    #[test]
+    fn adjudicates_drawn_positions() {
+        let mut stalemate = from_fen("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1");
+        assert_eq!(stalemate.adjudicate(), Some(GameOverReason::Stalemate));
+        assert_eq!(stalemate.to_record().result, "1/2-1/2");
+
+        let mut bare_kings = from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1");
+        assert_eq!(bare_kings.adjudicate(), Some(GameOverReason::InsufficientMaterial));
+
+        let mut seventy_five = from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 148 100");
+        play(&mut seventy_five, "a1a2");
+        assert_eq!(seventy_five.adjudicate(), None);
+        play(&mut seventy_five, "e8d8");
+        assert_eq!(seventy_five.adjudicate(), Some(GameOverReason::SeventyFiveMoveRule));
+    }
+
+    #[test]
+    fn claims_a_draw_only_when_the_rules_allow_one() {
+        let mut game = GameState::new(Board::new());
+        assert_eq!(game.claim_draw(), Err(PlayError::NoDrawToClaim));
+
+        play(&mut game, "g1f3 g8f6 f3g1 f6g8 g1f3 g8f6 f3g1");
+        assert_eq!(game.claimable_draw(), None);
+        play(&mut game, "f6g8");
+        assert_eq!(game.repetitions(), 3);
+        assert_eq!(game.claim_draw(), Ok(DrawClaim::ThreefoldRepetition));
+        assert_eq!(game.result(), Some(GameOverReason::DrawClaimed(DrawClaim::ThreefoldRepetition)));
+        assert_eq!(game.claim_draw(), Err(PlayError::GameOver));
+    }
+
+    #[test]
+    fn draw_offers_stand_until_the_other_side_moves() {
+        let mut game = GameState::new(Board::new());
+        play(&mut game, "e2e4");
+        game.offer_draw(Color::White).unwrap();
+        assert_eq!(game.accept_draw(Color::White), Err(PlayError::NoDrawOffer));
+        play(&mut game, "e7e5");
+        assert_eq!(game.draw_offer(), None);
+        assert_eq!(game.accept_draw(Color::Black), Err(PlayError::NoDrawOffer));
+
+        game.offer_draw(Color::White).unwrap();
+        game.decline_draw();
+        assert_eq!(game.accept_draw(Color::Black), Err(PlayError::NoDrawOffer));
+
+        // Offered with a move, as over the board
+        game.offer_draw(Color::White).unwrap();
+        play(&mut game, "g1f3");
+        assert_eq!(game.draw_offer(), Some(Color::White));
+        game.accept_draw(Color::Black).unwrap();
+        assert_eq!(game.result(), Some(GameOverReason::DrawAgreed));
+        assert_eq!(game.offer_draw(Color::White), Err(PlayError::GameOver));
+    }
+
+    #[test]
+    fn flag_falls_when_the_clock_runs_out() {
+        let mut game = GameState::new(Board::new()).with_clock(Some(Clock::new(TimeControl::new(1, 0))));
+        let events = subscribe(&mut game);
+        assert!(!game.charge_clock(50_000));
+        assert_eq!(take(&events), [GameEvent::Clock { color: Color::White, remaining_ms: 10_000 }]);
+        assert!(!game.charge_clock(5_000));
+        assert!(take(&events).is_empty());
+
+        assert!(game.charge_clock(5_000));
+        assert_eq!(game.result(), Some(GameOverReason::Timeout { flagged: Color::White, winner: Some(Color::Black) }));
+        assert!(!game.charge_clock(1_000));
+
+        // Nobody wins on time against a bare king
+        let clock = Clock::new(TimeControl::new(1, 0));
+        let mut bare_king = from_fen("4k3/8/8/8/8/8/8/3QK3 w - - 0 1").with_clock(Some(clock));
+        assert!(bare_king.charge_clock(60_000));
+        assert_eq!(bare_king.result(), Some(GameOverReason::Timeout { flagged: Color::White, winner: None }));
+        assert_eq!(bare_king.to_record().result, "1/2-1/2");
+    }
+}
@@ -0,0 +1,128 @@
+use super::moves::Move;
+use super::pgn::MoveAnnotation;
+
+/// Index of a position in a `MoveTree`
+pub type NodeId = usize;
+
+#[derive(Debug, Clone)]
+struct Node {
+    mv: Option<Move>,
+    annotation: MoveAnnotation,
+    parent: Option<NodeId>,
+    /// The main continuation first, then the variations
+    children: Vec<NodeId>,
+}
+
+/// A game's moves with the variations branching off them. Each node is a
+/// position, reached from its parent by a move; the root is the starting
+/// position.
+#[derive(Debug, Clone)]
+pub struct MoveTree {
+    nodes: Vec<Node>,
+}
+
+impl Default for MoveTree {
+    fn default() -> Self {
+        MoveTree::new()
+    }
+}
+
+impl MoveTree {
+    pub const ROOT: NodeId = 0;
+
+    pub fn new() -> Self {
+        MoveTree {
+            nodes: vec![Node {
+                mv: None,
+                annotation: MoveAnnotation::default(),
+                parent: None,
+                children: Vec::new(),
+            }],
+        }
+    }
+
+    /// The move leading to `node`; None for the root
+    pub fn mv(&self, node: NodeId) -> Option<&Move> {
+        self.nodes[node].mv.as_ref()
+    }
+
+    /// The comment and NAG on the move leading to `node`
+    pub fn annotation(&self, node: NodeId) -> &MoveAnnotation {
+        &self.nodes[node].annotation
+    }
+
+    pub fn annotation_mut(&mut self, node: NodeId) -> &mut MoveAnnotation {
+        &mut self.nodes[node].annotation
+    }
+
+    pub fn parent(&self, node: NodeId) -> Option<NodeId> {
+        self.nodes[node].parent
+    }
+
+    /// The main continuation from `node` followed by its variations
+    pub fn children(&self, node: NodeId) -> &[NodeId] {
+        &self.nodes[node].children
+    }
+
+    /// The main continuation from `node`, if any
+    pub fn main_child(&self, node: NodeId) -> Option<NodeId> {
+        self.nodes[node].children.first().copied()
+    }
+
+    /// The position reached by playing `mv` at `node`, added as the last
+    /// variation if it isn't in the tree yet
+    pub fn add(&mut self, node: NodeId, mv: Move) -> NodeId {
+        if let Some(child) = self.child_with(node, &mv) {
+            return child;
+        }
+        let child = self.nodes.len();
+        self.nodes.push(Node {
+            mv: Some(mv),
+            annotation: MoveAnnotation::default(),
+            parent: Some(node),
+            children: Vec::new(),
+        });
+        self.nodes[node].children.push(child);
+        child
+    }
+
+    /// Like `add`, but makes `mv` the main continuation from `node`
+    pub fn add_main(&mut self, node: NodeId, mv: Move) -> NodeId {
+        let child = self.add(node, mv);
+        let children = &mut self.nodes[node].children;
+        let index = children.iter().position(|&id| id == child).expect("child was just added");
+        children[..=index].rotate_right(1);
+        child
+    }
+
+    fn child_with(&self, node: NodeId, mv: &Move) -> Option<NodeId> {
+        self.nodes[node]
+            .children
+            .iter()
+            .copied()
+            .find(|&child| self.nodes[child].mv.as_ref() == Some(mv))
+    }
+
+    /// The moves from the starting position to `node`
+    pub fn moves_to(&self, node: NodeId) -> Vec<Move> {
+        let mut moves = Vec::new();
+        let mut current = node;
+        while let (Some(mv), Some(parent)) = (self.nodes[current].mv, self.nodes[current].parent) {
+            moves.push(mv);
+            current = parent;
+        }
+        moves.reverse();
+        moves
+    }
+
+    /// Number of moves from the starting position to `node`
+    pub fn depth(&self, node: NodeId) -> usize {
+        let mut depth = 0;
+        let mut current = node;
+        while let Some(parent) = self.nodes[current].parent {
+            depth += 1;
+            current = parent;
+        }
+        depth
+    }
+}
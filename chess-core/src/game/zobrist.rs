@@ -0,0 +1,134 @@
+use super::board::Board;
+use super::pieces::{Color, PieceType, Position};
+
+struct ZobristKeys {
+    pieces: [[u64; 64]; 12],
+    black_to_move: u64,
+    castling: [u64; 4],
+    en_passant: [u64; 8],
+}
+
+// SplitMix64 step, usable in const context so the key table is built at compile time
+const fn next_key(state: u64) -> (u64, u64) {
+    let state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    (state, z ^ (z >> 31))
+}
+
+const fn generate_keys() -> ZobristKeys {
+    let mut keys = ZobristKeys {
+        pieces: [[0; 64]; 12],
+        black_to_move: 0,
+        castling: [0; 4],
+        en_passant: [0; 8],
+    };
+    let mut state = 0x5EED_C0DE_u64;
+
+    let mut piece = 0;
+    while piece < 12 {
+        let mut square = 0;
+        while square < 64 {
+            let (next, key) = next_key(state);
+            state = next;
+            keys.pieces[piece][square] = key;
+            square += 1;
+        }
+        piece += 1;
+    }
+
+    let (next, key) = next_key(state);
+    state = next;
+    keys.black_to_move = key;
+
+    let mut i = 0;
+    while i < 4 {
+        let (next, key) = next_key(state);
+        state = next;
+        keys.castling[i] = key;
+        i += 1;
+    }
+
+    let mut file = 0;
+    while file < 8 {
+        let (next, key) = next_key(state);
+        state = next;
+        keys.en_passant[file] = key;
+        file += 1;
+    }
+
+    keys
+}
+
+static KEYS: ZobristKeys = generate_keys();
+
+fn piece_index(piece_type: PieceType, color: Color) -> usize {
+    let type_index = match piece_type {
+        PieceType::Pawn => 0,
+        PieceType::Knight => 1,
+        PieceType::Bishop => 2,
+        PieceType::Rook => 3,
+        PieceType::Queen => 4,
+        PieceType::King => 5,
+    };
+    match color {
+        Color::White => type_index,
+        Color::Black => type_index + 6,
+    }
+}
+
+/// Computes the Zobrist hash of a position. Two boards with the same pieces,
+/// side to move, castling rights and capturable en passant square hash equally.
+pub fn hash(board: &Board) -> u64 {
+    let mut key = 0;
+
+    for (pos, piece) in board.pieces() {
+        key ^= KEYS.pieces[piece_index(piece.piece_type, piece.color)][pos.row * 8 + pos.col];
+    }
+
+    if board.current_player == Color::Black {
+        key ^= KEYS.black_to_move;
+    }
+
+    let rights = board.castling_rights;
+    for (i, allowed) in [
+        rights.white_kingside,
+        rights.white_queenside,
+        rights.black_kingside,
+        rights.black_queenside,
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        if allowed {
+            key ^= KEYS.castling[i];
+        }
+    }
+
+    // Only count the en passant square when it can actually be captured,
+    // otherwise transpositions after a double pawn push would never repeat
+    if let Some(target) = board.en_passant_target {
+        if en_passant_capturable(board, target) {
+            key ^= KEYS.en_passant[target.col];
+        }
+    }
+
+    key
+}
+
+fn en_passant_capturable(board: &Board, target: Position) -> bool {
+    let pawn_row = match board.current_player {
+        Color::White => target.row + 1,
+        Color::Black => target.row.wrapping_sub(1),
+    };
+    [target.col.wrapping_sub(1), target.col + 1]
+        .into_iter()
+        .map(|col| Position::new(pawn_row, col))
+        .filter(|pos| pos.is_valid())
+        .any(|pos| {
+            board.get_piece(pos).is_some_and(|p| {
+                p.piece_type == PieceType::Pawn && p.color == board.current_player
+            })
+        })
+}
@@ -0,0 +1,7 @@
+//! Chess rules and engine, independent of any user interface.
+//!
+//! `game` holds the board representation, move generation and rules
+//! (including FEN), and `ai` the minimax search.
+
+pub mod game;
+pub mod ai;
@@ -0,0 +1,138 @@
+//! The engine for the browser: a small API for JavaScript, built for
+//! `wasm32-unknown-unknown` without any binding generator. JavaScript
+//! writes its text into the input buffer, calls a function with the text's
+//! length, and reads the answer back from the output buffer: the function
+//! returns the answer's length, or -1 when the text makes no sense. The
+//! page in `web/` plays against the engine this way.
+
+use std::sync::Mutex;
+use std::time::Duration;
+
+use chess_core::ai::ChessAI;
+use chess_core::game::{Board, Move, MoveGenerator};
+
+/// Bytes each buffer holds
+pub const BUFFER_SIZE: usize = 4096;
+
+/// Deepest search `best_move` will run
+pub const MAX_DEPTH: u32 = 8;
+
+struct Buffers {
+    input: [u8; BUFFER_SIZE],
+    output: [u8; BUFFER_SIZE],
+}
+
+static BUFFERS: Mutex<Buffers> = Mutex::new(Buffers { input: [0; BUFFER_SIZE], output: [0; BUFFER_SIZE] });
+
+// The page's clock, performance.now(), for timing searches
+#[cfg(target_arch = "wasm32")]
+extern "C" {
+    fn now_ms() -> f64;
+}
+
+#[cfg(target_arch = "wasm32")]
+fn host_time() -> u64 {
+    // SAFETY: the page passes now_ms in when it loads the module
+    unsafe { now_ms() as u64 }
+}
+
+// Reads the `len` bytes of text in the input buffer, and writes `answer`'s
+// answer to it into the output buffer
+fn respond(len: usize, answer: impl FnOnce(&str) -> Option<String>) -> i32 {
+    let mut buffers = BUFFERS.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let Some(input) = buffers.input.get(..len) else {
+        return -1;
+    };
+    let Some(text) = std::str::from_utf8(input).ok().and_then(|text| answer(text.trim())) else {
+        return -1;
+    };
+    let bytes = text.as_bytes();
+    let len = bytes.len().min(BUFFER_SIZE);
+    buffers.output[..len].copy_from_slice(&bytes[..len]);
+    len as i32
+}
+
+// A FEN, then on the next line a move in UCI coordinates
+fn position_and_move(text: &str) -> Option<(Board, Move)> {
+    let (fen, uci) = text.split_once('\n')?;
+    let board = Board::from_fen(fen.trim()).ok()?;
+    let mv = MoveGenerator::find_uci_move(&board, uci.trim())?;
+    Some((board, mv))
+}
+
+/// Where JavaScript writes its text, `BUFFER_SIZE` bytes
+#[no_mangle]
+pub extern "C" fn input_buffer() -> *mut u8 {
+    BUFFERS.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).input.as_mut_ptr()
+}
+
+/// Where the answers are written, `BUFFER_SIZE` bytes
+#[no_mangle]
+pub extern "C" fn output_buffer() -> *const u8 {
+    BUFFERS.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).output.as_ptr()
+}
+
+#[no_mangle]
+pub extern "C" fn buffer_size() -> usize {
+    BUFFER_SIZE
+}
+
+/// The legal moves in the FEN position, in UCI coordinates separated by spaces
+#[no_mangle]
+pub extern "C" fn legal_moves(len: usize) -> i32 {
+    respond(len, |fen| {
+        let board = Board::from_fen(fen).ok()?;
+        let moves: Vec<String> = MoveGenerator::generate_all_legal_moves(&board).into_iter().map(Move::to_uci).collect();
+        Some(moves.join(" "))
+    })
+}
+
+/// The FEN after a move: the input is the FEN, then the move in UCI
+/// coordinates on the next line. -1 if the move is illegal.
+#[no_mangle]
+pub extern "C" fn make_move(len: usize) -> i32 {
+    respond(len, |text| {
+        let (mut board, mv) = position_and_move(text)?;
+        board.make_move(&mv);
+        Some(board.to_fen())
+    })
+}
+
+/// The engine's move in the FEN position, in UCI coordinates, searching
+/// `depth` plies or for `time_limit_ms` if that's not 0, and choosing
+/// among equal moves by `seed`. An empty answer when there's no move.
+#[no_mangle]
+pub extern "C" fn best_move(len: usize, depth: u32, time_limit_ms: u32, seed: u32) -> i32 {
+    respond(len, |fen| {
+        let board = Board::from_fen(fen).ok()?;
+        let mut engine = ChessAI::new(depth.clamp(1, MAX_DEPTH)).with_seed(u64::from(seed));
+        #[cfg(target_arch = "wasm32")]
+        {
+            engine = engine.with_time_source(host_time);
+        }
+        if time_limit_ms > 0 {
+            engine = engine.with_time_limit(Duration::from_millis(u64::from(time_limit_ms)));
+        }
+        Some(engine.get_best_move(&board).map_or(String::new(), |mv| mv.to_uci()))
+    })
+}
+
+/// Whether the game in the FEN position goes on: "playing", "checkmate",
+/// "stalemate" or "draw" for a position neither side can win
+#[no_mangle]
+pub extern "C" fn status(len: usize) -> i32 {
+    respond(len, |fen| {
+        let board = Board::from_fen(fen).ok()?;
+        let side = board.current_player;
+        let status = if MoveGenerator::is_checkmate(&board, side) {
+            "checkmate"
+        } else if MoveGenerator::is_stalemate(&board, side) {
+            "stalemate"
+        } else if MoveGenerator::is_dead_position(&board) {
+            "draw"
+        } else {
+            "playing"
+        };
+        Some(status.to_string())
+    })
+}
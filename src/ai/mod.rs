@@ -1,3 +0,0 @@
-pub mod engine;
-
-pub use engine::ChessAI;
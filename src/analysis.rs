@@ -0,0 +1,177 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+
+use chess_core::ai::eval::EvalWeights;
+use chess_core::ai::{mate_in, ChessAI};
+use chess_core::game::pgn::{MoveAnnotation, PgnGame};
+use chess_core::game::san::to_san;
+use chess_core::game::Color;
+
+/// Centipawns as pawns with a sign, or e.g. "+M4" for a forced mate in 4
+/// moves, "-M4" for being mated in 4
+pub fn format_score(score: i32) -> String {
+    match mate_in(score) {
+        // By the sign of the score, since a mate on the board is in 0
+        Some(moves) if score > 0 => format!("+M{}", moves),
+        Some(moves) => format!("-M{}", -moves),
+        None => format!("{:+.2}", score as f64 / 100.0),
+    }
+}
+
+/// The engine's verdict on one move of a game
+pub struct MoveReview {
+    pub mover: Color,
+    pub fullmove_number: u32,
+    pub san: String,
+    /// Score after the move, from White's point of view
+    pub score: i32,
+    /// "?!", "?" or "??" for inaccuracies, mistakes and blunders
+    pub mark: &'static str,
+    /// The engine's preferred move, for marked moves only
+    pub best: Option<String>,
+}
+
+impl MoveReview {
+    fn nag(&self) -> Option<u8> {
+        match self.mark {
+            "?" => Some(2),
+            "??" => Some(4),
+            "?!" => Some(6),
+            _ => None,
+        }
+    }
+
+    fn annotation(&self) -> MoveAnnotation {
+        let mut comment = if let Some(moves) = mate_in(self.score) {
+            let winner = if self.score > 0 { "White" } else { "Black" };
+            // Lichess-style mate tag, negative when Black mates
+            format!("[%eval #{}] {} has a forced mate in {}.", moves, winner, moves.abs())
+        } else {
+            // Lichess-style eval tag, in pawns
+            format!("[%eval {:.2}]", self.score as f64 / 100.0)
+        };
+        if let Some(best) = &self.best {
+            comment.push_str(&format!(" {} was better.", best));
+        }
+        MoveAnnotation {
+            nag: self.nag(),
+            comment: Some(comment),
+            ..MoveAnnotation::default()
+        }
+    }
+}
+
+/// How far a move fell short of the engine's choice, in centipawns, as a NAG-style mark
+fn judgement(loss: i32) -> &'static str {
+    match loss {
+        300.. => "??",
+        100.. => "?",
+        50.. => "?!",
+        _ => "",
+    }
+}
+
+pub fn review_game(game: &PgnGame, ai: &ChessAI) -> Vec<MoveReview> {
+    let mut board = game.initial_board.clone();
+    let mut reviews = Vec::with_capacity(game.moves.len());
+
+    for (index, mv) in game.moves.iter().enumerate() {
+        let mover = board.current_player;
+        ai.set_history(&game.initial_board, &game.moves[..index]);
+        let (mark, best, played) = match ai.search(&board) {
+            Some((best, best_score)) if best != *mv => {
+                let played = ai.score_move(&board, mv);
+                let mark = judgement(best_score - played);
+                let best = (!mark.is_empty()).then(|| to_san(&board, &best));
+                (mark, best, played)
+            }
+            Some((_, best_score)) => ("", None, best_score),
+            None => ("", None, 0),
+        };
+
+        reviews.push(MoveReview {
+            mover,
+            fullmove_number: board.fullmove_number,
+            san: to_san(&board, mv),
+            score: if mover == Color::White { played } else { -played },
+            mark,
+            best,
+        });
+        board.make_move(mv);
+    }
+
+    reviews
+}
+
+pub fn print_review(game: &PgnGame, reviews: &[MoveReview]) {
+    println!(
+        "{} - {}  {}",
+        game.tag("White").unwrap_or("?"),
+        game.tag("Black").unwrap_or("?"),
+        game.result
+    );
+    for review in reviews {
+        let number = match review.mover {
+            Color::White => format!("{}.", review.fullmove_number),
+            Color::Black => format!("{}...", review.fullmove_number),
+        };
+        let suggestion = review.best.as_ref().map_or(String::new(), |best| format!("best {}", best));
+        println!(
+            "{:>6} {:<9} {:>7}  {}",
+            number,
+            format!("{}{}", review.san, review.mark),
+            format_score(review.score),
+            suggestion
+        );
+    }
+    println!();
+}
+
+/// The game as PGN with an evaluation comment on every move and NAGs on bad ones
+pub fn annotate(game: &PgnGame, reviews: &[MoveReview]) -> String {
+    let annotations: Vec<MoveAnnotation> = reviews.iter().map(MoveReview::annotation).collect();
+    game.to_annotated_pgn(&annotations)
+}
+
+/// Annotates every game using `jobs` worker threads, calling `progress` with
+/// the number of finished games as they complete. Results keep the input order.
+pub fn annotate_all(
+    games: &[PgnGame],
+    depth: u32,
+    weights: &EvalWeights,
+    jobs: usize,
+    mut progress: impl FnMut(usize),
+) -> Vec<String> {
+    let next = AtomicUsize::new(0);
+    let (sender, receiver) = mpsc::channel();
+    let mut annotated = vec![String::new(); games.len()];
+
+    thread::scope(|scope| {
+        for _ in 0..jobs.clamp(1, games.len().max(1)) {
+            let sender = sender.clone();
+            let next = &next;
+            scope.spawn(move || {
+                let ai = ChessAI::new(depth).with_weights(weights.clone());
+                loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(game) = games.get(index) else {
+                        break;
+                    };
+                    let pgn = annotate(game, &review_game(game, &ai));
+                    if sender.send((index, pgn)).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(sender);
+
+        for (done, (index, pgn)) in receiver.iter().enumerate() {
+            annotated[index] = pgn;
+            progress(done + 1);
+        }
+    });
+
+    annotated
+}
@@ -0,0 +1,13 @@
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+use chess_core::ai::script::BotScript;
+
+/// Reads a custom bot's script, a TOML file of opening lines, code and rules
+pub fn load(path: &Path) -> Result<BotScript, Box<dyn Error>> {
+    let text = fs::read_to_string(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+    let script: BotScript = toml::from_str(&text).map_err(|err| format!("{}: {}", path.display(), err))?;
+    script.validate().map_err(|err| format!("{}: {}", path.display(), err))?;
+    Ok(script)
+}
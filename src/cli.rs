@@ -0,0 +1,881 @@
+use std::error::Error;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use clap::{Parser, Subcommand};
+
+use chess_core::ai::rng::Rng;
+use chess_core::ai::eval::EvalWeights;
+use chess_core::ai::training::TrainingPosition;
+use chess_core::ai::tune::{self, LabeledPosition, Tuner};
+use chess_core::ai::{bench, ChessAI, Pruning, MATE_THRESHOLD, OPENING_VARIETY_MOVES};
+use chess_core::game::epd;
+use chess_core::game::fen::STARTING_FEN;
+use chess_core::game::perft;
+use chess_core::game::pgn::{self, PgnGame};
+use chess_core::game::san::to_san;
+use chess_core::game::{Board, Color, Game, Move, MoveGenerator, Position};
+
+use crate::analysis::{self, format_score};
+use crate::engine_match::{self, Adjudication, PruningFlags};
+use crate::eval_weights::{self, WeightsFile};
+use crate::headless;
+use crate::server;
+use crate::tournament::{self, Crosstable, EngineSpec, Entrant, Format};
+use crate::painter::svg::SvgOptions;
+use crate::painter::BoardPainter;
+
+const DEFAULT_DEPTH: u32 = 3;
+// Deep enough for the timing to settle, a few seconds in a release build
+const BENCH_DEPTH: u32 = 4;
+
+#[derive(Parser)]
+#[command(name = "chess", version, about = "Terminal chess with an AI opponent")]
+pub struct Cli {
+    /// A PGN or JSON game to open: a finished one to step through, or one
+    /// in progress to play on from
+    #[arg(value_name = "GAME", conflicts_with = "fen")]
+    pub game: Option<PathBuf>,
+
+    /// A position to open in the UI, to play both sides from
+    #[arg(long)]
+    pub fen: Option<String>,
+
+    /// Run the headless WebSocket game server instead of the TUI
+    #[arg(long, value_name = "ADDR", num_args = 0..=1, default_missing_value = server::DEFAULT_ADDR)]
+    pub serve: Option<String>,
+
+    /// Draw the board and interface in plain ASCII, for terminals without
+    /// chess or box drawing glyphs
+    #[arg(long)]
+    pub ascii: bool,
+
+    /// Describe each move in words beside the board and keep the command
+    /// line open for typed moves, for playing with a screen reader
+    #[arg(long)]
+    pub screen_reader: bool,
+
+    /// Append a log of the computer's searches, key presses and screen
+    /// changes to this file, for tracking down problems
+    #[arg(long, value_name = "FILE")]
+    pub log_file: Option<PathBuf>,
+
+    /// Seed the computer's choice among equally good moves in the first
+    /// moves of a game, so the same moves get the same replies
+    #[arg(long, value_name = "N")]
+    pub seed: Option<u64>,
+
+    /// Evaluate the computer's moves in the UI with the piece values and
+    /// square tables in this file, as written by `chess tune` (JSON, or
+    /// TOML for a .toml file). The commands that search take their own.
+    #[arg(long, value_name = "FILE")]
+    pub eval_weights: Option<PathBuf>,
+
+    /// Load a custom bot from this script, to play as "Custom Bot" from
+    /// the menu: a TOML file with its name, opening lines in UCI and the
+    /// rules it picks its moves by
+    #[arg(long, value_name = "FILE")]
+    pub bot: Option<PathBuf>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Play in the terminal UI (the default), or headless with --engine-depth
+    Play {
+        /// Play without the UI: read the opponent's moves from stdin as UCI
+        /// coordinates (e2e4, e7e8q) and print the engine's replies to stdout
+        #[arg(long, value_name = "DEPTH")]
+        engine_depth: Option<u32>,
+        /// Let the engine play White and move first
+        #[arg(long, requires = "engine_depth")]
+        white: bool,
+        /// Position to start from in headless mode
+        #[arg(long, requires = "engine_depth")]
+        fen: Option<String>,
+        /// Seed the engine's choice among equally good opening moves
+        #[arg(long, value_name = "N", requires = "engine_depth")]
+        seed: Option<u64>,
+        #[command(flatten)]
+        weights: WeightsFile,
+        #[command(flatten)]
+        adjudication: Adjudication,
+    },
+    /// Play the engine against itself at two depths and report the score.
+    /// Only the first engine evaluates with --eval-weights.
+    Match {
+        /// The first engine's search depth
+        #[arg(long, default_value_t = DEFAULT_DEPTH)]
+        depth: u32,
+        /// The second engine's search depth
+        #[arg(long, value_name = "DEPTH", default_value_t = DEFAULT_DEPTH)]
+        opponent_depth: u32,
+        /// Games to play; the engines swap colors after each
+        #[arg(long, default_value_t = 2)]
+        games: u32,
+        /// Position to start every game from
+        #[arg(long)]
+        fen: Option<String>,
+        /// File to write the games to as PGN
+        #[arg(long)]
+        out: Option<PathBuf>,
+        /// Seed the engines' choices among equally good opening moves, to
+        /// replay a match; a random seed is picked and printed otherwise
+        #[arg(long, value_name = "N")]
+        seed: Option<u64>,
+        #[command(flatten)]
+        pruning: PruningFlags,
+        #[command(flatten)]
+        weights: WeightsFile,
+        #[command(flatten)]
+        adjudication: Adjudication,
+    },
+    /// Play several engines, built in or external UCI ones, against each
+    /// other and print a crosstable of the scores
+    Tournament {
+        /// An engine to enter, e.g. "depth=4", "level=beginner,depth=2",
+        /// "weights=tuned.json,name=Tuned" or "uci=/usr/bin/stockfish,movetime=100";
+        /// give at least two
+        #[arg(long = "engine", value_name = "SPEC", required = true, num_args = 1)]
+        engines: Vec<EngineSpec>,
+        /// Games each pair of engines plays; they swap colors after each
+        #[arg(long, default_value_t = 2)]
+        games: u32,
+        /// Play the first engine against each of the others only, instead
+        /// of every engine against every other
+        #[arg(long)]
+        gauntlet: bool,
+        /// Position to start every game from
+        #[arg(long)]
+        fen: Option<String>,
+        /// File to write all the games to as PGN
+        #[arg(long)]
+        out: Option<PathBuf>,
+        /// Seed the built-in engines' choices among equally good opening
+        /// moves, to replay a tournament; a random seed is picked and
+        /// printed otherwise
+        #[arg(long, value_name = "N")]
+        seed: Option<u64>,
+        // For the built-in engines whose spec names no weights of their own
+        #[command(flatten)]
+        weights: WeightsFile,
+        #[command(flatten)]
+        adjudication: Adjudication,
+    },
+    /// Play the engine against itself and record every position searched,
+    /// with its score and the game's result, as training data for `tune`
+    Selfplay {
+        /// Games to play
+        #[arg(long, default_value_t = 10)]
+        games: u32,
+        #[arg(long, default_value_t = DEFAULT_DEPTH)]
+        depth: u32,
+        /// Position to start every game from
+        #[arg(long)]
+        fen: Option<String>,
+        /// File to write the positions to
+        #[arg(long, default_value = "selfplay.bin")]
+        out: PathBuf,
+        /// Seed the engine's choices among equally good opening moves, to
+        /// play the same games again; a random seed is picked otherwise
+        #[arg(long, value_name = "N")]
+        seed: Option<u64>,
+        #[command(flatten)]
+        weights: WeightsFile,
+        #[command(flatten)]
+        adjudication: Adjudication,
+    },
+    /// Count the leaf nodes of the legal move tree
+    Perft {
+        depth: u32,
+        /// Position to count from instead of the starting position
+        #[arg(long)]
+        fen: Option<String>,
+        /// Also print the count under each root move, to narrow down where a
+        /// wrong total comes from
+        #[arg(long)]
+        divide: bool,
+        /// Threads to share the root moves among (one per CPU by default)
+        #[arg(long)]
+        jobs: Option<usize>,
+    },
+    /// Print the engine's move for a position
+    Bestmove {
+        #[arg(long, default_value = STARTING_FEN)]
+        fen: String,
+        #[arg(long, default_value_t = DEFAULT_DEPTH)]
+        depth: u32,
+        #[command(flatten)]
+        weights: WeightsFile,
+    },
+    /// Evaluate every move of the games in a PGN file
+    Analyze {
+        pgn: PathBuf,
+        #[arg(long, default_value_t = DEFAULT_DEPTH)]
+        depth: u32,
+        #[command(flatten)]
+        weights: WeightsFile,
+    },
+    /// Search a fixed set of positions and report nodes and speed
+    Bench {
+        #[arg(long, default_value_t = BENCH_DEPTH)]
+        depth: u32,
+    },
+    /// Run the engine over an EPD test suite and report how many it solves
+    Epd {
+        file: PathBuf,
+        #[arg(long, default_value_t = DEFAULT_DEPTH)]
+        depth: u32,
+        #[command(flatten)]
+        weights: WeightsFile,
+    },
+    /// Tune the evaluation's piece values and square tables to positions
+    /// labeled with the results of their games
+    Tune {
+        /// EPD file of positions, each with its game's result as a `c9`
+        /// operation, e.g. `c9 "1-0";`, or a .bin file from `selfplay`
+        positions: PathBuf,
+        /// File to write the tuned weights to, as JSON
+        #[arg(long, default_value = "weights.json")]
+        out: PathBuf,
+        /// Gradient descent steps to take
+        #[arg(long, default_value_t = 1000)]
+        iterations: u32,
+        // Weights to start tuning from, instead of the built-in ones
+        #[command(flatten)]
+        weights: WeightsFile,
+    },
+    /// Print a position as a text diagram
+    Show {
+        #[arg(long, default_value = STARTING_FEN)]
+        fen: String,
+        /// View the board from Black's side
+        #[arg(long)]
+        flip: bool,
+    },
+    /// Draw a position as an SVG image
+    Svg {
+        #[arg(long, default_value = STARTING_FEN)]
+        fen: String,
+        /// Move that led to the position, in UCI coordinates, to highlight
+        #[arg(long, value_name = "UCI")]
+        last_move: Option<String>,
+        /// Light square color (any SVG color, e.g. "#f0d9b5")
+        #[arg(long)]
+        light: Option<String>,
+        /// Dark square color
+        #[arg(long)]
+        dark: Option<String>,
+        /// Don't highlight the last move or a king in check
+        #[arg(long)]
+        no_highlights: bool,
+        /// View the board from Black's side
+        #[arg(long)]
+        flip: bool,
+        /// File to write the SVG to (standard output by default)
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+    /// Convert the games in a PGN file to JSON
+    ExportJson {
+        pgn: PathBuf,
+        /// File to write the JSON to (standard output by default)
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+    /// Annotate every game in a PGN file with evaluations and blunder marks
+    AnalyzeBatch {
+        pgn: PathBuf,
+        #[arg(long, default_value_t = DEFAULT_DEPTH)]
+        depth: u32,
+        /// File to write the annotated PGN to (standard output by default)
+        #[arg(long)]
+        out: Option<PathBuf>,
+        /// Games to analyze in parallel (one per CPU by default)
+        #[arg(long)]
+        jobs: Option<usize>,
+        #[command(flatten)]
+        weights: WeightsFile,
+    },
+}
+
+pub fn run(command: Command) -> Result<(), Box<dyn Error>> {
+    match command {
+        // main starts the TUI itself
+        Command::Play { engine_depth: None, .. } => Ok(()),
+        Command::Play { engine_depth: Some(depth), white, fen, seed, weights, adjudication } => {
+            run_headless(depth, white, fen.as_deref(), seed, weights.load()?, &adjudication)
+        }
+        Command::Match { depth, opponent_depth, games, fen, out, seed, pruning, weights, adjudication } => {
+            let first = FirstEngine { pruning: &pruning, weights: weights.load_with_path()? };
+            run_match([depth, opponent_depth], games, fen.as_deref(), out.as_deref(), seed, first, &adjudication)
+        }
+        Command::Tournament { engines, games, gauntlet, fen, out, seed, weights, adjudication } => {
+            let format = if gauntlet { Format::Gauntlet } else { Format::RoundRobin };
+            let event = TournamentEvent { format, games, fen: fen.as_deref(), seed };
+            run_tournament(&engines, event, out.as_deref(), &weights.load()?, &adjudication)
+        }
+        Command::Selfplay { games, depth, fen, out, seed, weights, adjudication } => {
+            run_selfplay(games, depth, fen.as_deref(), &out, seed, weights.load()?, &adjudication)
+        }
+        Command::Perft { depth, fen, divide, jobs } => run_perft(depth, fen.as_deref(), divide, jobs),
+        Command::Bestmove { fen, depth, weights } => run_bestmove(&fen, depth, weights.load()?),
+        Command::Analyze { pgn, depth, weights } => run_analyze(&pgn, depth, weights.load()?),
+        Command::Bench { depth } => run_bench(depth),
+        Command::Epd { file, depth, weights } => run_epd(&file, depth, weights.load()?),
+        Command::Tune { positions, out, iterations, weights } => {
+            run_tune(&positions, &out, iterations, &weights.load()?)
+        }
+        Command::Show { fen, flip } => {
+            print!("{}", BoardPainter::new(&Board::from_fen(&fen)?).flip(flip).to_text());
+            Ok(())
+        }
+        Command::Svg { fen, last_move, light, dark, no_highlights, flip, out } => {
+            let mut options = SvgOptions::default();
+            options.light_square = light.unwrap_or(options.light_square);
+            options.dark_square = dark.unwrap_or(options.dark_square);
+            if no_highlights {
+                options.last_move = None;
+                options.check = None;
+            }
+            run_svg(&fen, last_move.as_deref(), &options, flip, out.as_deref())
+        }
+        Command::ExportJson { pgn, out } => run_export_json(&pgn, out.as_deref()),
+        Command::AnalyzeBatch { pgn, depth, out, jobs, weights } => {
+            run_analyze_batch(&pgn, depth, &weights.load()?, out.as_deref(), jobs)
+        }
+    }
+}
+
+fn check_depth(depth: u32) -> Result<(), Box<dyn Error>> {
+    if depth == 0 {
+        return Err("depth must be at least 1".into());
+    }
+    Ok(())
+}
+
+fn run_perft(depth: u32, fen: Option<&str>, divide: bool, jobs: Option<usize>) -> Result<(), Box<dyn Error>> {
+    let board = Board::from_fen(fen.unwrap_or(STARTING_FEN))?;
+    let jobs = jobs
+        .or_else(|| thread::available_parallelism().ok().map(|jobs| jobs.get()))
+        .unwrap_or(1);
+    let started = Instant::now();
+    let mut counts = perft::divide(&board, depth, jobs);
+    let nodes = if depth == 0 { 1 } else { counts.iter().map(|&(_, count)| count).sum() };
+    let elapsed = started.elapsed();
+
+    if divide {
+        eprintln!("{}\n", board);
+        // Sorted the way other engines list them, to compare line by line
+        counts.sort_by_cached_key(|(mv, _)| mv.to_uci());
+        for (mv, count) in &counts {
+            println!("{}: {}", mv.to_uci(), count);
+        }
+        println!();
+    }
+    println!("{}", nodes);
+    eprintln!(
+        "perft({}) in {:.3}s ({:.0} nodes/s)",
+        depth,
+        elapsed.as_secs_f64(),
+        nodes as f64 / elapsed.as_secs_f64().max(1e-9)
+    );
+    Ok(())
+}
+
+fn run_bestmove(fen: &str, depth: u32, weights: EvalWeights) -> Result<(), Box<dyn Error>> {
+    check_depth(depth)?;
+    let board = Board::from_fen(fen)?;
+    match ChessAI::new(depth).with_weights(weights).search(&board) {
+        Some((mv, score)) => {
+            println!("info depth {} score {} san {}", depth, format_score(score), to_san(&board, &mv));
+            println!("bestmove {}", mv.to_uci());
+        }
+        None => println!("bestmove (none)"),
+    }
+    Ok(())
+}
+
+fn run_headless(
+    depth: u32,
+    white: bool,
+    fen: Option<&str>,
+    seed: Option<u64>,
+    weights: EvalWeights,
+    adjudication: &Adjudication,
+) -> Result<(), Box<dyn Error>> {
+    check_depth(depth)?;
+    let board = Board::from_fen(fen.unwrap_or(STARTING_FEN))?;
+    let engine_color = if white { Color::White } else { Color::Black };
+    let mut ai = ChessAI::new(depth).with_variety(OPENING_VARIETY_MOVES).with_weights(weights);
+    ai.seed = seed;
+    headless::play(board, &ai, engine_color, adjudication)
+}
+
+// What the first engine of a match plays with that its opponent doesn't
+struct FirstEngine<'a> {
+    pruning: &'a PruningFlags,
+    // The --eval-weights file and its weights
+    weights: Option<(&'a Path, EvalWeights)>,
+}
+
+// `depths` are the first engine's and its opponent's
+fn run_match(
+    depths: [u32; 2],
+    games: u32,
+    fen: Option<&str>,
+    out: Option<&Path>,
+    seed: Option<u64>,
+    first: FirstEngine,
+    adjudication: &Adjudication,
+) -> Result<(), Box<dyn Error>> {
+    let [depth, opponent_depth] = depths;
+    check_depth(depth)?;
+    check_depth(opponent_depth)?;
+    let initial = Board::from_fen(fen.unwrap_or(STARTING_FEN))?;
+    let seed = seed.unwrap_or_else(|| {
+        let seed = Rng::from_time().next_u64();
+        println!("Seed {} (--seed {} plays these games again)", seed, seed);
+        seed
+    });
+    let pruning = first.pruning;
+    let with_weights = first.weights.as_ref().map_or(String::new(), |(path, _)| format!(" with {}", path.display()));
+    let names = [format!("Depth {}{}{}", depth, with_weights, pruning.label()), format!("Depth {}", opponent_depth)];
+    let weights = [first.weights.map(|(_, weights)| weights).unwrap_or_default(), EvalWeights::default()];
+
+    // Wins, losses and draws of the first engine
+    let (mut wins, mut losses, mut draws) = (0, 0, 0);
+    let mut pgn = Vec::new();
+    for round in 1..=games {
+        // The first engine takes White in odd rounds
+        let (white, black) = if round % 2 == 1 { (0, 1) } else { (1, 0) };
+        // Each round's own seed, so repeated pairings still play new games
+        // Only the first engine leaves out the prunings the flags name
+        // and evaluates with --eval-weights
+        let engines = [(depth, pruning.pruning(), &weights[0]), (opponent_depth, Pruning::default(), &weights[1])]
+            .map(|(depth, pruning, weights)| {
+                ChessAI::new(depth)
+                    .with_variety(OPENING_VARIETY_MOVES)
+                    .with_seed(seed.wrapping_add(u64::from(round)))
+                    .with_pruning(pruning)
+                    .with_weights(weights.clone())
+            });
+        let (mut game, reason) = engine_match::play_game(&initial, &engines[white], &engines[black], adjudication, |_, _| {});
+        let first_won = match game.result.as_str() {
+            "1-0" => Some(white == 0),
+            "0-1" => Some(black == 0),
+            _ => None,
+        };
+        match first_won {
+            Some(true) => wins += 1,
+            Some(false) => losses += 1,
+            None => draws += 1,
+        }
+        println!(
+            "Game {:>3}: {} - {}  {} {{{}}} in {} moves",
+            round,
+            names[white],
+            names[black],
+            game.result,
+            reason,
+            game.moves.len().div_ceil(2)
+        );
+
+        let mut tags = vec![
+            ("Event".to_string(), "Engine match".to_string()),
+            ("Round".to_string(), round.to_string()),
+            ("White".to_string(), names[white].clone()),
+            ("Black".to_string(), names[black].clone()),
+            ("Result".to_string(), game.result.clone()),
+        ];
+        tags.append(&mut game.tags);
+        game.tags = tags;
+        pgn.push(PgnGame::from(game).to_pgn());
+    }
+
+    println!(
+        "{} vs {}: {} - {} (+{} -{} ={})",
+        names[0],
+        names[1],
+        wins as f64 + draws as f64 / 2.0,
+        losses as f64 + draws as f64 / 2.0,
+        wins,
+        losses,
+        draws
+    );
+    if let Some(out) = out {
+        fs::write(out, pgn.join("\n"))?;
+    }
+    Ok(())
+}
+
+// How a tournament is played, apart from who plays in it
+struct TournamentEvent<'a> {
+    format: Format,
+    // Games per pairing
+    games: u32,
+    fen: Option<&'a str>,
+    seed: Option<u64>,
+}
+
+fn run_tournament(
+    specs: &[EngineSpec],
+    event: TournamentEvent,
+    out: Option<&Path>,
+    weights: &EvalWeights,
+    adjudication: &Adjudication,
+) -> Result<(), Box<dyn Error>> {
+    if specs.len() < 2 {
+        return Err("a tournament needs at least two engines".into());
+    }
+    let initial = Board::from_fen(event.fen.unwrap_or(STARTING_FEN))?;
+    let mut names = Vec::new();
+    let mut entrants = Vec::new();
+    for spec in specs {
+        let (name, entrant) = Entrant::start(spec, weights)?;
+        // Numbered when the same engine is entered twice, to tell them apart
+        let repeats = names.iter().filter(|other: &&String| other.split(" #").next() == Some(name.as_str())).count();
+        names.push(if repeats == 0 { name } else { format!("{} #{}", name, repeats + 1) });
+        entrants.push(entrant);
+    }
+    let seed = event.seed.unwrap_or_else(|| {
+        let seed = Rng::from_time().next_u64();
+        println!("Seed {} (--seed {} plays these games again)", seed, seed);
+        seed
+    });
+
+    let mut crosstable = Crosstable::new(names.clone());
+    let mut pgn = Vec::new();
+    let mut round: u32 = 0;
+    for (first, second) in tournament::pairings(entrants.len(), event.format) {
+        for game_number in 1..=event.games {
+            round += 1;
+            let (white, black) = if game_number % 2 == 1 { (first, second) } else { (second, first) };
+            // Each game's own seed, so repeated pairings still play new games
+            let game_seed = seed.wrapping_add(u64::from(round));
+            let (mut built_white, mut built_black) = (None, None);
+            let white_engine = entrants[white].engine(game_seed, &mut built_white);
+            let black_engine = entrants[black].engine(game_seed, &mut built_black);
+            let (mut game, reason) =
+                engine_match::play_game(&initial, white_engine, black_engine, adjudication, |_, _| {});
+            crosstable.record(white, black, &game.result);
+            println!(
+                "Game {:>3}: {} - {}  {} {{{}}} in {} moves",
+                round,
+                names[white],
+                names[black],
+                game.result,
+                reason,
+                game.moves.len().div_ceil(2)
+            );
+
+            let mut tags = vec![
+                ("Event".to_string(), "Engine tournament".to_string()),
+                ("Round".to_string(), round.to_string()),
+                ("White".to_string(), names[white].clone()),
+                ("Black".to_string(), names[black].clone()),
+                ("Result".to_string(), game.result.clone()),
+            ];
+            tags.append(&mut game.tags);
+            game.tags = tags;
+            pgn.push(PgnGame::from(game).to_pgn());
+        }
+    }
+
+    print!("\n{}", crosstable);
+    if let Some(out) = out {
+        fs::write(out, pgn.join("\n"))?;
+    }
+    Ok(())
+}
+
+fn run_selfplay(
+    games: u32,
+    depth: u32,
+    fen: Option<&str>,
+    out: &Path,
+    seed: Option<u64>,
+    weights: EvalWeights,
+    adjudication: &Adjudication,
+) -> Result<(), Box<dyn Error>> {
+    check_depth(depth)?;
+    let initial = Board::from_fen(fen.unwrap_or(STARTING_FEN))?;
+    let seed = seed.unwrap_or_else(|| {
+        let seed = Rng::from_time().next_u64();
+        println!("Seed {} (--seed {} plays these games again)", seed, seed);
+        seed
+    });
+
+    let mut data = Vec::new();
+    let mut recorded = 0;
+    for round in 1..=games {
+        let engine = ChessAI::new(depth)
+            .with_variety(OPENING_VARIETY_MOVES)
+            .with_seed(seed.wrapping_add(u64::from(round)))
+            .with_weights(weights.clone());
+        // Positions in check or with a mate found say little about the
+        // evaluation, so they're left out
+        let mut searched = Vec::new();
+        let (game, reason) = engine_match::play_game(&initial, &engine, &engine, adjudication, |board, score| {
+            if score.abs() < MATE_THRESHOLD && !MoveGenerator::is_in_check(board, board.current_player) {
+                searched.push((board.clone(), score));
+            }
+        });
+        let Some(result) = tune::parse_result(&game.result) else {
+            println!("Game {:>3}: no result ({}), positions left out", round, reason);
+            continue;
+        };
+        for (board, score) in searched.iter().cloned() {
+            TrainingPosition { board, score, result }.encode(&mut data);
+        }
+        recorded += searched.len();
+        println!(
+            "Game {:>3}: {} {{{}}} in {} moves, {} positions",
+            round,
+            game.result,
+            reason,
+            game.moves.len().div_ceil(2),
+            searched.len()
+        );
+    }
+
+    fs::write(out, data)?;
+    println!("Wrote {} positions to {}", recorded, out.display());
+    Ok(())
+}
+
+fn run_analyze(path: &Path, depth: u32, weights: EvalWeights) -> Result<(), Box<dyn Error>> {
+    check_depth(depth)?;
+    let games = read_games(path)?;
+
+    let ai = ChessAI::new(depth).with_weights(weights);
+    for game in &games {
+        analysis::print_review(game, &analysis::review_game(game, &ai));
+    }
+    Ok(())
+}
+
+fn read_games(path: &Path) -> Result<Vec<PgnGame>, Box<dyn Error>> {
+    let text = fs::read_to_string(path)?;
+    let games = pgn::parse_pgn(&text)?;
+    if games.is_empty() {
+        return Err(format!("no games found in {}", path.display()).into());
+    }
+    Ok(games)
+}
+
+fn draw_progress(done: usize, total: usize) {
+    const WIDTH: usize = 30;
+    let filled = done * WIDTH / total;
+    eprint!(
+        "\r[{}{}] {}/{} games",
+        "#".repeat(filled),
+        "-".repeat(WIDTH - filled),
+        done,
+        total
+    );
+    let _ = io::stderr().flush();
+}
+
+fn run_analyze_batch(
+    path: &Path,
+    depth: u32,
+    weights: &EvalWeights,
+    out: Option<&Path>,
+    jobs: Option<usize>,
+) -> Result<(), Box<dyn Error>> {
+    check_depth(depth)?;
+    let games = read_games(path)?;
+    let jobs = jobs
+        .or_else(|| thread::available_parallelism().ok().map(|jobs| jobs.get()))
+        .unwrap_or(1);
+
+    draw_progress(0, games.len());
+    let annotated = analysis::annotate_all(&games, depth, weights, jobs, |done| draw_progress(done, games.len()));
+    eprintln!();
+
+    let output = annotated.join("\n");
+    match out {
+        Some(out) => fs::write(out, output)?,
+        None => print!("{}", output),
+    }
+    Ok(())
+}
+
+fn run_svg(
+    fen: &str,
+    last_move: Option<&str>,
+    options: &SvgOptions,
+    flip: bool,
+    out: Option<&Path>,
+) -> Result<(), Box<dyn Error>> {
+    let board = Board::from_fen(fen)?;
+    // The move is already on the board, so only its squares are needed
+    let last_move = match last_move {
+        Some(uci) => {
+            let squares = (uci.get(0..2).and_then(Position::from_algebraic), uci.get(2..4).and_then(Position::from_algebraic));
+            let (Some(from), Some(to)) = squares else {
+                return Err(format!("invalid move: {}", uci).into());
+            };
+            let piece = board.get_piece(to).ok_or_else(|| format!("no piece on the destination of {}", uci))?;
+            Some(Move::new(from, to, piece))
+        }
+        None => None,
+    };
+
+    let image = BoardPainter::new(&board)
+        .last_move(last_move.as_ref())
+        .show_check(true)
+        .flip(flip)
+        .to_svg(options);
+    match out {
+        Some(out) => fs::write(out, image)?,
+        None => print!("{}", image),
+    }
+    Ok(())
+}
+
+fn run_export_json(path: &Path, out: Option<&Path>) -> Result<(), Box<dyn Error>> {
+    let games: Vec<Game> = read_games(path)?.into_iter().map(Game::from).collect();
+    let json = serde_json::to_string_pretty(&games)?;
+    match out {
+        Some(out) => fs::write(out, json)?,
+        None => println!("{}", json),
+    }
+    Ok(())
+}
+
+fn run_epd(path: &Path, depth: u32, weights: EvalWeights) -> Result<(), Box<dyn Error>> {
+    check_depth(depth)?;
+    let records = epd::parse_epd(&fs::read_to_string(path)?)?;
+    let ai = ChessAI::new(depth).with_weights(weights);
+
+    let mut solved = 0;
+    let mut tested = 0;
+    let mut total_time = Duration::ZERO;
+
+    for (index, record) in records.iter().enumerate() {
+        let name = record.id().map_or_else(|| format!("#{}", index + 1), str::to_string);
+        let (best, avoid) = match (record.best_moves(), record.avoid_moves()) {
+            (Ok(best), Ok(avoid)) => (best, avoid),
+            (Err(err), _) | (_, Err(err)) => {
+                println!("{:<16} skipped: {}", name, err);
+                continue;
+            }
+        };
+        if best.is_empty() && avoid.is_empty() {
+            println!("{:<16} skipped: no bm or am operation", name);
+            continue;
+        }
+
+        let started = Instant::now();
+        let found = ai.get_best_move(&record.board);
+        let elapsed = started.elapsed();
+        total_time += elapsed;
+        tested += 1;
+
+        let ok = found.is_some_and(|mv| (best.is_empty() || best.contains(&mv)) && !avoid.contains(&mv));
+        if ok {
+            solved += 1;
+        }
+
+        let expected: Vec<String> = best
+            .iter()
+            .map(|mv| format!("bm {}", to_san(&record.board, mv)))
+            .chain(avoid.iter().map(|mv| format!("am {}", to_san(&record.board, mv))))
+            .collect();
+        println!(
+            "{:<16} {:<4} {:<8} ({})  {:.2}s",
+            name,
+            if ok { "ok" } else { "FAIL" },
+            found.map_or("none".to_string(), |mv| to_san(&record.board, &mv)),
+            expected.join(", "),
+            elapsed.as_secs_f64()
+        );
+    }
+
+    if tested > 0 {
+        println!(
+            "Solved {}/{} ({:.1}%) in {:.2}s, {:.2}s per position",
+            solved,
+            tested,
+            solved as f64 * 100.0 / tested as f64,
+            total_time.as_secs_f64(),
+            total_time.as_secs_f64() / tested as f64
+        );
+    } else {
+        println!("No testable positions in {}", path.display());
+    }
+    Ok(())
+}
+
+// Tunes from `weights`, the defaults unless --eval-weights gives others
+fn run_tune(path: &Path, out: &Path, iterations: u32, weights: &EvalWeights) -> Result<(), Box<dyn Error>> {
+    let positions = read_labeled_positions(path)?;
+
+    let mut tuner = Tuner::new(&positions, weights);
+    if tuner.positions() == 0 {
+        return Err(format!("no positions to tune with in {}", path.display()).into());
+    }
+    let started = Instant::now();
+    println!("{} positions, scale {:.3}, error {:.6}", tuner.positions(), tuner.scale(), tuner.error());
+    for iteration in 1..=iterations {
+        tuner.step();
+        if iteration % 100 == 0 || iteration == iterations {
+            println!("Iteration {:>5}: error {:.6}", iteration, tuner.error());
+        }
+    }
+
+    eval_weights::save(out, &tuner.weights())?;
+    println!("Wrote {} after {:.1}s", out.display(), started.elapsed().as_secs_f64());
+    Ok(())
+}
+
+// Positions with their games' results, from an EPD file or, for a .bin
+// file, the training data `selfplay` writes
+fn read_labeled_positions(path: &Path) -> Result<Vec<LabeledPosition>, Box<dyn Error>> {
+    if path.extension().is_some_and(|extension| extension == "bin") {
+        let positions = TrainingPosition::decode_all(&fs::read(path)?)?;
+        return Ok(positions
+            .into_iter()
+            .map(|position| LabeledPosition { board: position.board, result: position.result })
+            .collect());
+    }
+    let records = epd::parse_epd(&fs::read_to_string(path)?)?;
+    let positions = records
+        .into_iter()
+        .enumerate()
+        .map(|(index, record)| {
+            let result = record.operands("c9").and_then(|operands| tune::parse_result(operands.first()?));
+            match result {
+                Some(result) => Ok(LabeledPosition { board: record.board, result }),
+                None => Err(format!("position {}: no game result in a c9 operation", index + 1)),
+            }
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(positions)
+}
+
+fn run_bench(depth: u32) -> Result<(), Box<dyn Error>> {
+    check_depth(depth)?;
+    let total = bench::bench(depth, |index, result| {
+        println!(
+            "Position {:>2}/{}: {:>10} nodes  {:>9.0} nps",
+            index + 1,
+            bench::BENCH_POSITIONS.len(),
+            result.nodes,
+            result.nodes_per_second()
+        );
+    });
+
+    println!("===========================");
+    println!("Total time (ms) : {}", total.elapsed.as_millis());
+    println!("Nodes searched  : {}", total.nodes);
+    println!("Nodes/second    : {:.0}", total.nodes_per_second());
+    Ok(())
+}
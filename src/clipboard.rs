@@ -0,0 +1,65 @@
+use std::fmt;
+
+#[derive(Debug)]
+pub enum ClipboardError {
+    /// Built without the `clipboard` feature
+    #[cfg(not(feature = "clipboard"))]
+    Unsupported,
+    #[cfg(feature = "clipboard")]
+    Failed(String),
+}
+
+impl fmt::Display for ClipboardError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            #[cfg(not(feature = "clipboard"))]
+            ClipboardError::Unsupported => write!(f, "clipboard support is not built in"),
+            #[cfg(feature = "clipboard")]
+            ClipboardError::Failed(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for ClipboardError {}
+
+/// The system clipboard. On X11 copied text is served by this process, so
+/// one instance is kept for the whole session rather than one per copy.
+#[derive(Default)]
+pub struct Clipboard {
+    #[cfg(feature = "clipboard")]
+    inner: Option<arboard::Clipboard>,
+}
+
+#[cfg(feature = "clipboard")]
+impl Clipboard {
+    fn system(&mut self) -> Result<&mut arboard::Clipboard, ClipboardError> {
+        if self.inner.is_none() {
+            let clipboard = arboard::Clipboard::new().map_err(|err| ClipboardError::Failed(err.to_string()))?;
+            self.inner = Some(clipboard);
+        }
+        Ok(self.inner.as_mut().expect("clipboard was just opened"))
+    }
+
+    pub fn copy(&mut self, text: &str) -> Result<(), ClipboardError> {
+        self.system()?
+            .set_text(text)
+            .map_err(|err| ClipboardError::Failed(err.to_string()))
+    }
+
+    pub fn paste(&mut self) -> Result<String, ClipboardError> {
+        self.system()?
+            .get_text()
+            .map_err(|err| ClipboardError::Failed(err.to_string()))
+    }
+}
+
+#[cfg(not(feature = "clipboard"))]
+impl Clipboard {
+    pub fn copy(&mut self, _text: &str) -> Result<(), ClipboardError> {
+        Err(ClipboardError::Unsupported)
+    }
+
+    pub fn paste(&mut self) -> Result<String, ClipboardError> {
+        Err(ClipboardError::Unsupported)
+    }
+}
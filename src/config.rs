@@ -0,0 +1,422 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use ratatui::style::Color;
+use serde::Deserialize;
+use toml_edit::{table, value, DocumentMut, Item};
+
+use chess_core::ai::Selection;
+use chess_core::game::puzzle::PuzzleFilter;
+use chess_core::game::TimeControl;
+use crate::painter::art::{CustomArt, PieceArt};
+use crate::painter::theme::{Palette, Theme};
+use crate::ui::app::Blindfold;
+use crate::ui::keymap::{self, Keymap};
+
+// Deeper searches take minutes per move
+pub const MAX_AI_DEPTH: u32 = 8;
+// Longer pauses before the computer replies would just feel broken
+pub const MAX_AI_DELAY_MS: u64 = 10_000;
+/// How deep the beginner level searches before picking loosely among the
+/// best moves
+pub const BEGINNER_DEPTH: u32 = 2;
+
+// Config file names of the ways the computer picks its moves
+const SELECTION_NAMES: [(&str, Selection); 3] =
+    [("best", Selection::Best), ("beginner", Selection::Beginner), ("random", Selection::Random)];
+
+/// The computer's strengths from the weakest up, as how it picks its
+/// moves and how deep it searches: random moves, the beginner level, then
+/// the best move at each depth
+pub fn ai_levels() -> Vec<(Selection, u32)> {
+    let mut levels = vec![(Selection::Random, 1), (Selection::Beginner, BEGINNER_DEPTH)];
+    levels.extend((1..=MAX_AI_DEPTH).map(|depth| (Selection::Best, depth)));
+    levels
+}
+
+/// The strength `steps` levels above `level` in `ai_levels`, or below
+/// for a negative count, stopping at the weakest and strongest
+pub fn step_ai_level(level: (Selection, u32), steps: i32) -> (Selection, u32) {
+    let levels = ai_levels();
+    let current = levels.iter().position(|&known| known == level).unwrap_or(0);
+    levels[(current as i32 + steps).clamp(0, levels.len() as i32 - 1) as usize]
+}
+
+/// How a strength is shown: "Random moves", "Beginner" or "Depth 3"
+pub fn ai_level_label(selection: Selection, depth: u32) -> String {
+    match selection {
+        Selection::Best => format!("Depth {}", depth),
+        Selection::Beginner => "Beginner".to_string(),
+        Selection::Random => "Random moves".to_string(),
+    }
+}
+
+/// config.toml as written by the user
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ConfigFile {
+    ai: AiSection,
+    clock: ClockSection,
+    colors: ColorsSection,
+    display: DisplaySection,
+    game: GameSection,
+    puzzles: PuzzlesSection,
+    sound: SoundSection,
+    /// Action names to the keys that trigger them
+    keys: HashMap<String, KeyList>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct AiSection {
+    depth: Option<u32>,
+    /// "best", or "beginner" or "random" for the levels below depth 1
+    level: Option<String>,
+    adaptive: Option<bool>,
+    delay_ms: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ClockSection {
+    /// "untimed", or a time control like "3+2" or "5 d3"
+    time_control: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct DisplaySection {
+    /// Letters and ./# squares instead of chess glyphs and box drawing
+    ascii: bool,
+    /// "auto", or one of the piece art sets
+    pieces: Option<String>,
+    /// A file of piece art, relative to the config file's directory
+    piece_art_file: Option<String>,
+    auto_flip: Option<bool>,
+    coordinates: Option<bool>,
+    animate: Option<bool>,
+    /// "off", "last_move" or "hidden"
+    blindfold: Option<String>,
+    screen_reader: Option<bool>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct GameSection {
+    auto_queen: Option<bool>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct PuzzlesSection {
+    /// The Lichess puzzle database CSV, relative to the config file's directory
+    file: Option<String>,
+    /// Lichess theme names such as "fork" or "mateIn2"
+    themes: Vec<String>,
+    min_rating: Option<u32>,
+    max_rating: Option<u32>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct SoundSection {
+    enabled: Option<bool>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct ColorsSection {
+    /// A built-in palette to start from
+    palette: Option<String>,
+    /// Theme color names to a color: a name like "darkgray" or "#rrggbb"
+    #[serde(flatten)]
+    overrides: HashMap<String, String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum KeyList {
+    One(String),
+    Many(Vec<String>),
+}
+
+/// Preferences the settings screen can change and save back to the file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Settings {
+    pub ai_depth: u32,
+    /// The beginner and random levels, below the lowest depth
+    pub ai_selection: Selection,
+    /// Moves the strength up a level after each game won against the
+    /// computer and down after each one lost
+    pub adaptive_ai: bool,
+    /// Least time the computer takes to reply, so its moves can be followed
+    pub ai_delay_ms: u64,
+    pub palette: Palette,
+    pub piece_art: PieceArt,
+    /// Promote to a queen without asking
+    pub auto_queen: bool,
+    /// Sound effects for moves, or the terminal bell when the opponent moves
+    pub sound: bool,
+    /// Show the board from Black's side when playing Black
+    pub auto_flip: bool,
+    /// Rank and file labels along the board's edges
+    pub coordinates: bool,
+    /// Slide pieces to their squares instead of moving them at once
+    pub animate_moves: bool,
+    /// Hide the pieces, for blindfold practice
+    pub blindfold: Blindfold,
+    /// Describe each move in words beside the board and keep the command
+    /// line open for typed moves, for playing with a screen reader
+    pub screen_reader: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            ai_depth: 3,
+            ai_selection: Selection::Best,
+            adaptive_ai: false,
+            ai_delay_ms: 0,
+            palette: Palette::default(),
+            piece_art: PieceArt::default(),
+            auto_queen: false,
+            sound: false,
+            auto_flip: true,
+            coordinates: true,
+            animate_moves: true,
+            blindfold: Blindfold::Off,
+            screen_reader: false,
+        }
+    }
+}
+
+/// Settings read from the config file, with defaults for anything it leaves out
+#[derive(Default)]
+pub struct Config {
+    pub settings: Settings,
+    /// Colors set individually, applied over whichever palette is in use
+    pub custom_colors: Vec<(String, Color)>,
+    pub ascii: bool,
+    pub custom_art: Option<CustomArt>,
+    pub time_control: Option<TimeControl>,
+    pub keymap: Keymap,
+    /// A Lichess puzzle database and which of its puzzles to use
+    pub puzzle_database: Option<(PathBuf, PuzzleFilter)>,
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(io::Error),
+    Parse(toml::de::Error),
+    /// The file couldn't be read back to save settings into it
+    Edit(toml_edit::TomlError),
+    /// A setting with a value that can't be used, e.g. `colors.cursor = "plaid"`
+    Invalid { setting: String, reason: String },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::Io(err) => write!(f, "{}", err),
+            ConfigError::Parse(err) => write!(f, "{}", err),
+            ConfigError::Edit(err) => write!(f, "{}", err),
+            ConfigError::Invalid { setting, reason } => write!(f, "{}: {}", setting, reason),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+fn invalid(setting: impl Into<String>, reason: impl Into<String>) -> ConfigError {
+    ConfigError::Invalid {
+        setting: setting.into(),
+        reason: reason.into(),
+    }
+}
+
+/// `$XDG_CONFIG_HOME/chess/config.toml`, falling back to `~/.config/chess/config.toml`
+pub fn config_path() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(base.join("chess").join("config.toml"))
+}
+
+/// Reads the config file. A missing file means all defaults.
+pub fn load() -> Result<Config, ConfigError> {
+    let Some(path) = config_path() else {
+        return Ok(Config::default());
+    };
+    match fs::read_to_string(&path) {
+        Ok(text) => parse(&text),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Config::default()),
+        Err(err) => Err(ConfigError::Io(err)),
+    }
+}
+
+pub fn parse(text: &str) -> Result<Config, ConfigError> {
+    let file: ConfigFile = toml::from_str(text).map_err(ConfigError::Parse)?;
+    let mut config = Config {
+        ascii: file.display.ascii,
+        ..Config::default()
+    };
+
+    let dir = config_path().and_then(|path| path.parent().map(PathBuf::from)).unwrap_or_default();
+    let settings = &mut config.settings;
+    if let Some(depth) = file.ai.depth {
+        if !(1..=MAX_AI_DEPTH).contains(&depth) {
+            return Err(invalid("ai.depth", format!("must be between 1 and {}", MAX_AI_DEPTH)));
+        }
+        settings.ai_depth = depth;
+    }
+    if let Some(name) = &file.ai.level {
+        let (_, selection) = SELECTION_NAMES.iter().find(|&&(level, _)| level == name).ok_or_else(|| {
+            let names: Vec<_> = SELECTION_NAMES.iter().map(|&(level, _)| level).collect();
+            invalid("ai.level", format!("expected one of {}", names.join(", ")))
+        })?;
+        settings.ai_selection = *selection;
+        // The levels below depth 1 have a depth of their own
+        if *selection != Selection::Best {
+            if let Some(&(_, depth)) = ai_levels().iter().find(|&&(level, _)| level == *selection) {
+                settings.ai_depth = depth;
+            }
+        }
+    }
+    settings.adaptive_ai = file.ai.adaptive.unwrap_or(settings.adaptive_ai);
+    if let Some(delay_ms) = file.ai.delay_ms {
+        if delay_ms > MAX_AI_DELAY_MS {
+            return Err(invalid("ai.delay_ms", format!("must be at most {}", MAX_AI_DELAY_MS)));
+        }
+        settings.ai_delay_ms = delay_ms;
+    }
+    settings.auto_queen = file.game.auto_queen.unwrap_or(settings.auto_queen);
+    settings.sound = file.sound.enabled.unwrap_or(settings.sound);
+    settings.auto_flip = file.display.auto_flip.unwrap_or(settings.auto_flip);
+    settings.coordinates = file.display.coordinates.unwrap_or(settings.coordinates);
+    settings.animate_moves = file.display.animate.unwrap_or(settings.animate_moves);
+    settings.screen_reader = file.display.screen_reader.unwrap_or(settings.screen_reader);
+
+    config.time_control = match file.clock.time_control.as_deref() {
+        None | Some("untimed") => None,
+        Some(text) => Some(
+            text.parse()
+                .map_err(|err| invalid("clock.time_control", format!("{}", err)))?,
+        ),
+    };
+
+    if let Some(name) = &file.colors.palette {
+        config.settings.palette = Palette::named(name).ok_or_else(|| {
+            let names: Vec<_> = Palette::ALL.iter().map(|palette| palette.name()).collect();
+            invalid("colors.palette", format!("expected one of {}", names.join(", ")))
+        })?;
+    }
+
+    if let Some(name) = &file.display.blindfold {
+        config.settings.blindfold = Blindfold::named(name).ok_or_else(|| {
+            let names: Vec<_> = Blindfold::ALL.iter().map(|blindfold| blindfold.name()).collect();
+            invalid("display.blindfold", format!("expected one of {}", names.join(", ")))
+        })?;
+    }
+    if let Some(file_name) = &file.display.piece_art_file {
+        let text = fs::read_to_string(dir.join(file_name))
+            .map_err(|err| invalid("display.piece_art_file", format!("{}: {}", file_name, err)))?;
+        let art = CustomArt::parse(&text)
+            .map_err(|err| invalid("display.piece_art_file", format!("{}: {}", file_name, err)))?;
+        config.custom_art = Some(art);
+        config.settings.piece_art = PieceArt::Custom;
+    }
+    if let Some(name) = &file.display.pieces {
+        config.settings.piece_art = PieceArt::named(name).ok_or_else(|| {
+            let names: Vec<_> = PieceArt::ALL.iter().map(|art| art.name()).collect();
+            invalid("display.pieces", format!("expected one of {}", names.join(", ")))
+        })?;
+        if config.settings.piece_art == PieceArt::Custom && config.custom_art.is_none() {
+            return Err(invalid("display.pieces", "\"custom\" needs display.piece_art_file"));
+        }
+    }
+
+    let puzzles = file.puzzles;
+    if let (Some(min), Some(max)) = (puzzles.min_rating, puzzles.max_rating) {
+        if min > max {
+            return Err(invalid("puzzles.min_rating", "must not be above puzzles.max_rating"));
+        }
+    }
+    if let Some(file_name) = puzzles.file {
+        let filter = PuzzleFilter {
+            themes: puzzles.themes,
+            min_rating: puzzles.min_rating,
+            max_rating: puzzles.max_rating,
+        };
+        config.puzzle_database = Some((dir.join(file_name), filter));
+    }
+
+    for (name, value) in &file.colors.overrides {
+        let setting = format!("colors.{}", name);
+        let color: Color = value
+            .parse()
+            .map_err(|_| invalid(&setting, format!("unknown color \"{}\"", value)))?;
+        if !Theme::default().set(name, color) {
+            return Err(invalid(setting, "no such color"));
+        }
+        config.custom_colors.push((name.clone(), color));
+    }
+
+    for (name, keys) in &file.keys {
+        let setting = format!("keys.{}", name);
+        let action = keymap::action_named(name).ok_or_else(|| invalid(&setting, "no such action"))?;
+        let names = match keys {
+            KeyList::One(key) => std::slice::from_ref(key),
+            KeyList::Many(keys) => keys.as_slice(),
+        };
+        let codes = names
+            .iter()
+            .map(|key| keymap::parse_key(key).ok_or_else(|| invalid(&setting, format!("unknown key \"{}\"", key))))
+            .collect::<Result<Vec<_>, _>>()?;
+        config.keymap.bind(action, &codes);
+    }
+
+    Ok(config)
+}
+
+/// Writes `settings` into the config file, leaving everything else in it,
+/// comments included, as it was
+pub fn save_settings(settings: &Settings) -> Result<(), ConfigError> {
+    let path = config_path()
+        .ok_or_else(|| ConfigError::Io(io::Error::new(io::ErrorKind::NotFound, "no home directory")))?;
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(err) => return Err(ConfigError::Io(err)),
+    };
+    let mut doc: DocumentMut = text.parse().map_err(ConfigError::Edit)?;
+
+    let mut set = |section: &str, key: &str, item: Item| {
+        doc.as_table_mut().entry(section).or_insert(table())[key] = item;
+    };
+    set("ai", "depth", value(i64::from(settings.ai_depth)));
+    let level = SELECTION_NAMES
+        .iter()
+        .find(|&&(_, selection)| selection == settings.ai_selection)
+        .map_or("best", |&(name, _)| name);
+    set("ai", "level", value(level));
+    set("ai", "adaptive", value(settings.adaptive_ai));
+    set("ai", "delay_ms", value(settings.ai_delay_ms as i64));
+    set("colors", "palette", value(settings.palette.name()));
+    set("display", "pieces", value(settings.piece_art.name()));
+    set("display", "auto_flip", value(settings.auto_flip));
+    set("display", "coordinates", value(settings.coordinates));
+    set("display", "animate", value(settings.animate_moves));
+    set("display", "blindfold", value(settings.blindfold.name()));
+    set("display", "screen_reader", value(settings.screen_reader));
+    set("game", "auto_queen", value(settings.auto_queen));
+    set("sound", "enabled", value(settings.sound));
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(ConfigError::Io)?;
+    }
+    fs::write(&path, doc.to_string()).map_err(ConfigError::Io)
+}
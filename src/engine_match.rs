@@ -0,0 +1,199 @@
+use clap::Args;
+
+use chess_core::ai::{ChessAI, Pruning};
+use chess_core::game::draw;
+use chess_core::game::{Board, Color, Game, Move};
+
+use crate::headless;
+
+/// The move draw adjudication starts counting after, as in most engine
+/// testing tools: before it, level scores say little about the result
+pub const DRAW_ADJUDICATION_START: u32 = 40;
+
+/// Rules for ending engine games early, once the result is clear or the
+/// game has gone on long enough. Each is off unless its flag is given.
+#[derive(Args, Debug, Clone, Default)]
+pub struct Adjudication {
+    /// Resign for an engine whose own score stays below minus this many
+    /// centipawns for --resign-moves of its moves in a row
+    #[arg(long, value_name = "CP")]
+    pub resign_score: Option<i32>,
+    #[arg(long, value_name = "N", default_value_t = 3)]
+    pub resign_moves: u32,
+    /// Call a draw once the scores stay within this many centipawns of
+    /// level for --draw-moves moves by each engine, after move 40
+    #[arg(long, value_name = "CP")]
+    pub draw_score: Option<i32>,
+    #[arg(long, value_name = "N", default_value_t = 8)]
+    pub draw_moves: u32,
+    /// Call a draw once the game reaches this many moves
+    #[arg(long, value_name = "N")]
+    pub max_moves: Option<u32>,
+}
+
+/// Prunings to leave out of the first engine's search, to measure what
+/// they're worth against an opponent that makes them
+#[derive(Args, Debug, Clone, Default)]
+pub struct PruningFlags {
+    /// Search the first engine without futility pruning
+    #[arg(long)]
+    pub no_futility: bool,
+    /// Search the first engine without reverse futility pruning
+    #[arg(long)]
+    pub no_reverse_futility: bool,
+}
+
+impl PruningFlags {
+    pub fn pruning(&self) -> Pruning {
+        Pruning { futility: !self.no_futility, reverse_futility: !self.no_reverse_futility }
+    }
+
+    /// What the first engine goes without, e.g. " (no futility)", for its name
+    pub fn label(&self) -> String {
+        let off: Vec<&str> = [(self.no_futility, "futility"), (self.no_reverse_futility, "reverse futility")]
+            .into_iter()
+            .filter_map(|(off, name)| off.then_some(name))
+            .collect();
+        if off.is_empty() { String::new() } else { format!(" (no {})", off.join(", no ")) }
+    }
+}
+
+/// A side in an engine game: the built-in engine at some level, or an
+/// external one
+pub trait MatchEngine {
+    /// Gets ready for a new game
+    fn new_game(&self) {}
+
+    /// The move to play on `board`, reached by `moves` from `initial`, and
+    /// its score in centipawns from the side to move's point of view. None
+    /// for an engine that failed, which forfeits the game.
+    fn choose(&self, initial: &Board, moves: &[Move], board: &Board) -> Option<(Move, i32)>;
+}
+
+impl MatchEngine for ChessAI {
+    fn choose(&self, initial: &Board, moves: &[Move], board: &Board) -> Option<(Move, i32)> {
+        self.set_history(initial, moves);
+        self.search(board)
+    }
+}
+
+/// Applies the adjudication rules to the scores of a game's searches as
+/// they come in
+pub struct Adjudicator<'a> {
+    rules: &'a Adjudication,
+    // Moves in a row each side has scored itself lost, White's first
+    losing: [u32; 2],
+    // Moves in a row each side has scored near level. None for a side
+    // whose scores aren't known, as for the opponent in headless play.
+    level: [Option<u32>; 2],
+}
+
+fn side_index(color: Color) -> usize {
+    match color {
+        Color::White => 0,
+        Color::Black => 1,
+    }
+}
+
+impl<'a> Adjudicator<'a> {
+    /// For a game in which the searches of the `scored` sides are seen
+    pub fn new(rules: &'a Adjudication, scored: &[Color]) -> Self {
+        let mut level = [None; 2];
+        for &color in scored {
+            level[side_index(color)] = Some(0);
+        }
+        Adjudicator { rules, losing: [0; 2], level }
+    }
+
+    /// Takes the score `mover` gave the move it just played, from its own
+    /// point of view, with `board` the position after it. Returns the
+    /// result and reason if that ends the game, e.g. ("0-1", "White resigns").
+    pub fn record(&mut self, mover: Color, score: i32, board: &Board) -> Option<(&'static str, &'static str)> {
+        let side = side_index(mover);
+
+        if let Some(resign_score) = self.rules.resign_score {
+            self.losing[side] = if score < -resign_score { self.losing[side] + 1 } else { 0 };
+            if self.losing[side] >= self.rules.resign_moves.max(1) {
+                return Some(match mover {
+                    Color::White => ("0-1", "White resigns"),
+                    Color::Black => ("1-0", "Black resigns"),
+                });
+            }
+        }
+
+        if let Some(draw_score) = self.rules.draw_score {
+            let counting = board.fullmove_number > DRAW_ADJUDICATION_START && score.abs() <= draw_score;
+            self.level[side] = self.level[side].map(|moves| if counting { moves + 1 } else { 0 });
+            let needed = self.rules.draw_moves.max(1);
+            if self.level.iter().flatten().all(|&moves| moves >= needed) {
+                return Some(("1/2-1/2", "draw adjudication"));
+            }
+        }
+
+        if self.rules.max_moves.is_some_and(|max_moves| board.fullmove_number > max_moves) {
+            return Some(("1/2-1/2", "maximum game length"));
+        }
+        None
+    }
+}
+
+/// The rules' result and reason once the game from `initial` is over,
+/// taking threefold repetition as an agreed draw
+fn outcome(initial: &Board, game: &Game, board: &Board) -> Option<(&'static str, &'static str)> {
+    if let Some(outcome) = headless::outcome(board) {
+        return Some(outcome);
+    }
+    (draw::repetitions(initial, &game.moves) >= 3).then_some(("1/2-1/2", "threefold repetition"))
+}
+
+/// Plays `white` against `black` from `initial` to the end, or until the
+/// adjudication rules stop the game, calling `on_search` with each position
+/// searched and its score. Returns the game, with its result and
+/// Termination tag, and the reason it ended.
+pub fn play_game(
+    initial: &Board,
+    white: &dyn MatchEngine,
+    black: &dyn MatchEngine,
+    rules: &Adjudication,
+    mut on_search: impl FnMut(&Board, i32),
+) -> (Game, &'static str) {
+    let mut game = Game::new(initial.clone());
+    let mut board = initial.clone();
+    let mut adjudicator = Adjudicator::new(rules, &[Color::White, Color::Black]);
+
+    white.new_game();
+    black.new_game();
+    let (mut adjudicated, mut forfeited) = (false, false);
+    let (result, reason) = loop {
+        if let Some(outcome) = outcome(initial, &game, &board) {
+            break outcome;
+        }
+        let mover = board.current_player;
+        let engine = if mover == Color::White { white } else { black };
+        // Without a move the game would have ended by the rules above,
+        // so an external engine failed, and loses
+        let Some((mv, score)) = engine.choose(initial, &game.moves, &board) else {
+            forfeited = true;
+            break match mover {
+                Color::White => ("0-1", "White's engine failed"),
+                Color::Black => ("1-0", "Black's engine failed"),
+            };
+        };
+        on_search(&board, score);
+        board.make_move(&mv);
+        game.moves.push(mv);
+        if let Some(verdict) = adjudicator.record(mover, score, &board) {
+            adjudicated = true;
+            break verdict;
+        }
+    };
+
+    game.result = result.to_string();
+    let termination = match (adjudicated, forfeited) {
+        (true, _) => "adjudication",
+        (_, true) => "rules infraction",
+        _ => "normal",
+    };
+    game.tags.push(("Termination".to_string(), termination.to_string()));
+    (game, reason)
+}
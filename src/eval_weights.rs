@@ -0,0 +1,50 @@
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chess_core::ai::eval::EvalWeights;
+use clap::Args;
+
+/// The weights file option of the commands that search
+#[derive(Args, Debug, Clone, Default)]
+pub struct WeightsFile {
+    /// Evaluate with the piece values and square tables in this file, as
+    /// written by `chess tune` (JSON, or TOML for a .toml file)
+    #[arg(long, value_name = "FILE")]
+    pub eval_weights: Option<PathBuf>,
+}
+
+impl WeightsFile {
+    /// The file's path and weights, if one was given
+    pub fn load_with_path(&self) -> Result<Option<(&Path, EvalWeights)>, Box<dyn Error>> {
+        self.eval_weights.as_deref().map(|path| load(path).map(|weights| (path, weights))).transpose()
+    }
+
+    /// The file's weights, or the built-in ones without a file
+    pub fn load(&self) -> Result<EvalWeights, Box<dyn Error>> {
+        Ok(self.eval_weights.as_deref().map(load).transpose()?.unwrap_or_default())
+    }
+}
+
+// Weight files are TOML when their name ends in .toml, and JSON otherwise
+fn is_toml(path: &Path) -> bool {
+    path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("toml"))
+}
+
+/// Reads a set of evaluation weights, as written by `chess tune`
+pub fn load(path: &Path) -> Result<EvalWeights, Box<dyn Error>> {
+    let text = fs::read_to_string(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+    let weights: EvalWeights = if is_toml(path) {
+        toml::from_str(&text).map_err(|err| format!("{}: {}", path.display(), err))?
+    } else {
+        serde_json::from_str(&text).map_err(|err| format!("{}: {}", path.display(), err))?
+    };
+    weights.validate().map_err(|err| format!("{}: {}", path.display(), err))?;
+    Ok(weights)
+}
+
+pub fn save(path: &Path, weights: &EvalWeights) -> Result<(), Box<dyn Error>> {
+    let text = if is_toml(path) { toml::to_string(weights)? } else { serde_json::to_string_pretty(weights)? };
+    fs::write(path, text)?;
+    Ok(())
+}
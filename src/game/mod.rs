@@ -1,9 +0,0 @@
-pub mod board;
-pub mod pieces;
-pub mod moves;
-pub mod rules;
-
-pub use board::Board;
-pub use pieces::{Color, PieceType, Position};
-pub use moves::{Move, MoveType};
-pub use rules::MoveGenerator;
@@ -0,0 +1,77 @@
+use std::error::Error;
+use std::io::{self, BufRead, Write};
+
+use chess_core::ai::ChessAI;
+use chess_core::game::{Board, Color, MoveGenerator};
+
+use crate::engine_match::{Adjudication, Adjudicator};
+
+/// Result and reason once the game is over, e.g. ("1-0", "checkmate")
+pub fn outcome(board: &Board) -> Option<(&'static str, &'static str)> {
+    let to_move = board.current_player;
+    if MoveGenerator::is_checkmate(board, to_move) {
+        let result = if to_move == Color::White { "0-1" } else { "1-0" };
+        Some((result, "checkmate"))
+    } else if MoveGenerator::is_stalemate(board, to_move) {
+        Some(("1/2-1/2", "stalemate"))
+    } else if MoveGenerator::is_insufficient_material(board) {
+        Some(("1/2-1/2", "insufficient material"))
+    } else if MoveGenerator::is_dead_position(board) {
+        Some(("1/2-1/2", "dead position"))
+    } else if board.is_fifty_move_draw() {
+        Some(("1/2-1/2", "fifty-move rule"))
+    } else {
+        None
+    }
+}
+
+/// Plays the engine against moves read from stdin, one UCI move per line.
+/// Only the engine's moves go to stdout, so two instances can be piped into
+/// each other; everything else is written to stderr. The adjudication rules
+/// go by the engine's own scores, and end the game after its move.
+pub fn play(board: Board, ai: &ChessAI, engine_color: Color, rules: &Adjudication) -> Result<(), Box<dyn Error>> {
+    let mut adjudicator = Adjudicator::new(rules, &[engine_color]);
+    let initial = board.clone();
+    let mut board = board;
+    let mut moves = Vec::new();
+    let mut stdout = io::stdout();
+    let mut lines = io::stdin().lock().lines();
+
+    loop {
+        if let Some((result, reason)) = outcome(&board) {
+            eprintln!("{} {{{}}}", result, reason);
+            return Ok(());
+        }
+
+        if board.current_player == engine_color {
+            ai.set_history(&initial, &moves);
+            let (mv, score) = ai.search(&board).ok_or("engine found no move")?;
+            board.make_move(&mv);
+            moves.push(mv);
+            writeln!(stdout, "{}", mv.to_uci())?;
+            stdout.flush()?;
+            if let Some((result, reason)) = adjudicator.record(engine_color, score, &board) {
+                eprintln!("{} {{{}}}", result, reason);
+                return Ok(());
+            }
+            continue;
+        }
+
+        let Some(line) = lines.next() else {
+            return Ok(());
+        };
+        let input = line?;
+        let input = input.trim();
+        match input {
+            "" => {}
+            "quit" => return Ok(()),
+            uci => match MoveGenerator::find_uci_move(&board, uci) {
+                Some(mv) => {
+                    board.make_move(&mv);
+                    moves.push(mv);
+                }
+                None => eprintln!("illegal move: {}\n{}", uci, board),
+            },
+        }
+    }
+}
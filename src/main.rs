@@ -1,7 +1,26 @@
-mod game;
-mod ai;
+mod analysis;
+mod bot_script;
+mod cli;
+mod clipboard;
+mod config;
+mod engine_match;
+mod eval_weights;
+mod headless;
+mod logging;
+mod online;
+mod painter;
+mod profiles;
+mod puzzle_db;
+mod saves;
+mod server;
+mod sound;
+mod stats;
+mod suspend;
+mod tournament;
+mod uci_engine;
 mod ui;
 
+use clap::Parser;
 use crossterm::{
     event::{DisableMouseCapture, EnableMouseCapture},
     execute,
@@ -10,9 +29,79 @@ use crossterm::{
 use ratatui::{backend::CrosstermBackend, Terminal};
 use std::io;
 
+use chess_core::game::Board;
+
+use cli::{Cli, Command};
+use profiles::Profiles;
+use ui::commands::read_game;
 use ui::{App, handle_input, render};
 
 fn main() -> io::Result<()> {
+    let cli = Cli::parse();
+    if let Some(path) = &cli.log_file {
+        if let Err(err) = logging::init(path) {
+            eprintln!("Error: couldn't open the log file {}: {}", path.display(), err);
+            std::process::exit(1);
+        }
+    }
+    if let Some(addr) = cli.serve {
+        return server::serve(&addr);
+    }
+    let mut weights_file = cli.eval_weights;
+    match cli.command {
+        None => {}
+        Some(Command::Play { engine_depth: None, weights, .. }) => {
+            weights_file = weights_file.or(weights.eval_weights);
+        }
+        Some(command) => {
+            if weights_file.is_some() {
+                eprintln!("Error: give --eval-weights after the command it's for");
+                std::process::exit(2);
+            }
+            if let Err(err) = cli::run(command) {
+                eprintln!("Error: {}", err);
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+    }
+
+    // Read the config before taking over the terminal, so errors stay visible
+    let config = match config::load() {
+        Ok(config) => config,
+        Err(err) => {
+            let path = config::config_path().unwrap_or_default();
+            eprintln!("Error in {}: {}", path.display(), err);
+            std::process::exit(1);
+        }
+    };
+    let game = match cli.game.as_deref().map(read_game).transpose() {
+        Ok(game) => game,
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            std::process::exit(1);
+        }
+    };
+    if let Some(err) = cli.fen.as_deref().and_then(|fen| Board::from_fen(fen).err()) {
+        eprintln!("Error: bad FEN: {}", err);
+        std::process::exit(1);
+    }
+    let weights = match weights_file.as_deref().map(eval_weights::load).transpose() {
+        Ok(weights) => weights.unwrap_or_default(),
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            std::process::exit(1);
+        }
+    };
+
+    let bot = match cli.bot.as_deref().map(bot_script::load).transpose() {
+        Ok(bot) => bot,
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            std::process::exit(1);
+        }
+    };
+
     // Setup terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
@@ -22,6 +111,25 @@ fn main() -> io::Result<()> {
 
     // Create app
     let mut app = App::new();
+    app.settings = config.settings;
+    app.settings.screen_reader |= cli.screen_reader;
+    app.ai_seed = cli.seed;
+    app.ai_weights = weights;
+    app.custom_bot = bot;
+    app.custom_colors = config.custom_colors;
+    app.apply_palette();
+    app.ascii = config.ascii || cli.ascii;
+    app.custom_art = config.custom_art;
+    app.keymap = config.keymap;
+    app.session_mut().time_control = config.time_control;
+    app.puzzle_database = config.puzzle_database;
+    app.profiles = Profiles::load();
+    // Both were checked above, so these can't fail
+    if let Some(game) = &game {
+        let _ = app.open_game(game);
+    } else if let Some(fen) = &cli.fen {
+        let _ = app.start_from_fen(fen);
+    }
 
     // Main loop
     let res = run_app(&mut terminal, &mut app);
@@ -47,9 +155,17 @@ fn run_app<B: ratatui::backend::Backend>(
     app: &mut App,
 ) -> io::Result<()> {
     loop {
+        let mode = app.session().mode;
+        app.update();
         terminal.draw(|f| render(app, f))?;
+        if let Some(cue) = app.take_sound() {
+            sound::play(cue, &mut io::stdout())?;
+        }
 
         handle_input(app)?;
+        if app.session().mode != mode {
+            log::debug!("screen {:?} -> {:?}", mode, app.session().mode);
+        }
 
         if app.should_quit {
             break;
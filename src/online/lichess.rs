@@ -0,0 +1,246 @@
+use serde::Deserialize;
+use std::fmt;
+use std::io::{BufRead, BufReader, Read};
+
+const API_BASE: &str = "https://lichess.org";
+
+#[derive(Debug)]
+pub enum LichessError {
+    Http(String),
+    Io(std::io::Error),
+    Json(serde_json::Error),
+}
+
+impl fmt::Display for LichessError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LichessError::Http(message) => write!(f, "HTTP error: {}", message),
+            LichessError::Io(err) => write!(f, "connection error: {}", err),
+            LichessError::Json(err) => write!(f, "unexpected response: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for LichessError {}
+
+impl From<ureq::Error> for LichessError {
+    fn from(err: ureq::Error) -> Self {
+        match err {
+            ureq::Error::Status(code, response) => {
+                let body = response.into_string().unwrap_or_default();
+                LichessError::Http(format!("{} {}", code, body.trim()))
+            }
+            ureq::Error::Transport(transport) => LichessError::Http(transport.to_string()),
+        }
+    }
+}
+
+impl From<std::io::Error> for LichessError {
+    fn from(err: std::io::Error) -> Self {
+        LichessError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for LichessError {
+    fn from(err: serde_json::Error) -> Self {
+        LichessError::Json(err)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Account {
+    pub id: String,
+    pub username: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct GameRef {
+    pub id: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ChallengeRef {
+    pub id: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Challenger {
+    pub name: String,
+    pub rating: Option<u32>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Variant {
+    pub key: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Challenge {
+    pub id: String,
+    pub challenger: Option<Challenger>,
+    pub rated: bool,
+    pub speed: String,
+    pub variant: Variant,
+}
+
+/// Events from the account-wide `/api/stream/event` stream
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum AccountEvent {
+    GameStart { game: GameRef },
+    Challenge { challenge: Challenge },
+    ChallengeCanceled { challenge: ChallengeRef },
+    ChallengeDeclined { challenge: ChallengeRef },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct GamePlayer {
+    pub id: Option<String>,
+    pub name: Option<String>,
+    pub rating: Option<u32>,
+    #[serde(rename = "aiLevel")]
+    pub ai_level: Option<u32>,
+}
+
+impl GamePlayer {
+    pub fn display_name(&self) -> String {
+        match (&self.name, self.ai_level) {
+            (Some(name), _) => match self.rating {
+                Some(rating) => format!("{} ({})", name, rating),
+                None => name.clone(),
+            },
+            (None, Some(level)) => format!("Stockfish level {}", level),
+            (None, None) => "Anonymous".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct GameState {
+    pub moves: String,
+    pub wtime: u64,
+    pub btime: u64,
+    pub status: String,
+    pub winner: Option<String>,
+}
+
+/// Events from the per-game `/api/board/game/stream/{id}` stream
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum GameEvent {
+    GameFull {
+        id: String,
+        rated: bool,
+        white: GamePlayer,
+        black: GamePlayer,
+        #[serde(rename = "initialFen")]
+        initial_fen: String,
+        state: GameState,
+    },
+    GameState(GameState),
+    #[serde(other)]
+    Other,
+}
+
+/// Blocking client for the Lichess Board API. Streaming calls never return
+/// while the connection is open, so callers run them on their own thread.
+#[derive(Clone)]
+pub struct LichessClient {
+    agent: ureq::Agent,
+    token: String,
+}
+
+impl LichessClient {
+    pub fn new(token: String) -> Self {
+        LichessClient {
+            agent: ureq::AgentBuilder::new().build(),
+            token,
+        }
+    }
+
+    fn get(&self, path: &str) -> ureq::Request {
+        self.agent
+            .get(&format!("{}{}", API_BASE, path))
+            .set("Authorization", &format!("Bearer {}", self.token))
+    }
+
+    fn post(&self, path: &str) -> ureq::Request {
+        self.agent
+            .post(&format!("{}{}", API_BASE, path))
+            .set("Authorization", &format!("Bearer {}", self.token))
+    }
+
+    pub fn account(&self) -> Result<Account, LichessError> {
+        let body = self.get("/api/account").call()?.into_string()?;
+        Ok(serde_json::from_str(&body)?)
+    }
+
+    /// Streams account events, calling `on_event` for each one until the connection closes
+    pub fn stream_events(&self, on_event: impl FnMut(AccountEvent) -> bool) -> Result<(), LichessError> {
+        let response = self.get("/api/stream/event").call()?;
+        Self::read_ndjson(response.into_reader(), on_event)
+    }
+
+    /// Streams a game's full state followed by each state change. Returning
+    /// `false` from `on_event` stops listening.
+    pub fn stream_game(&self, game_id: &str, on_event: impl FnMut(GameEvent) -> bool) -> Result<(), LichessError> {
+        let response = self.get(&format!("/api/board/game/stream/{}", game_id)).call()?;
+        Self::read_ndjson(response.into_reader(), on_event)
+    }
+
+    /// Creates a public seek. Lichess keeps the request open until someone
+    /// accepts, at which point a `GameStart` event arrives on the event stream.
+    pub fn seek(&self, rated: bool, minutes: u32, increment: u32) -> Result<(), LichessError> {
+        let rated = rated.to_string();
+        let minutes = minutes.to_string();
+        let increment = increment.to_string();
+        let response = self.post("/api/board/seek").send_form(&[
+            ("rated", rated.as_str()),
+            ("time", minutes.as_str()),
+            ("increment", increment.as_str()),
+            ("variant", "standard"),
+        ])?;
+        // Drain the keep-alive stream until the seek is matched or dropped
+        std::io::copy(&mut response.into_reader(), &mut std::io::sink())?;
+        Ok(())
+    }
+
+    pub fn accept_challenge(&self, challenge_id: &str) -> Result<(), LichessError> {
+        self.post(&format!("/api/challenge/{}/accept", challenge_id)).call()?;
+        Ok(())
+    }
+
+    pub fn decline_challenge(&self, challenge_id: &str) -> Result<(), LichessError> {
+        self.post(&format!("/api/challenge/{}/decline", challenge_id)).call()?;
+        Ok(())
+    }
+
+    pub fn make_move(&self, game_id: &str, uci: &str) -> Result<(), LichessError> {
+        self.post(&format!("/api/board/game/{}/move/{}", game_id, uci)).call()?;
+        Ok(())
+    }
+
+    pub fn resign(&self, game_id: &str) -> Result<(), LichessError> {
+        self.post(&format!("/api/board/game/{}/resign", game_id)).call()?;
+        Ok(())
+    }
+
+    fn read_ndjson<T: for<'de> Deserialize<'de>>(
+        reader: impl Read,
+        mut on_event: impl FnMut(T) -> bool,
+    ) -> Result<(), LichessError> {
+        for line in BufReader::new(reader).lines() {
+            let line = line?;
+            // Lichess sends empty lines as keep-alives
+            if line.trim().is_empty() {
+                continue;
+            }
+            if !on_event(serde_json::from_str(&line)?) {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,298 @@
+pub mod lichess;
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::thread;
+
+use chess_core::game::Color;
+pub use lichess::{Account, AccountEvent, Challenge, GameEvent, GameState, LichessClient};
+
+/// Time control used for seeks. The Board API only allows rapid or slower games.
+pub const SEEK_MINUTES: u32 = 15;
+pub const SEEK_INCREMENT: u32 = 10;
+
+pub enum OnlineEvent {
+    Connected(Account),
+    Account(AccountEvent),
+    /// An event on the game stream numbered by the `u64`
+    Game(u64, String, GameEvent),
+    Error(String),
+}
+
+pub struct OnlineGame {
+    pub id: String,
+    pub color: Color,
+    pub rated: bool,
+    pub white_name: String,
+    pub black_name: String,
+    pub initial_fen: String,
+    pub moves: Vec<String>,
+    pub status: String,
+    pub winner: Option<Color>,
+    pub white_time_ms: u64,
+    pub black_time_ms: u64,
+}
+
+impl OnlineGame {
+    pub fn is_finished(&self) -> bool {
+        !matches!(self.status.as_str(), "created" | "started")
+    }
+
+    pub fn end_reason(&self) -> &'static str {
+        match self.status.as_str() {
+            "mate" => "Checkmate!",
+            "resign" => "Resignation!",
+            "stalemate" => "Stalemate!",
+            "outoftime" | "timeout" => "Time out!",
+            "draw" => "Draw agreed!",
+            "aborted" | "noStart" => "Game aborted!",
+            _ => "Game over!",
+        }
+    }
+
+    pub fn opponent_name(&self) -> &str {
+        match self.color {
+            Color::White => &self.black_name,
+            Color::Black => &self.white_name,
+        }
+    }
+
+    fn apply_state(&mut self, state: GameState) {
+        self.moves = state.moves.split_whitespace().map(str::to_string).collect();
+        self.status = state.status;
+        self.winner = match state.winner.as_deref() {
+            Some("white") => Some(Color::White),
+            Some("black") => Some(Color::Black),
+            _ => None,
+        };
+        self.white_time_ms = state.wtime;
+        self.black_time_ms = state.btime;
+    }
+}
+
+/// A connection to Lichess. Network calls run on background threads and
+/// report back over a channel that the UI drains with `poll` every frame.
+pub struct OnlineSession {
+    client: LichessClient,
+    sender: Sender<OnlineEvent>,
+    receiver: Receiver<OnlineEvent>,
+    // Number of the game stream being listened to. Each new stream takes
+    // the next number, and older ones stop at their next event.
+    game_stream: Arc<AtomicU64>,
+    pub account: Option<Account>,
+    pub status: String,
+    pub challenges: Vec<Challenge>,
+    pub seeking: bool,
+    pub game: Option<OnlineGame>,
+}
+
+impl OnlineSession {
+    pub fn connect(token: String) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let session = OnlineSession {
+            client: LichessClient::new(token),
+            sender,
+            receiver,
+            game_stream: Arc::new(AtomicU64::new(0)),
+            account: None,
+            status: "Connecting to Lichess...".to_string(),
+            challenges: Vec::new(),
+            seeking: false,
+            game: None,
+        };
+
+        let client = session.client.clone();
+        let tx = session.sender.clone();
+        thread::spawn(move || {
+            let account = match client.account() {
+                Ok(account) => account,
+                Err(err) => {
+                    let _ = tx.send(OnlineEvent::Error(err.to_string()));
+                    return;
+                }
+            };
+            if tx.send(OnlineEvent::Connected(account)).is_err() {
+                return;
+            }
+            let result = client.stream_events(|event| tx.send(OnlineEvent::Account(event)).is_ok());
+            let message = match result {
+                Ok(()) => "Event stream closed by Lichess".to_string(),
+                Err(err) => err.to_string(),
+            };
+            let _ = tx.send(OnlineEvent::Error(message));
+        });
+
+        session
+    }
+
+    /// Processes pending network events. Returns true when a new game has started.
+    pub fn poll(&mut self) -> bool {
+        let mut game_started = false;
+
+        while let Ok(event) = self.receiver.try_recv() {
+            match event {
+                OnlineEvent::Connected(account) => {
+                    self.status = format!("Connected as {}", account.username);
+                    self.account = Some(account);
+                }
+                OnlineEvent::Account(AccountEvent::GameStart { game }) => {
+                    self.seeking = false;
+                    self.watch_game(game.id);
+                }
+                OnlineEvent::Account(AccountEvent::Challenge { challenge }) => {
+                    // Our own outgoing challenges are echoed on the stream too
+                    let ours = self.account.as_ref().is_some_and(|account| {
+                        challenge.challenger.as_ref().is_some_and(|c| c.name == account.username)
+                    });
+                    if !ours {
+                        self.challenges.push(challenge);
+                    }
+                }
+                OnlineEvent::Account(AccountEvent::ChallengeCanceled { challenge })
+                | OnlineEvent::Account(AccountEvent::ChallengeDeclined { challenge }) => {
+                    self.challenges.retain(|c| c.id != challenge.id);
+                }
+                OnlineEvent::Account(AccountEvent::Other) => {}
+                OnlineEvent::Game(stream, game_id, event) => {
+                    // Events a replaced stream sent before it stopped
+                    if stream == self.game_stream.load(Ordering::SeqCst) {
+                        game_started |= self.handle_game_event(game_id, event);
+                    }
+                }
+                OnlineEvent::Error(message) => {
+                    log::warn!("Lichess: {}", message);
+                    self.seeking = false;
+                    self.status = format!("Error: {}", message);
+                }
+            }
+        }
+
+        game_started
+    }
+
+    fn handle_game_event(&mut self, game_id: String, event: GameEvent) -> bool {
+        match event {
+            GameEvent::GameFull { id, rated, white, black, initial_fen, state } => {
+                let user_id = self.account.as_ref().map(|account| account.id.as_str());
+                let color = if white.id.as_deref() == user_id { Color::White } else { Color::Black };
+                let mut game = OnlineGame {
+                    id,
+                    color,
+                    rated,
+                    white_name: white.display_name(),
+                    black_name: black.display_name(),
+                    initial_fen,
+                    moves: Vec::new(),
+                    status: String::new(),
+                    winner: None,
+                    white_time_ms: 0,
+                    black_time_ms: 0,
+                };
+                game.apply_state(state);
+                let is_new = self.game.as_ref().is_none_or(|current| current.id != game.id);
+                self.game = Some(game);
+                is_new
+            }
+            GameEvent::GameState(state) => {
+                if let Some(game) = self.game.as_mut().filter(|game| game.id == game_id) {
+                    game.apply_state(state);
+                }
+                false
+            }
+            GameEvent::Other => false,
+        }
+    }
+
+    // Streams `game_id` in place of the game stream listened to until now
+    fn watch_game(&self, game_id: String) {
+        let client = self.client.clone();
+        let tx = self.sender.clone();
+        let current = Arc::clone(&self.game_stream);
+        let stream = current.fetch_add(1, Ordering::SeqCst) + 1;
+        thread::spawn(move || {
+            let result = client.stream_game(&game_id, |event| {
+                current.load(Ordering::SeqCst) == stream
+                    && tx.send(OnlineEvent::Game(stream, game_id.clone(), event)).is_ok()
+            });
+            if current.load(Ordering::SeqCst) != stream {
+                return;
+            }
+            if let Err(err) = result {
+                let _ = tx.send(OnlineEvent::Error(err.to_string()));
+            }
+        });
+    }
+
+    /// Re-subscribes to the active game after the machine wakes up, since
+    /// the old stream's connection has usually gone stale. The fresh stream
+    /// starts with the full game, which brings moves and clocks up to date.
+    pub fn resync(&mut self) {
+        if let Some(game) = self.game.as_ref().filter(|game| !game.is_finished()) {
+            self.watch_game(game.id.clone());
+        }
+    }
+
+    pub fn has_active_game(&self) -> bool {
+        self.game.as_ref().is_some_and(|game| !game.is_finished())
+    }
+
+    pub fn seek(&mut self, rated: bool) {
+        if self.seeking || self.account.is_none() {
+            return;
+        }
+        self.seeking = true;
+        self.status = format!(
+            "Seeking a {} {}+{} game...",
+            if rated { "rated" } else { "casual" },
+            SEEK_MINUTES,
+            SEEK_INCREMENT
+        );
+        self.spawn_request(move |client| client.seek(rated, SEEK_MINUTES, SEEK_INCREMENT));
+    }
+
+    pub fn accept_challenge(&mut self, index: usize) {
+        if index < self.challenges.len() {
+            let challenge = self.challenges.remove(index);
+            self.status = "Accepting challenge...".to_string();
+            self.spawn_request(move |client| client.accept_challenge(&challenge.id));
+        }
+    }
+
+    pub fn decline_challenge(&mut self, index: usize) {
+        if index < self.challenges.len() {
+            let challenge = self.challenges.remove(index);
+            self.spawn_request(move |client| client.decline_challenge(&challenge.id));
+        }
+    }
+
+    pub fn send_move(&mut self, uci: String) {
+        if let Some(game) = self.game.as_mut() {
+            // Record the move locally so the next poll doesn't treat the
+            // board as ahead of the server
+            game.moves.push(uci.clone());
+            let game_id = game.id.clone();
+            self.spawn_request(move |client| client.make_move(&game_id, &uci));
+        }
+    }
+
+    pub fn resign(&mut self) {
+        if let Some(game) = self.game.as_ref().filter(|game| !game.is_finished()) {
+            let game_id = game.id.clone();
+            self.spawn_request(move |client| client.resign(&game_id));
+        }
+    }
+
+    fn spawn_request<F>(&self, request: F)
+    where
+        F: FnOnce(&LichessClient) -> Result<(), lichess::LichessError> + Send + 'static,
+    {
+        let client = self.client.clone();
+        let tx = self.sender.clone();
+        thread::spawn(move || {
+            if let Err(err) = request(&client) {
+                let _ = tx.send(OnlineEvent::Error(err.to_string()));
+            }
+        });
+    }
+}
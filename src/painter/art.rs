@@ -0,0 +1,232 @@
+use chess_core::game::pieces::{Piece, PieceType};
+use chess_core::game::Color;
+
+use super::piece_letter;
+
+/// How pieces are drawn on the terminal board
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PieceArt {
+    /// The largest art that fits the squares
+    #[default]
+    Auto,
+    /// Three lines of block characters
+    Block,
+    /// Five lines of block characters, for big terminals
+    Large,
+    /// A single chess glyph
+    Glyph,
+    /// A single letter, uppercase for White
+    Letter,
+    /// Art read from the file named in the config
+    Custom,
+}
+
+impl PieceArt {
+    pub const ALL: [PieceArt; 6] = [
+        PieceArt::Auto,
+        PieceArt::Block,
+        PieceArt::Large,
+        PieceArt::Glyph,
+        PieceArt::Letter,
+        PieceArt::Custom,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PieceArt::Auto => "Auto",
+            PieceArt::Block => "Block",
+            PieceArt::Large => "Large",
+            PieceArt::Glyph => "Glyphs",
+            PieceArt::Letter => "Letters",
+            PieceArt::Custom => "Custom",
+        }
+    }
+
+    /// Name used in the config file
+    pub fn name(self) -> &'static str {
+        match self {
+            PieceArt::Auto => "auto",
+            PieceArt::Block => "block",
+            PieceArt::Large => "large",
+            PieceArt::Glyph => "glyph",
+            PieceArt::Letter => "letter",
+            PieceArt::Custom => "custom",
+        }
+    }
+
+    pub fn named(name: &str) -> Option<PieceArt> {
+        PieceArt::ALL.into_iter().find(|art| art.name() == name)
+    }
+
+    /// The art to draw on squares `width` by `height` characters. Auto
+    /// picks the largest that fits, and art too big for the squares gives
+    /// way to the next size down. Custom art is drawn by the painter,
+    /// which falls back on Auto's choice when it doesn't fit.
+    pub fn fitting(self, width: u16, height: u16) -> PieceArt {
+        match self {
+            PieceArt::Auto | PieceArt::Large | PieceArt::Custom if width >= 7 && height >= 5 => PieceArt::Large,
+            PieceArt::Auto | PieceArt::Large | PieceArt::Block | PieceArt::Custom if width >= 3 && height >= 3 => {
+                PieceArt::Block
+            }
+            PieceArt::Auto | PieceArt::Large | PieceArt::Block | PieceArt::Custom => PieceArt::Glyph,
+            art => art,
+        }
+    }
+
+    /// The lines drawing `piece`, all the same width
+    pub fn lines(self, piece: Piece) -> Vec<String> {
+        match self {
+            PieceArt::Auto | PieceArt::Block | PieceArt::Custom => piece.piece_type.ascii_art().into_iter().map(String::from).collect(),
+            PieceArt::Large => large_art(piece.piece_type).iter().map(|line| line.to_string()).collect(),
+            PieceArt::Glyph => vec![piece.symbol().to_string()],
+            PieceArt::Letter => vec![piece_letter(piece).to_string()],
+        }
+    }
+}
+
+/// Piece art read from a text file. Each piece's lines follow a header
+/// naming it, like `[white knight]`; `[knight]` gives both colors the
+/// same art. Blank lines around the art and `#` comments before the first
+/// header are skipped. Every piece needs art in both colors.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CustomArt {
+    pieces: Vec<(Piece, Vec<String>)>,
+}
+
+impl CustomArt {
+    pub fn parse(text: &str) -> Result<CustomArt, String> {
+        let mut pieces: Vec<(Piece, Vec<String>)> = Vec::new();
+        // Where the lines under the current header go
+        let mut section: Option<(usize, usize)> = None;
+
+        for (number, line) in text.lines().enumerate() {
+            let trimmed = line.trim();
+            let header = trimmed.strip_prefix('[').and_then(|header| header.strip_suffix(']'));
+            // Other bracketed lines, like "[Q]", are art
+            if let Some((header, named)) = header.and_then(|header| Some((header, header_pieces(header)?))) {
+                let start = pieces.len();
+                for piece in named {
+                    if pieces.iter().any(|(other, _)| *other == piece) {
+                        return Err(format!("line {}: second art for [{}]", number + 1, header));
+                    }
+                    pieces.push((piece, Vec::new()));
+                }
+                section = Some((start, pieces.len()));
+                continue;
+            }
+            match section {
+                Some((start, end)) => {
+                    for (_, lines) in &mut pieces[start..end] {
+                        lines.push(line.trim_end().to_string());
+                    }
+                }
+                None if trimmed.is_empty() || trimmed.starts_with('#') => {}
+                None => {
+                    return Err(format!("line {}: expected a piece like [white king] before the art", number + 1));
+                }
+            }
+        }
+
+        for (_, lines) in &mut pieces {
+            while lines.last().is_some_and(|line| line.is_empty()) {
+                lines.pop();
+            }
+            let blank = lines.iter().take_while(|line| line.is_empty()).count();
+            lines.drain(..blank);
+        }
+        for color in [Color::White, Color::Black] {
+            for piece_type in PIECE_NAMES.map(|(_, piece_type)| piece_type) {
+                let piece = Piece::new(piece_type, color);
+                if !pieces.iter().any(|(other, lines)| *other == piece && !lines.is_empty()) {
+                    return Err(format!("no art for the {}", piece_name(piece)));
+                }
+            }
+        }
+        Ok(CustomArt { pieces })
+    }
+
+    pub fn lines(&self, piece: Piece) -> &[String] {
+        self.pieces
+            .iter()
+            .find(|(other, _)| *other == piece)
+            .map_or(&[], |(_, lines)| lines.as_slice())
+    }
+}
+
+const PIECE_NAMES: [(&str, PieceType); 6] = [
+    ("pawn", PieceType::Pawn),
+    ("knight", PieceType::Knight),
+    ("bishop", PieceType::Bishop),
+    ("rook", PieceType::Rook),
+    ("queen", PieceType::Queen),
+    ("king", PieceType::King),
+];
+
+/// The pieces a header's name covers: "white king", or "king" for both
+fn header_pieces(header: &str) -> Option<Vec<Piece>> {
+    let words: Vec<&str> = header.split_whitespace().collect();
+    let (colors, name) = match words.as_slice() {
+        ["white", name] => (vec![Color::White], *name),
+        ["black", name] => (vec![Color::Black], *name),
+        [name] => (vec![Color::White, Color::Black], *name),
+        _ => return None,
+    };
+    let &(_, piece_type) = PIECE_NAMES.iter().find(|&&(piece_name, _)| piece_name == name)?;
+    Some(colors.into_iter().map(|color| Piece::new(piece_type, color)).collect())
+}
+
+fn piece_name(piece: Piece) -> String {
+    let color = match piece.color {
+        Color::White => "white",
+        Color::Black => "black",
+    };
+    let &(name, _) = PIECE_NAMES.iter().find(|&&(_, piece_type)| piece_type == piece.piece_type).expect("every piece is named");
+    format!("{} {}", color, name)
+}
+
+fn large_art(piece_type: PieceType) -> [&'static str; 5] {
+    match piece_type {
+        PieceType::Pawn => [
+            "  ▄█▄  ",
+            "  ▀█▀  ",
+            "  ▐█▌  ",
+            " ▄███▄ ",
+            " █████ ",
+        ],
+        PieceType::Knight => [
+            "  ▄██▄ ",
+            " ▀▀███ ",
+            "  ▐██▌ ",
+            " ▄███▄ ",
+            " █████ ",
+        ],
+        PieceType::Bishop => [
+            "   ●   ",
+            "  ▐▀▌  ",
+            "  ▐█▌  ",
+            " ▄███▄ ",
+            " █████ ",
+        ],
+        PieceType::Rook => [
+            " █▄█▄█ ",
+            " ▀███▀ ",
+            "  ███  ",
+            " ▄███▄ ",
+            " █████ ",
+        ],
+        PieceType::Queen => [
+            " ◆ ◆ ◆ ",
+            " ▀█▄█▀ ",
+            "  ███  ",
+            " ▄███▄ ",
+            " █████ ",
+        ],
+        PieceType::King => [
+            "  ─┼─  ",
+            "  ▀█▀  ",
+            "  ███  ",
+            " ▄███▄ ",
+            " █████ ",
+        ],
+    }
+}
@@ -0,0 +1,298 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+};
+
+use chess_core::game::marks::MarkColor;
+use chess_core::game::pieces::Piece;
+use chess_core::game::{Color as PieceColor, Position};
+
+use super::theme::Theme;
+use super::art::PieceArt;
+use super::{mark_rgb, BoardPainter, Highlight, SquareView};
+
+fn mark_color(color: MarkColor) -> Color {
+    let (r, g, b) = mark_rgb(color);
+    Color::Rgb(r, g, b)
+}
+
+fn background(square: &SquareView, theme: &Theme) -> Color {
+    match square.highlight {
+        Some(Highlight::Cursor) => theme.cursor,
+        Some(Highlight::Selected) => theme.selected,
+        Some(Highlight::Capture) => theme.capture,
+        Some(Highlight::Target) => theme.target,
+        Some(Highlight::Check) => theme.check,
+        Some(Highlight::Premove) => theme.premove,
+        Some(Highlight::Mark(color)) => mark_color(color),
+        Some(Highlight::LastMove) => theme.last_move,
+        None if square.light => theme.light_square,
+        None => theme.dark_square,
+    }
+}
+
+impl BoardPainter<'_> {
+    /// Draws the board into a ratatui buffer, each square `cell_width` by
+    /// `cell_height` characters, starting at the top left of `area`
+    pub fn paint_buffer(&self, area: Rect, buf: &mut Buffer, cell_width: u16, cell_height: u16, theme: &Theme) {
+        for square in self.squares() {
+            let (row, col) = self.screen_position(square.pos);
+            let cell = Rect {
+                x: area.x + col as u16 * cell_width,
+                y: area.y + row as u16 * cell_height,
+                width: cell_width,
+                height: cell_height,
+            };
+            self.paint_cell(&square, cell, buf, theme);
+        }
+
+        for &arrow in self.arrows() {
+            self.paint_arrow(arrow, area, buf, (cell_width, cell_height));
+        }
+
+        // A moving piece slides over the squares between its from and to squares
+        if let Some((mv, progress)) = self.animation {
+            let (from_row, from_col) = self.screen_position(mv.from);
+            let (to_row, to_col) = self.screen_position(mv.to);
+            let lerp = |from: usize, to: usize, size: u16| {
+                ((from as f32 + (to as f32 - from as f32) * progress) * size as f32).round() as u16
+            };
+            let cell = Rect {
+                x: area.x + lerp(from_col, to_col, cell_width),
+                y: area.y + lerp(from_row, to_row, cell_height),
+                width: cell_width,
+                height: cell_height,
+            };
+            let fg = match mv.piece.color {
+                PieceColor::White => theme.white_piece,
+                PieceColor::Black => theme.black_piece,
+            };
+            self.paint_piece(mv.piece, cell, buf, fg, None);
+        }
+    }
+
+    /// Draws an arrow as a line through the empty squares between `from`
+    /// and `to`, with its head on the side of `to` it comes in from.
+    /// Arrows that aren't straight or diagonal, like a knight's, go
+    /// straight first and finish diagonally.
+    fn paint_arrow(&self, arrow: (Position, Position, MarkColor), area: Rect, buf: &mut Buffer, cell: (u16, u16)) {
+        let (from, to, color) = arrow;
+        let (cell_width, cell_height) = cell;
+        let style = Style::default().fg(mark_color(color)).add_modifier(Modifier::BOLD);
+        let mut set = |x: u16, y: u16, ch: char| {
+            if x < buf.area.width && y < buf.area.height {
+                buf[(x, y)].set_char(ch).set_style(style);
+            }
+        };
+        let (to_row, to_col) = self.screen_position(to);
+        let (mut row, mut col) = self.screen_position(from);
+        let (mut step_row, mut step_col) = (0, 0);
+
+        while (row, col) != (to_row, to_col) {
+            let (rows_left, cols_left) = (to_row as isize - row as isize, to_col as isize - col as isize);
+            (step_row, step_col) = match rows_left.abs().cmp(&cols_left.abs()) {
+                std::cmp::Ordering::Equal => (rows_left.signum(), cols_left.signum()),
+                std::cmp::Ordering::Greater => (rows_left.signum(), 0),
+                std::cmp::Ordering::Less => (0, cols_left.signum()),
+            };
+            // The line leaves each square the way it steps into the next
+            if (row, col) != self.screen_position(from) && self.board.get_piece(self.board_position(row, col)).is_none() {
+                let (x, y) = (area.x + col as u16 * cell_width, area.y + row as u16 * cell_height);
+                let (center_x, center_y) = (x + cell_width / 2, y + cell_height / 2);
+                match (step_row, step_col) {
+                    (0, _) => (x..x + cell_width).for_each(|x| set(x, center_y, if self.ascii { '-' } else { '─' })),
+                    (_, 0) => (y..y + cell_height).for_each(|y| set(center_x, y, if self.ascii { '|' } else { '│' })),
+                    (1, 1) | (-1, -1) => set(center_x, center_y, if self.ascii { '\\' } else { '╲' }),
+                    _ => set(center_x, center_y, if self.ascii { '/' } else { '╱' }),
+                }
+            }
+            row = row.wrapping_add_signed(step_row);
+            col = col.wrapping_add_signed(step_col);
+        }
+
+        let head = match (step_row, step_col, self.ascii) {
+            (-1, 0, false) => '↑',
+            (1, 0, false) => '↓',
+            (0, -1, false) => '←',
+            (0, 1, false) => '→',
+            (-1, -1, false) => '↖',
+            (-1, 1, false) => '↗',
+            (1, -1, false) => '↙',
+            (1, 1, false) => '↘',
+            (-1, _, true) => '^',
+            (1, _, true) => 'v',
+            (_, -1, true) => '<',
+            (_, 1, true) => '>',
+            _ => return,
+        };
+        // On the edge it comes in from, out of the way of the piece there
+        let (x, y) = (area.x + to_col as u16 * cell_width, area.y + to_row as u16 * cell_height);
+        let x = match step_col {
+            1 => x,
+            -1 => x + cell_width - 1,
+            _ => x + cell_width / 2,
+        };
+        let y = match step_row {
+            1 => y,
+            -1 => y + cell_height - 1,
+            _ => y + cell_height / 2,
+        };
+        set(x, y, head);
+    }
+
+    /// Draws a piece centred in `area`. Without a background color the
+    /// piece is drawn over what's already there, blanks included.
+    fn paint_piece(&self, piece: Piece, area: Rect, buf: &mut Buffer, fg: Color, bg: Option<Color>) {
+        let mut draw = |x: u16, y: u16, ch: char| {
+            if x >= buf.area.width || y >= buf.area.height || (bg.is_none() && ch == ' ') {
+                return;
+            }
+            let cell = buf[(x, y)]
+                .set_char(ch)
+                .set_fg(fg)
+                .set_style(Style::default().add_modifier(Modifier::BOLD));
+            if let Some(bg) = bg {
+                cell.set_bg(bg);
+            }
+        };
+
+        let custom = self.custom_art.map(|custom| custom.lines(piece)).filter(|lines| {
+            lines.len() as u16 <= area.height && lines.iter().all(|line| line.chars().count() as u16 <= area.width)
+        });
+        let lines = match custom {
+            Some(lines) if self.art == PieceArt::Custom && !self.ascii => lines.to_vec(),
+            _ if self.ascii => PieceArt::Letter.lines(piece),
+            _ => self.art.fitting(area.width, area.height).lines(piece),
+        };
+        let art_width = lines.iter().map(|line| line.chars().count()).max().unwrap_or(0) as u16;
+        let art_height = lines.len() as u16;
+
+        // Single characters sit on the square's center, like move markers
+        let (start_x, start_y) = match art_height {
+            1 => (
+                area.x + (area.width + 1).saturating_sub(art_width) / 2,
+                area.y + area.height / 2,
+            ),
+            _ => (
+                area.x + area.width.saturating_sub(art_width) / 2,
+                area.y + area.height.saturating_sub(art_height) / 2,
+            ),
+        };
+        for (i, line) in lines.iter().enumerate() {
+            let y = start_y + i as u16;
+            if y >= area.y + area.height {
+                break;
+            }
+            for (j, ch) in line.chars().enumerate() {
+                let x = start_x + j as u16;
+                if x < area.x + area.width {
+                    draw(x, y, ch);
+                }
+            }
+        }
+    }
+
+    fn paint_cell(&self, square: &SquareView, area: Rect, buf: &mut Buffer, theme: &Theme) {
+        let pos = square.pos;
+        let bg_color = background(square, theme);
+
+        // Labels take the other square color, or white on highlights
+        let fg_color = match square.highlight {
+            Some(_) => Color::White,
+            None if square.light => theme.dark_square,
+            None => theme.light_square,
+        };
+
+        // Fill cell background
+        for dy in 0..area.height {
+            for dx in 0..area.width {
+                let x = area.x + dx;
+                let y = area.y + dy;
+                if x < buf.area.width && y < buf.area.height {
+                    buf[(x, y)].set_bg(bg_color);
+                    if self.ascii {
+                        buf[(x, y)].set_char(if square.light { '.' } else { '#' }).set_fg(fg_color);
+                    }
+                }
+            }
+        }
+        let center_x = area.x + area.width / 2;
+        let center_y = area.y + area.height / 2;
+
+        // Without colors the cursor and selection need marks of their own,
+        // and pieces some room among the '#'s
+        if self.ascii && area.width >= 5 && center_y < buf.area.height {
+            let edges = match square.highlight {
+                Some(Highlight::Cursor) => Some(('[', ']')),
+                Some(Highlight::Selected) => Some(('(', ')')),
+                Some(Highlight::Capture) => Some(('x', 'x')),
+                Some(Highlight::Premove) => Some(('<', '>')),
+                Some(Highlight::Mark(_)) => Some(('+', '+')),
+                _ if square.piece.is_some() || square.marker => Some((' ', ' ')),
+                _ => None,
+            };
+            if let Some((open, close)) = edges {
+                for (dx, ch) in [(-2, open), (-1, ' '), (1, ' '), (2, close)] {
+                    let x = center_x.saturating_add_signed(dx);
+                    if x < buf.area.width {
+                        buf[(x, center_y)].set_char(ch).set_fg(fg_color);
+                    }
+                }
+            }
+        }
+
+        let marker = if self.ascii { '*' } else { '●' };
+
+        // Render piece
+        if let Some(piece) = square.piece {
+            let picked = matches!(square.highlight, Some(Highlight::Cursor | Highlight::Selected));
+            let piece_fg = match piece.color {
+                _ if picked => Color::White, // Bright white on green for selected
+                PieceColor::White => theme.white_piece,
+                PieceColor::Black => theme.black_piece,
+            };
+
+            self.paint_piece(piece, area, buf, piece_fg, Some(bg_color));
+        } else if square.marker && self.big_hints_enabled() {
+            // Fill the middle of the square so targets are easy to spot
+            for dy in area.height / 3..area.height - area.height / 3 {
+                for dx in area.width / 4..area.width - area.width / 4 {
+                    let (x, y) = (area.x + dx, area.y + dy);
+                    if x < buf.area.width && y < buf.area.height {
+                        buf[(x, y)].set_char(marker).set_fg(Color::White).set_bg(bg_color);
+                    }
+                }
+            }
+        } else if square.marker {
+            // Show dot for legal move squares
+            if center_x < buf.area.width && center_y < buf.area.height {
+                buf[(center_x, center_y)]
+                    .set_char(marker)
+                    .set_fg(Color::White)
+                    .set_bg(bg_color);
+            }
+        }
+
+        // Add file/rank labels on the bottom and left edges
+        let (row, col) = self.screen_position(pos);
+        if !self.coordinates {
+            return;
+        }
+        if row == 7 && area.y + area.height < buf.area.height {
+            let file_label = (b'a' + pos.col as u8) as char;
+            buf[(area.x + area.width / 2, area.y + area.height - 1)]
+                .set_char(file_label)
+                .set_fg(fg_color)
+                .set_bg(bg_color);
+        }
+
+        if col == 0 && area.x > 0 {
+            let rank_label = (b'8' - pos.row as u8) as char;
+            buf[(area.x, area.y)]
+                .set_char(rank_label)
+                .set_fg(fg_color)
+                .set_bg(bg_color);
+        }
+    }
+}
@@ -0,0 +1,306 @@
+pub mod art;
+pub mod buffer;
+pub mod svg;
+pub mod theme;
+
+use chess_core::game::marks::{MarkColor, Marks};
+use art::{CustomArt, PieceArt};
+use chess_core::game::pieces::Piece;
+use chess_core::game::{Board, Color, Move, MoveGenerator, MoveType, PieceType, Position};
+
+/// Why a square stands out. Only the most important one is shown, in this order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Highlight {
+    Cursor,
+    Selected,
+    /// An enemy piece the selected piece can take, or the empty square
+    /// it takes en passant on
+    Capture,
+    /// A square the selected piece can move to
+    Target,
+    /// The king of the side to move, in check, and on checkmate the
+    /// pieces giving it
+    Check,
+    /// The from or to square of a queued premove
+    Premove,
+    /// A square marked for analysis
+    Mark(MarkColor),
+    /// The from or to square of the last move
+    LastMove,
+}
+
+/// The RGB color squares and arrows are marked in
+pub fn mark_rgb(color: MarkColor) -> (u8, u8, u8) {
+    match color {
+        MarkColor::Green => (21, 120, 27),
+        MarkColor::Red => (136, 32, 32),
+        MarkColor::Yellow => (230, 143, 0),
+        MarkColor::Blue => (0, 48, 136),
+    }
+}
+
+/// Everything a backend needs to draw one square
+#[derive(Debug, Clone, Copy)]
+pub struct SquareView {
+    pub pos: Position,
+    pub light: bool,
+    pub piece: Option<Piece>,
+    pub highlight: Option<Highlight>,
+    /// Draw a move marker on this empty square (large with big hints)
+    pub marker: bool,
+}
+
+/// Decides what each square of a position shows, independent of where it is
+/// drawn: the terminal, a text diagram or an SVG image
+pub struct BoardPainter<'a> {
+    board: &'a Board,
+    cursor: Option<Position>,
+    selected: Option<Position>,
+    targets: &'a [Move],
+    big_hints: bool,
+    last_move: Option<Move>,
+    // Squares marked by show_check
+    check_squares: Vec<Position>,
+    flipped: bool,
+    ascii: bool,
+    art: PieceArt,
+    custom_art: Option<&'a CustomArt>,
+    coordinates: bool,
+    // A move being animated, and how far along it is from 0 to 1
+    animation: Option<(Move, f32)>,
+    premove: Option<Move>,
+    hide_pieces: bool,
+    marked: &'a [(Position, MarkColor)],
+    arrows: &'a [(Position, Position, MarkColor)],
+    arrow_start: Option<Position>,
+}
+
+impl<'a> BoardPainter<'a> {
+    pub fn new(board: &'a Board) -> Self {
+        BoardPainter {
+            board,
+            cursor: None,
+            selected: None,
+            targets: &[],
+            big_hints: false,
+            last_move: None,
+            check_squares: Vec::new(),
+            flipped: false,
+            ascii: false,
+            art: PieceArt::Auto,
+            custom_art: None,
+            coordinates: true,
+            animation: None,
+            premove: None,
+            hide_pieces: false,
+            marked: &[],
+            arrows: &[],
+            arrow_start: None,
+        }
+    }
+
+    pub fn cursor(mut self, cursor: Position) -> Self {
+        self.cursor = Some(cursor);
+        self
+    }
+
+    /// The selected piece and the moves it can make
+    pub fn selection(mut self, selected: Option<Position>, targets: &'a [Move]) -> Self {
+        self.selected = selected;
+        self.targets = targets;
+        self
+    }
+
+    /// Kids mode hints: large move markers
+    pub fn big_hints(mut self, big_hints: bool) -> Self {
+        self.big_hints = big_hints;
+        self
+    }
+
+    pub fn last_move(mut self, last_move: Option<&Move>) -> Self {
+        self.last_move = last_move.copied();
+        self
+    }
+
+    /// Marks the king of the side to move if it's in check, and the
+    /// pieces giving checkmate
+    pub fn show_check(mut self, show_check: bool) -> Self {
+        self.check_squares.clear();
+        let to_move = self.board.current_player;
+        let Some(king) = self.board.find_king(to_move) else {
+            return self;
+        };
+        if show_check && MoveGenerator::is_in_check(self.board, to_move) {
+            self.check_squares.push(king);
+            if MoveGenerator::is_checkmate(self.board, to_move) {
+                self.check_squares.extend(MoveGenerator::attackers(self.board, king, to_move.opposite()));
+            }
+        }
+        self
+    }
+
+    /// Draws the board from Black's side, with rank 1 at the top
+    pub fn flip(mut self, flipped: bool) -> Self {
+        self.flipped = flipped;
+        self
+    }
+
+    /// Plain ASCII in the terminal: piece letters on '.' and '#' squares
+    pub fn ascii(mut self, ascii: bool) -> Self {
+        self.ascii = ascii;
+        self
+    }
+
+    /// How pieces are drawn in the terminal, unless it's plain ASCII
+    pub fn art(mut self, art: PieceArt) -> Self {
+        self.art = art;
+        self
+    }
+
+    /// The art drawn when the art set is Custom
+    pub fn custom_art(mut self, custom_art: Option<&'a CustomArt>) -> Self {
+        self.custom_art = custom_art;
+        self
+    }
+
+    /// Rank and file labels along the left and bottom edges
+    pub fn coordinates(mut self, coordinates: bool) -> Self {
+        self.coordinates = coordinates;
+        self
+    }
+
+    /// Shows `mv`, the move that led to the position, still on its way:
+    /// `progress` from 0 at the from square to 1 at the to square
+    pub fn animation(mut self, animation: Option<(Move, f32)>) -> Self {
+        self.animation = animation;
+        self
+    }
+
+    /// A move queued to be played once it's the player's turn
+    pub fn premove(mut self, premove: Option<Move>) -> Self {
+        self.premove = premove;
+        self
+    }
+
+    /// Leaves every square empty, for blindfold play
+    pub fn hide_pieces(mut self, hide_pieces: bool) -> Self {
+        self.hide_pieces = hide_pieces;
+        self
+    }
+
+    /// Squares and arrows marked on the position, and the start of an
+    /// arrow still being drawn, shown like a selection
+    pub fn marks(mut self, marks: &'a Marks, arrow_start: Option<Position>) -> Self {
+        self.marked = &marks.squares;
+        self.arrows = &marks.arrows;
+        self.arrow_start = arrow_start;
+        self
+    }
+
+    pub fn arrows(&self) -> &[(Position, Position, MarkColor)] {
+        self.arrows
+    }
+
+    /// Where a square is drawn, as (row, column) counted from the top left
+    pub fn screen_position(&self, pos: Position) -> (usize, usize) {
+        if self.flipped {
+            (7 - pos.row, 7 - pos.col)
+        } else {
+            (pos.row, pos.col)
+        }
+    }
+
+    /// The square drawn at a (row, column) counted from the top left
+    pub fn board_position(&self, row: usize, col: usize) -> Position {
+        let (row, col) = self.screen_position(Position::new(row, col));
+        Position::new(row, col)
+    }
+
+    pub fn big_hints_enabled(&self) -> bool {
+        self.big_hints
+    }
+
+    pub fn square(&self, pos: Position) -> SquareView {
+        let mut piece = self.board.get_piece(pos);
+        // The moving piece hasn't arrived yet, so what it takes is still there
+        if let Some((mv, _)) = self.animation.filter(|(mv, _)| mv.to == pos) {
+            piece = mv.captured.filter(|_| mv.move_type != MoveType::EnPassant);
+        }
+        if self.hide_pieces {
+            piece = None;
+        }
+        let target = self.targets.iter().find(|mv| mv.to == pos);
+        let in_check = self.check_squares.contains(&pos);
+
+        let highlight = if self.cursor == Some(pos) {
+            Some(Highlight::Cursor)
+        } else if self.selected == Some(pos) || self.arrow_start == Some(pos) {
+            Some(Highlight::Selected)
+        } else if target.is_some_and(|mv| mv.captured.is_some()) {
+            Some(Highlight::Capture)
+        } else if target.is_some() {
+            Some(Highlight::Target)
+        } else if in_check {
+            Some(Highlight::Check)
+        } else if self.premove.is_some_and(|mv| mv.from == pos || mv.to == pos) {
+            Some(Highlight::Premove)
+        } else if let Some(&(_, color)) = self.marked.iter().find(|&&(square, _)| square == pos) {
+            Some(Highlight::Mark(color))
+        } else if self.last_move.is_some_and(|mv| mv.from == pos || mv.to == pos) {
+            Some(Highlight::LastMove)
+        } else {
+            None
+        };
+
+        SquareView {
+            pos,
+            light: (pos.row + pos.col).is_multiple_of(2),
+            piece,
+            highlight,
+            marker: target.is_some() && piece.is_none(),
+        }
+    }
+
+    /// Squares row by row as drawn: a8 to h1, or h1 to a8 when flipped
+    pub fn squares(&self) -> impl Iterator<Item = SquareView> + '_ {
+        (0..8).flat_map(move |row| (0..8).map(move |col| self.square(self.board_position(row, col))))
+    }
+
+    /// The position as a plain text diagram, one rank per line: FEN piece
+    /// letters, '.' for empty squares and '*' for move markers
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for row in 0..8 {
+            text.push_str(&format!("{} ", 8 - self.board_position(row, 0).row));
+            for col in 0..8 {
+                let square = self.square(self.board_position(row, col));
+                let symbol = match square.piece {
+                    Some(piece) => piece_letter(piece),
+                    None if square.marker => '*',
+                    None => '.',
+                };
+                text.push(' ');
+                text.push(symbol);
+            }
+            text.push('\n');
+        }
+        text.push_str(if self.flipped { "   h g f e d c b a\n" } else { "   a b c d e f g h\n" });
+        text
+    }
+}
+
+/// FEN letter of a piece: uppercase for White, lowercase for Black
+pub fn piece_letter(piece: Piece) -> char {
+    let letter = match piece.piece_type {
+        PieceType::Pawn => 'p',
+        PieceType::Knight => 'n',
+        PieceType::Bishop => 'b',
+        PieceType::Rook => 'r',
+        PieceType::Queen => 'q',
+        PieceType::King => 'k',
+    };
+    match piece.color {
+        Color::White => letter.to_ascii_uppercase(),
+        Color::Black => letter,
+    }
+}
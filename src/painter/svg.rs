@@ -0,0 +1,144 @@
+use chess_core::game::{Color, PieceType, Position};
+
+use super::{mark_rgb, BoardPainter, Highlight, SquareView};
+
+const SQUARE: usize = 60;
+// Room around the board for the file letters and rank numbers
+const MARGIN: usize = 24;
+// Cursor, selection and move markers, which only appear in UI snapshots
+const SELECTION: &str = "#00a000";
+
+/// Colors for a board image. Highlights whose color is `None` aren't drawn.
+pub struct SvgOptions {
+    pub light_square: String,
+    pub dark_square: String,
+    /// Tint over the from and to squares of the last move
+    pub last_move: Option<String>,
+    /// Glow under a king in check
+    pub check: Option<String>,
+}
+
+impl Default for SvgOptions {
+    fn default() -> Self {
+        // The familiar brown wooden board
+        SvgOptions {
+            light_square: "#f0d9b5".to_string(),
+            dark_square: "#b58863".to_string(),
+            last_move: Some("#cdd26a".to_string()),
+            check: Some("#ff0000".to_string()),
+        }
+    }
+}
+
+fn glyph(piece_type: PieceType) -> char {
+    match piece_type {
+        PieceType::King => '♚',
+        PieceType::Queen => '♛',
+        PieceType::Rook => '♜',
+        PieceType::Bishop => '♝',
+        PieceType::Knight => '♞',
+        PieceType::Pawn => '♟',
+    }
+}
+
+/// Keeps user-supplied colors from breaking out of their attribute
+fn escape(value: &str) -> String {
+    value.replace('&', "&amp;").replace('"', "&quot;").replace('<', "&lt;")
+}
+
+impl BoardPainter<'_> {
+    fn square_origin(&self, pos: Position) -> (usize, usize) {
+        let (row, col) = self.screen_position(pos);
+        (MARGIN + col * SQUARE, MARGIN + row * SQUARE)
+    }
+
+    /// Draws the position as a standalone SVG document, White at the bottom
+    /// unless flipped
+    pub fn to_svg(&self, options: &SvgOptions) -> String {
+        let size = SQUARE * 8 + MARGIN * 2;
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{size}\" height=\"{size}\" viewBox=\"0 0 {size} {size}\">\n"
+        );
+        svg.push_str(&format!("<rect width=\"{size}\" height=\"{size}\" fill=\"#312e2b\"/>\n"));
+        if let Some(color) = &options.check {
+            svg.push_str(&format!(
+                "<defs><radialGradient id=\"check\"><stop offset=\"0%\" stop-color=\"{0}\"/><stop offset=\"100%\" stop-color=\"{0}\" stop-opacity=\"0\"/></radialGradient></defs>\n",
+                escape(color)
+            ));
+        }
+
+        let squares: Vec<SquareView> = self.squares().collect();
+        for square in &squares {
+            let (x, y) = self.square_origin(square.pos);
+            let color = if square.light { &options.light_square } else { &options.dark_square };
+            svg.push_str(&format!(
+                "<rect x=\"{x}\" y=\"{y}\" width=\"{SQUARE}\" height=\"{SQUARE}\" fill=\"{}\"/>\n",
+                escape(color)
+            ));
+
+            let overlay = match square.highlight {
+                Some(Highlight::LastMove) => options.last_move.as_deref().map(escape),
+                Some(Highlight::Check) if options.check.is_some() => Some("url(#check)".to_string()),
+                Some(Highlight::Mark(color)) => {
+                    let (r, g, b) = mark_rgb(color);
+                    Some(format!("#{:02x}{:02x}{:02x}", r, g, b))
+                }
+                Some(Highlight::Check | Highlight::Capture) | None => None,
+                Some(_) => Some(SELECTION.to_string()),
+            };
+            if let Some(fill) = overlay {
+                svg.push_str(&format!(
+                    "<rect x=\"{x}\" y=\"{y}\" width=\"{SQUARE}\" height=\"{SQUARE}\" fill=\"{fill}\" fill-opacity=\"0.6\"/>\n"
+                ));
+            }
+            if square.highlight == Some(Highlight::Capture) {
+                // A ring around the piece to take, unlike the dots for quiet moves
+                svg.push_str(&format!(
+                    "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"none\" stroke=\"{SELECTION}\" stroke-width=\"5\"/>\n",
+                    x + SQUARE / 2,
+                    y + SQUARE / 2,
+                    SQUARE / 2 - 4
+                ));
+            } else if square.marker {
+                svg.push_str(&format!(
+                    "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"{SELECTION}\"/>\n",
+                    x + SQUARE / 2,
+                    y + SQUARE / 2,
+                    if self.big_hints_enabled() { SQUARE / 3 } else { SQUARE / 6 }
+                ));
+            }
+        }
+
+        svg.push_str("<g font-family=\"DejaVu Sans, Segoe UI Symbol, sans-serif\" font-size=\"48\" text-anchor=\"middle\" dominant-baseline=\"central\">\n");
+        for square in &squares {
+            let Some(piece) = square.piece else {
+                continue;
+            };
+            let (x, y) = self.square_origin(square.pos);
+            let (fill, stroke) = match piece.color {
+                Color::White => ("#ffffff", "#000000"),
+                Color::Black => ("#000000", "#ffffff"),
+            };
+            svg.push_str(&format!(
+                "<text x=\"{}\" y=\"{}\" fill=\"{fill}\" stroke=\"{stroke}\" stroke-width=\"1.5\">{}</text>\n",
+                x + SQUARE / 2,
+                y + SQUARE / 2,
+                glyph(piece.piece_type)
+            ));
+        }
+        svg.push_str("</g>\n");
+
+        svg.push_str("<g font-family=\"sans-serif\" font-size=\"14\" fill=\"#bababa\" text-anchor=\"middle\" dominant-baseline=\"central\">\n");
+        for index in 0..8 {
+            let center = MARGIN + index * SQUARE + SQUARE / 2;
+            let corner = self.board_position(index, index);
+            let file = (b'a' + corner.col as u8) as char;
+            let rank = 8 - corner.row;
+            svg.push_str(&format!("<text x=\"{center}\" y=\"{}\">{file}</text>\n", size - MARGIN / 2));
+            svg.push_str(&format!("<text x=\"{}\" y=\"{center}\">{rank}</text>\n", MARGIN / 2));
+        }
+        svg.push_str("</g>\n</svg>\n");
+
+        svg
+    }
+}
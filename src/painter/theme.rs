@@ -0,0 +1,136 @@
+use ratatui::style::Color;
+
+/// Colors of the terminal board and the interface around it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+    pub light_square: Color,
+    pub dark_square: Color,
+    pub cursor: Color,
+    pub selected: Color,
+    pub target: Color,
+    /// Capturable pieces, when big hints are on
+    pub capture: Color,
+    pub check: Color,
+    pub last_move: Color,
+    /// A move queued while the opponent thinks
+    pub premove: Color,
+    pub white_piece: Color,
+    pub black_piece: Color,
+    /// Borders, titles and menu highlights
+    pub accent: Color,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Palette::Classic.theme()
+    }
+}
+
+/// Built-in color schemes, chosen in the menu or the config file
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Palette {
+    /// Gold and blue pieces on white and black squares
+    #[default]
+    Classic,
+    /// White and black pieces on mid-gray squares, every pairing readable
+    HighContrast,
+    /// Blue and orange instead of red and green, for deuteranopia
+    Deuteranopia,
+}
+
+impl Palette {
+    pub const ALL: [Palette; 3] = [Palette::Classic, Palette::HighContrast, Palette::Deuteranopia];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Palette::Classic => "Classic",
+            Palette::HighContrast => "High contrast",
+            Palette::Deuteranopia => "Deuteranopia",
+        }
+    }
+
+    /// Name used in the config file
+    pub fn name(self) -> &'static str {
+        match self {
+            Palette::Classic => "classic",
+            Palette::HighContrast => "high_contrast",
+            Palette::Deuteranopia => "deuteranopia",
+        }
+    }
+
+    pub fn named(name: &str) -> Option<Palette> {
+        Palette::ALL.into_iter().find(|palette| palette.name() == name)
+    }
+
+    pub fn theme(self) -> Theme {
+        match self {
+            Palette::Classic => Theme {
+                light_square: Color::White,
+                dark_square: Color::Black,
+                cursor: Color::Green,
+                selected: Color::Rgb(0, 100, 0),
+                target: Color::Rgb(0, 150, 0),
+                capture: Color::Rgb(180, 70, 0),
+                check: Color::Rgb(200, 30, 30),
+                last_move: Color::Rgb(110, 100, 40),
+                premove: Color::Rgb(70, 70, 160),
+                white_piece: Color::Rgb(255, 215, 0),
+                black_piece: Color::Rgb(0, 180, 255),
+                accent: Color::Green,
+            },
+            Palette::HighContrast => Theme {
+                light_square: Color::Rgb(150, 150, 150),
+                dark_square: Color::Rgb(90, 90, 90),
+                cursor: Color::Rgb(0, 0, 220),
+                selected: Color::Rgb(0, 0, 140),
+                target: Color::Rgb(0, 110, 0),
+                capture: Color::Rgb(170, 70, 0),
+                check: Color::Rgb(210, 0, 0),
+                last_move: Color::Rgb(120, 90, 0),
+                premove: Color::Rgb(110, 0, 130),
+                white_piece: Color::Rgb(255, 255, 255),
+                black_piece: Color::Rgb(0, 0, 0),
+                accent: Color::Yellow,
+            },
+            // Built on the Okabe-Ito colors, which stay apart with red-green color blindness
+            Palette::Deuteranopia => Theme {
+                light_square: Color::Rgb(230, 159, 0),
+                dark_square: Color::Rgb(0, 114, 178),
+                cursor: Color::Rgb(204, 121, 167),
+                selected: Color::Rgb(120, 60, 110),
+                target: Color::Rgb(86, 180, 233),
+                capture: Color::Rgb(213, 94, 0),
+                check: Color::Rgb(160, 0, 60),
+                last_move: Color::Rgb(150, 140, 40),
+                premove: Color::Rgb(90, 70, 140),
+                white_piece: Color::Rgb(255, 255, 255),
+                black_piece: Color::Rgb(0, 0, 0),
+                accent: Color::Rgb(86, 180, 233),
+            },
+        }
+    }
+}
+
+impl Theme {
+    /// Sets the color with the given config name, e.g. "light_square".
+    /// Returns false if there's no such color.
+    pub fn set(&mut self, name: &str, color: Color) -> bool {
+        let slot = match name {
+            "light_square" => &mut self.light_square,
+            "dark_square" => &mut self.dark_square,
+            "cursor" => &mut self.cursor,
+            "selected" => &mut self.selected,
+            "target" => &mut self.target,
+            "capture" => &mut self.capture,
+            "check" => &mut self.check,
+            "last_move" => &mut self.last_move,
+            "premove" => &mut self.premove,
+            "white_piece" => &mut self.white_piece,
+            "black_piece" => &mut self.black_piece,
+            "accent" => &mut self.accent,
+            _ => return false,
+        };
+        *slot = color;
+        true
+    }
+}
@@ -0,0 +1,103 @@
+use chess_core::ai::Selection;
+use serde::{Deserialize, Serialize};
+use std::io;
+
+use crate::saves;
+
+const PROFILES_FILE: &str = "profiles.json";
+
+/// Where a new player's rating starts
+pub const START_RATING: i32 = 1200;
+// How quickly ratings move after a game
+const RATING_K: f64 = 32.0;
+pub const MAX_NAME_LENGTH: usize = 24;
+
+/// The fixed rating the computer plays at, by how it picks its moves and
+/// how deep it searches. The AI's ratings never move, so they anchor the
+/// players' ones.
+pub fn ai_rating(selection: Selection, depth: u32) -> i32 {
+    match selection {
+        Selection::Random => 200,
+        Selection::Beginner => 500,
+        Selection::Best => 600 + 200 * depth as i32,
+    }
+}
+
+/// How much a game against `opponent` moves `rating`, as in Elo, for a
+/// score of 1 for a win, 0.5 for a draw and 0 for a loss
+pub fn rating_change(rating: i32, opponent: i32, score: f64, k: f64) -> i32 {
+    let expected = 1.0 / (1.0 + 10f64.powf(f64::from(opponent - rating) / 400.0));
+    (k * (score - expected)).round() as i32
+}
+
+/// One person who plays on this machine
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Profile {
+    pub name: String,
+    pub rating: i32,
+    /// Rated games: those against the computer
+    pub games: u32,
+}
+
+/// The players, and which of them is at the keyboard
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Profiles {
+    pub profiles: Vec<Profile>,
+    /// Into profiles; None plays as a guest, unrated
+    #[serde(default)]
+    pub active: Option<usize>,
+}
+
+impl Profiles {
+    pub fn load() -> Profiles {
+        let mut profiles: Profiles = saves::load_data(PROFILES_FILE);
+        if profiles.active.is_some_and(|index| index >= profiles.profiles.len()) {
+            profiles.active = None;
+        }
+        profiles
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        saves::save_data(PROFILES_FILE, self)
+    }
+
+    pub fn active(&self) -> Option<&Profile> {
+        self.active.map(|index| &self.profiles[index])
+    }
+
+    /// Adds a player called `name` and makes them the active one
+    pub fn add(&mut self, name: &str) -> Result<(), String> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err("A profile needs a name".to_string());
+        }
+        if self.profiles.iter().any(|profile| profile.name.eq_ignore_ascii_case(name)) {
+            return Err(format!("There's already a profile called {}", name));
+        }
+        self.profiles.push(Profile { name: name.to_string(), rating: START_RATING, games: 0 });
+        self.active = Some(self.profiles.len() - 1);
+        Ok(())
+    }
+
+    pub fn remove(&mut self, index: usize) {
+        if index >= self.profiles.len() {
+            return;
+        }
+        self.profiles.remove(index);
+        self.active = match self.active {
+            Some(active) if active == index => None,
+            Some(active) if active > index => Some(active - 1),
+            active => active,
+        };
+    }
+
+    /// Rates the active player's game against `opponent`, returning the
+    /// new rating and how much it moved. None when playing as a guest.
+    pub fn rate_game(&mut self, opponent: i32, score: f64) -> Option<(i32, i32)> {
+        let profile = &mut self.profiles[self.active?];
+        let change = rating_change(profile.rating, opponent, score, RATING_K);
+        profile.rating += change;
+        profile.games += 1;
+        Some((profile.rating, change))
+    }
+}
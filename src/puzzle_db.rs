@@ -0,0 +1,59 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use chess_core::game::puzzle::{LichessRow, Puzzle, PuzzleFilter};
+
+/// The puzzles of a Lichess puzzle database file (lichess_db_puzzle.csv)
+/// that pass a filter. Only where each one starts in the file and its
+/// rating are kept, so the full database of millions fits in a few dozen
+/// megabytes; puzzles are read back as they come up.
+pub struct PuzzleDatabase {
+    path: PathBuf,
+    entries: Vec<(u64, Option<u32>)>,
+}
+
+impl PuzzleDatabase {
+    /// Scans the file for the puzzles `filter` lets through
+    pub fn open(path: &Path, filter: &PuzzleFilter) -> io::Result<PuzzleDatabase> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let mut entries = Vec::new();
+        let mut offset = 0;
+        let mut line = String::new();
+        loop {
+            line.clear();
+            let read = reader.read_line(&mut line)?;
+            if read == 0 {
+                break;
+            }
+            // Rows that don't parse are left out rather than failing the lot
+            let row = Some(line.as_str())
+                .filter(|line| !LichessRow::is_header(line))
+                .and_then(|line| LichessRow::parse(line).ok())
+                .filter(|row| filter.matches(row));
+            if let Some(row) = row {
+                entries.push((offset, row.rating));
+            }
+            offset += read as u64;
+        }
+        Ok(PuzzleDatabase { path: path.to_path_buf(), entries })
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// The `index`th puzzle's rating, without reading it back
+    pub fn rating(&self, index: usize) -> Option<u32> {
+        self.entries[index].1
+    }
+
+    /// Reads the `index`th puzzle back from the file
+    pub fn puzzle(&self, index: usize) -> io::Result<Puzzle> {
+        let mut file = BufReader::new(File::open(&self.path)?);
+        file.seek(SeekFrom::Start(self.entries[index].0))?;
+        let mut line = String::new();
+        file.read_line(&mut line)?;
+        Puzzle::from_lichess(&line).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+}
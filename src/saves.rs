@@ -0,0 +1,284 @@
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use chess_core::ai::Selection;
+use chess_core::game::pgn::{parse_pgn, PgnGame};
+use chess_core::game::puzzle::Puzzle;
+use chess_core::game::{Clock, Color};
+
+use crate::config::MAX_AI_DEPTH;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SavedMode {
+    TwoPlayer,
+    VsAi,
+    Kids,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SavedColor {
+    White,
+    Black,
+}
+
+impl From<Color> for SavedColor {
+    fn from(color: Color) -> Self {
+        match color {
+            Color::White => SavedColor::White,
+            Color::Black => SavedColor::Black,
+        }
+    }
+}
+
+impl From<SavedColor> for Color {
+    fn from(color: SavedColor) -> Self {
+        match color {
+            SavedColor::White => Color::White,
+            SavedColor::Black => Color::Black,
+        }
+    }
+}
+
+/// A game as stored on disk. The position is rebuilt by replaying `moves`
+/// from `initial_fen`; `fen` is kept so listings don't need to replay.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedGame {
+    pub id: String,
+    pub mode: SavedMode,
+    pub ai_color: Option<SavedColor>,
+    pub ai_depth: Option<u32>,
+    #[serde(default)]
+    pub ai_selection: Selection,
+    pub created: u64,
+    pub updated: u64,
+    pub initial_fen: String,
+    pub moves: Vec<String>,
+    pub fen: String,
+    /// PGN-style result ("1-0", "0-1", "1/2-1/2"); `None` while the game is in progress
+    pub result: Option<String>,
+    /// Time left when the game was last saved, for timed games
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub clock: Option<Clock>,
+}
+
+/// The tactics trainer's puzzle rating and what it has been through, kept
+/// between sessions
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PuzzleProgress {
+    pub rating: i32,
+    pub solved: u32,
+    pub failed: u32,
+    /// Ids of the puzzles that have been learned and don't come up again
+    #[serde(default)]
+    pub done: HashSet<String>,
+    /// Failed puzzles waiting to be tried again
+    #[serde(default)]
+    pub reviews: Vec<PuzzleReview>,
+}
+
+/// A failed puzzle kept whole, so it can come back whichever set of
+/// puzzles is in use then
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PuzzleReview {
+    pub puzzle: Puzzle,
+    /// How many other puzzles to leave between tries
+    pub interval: u32,
+    /// How many other puzzles are still to come before this one
+    pub due: u32,
+}
+
+// Where a new trainer starts, as on Lichess
+const START_PUZZLE_RATING: i32 = 1500;
+
+impl Default for PuzzleProgress {
+    fn default() -> Self {
+        PuzzleProgress {
+            rating: START_PUZZLE_RATING,
+            solved: 0,
+            failed: 0,
+            done: HashSet::new(),
+            reviews: Vec::new(),
+        }
+    }
+}
+
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+/// A Unix time as a PGN date tag value, "YYYY.MM.DD" (UTC)
+pub fn pgn_date(seconds: u64) -> String {
+    // Civil date from days since the epoch (Howard Hinnant's algorithm)
+    let days = (seconds / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{:04}.{:02}.{:02}", year, month, day)
+}
+
+/// Generates a new game id from the current time, unique within this process
+pub fn new_id() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_nanos());
+    format!("{:x}", nanos)
+}
+
+/// `$XDG_DATA_HOME/chess`, falling back to `~/.local/share/chess`
+pub fn data_dir() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_DATA_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))?;
+    Some(base.join("chess"))
+}
+
+fn games_dir() -> io::Result<PathBuf> {
+    data_dir()
+        .map(|dir| dir.join("games"))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no home directory to save games in"))
+}
+
+fn archive_dir() -> io::Result<PathBuf> {
+    data_dir()
+        .map(|dir| dir.join("archive"))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no home directory to archive games in"))
+}
+
+/// Writes a finished game's PGN to the archive, replacing any earlier
+/// ending of the same game
+pub fn archive(id: &str, pgn: &str) -> io::Result<()> {
+    let dir = archive_dir()?;
+    fs::create_dir_all(&dir)?;
+    fs::write(dir.join(format!("{}.pgn", id)), pgn)
+}
+
+/// Every archived game, most recently finished first
+pub fn list_archive() -> Vec<PgnGame> {
+    let Ok(dir) = archive_dir() else {
+        return Vec::new();
+    };
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut games: Vec<(SystemTime, PgnGame)> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "pgn"))
+        .filter_map(|path| {
+            let modified = fs::metadata(&path).and_then(|meta| meta.modified()).unwrap_or(UNIX_EPOCH);
+            let text = fs::read_to_string(&path).ok()?;
+            let game = parse_pgn(&text).ok()?.into_iter().next()?;
+            Some((modified, game))
+        })
+        .collect();
+    games.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+    games.into_iter().map(|(_, game)| game).collect()
+}
+
+/// Whether every word of `query` turns up in an archived game's tags or
+/// result, ignoring case
+pub fn archive_matches(game: &PgnGame, query: &str) -> bool {
+    let mut text = game.result.to_lowercase();
+    for (_, value) in &game.tags {
+        text.push(' ');
+        text.push_str(&value.to_lowercase());
+    }
+    query.to_lowercase().split_whitespace().all(|word| text.contains(word))
+}
+
+/// Writes an exported file (JSON, SVG, ...) to the exports directory and
+/// returns where it went
+pub fn write_export(name: &str, contents: &str) -> io::Result<PathBuf> {
+    let dir = data_dir()
+        .map(|dir| dir.join("exports"))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no home directory to export to"))?;
+    fs::create_dir_all(&dir)?;
+    let path = dir.join(name);
+    fs::write(&path, contents)?;
+    Ok(path)
+}
+
+/// Reads a JSON file kept directly in the data directory, or the default
+/// if there's none to read
+pub fn load_data<T: DeserializeOwned + Default>(name: &str) -> T {
+    data_dir()
+        .and_then(|dir| fs::read_to_string(dir.join(name)).ok())
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+/// Writes a JSON file directly in the data directory
+pub fn save_data<T: Serialize>(name: &str, value: &T) -> io::Result<()> {
+    let dir = data_dir().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no home directory to save to"))?;
+    fs::create_dir_all(&dir)?;
+    let json = serde_json::to_string_pretty(value)?;
+    // Write to a temporary file first so a crash never leaves it truncated
+    let tmp = dir.join(format!("{}.tmp", name));
+    fs::write(&tmp, json)?;
+    fs::rename(tmp, dir.join(name))
+}
+
+/// The trainer's saved progress, or a fresh start if there's none to read
+pub fn load_puzzle_progress() -> PuzzleProgress {
+    load_data("puzzles.json")
+}
+
+pub fn save_puzzle_progress(progress: &PuzzleProgress) -> io::Result<()> {
+    save_data("puzzles.json", progress)
+}
+
+pub fn save(game: &SavedGame) -> io::Result<()> {
+    let dir = games_dir()?;
+    fs::create_dir_all(&dir)?;
+    let json = serde_json::to_string_pretty(game)?;
+    // Write to a temporary file first so a crash never leaves a truncated save
+    let tmp = dir.join(format!("{}.json.tmp", game.id));
+    fs::write(&tmp, json)?;
+    fs::rename(tmp, dir.join(format!("{}.json", game.id)))
+}
+
+pub fn delete(id: &str) -> io::Result<()> {
+    fs::remove_file(games_dir()?.join(format!("{}.json", id)))
+}
+
+/// All saved games still in progress, most recently played first
+pub fn list_ongoing() -> Vec<SavedGame> {
+    let Ok(dir) = games_dir() else {
+        return Vec::new();
+    };
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut games: Vec<SavedGame> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|path| fs::read_to_string(path).ok())
+        .filter_map(|json| serde_json::from_str::<SavedGame>(&json).ok())
+        .filter(|game| game.result.is_none())
+        .map(|mut game| {
+            // A hand-edited save could ask for a depth the engine can't search to
+            game.ai_depth = game.ai_depth.map(|depth| depth.clamp(1, MAX_AI_DEPTH));
+            game
+        })
+        .collect();
+    games.sort_by_key(|game| std::cmp::Reverse(game.updated));
+    games
+}
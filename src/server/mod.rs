@@ -0,0 +1,435 @@
+pub mod protocol;
+
+use std::collections::HashMap;
+use std::io::{self, ErrorKind};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use tungstenite::{Message, WebSocket};
+
+use chess_core::game::draw::DrawClaim;
+use chess_core::game::{Board, Clock, ClockMode, Color, GameEvent, GameOverReason, GameState, MoveGenerator, TimeControl};
+use protocol::{ClientMessage, ServerMessage, Side};
+
+use crate::suspend::{Suspension, SuspendWatcher};
+
+pub const DEFAULT_ADDR: &str = "127.0.0.1:8080";
+
+fn side_of(color: Color) -> Side {
+    match color {
+        Color::White => Side::White,
+        Color::Black => Side::Black,
+    }
+}
+
+struct ServerGame {
+    id: String,
+    state: GameState,
+    // Time used so far by each side, for untimed games
+    white_used_ms: u64,
+    black_used_ms: u64,
+    turn_started: Option<Instant>,
+    // What happened in the game since it was last published
+    events: Receiver<GameEvent>,
+    // Who to tell about the game, by connection
+    subscribers: Vec<(u64, Sender<ServerMessage>)>,
+    // The connection playing each side, while it stays
+    seats: HashMap<Side, u64>,
+}
+
+impl ServerGame {
+    fn new(id: String, initial_ms: Option<u64>, increment_ms: u64) -> Self {
+        let clock = initial_ms
+            .map(|initial_ms| Clock::new(TimeControl { initial_ms, increment_ms, mode: ClockMode::Increment }));
+        let mut state = GameState::new(Board::new()).with_clock(clock);
+        let (sender, events) = mpsc::channel();
+        state.subscribe(Box::new(move |event| {
+            // The receiver goes with the game
+            let _ = sender.send(*event);
+        }));
+        ServerGame {
+            id,
+            state,
+            white_used_ms: 0,
+            black_used_ms: 0,
+            turn_started: None,
+            events,
+            subscribers: Vec::new(),
+            seats: HashMap::new(),
+        }
+    }
+
+    /// Stops telling `connection` about the game and frees its seat
+    fn leave(&mut self, connection: u64) {
+        self.subscribers.retain(|(id, _)| *id != connection);
+        self.seats.retain(|_, id| *id != connection);
+    }
+
+    /// Game status and winner as the protocol reports them
+    fn status(&self) -> (&'static str, Option<Side>) {
+        let Some(reason) = self.state.result() else {
+            return ("active", None);
+        };
+        let status = match reason {
+            GameOverReason::Checkmate(_) => "checkmate",
+            GameOverReason::Stalemate => "stalemate",
+            GameOverReason::InsufficientMaterial => "insufficient_material",
+            GameOverReason::DeadPosition => "dead_position",
+            GameOverReason::DrawClaimed(DrawClaim::ThreefoldRepetition) => "threefold_repetition",
+            GameOverReason::DrawClaimed(DrawClaim::FiftyMoves) => "fifty_move_rule",
+            GameOverReason::DrawAgreed => "draw_agreed",
+            GameOverReason::FivefoldRepetition => "fivefold_repetition",
+            GameOverReason::SeventyFiveMoveRule => "seventy_five_move_rule",
+            GameOverReason::Resignation(_) => "resignation",
+            GameOverReason::Timeout { .. } => "timeout",
+            GameOverReason::Remote { .. } | GameOverReason::Review { .. } => "ended",
+        };
+        let winner = match reason.result() {
+            "1-0" => Some(Side::White),
+            "0-1" => Some(Side::Black),
+            _ => None,
+        };
+        (status, winner)
+    }
+
+    /// Charges the running side for the time since its turn began
+    fn update_clock(&mut self) {
+        let Some(started) = self.turn_started else {
+            return;
+        };
+        if self.state.is_over() {
+            return;
+        }
+        let elapsed = started.elapsed().as_millis() as u64;
+        self.turn_started = Some(Instant::now());
+
+        if self.state.clock().is_some() {
+            self.state.charge_clock(elapsed);
+        } else {
+            match self.state.board().current_player {
+                Color::White => self.white_used_ms += elapsed,
+                Color::Black => self.black_used_ms += elapsed,
+            }
+        }
+    }
+
+    /// Gives back time charged to the running side while the server was
+    /// suspended, so nobody loses on time to a sleeping host
+    fn credit_suspension(&mut self, suspension: Suspension) {
+        if let Some(started) = self.turn_started {
+            self.turn_started = Some(started + suspension.monotonic.min(started.elapsed()));
+        }
+    }
+
+    fn play(&mut self, uci: &str) -> Result<(), String> {
+        self.update_clock();
+        if self.state.is_over() {
+            return Err("game is over".to_string());
+        }
+        let mv = MoveGenerator::find_uci_move(self.state.board(), uci)
+            .ok_or_else(|| format!("illegal move: {}", uci))?;
+        self.state.play(mv).map_err(|err| err.to_string())?;
+
+        let ticking = self.turn_started.is_some();
+        if let Some(clock) = self.state.clock_mut().filter(|_| ticking) {
+            clock.complete_move(mv.piece.color);
+        }
+        self.state.adjudicate();
+        // Clocks start with Black's first move, as on most servers
+        if self.state.moves().len() >= 2 || self.state.clock().is_none() {
+            self.turn_started = Some(Instant::now());
+        }
+        Ok(())
+    }
+
+    /// Claims a draw for the side to move, if the rules allow one
+    fn claim_draw(&mut self) -> Result<(), String> {
+        self.update_clock();
+        self.state.claim_draw().map_err(|err| err.to_string())?;
+        Ok(())
+    }
+
+    fn state_message(&self) -> ServerMessage {
+        let (status, winner) = self.status();
+        let board = self.state.board();
+        ServerMessage::State {
+            game: self.id.clone(),
+            fen: board.to_fen(),
+            moves: self.state.moves().iter().map(|mv| mv.to_uci()).collect(),
+            turn: side_of(board.current_player),
+            in_check: MoveGenerator::is_in_check(board, board.current_player),
+            status,
+            winner,
+        }
+    }
+
+    fn clock_message(&self) -> ServerMessage {
+        let running = if self.turn_started.is_some() && !self.state.is_over() {
+            Some(side_of(self.state.board().current_player))
+        } else {
+            None
+        };
+        let clock = self.state.clock();
+        ServerMessage::Clock {
+            game: self.id.clone(),
+            white_ms: clock.map_or(self.white_used_ms, |clock| clock.white_ms),
+            black_ms: clock.map_or(self.black_used_ms, |clock| clock.black_ms),
+            timed: clock.is_some(),
+            running,
+        }
+    }
+
+    /// Tells everyone in the game what happened in it since the last call:
+    /// the state after a move or the end of the game, the clocks after
+    /// either or a clock running low
+    fn publish(&mut self) {
+        let (mut state_changed, mut clock_changed) = (false, false);
+        for event in self.events.try_iter() {
+            match event {
+                GameEvent::MovePlayed(_) | GameEvent::GameEnded(_) => {
+                    state_changed = true;
+                    clock_changed = true;
+                }
+                GameEvent::Clock { .. } => clock_changed = true,
+                GameEvent::Captured(_) | GameEvent::Promotion { .. } | GameEvent::Check(_) => {}
+            }
+        }
+        if state_changed {
+            let state = self.state_message();
+            self.broadcast(state);
+        }
+        if clock_changed {
+            let clock = self.clock_message();
+            self.broadcast(clock);
+        }
+    }
+
+    fn broadcast(&mut self, message: ServerMessage) {
+        self.subscribers.retain(|(_, subscriber)| subscriber.send(message.clone()).is_ok());
+    }
+}
+
+type Games = Arc<Mutex<HashMap<String, ServerGame>>>;
+
+// Numbers each connection, to tell subscribers and seat holders apart
+static NEXT_CONNECTION: AtomicU64 = AtomicU64::new(0);
+
+/// Takes `connection` out of the game `id`, and drops the game once it's
+/// over and nobody is left in it
+fn leave(games: &mut HashMap<String, ServerGame>, id: &str, connection: u64) {
+    let Some(game) = games.get_mut(id) else {
+        return;
+    };
+    game.leave(connection);
+    if game.state.is_over() && game.subscribers.is_empty() {
+        games.remove(id);
+    }
+}
+
+type WsResult<T> = Result<T, Box<tungstenite::Error>>;
+
+struct Connection {
+    id: u64,
+    games: Games,
+    outbox: Sender<ServerMessage>,
+    game: Option<String>,
+    side: Side,
+}
+
+impl Connection {
+    fn handle(&mut self, message: ClientMessage) -> Vec<ServerMessage> {
+        match message {
+            ClientMessage::Join { game, side, initial_ms, increment_ms } => {
+                let mut games = self.games.lock().unwrap();
+                let holder = games.get(&game).and_then(|entry| entry.seats.get(&side)).copied();
+                if side != Side::Spectator && holder.is_some_and(|holder| holder != self.id) {
+                    return vec![error(&format!("{:?} is already taken in this game", side))];
+                }
+                if let Some(previous) = self.game.take().filter(|previous| *previous != game) {
+                    leave(&mut games, &previous, self.id);
+                }
+                let entry = games
+                    .entry(game.clone())
+                    .or_insert_with(|| ServerGame::new(game.clone(), initial_ms, increment_ms));
+                // Joining again, perhaps as the other side, replaces the
+                // earlier seat and subscription
+                entry.leave(self.id);
+                entry.subscribers.push((self.id, self.outbox.clone()));
+                if side != Side::Spectator {
+                    entry.seats.insert(side, self.id);
+                }
+                self.game = Some(game.clone());
+                self.side = side;
+                vec![
+                    ServerMessage::Joined { game, side },
+                    entry.state_message(),
+                    entry.clock_message(),
+                ]
+            }
+            ClientMessage::Move { uci } => {
+                let Some(id) = self.game.clone() else {
+                    return vec![error("join a game before moving")];
+                };
+                let mut games = self.games.lock().unwrap();
+                let Some(game) = games.get_mut(&id) else {
+                    return vec![error("game no longer exists")];
+                };
+                if self.side != side_of(game.state.board().current_player) {
+                    return vec![error("it is not your turn")];
+                }
+                let result = game.play(&uci);
+                // Even a move turned down may have run a clock out
+                game.publish();
+                match result {
+                    Ok(()) => Vec::new(),
+                    Err(message) => vec![error(&message)],
+                }
+            }
+            ClientMessage::ClaimDraw => {
+                let Some(id) = self.game.clone() else {
+                    return vec![error("join a game first")];
+                };
+                let mut games = self.games.lock().unwrap();
+                let Some(game) = games.get_mut(&id) else {
+                    return vec![error("game no longer exists")];
+                };
+                if self.side != side_of(game.state.board().current_player) {
+                    return vec![error("only the side to move can claim a draw")];
+                }
+                let result = game.claim_draw();
+                // Even a claim turned down may have run a clock out
+                game.publish();
+                match result {
+                    Ok(()) => Vec::new(),
+                    Err(message) => vec![error(&message)],
+                }
+            }
+            ClientMessage::State | ClientMessage::Clock => {
+                let Some(id) = self.game.clone() else {
+                    return vec![error("join a game first")];
+                };
+                let mut games = self.games.lock().unwrap();
+                let Some(game) = games.get_mut(&id) else {
+                    return vec![error("game no longer exists")];
+                };
+                // A flag that fell since the last move ends the game for everyone
+                game.update_clock();
+                game.publish();
+                match message {
+                    ClientMessage::State => vec![game.state_message()],
+                    _ => vec![game.clock_message()],
+                }
+            }
+        }
+    }
+}
+
+// A connection that closes, however it closes, leaves its game
+impl Drop for Connection {
+    fn drop(&mut self) {
+        if let (Some(id), Ok(mut games)) = (self.game.take(), self.games.lock()) {
+            leave(&mut games, &id, self.id);
+        }
+    }
+}
+
+fn error(message: &str) -> ServerMessage {
+    ServerMessage::Error { message: message.to_string() }
+}
+
+fn send(socket: &mut WebSocket<TcpStream>, message: &ServerMessage) -> WsResult<()> {
+    let json = serde_json::to_string(message).expect("server messages always serialize");
+    Ok(socket.send(Message::text(json))?)
+}
+
+fn handle_connection(stream: TcpStream, games: Games) -> WsResult<()> {
+    let mut socket = tungstenite::accept(stream).map_err(|err| match err {
+        tungstenite::HandshakeError::Failure(err) => err,
+        tungstenite::HandshakeError::Interrupted(_) => {
+            tungstenite::Error::Io(io::Error::new(ErrorKind::WouldBlock, "handshake interrupted"))
+        }
+    })?;
+    // Short read timeouts let one thread both read requests and push broadcasts
+    socket
+        .get_ref()
+        .set_read_timeout(Some(Duration::from_millis(50)))
+        .map_err(tungstenite::Error::Io)?;
+
+    let (outbox, inbox): (Sender<ServerMessage>, Receiver<ServerMessage>) = mpsc::channel();
+    let mut connection = Connection {
+        id: NEXT_CONNECTION.fetch_add(1, Ordering::Relaxed),
+        games,
+        outbox,
+        game: None,
+        side: Side::Spectator,
+    };
+
+    loop {
+        match socket.read() {
+            Ok(Message::Text(text)) => {
+                let replies = match serde_json::from_str::<ClientMessage>(&text) {
+                    Ok(message) => connection.handle(message),
+                    Err(err) => vec![error(&format!("invalid message: {}", err))],
+                };
+                for reply in &replies {
+                    send(&mut socket, reply)?;
+                }
+            }
+            Ok(Message::Close(_)) => break,
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(err))
+                if err.kind() == ErrorKind::WouldBlock || err.kind() == ErrorKind::TimedOut => {}
+            Err(tungstenite::Error::ConnectionClosed) => break,
+            Err(err) => return Err(Box::new(err)),
+        }
+
+        while let Ok(message) = inbox.try_recv() {
+            send(&mut socket, &message)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Runs the headless game server until the process is killed
+pub fn serve(addr: &str) -> io::Result<()> {
+    let listener = TcpListener::bind(addr)?;
+    let games: Games = Arc::new(Mutex::new(HashMap::new()));
+    println!("Chess server listening on ws://{}", listener.local_addr()?);
+
+    let watched_games = Arc::clone(&games);
+    thread::spawn(move || {
+        let watcher = SuspendWatcher::spawn();
+        while let Some(suspension) = watcher.wait() {
+            eprintln!("Resumed after {}s suspended, adjusting clocks", suspension.wall.as_secs());
+            for game in watched_games.lock().unwrap().values_mut() {
+                game.credit_suspension(suspension);
+                let clock = game.clock_message();
+                game.broadcast(clock);
+            }
+        }
+    });
+
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(err) => {
+                eprintln!("Failed to accept connection: {}", err);
+                continue;
+            }
+        };
+        let games = Arc::clone(&games);
+        thread::spawn(move || {
+            let peer = stream.peer_addr().ok();
+            if let Err(err) = handle_connection(stream, games) {
+                eprintln!("Connection {:?} closed with error: {}", peer, err);
+            }
+        });
+    }
+
+    Ok(())
+}
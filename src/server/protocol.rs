@@ -0,0 +1,64 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Side {
+    White,
+    Black,
+    Spectator,
+}
+
+/// Messages sent by frontends
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMessage {
+    /// Joins (creating if needed) a game. `initial_ms` and `increment_ms`
+    /// set a time control when the game is created; omit them for an untimed game.
+    Join {
+        game: String,
+        side: Side,
+        initial_ms: Option<u64>,
+        #[serde(default)]
+        increment_ms: u64,
+    },
+    /// A move in UCI coordinate notation, e.g. "e2e4" or "e7e8q"
+    Move {
+        #[serde(rename = "move")]
+        uci: String,
+    },
+    /// Claims a draw by threefold repetition or the fifty-move rule for
+    /// the side to move
+    ClaimDraw,
+    State,
+    Clock,
+}
+
+/// Messages sent to frontends
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    Joined {
+        game: String,
+        side: Side,
+    },
+    State {
+        game: String,
+        fen: String,
+        moves: Vec<String>,
+        turn: Side,
+        in_check: bool,
+        status: &'static str,
+        winner: Option<Side>,
+    },
+    /// Remaining time when the game has a time control, otherwise time used so far
+    Clock {
+        game: String,
+        white_ms: u64,
+        black_ms: u64,
+        timed: bool,
+        running: Option<Side>,
+    },
+    Error {
+        message: String,
+    },
+}
@@ -0,0 +1,130 @@
+use std::io::{self, Write};
+
+/// Something in the game worth hearing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sound {
+    Move,
+    Capture,
+    Check,
+    GameEnd,
+}
+
+/// A sound to play. Without sound effects, only alerts (the opponent
+/// moving, the game ending) ring the terminal bell instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cue {
+    pub sound: Sound,
+    pub alert: bool,
+}
+
+/// Plays `cue` as a sound effect, falling back to a bell written to `out`
+pub fn play(cue: Cue, out: &mut impl Write) -> io::Result<()> {
+    if effects::play(cue.sound) || !cue.alert {
+        return Ok(());
+    }
+    out.write_all(b"\x07")?;
+    out.flush()
+}
+
+#[cfg(feature = "sound")]
+mod effects {
+    use std::env;
+    use std::f32::consts::TAU;
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+    use std::sync::OnceLock;
+    use std::thread;
+
+    use super::Sound;
+
+    // Players that read a WAV file from stdin, tried in order
+    const PLAYERS: [&[&str]; 3] = [&["paplay"], &["pw-play", "-"], &["aplay", "-q"]];
+    const SAMPLE_RATE: u32 = 22_050;
+
+    fn player() -> Option<&'static [&'static str]> {
+        static PLAYER: OnceLock<Option<&'static [&'static str]>> = OnceLock::new();
+        *PLAYER.get_or_init(|| {
+            let paths = env::var_os("PATH")?;
+            PLAYERS
+                .into_iter()
+                .find(|command| env::split_paths(&paths).any(|dir| dir.join(command[0]).is_file()))
+        })
+    }
+
+    /// Starts playing `sound` in the background. Returns false if there's
+    /// no audio player to play it with.
+    pub fn play(sound: Sound) -> bool {
+        let Some(command) = player() else {
+            return false;
+        };
+        let child = Command::new(command[0])
+            .args(&command[1..])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn();
+        let Ok(mut child) = child else {
+            return false;
+        };
+        thread::spawn(move || {
+            if let Some(mut stdin) = child.stdin.take() {
+                let _ = stdin.write_all(&wav(sound));
+            }
+            let _ = child.wait();
+        });
+        true
+    }
+
+    /// Frequencies in Hz and lengths in ms of the notes making up `sound`
+    fn notes(sound: Sound) -> &'static [(f32, u32)] {
+        match sound {
+            Sound::Move => &[(620.0, 40)],
+            Sound::Capture => &[(420.0, 50), (310.0, 70)],
+            Sound::Check => &[(880.0, 70), (880.0, 110)],
+            Sound::GameEnd => &[(660.0, 130), (550.0, 130), (440.0, 260)],
+        }
+    }
+
+    /// `sound` as a mono 16-bit WAV file
+    fn wav(sound: Sound) -> Vec<u8> {
+        let mut samples = Vec::new();
+        for &(frequency, ms) in notes(sound) {
+            let count = SAMPLE_RATE * ms / 1000;
+            for i in 0..count {
+                let t = i as f32 / SAMPLE_RATE as f32;
+                // Each note dies away so consecutive ones don't click
+                let envelope = 1.0 - i as f32 / count as f32;
+                let sample = (TAU * frequency * t).sin() * envelope * envelope * 0.3;
+                samples.push((sample * i16::MAX as f32) as i16);
+            }
+        }
+
+        let data_len = samples.len() as u32 * 2;
+        let mut bytes = Vec::with_capacity(44 + data_len as usize);
+        bytes.extend_from_slice(b"RIFF");
+        bytes.extend_from_slice(&(36 + data_len).to_le_bytes());
+        bytes.extend_from_slice(b"WAVEfmt ");
+        bytes.extend_from_slice(&16u32.to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes()); // PCM
+        bytes.extend_from_slice(&1u16.to_le_bytes()); // mono
+        bytes.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+        bytes.extend_from_slice(&(SAMPLE_RATE * 2).to_le_bytes());
+        bytes.extend_from_slice(&2u16.to_le_bytes());
+        bytes.extend_from_slice(&16u16.to_le_bytes());
+        bytes.extend_from_slice(b"data");
+        bytes.extend_from_slice(&data_len.to_le_bytes());
+        for sample in samples {
+            bytes.extend_from_slice(&sample.to_le_bytes());
+        }
+        bytes
+    }
+}
+
+#[cfg(not(feature = "sound"))]
+mod effects {
+    use super::Sound;
+
+    pub fn play(_sound: Sound) -> bool {
+        false
+    }
+}
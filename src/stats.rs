@@ -0,0 +1,145 @@
+use chess_core::ai::Selection;
+use serde::{Deserialize, Serialize};
+use std::io;
+
+use crate::saves;
+
+const STATS_FILE: &str = "stats.json";
+
+/// The kinds of game results are kept apart by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StatsMode {
+    /// Counted from White's side
+    TwoPlayer,
+    VsAi,
+    Kids,
+    Endgame,
+    Online,
+}
+
+impl StatsMode {
+    pub fn label(self) -> &'static str {
+        match self {
+            StatsMode::TwoPlayer => "Two Player (as White)",
+            StatsMode::VsAi => "Play vs AI",
+            StatsMode::Kids => "Kids Mode",
+            StatsMode::Endgame => "Endgame Practice",
+            StatsMode::Online => "Online",
+        }
+    }
+}
+
+/// How a game ended for the player
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Win,
+    Loss,
+    Draw,
+}
+
+/// The results of one mode, at one AI level for games against the computer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Record {
+    pub mode: StatsMode,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ai_depth: Option<u32>,
+    /// The beginner and random levels, below depth 1
+    #[serde(default, skip_serializing_if = "is_best")]
+    pub ai_selection: Selection,
+    pub wins: u32,
+    pub losses: u32,
+    pub draws: u32,
+    /// Half-moves over all the games, for their average length
+    pub plies: u64,
+    /// Games won in a row if positive, lost in a row if negative
+    pub streak: i32,
+    pub best_streak: u32,
+}
+
+fn is_best(selection: &Selection) -> bool {
+    *selection == Selection::Best
+}
+
+impl Record {
+    pub fn games(&self) -> u32 {
+        self.wins + self.losses + self.draws
+    }
+
+    /// The average game length in moves
+    pub fn average_moves(&self) -> f64 {
+        match self.games() {
+            0 => 0.0,
+            games => self.plies as f64 / 2.0 / f64::from(games),
+        }
+    }
+}
+
+/// Every finished game's result, kept between sessions
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GameStats {
+    pub records: Vec<Record>,
+}
+
+impl GameStats {
+    pub fn load() -> GameStats {
+        saves::load_data(STATS_FILE)
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        saves::save_data(STATS_FILE, self)
+    }
+
+    /// Counts a game of `plies` half-moves that ended in `outcome`
+    pub fn record(
+        &mut self,
+        mode: StatsMode,
+        ai_level: Option<(Selection, u32)>,
+        outcome: Outcome,
+        plies: usize,
+    ) {
+        let ai_selection = ai_level.map_or(Selection::Best, |(selection, _)| selection);
+        let ai_depth = ai_level.map(|(_, depth)| depth);
+        // Kept in order, so the stats screen lists them by mode and level
+        let key = (mode, ai_selection, ai_depth);
+        let index = match self
+            .records
+            .binary_search_by_key(&key, |record| (record.mode, record.ai_selection, record.ai_depth))
+        {
+            Ok(index) => index,
+            Err(index) => {
+                let record = Record {
+                    mode,
+                    ai_depth,
+                    ai_selection,
+                    wins: 0,
+                    losses: 0,
+                    draws: 0,
+                    plies: 0,
+                    streak: 0,
+                    best_streak: 0,
+                };
+                self.records.insert(index, record);
+                index
+            }
+        };
+
+        let record = &mut self.records[index];
+        record.plies += plies as u64;
+        match outcome {
+            Outcome::Win => {
+                record.wins += 1;
+                record.streak = record.streak.max(0) + 1;
+                record.best_streak = record.best_streak.max(record.streak as u32);
+            }
+            Outcome::Loss => {
+                record.losses += 1;
+                record.streak = record.streak.min(0) - 1;
+            }
+            Outcome::Draw => {
+                record.draws += 1;
+                record.streak = 0;
+            }
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+const HEARTBEAT: Duration = Duration::from_secs(1);
+// Longer stalls than this mean the process was frozen, not just scheduled late
+const THRESHOLD: Duration = Duration::from_secs(3);
+
+/// A stretch of time the process was frozen, e.g. by system suspend or a
+/// stopped terminal job
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Suspension {
+    /// How far `Instant` moved during the gap. Clocks measured with it were
+    /// charged this much; on Linux and macOS it stops while suspended.
+    pub monotonic: Duration,
+    /// How far the wall clock moved, which always includes time asleep
+    pub wall: Duration,
+}
+
+impl Suspension {
+    fn merge(self, other: Suspension) -> Suspension {
+        Suspension {
+            monotonic: self.monotonic + other.monotonic,
+            wall: self.wall + other.wall,
+        }
+    }
+}
+
+/// Watches for suspend/resume from a heartbeat thread, which notices the
+/// jump even while the main thread is busy
+pub struct SuspendWatcher {
+    receiver: Receiver<Suspension>,
+}
+
+impl SuspendWatcher {
+    pub fn spawn() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || loop {
+            let instant = Instant::now();
+            let wall = SystemTime::now();
+            thread::sleep(HEARTBEAT);
+
+            let monotonic = instant.elapsed().saturating_sub(HEARTBEAT);
+            let wall = wall.elapsed().unwrap_or_default().saturating_sub(HEARTBEAT);
+            let frozen = monotonic > THRESHOLD || wall > THRESHOLD;
+            if frozen && sender.send(Suspension { monotonic, wall }).is_err() {
+                break;
+            }
+        });
+        SuspendWatcher { receiver }
+    }
+
+    /// Suspensions detected since the last call, merged into one
+    pub fn poll(&self) -> Option<Suspension> {
+        self.receiver.try_iter().reduce(Suspension::merge)
+    }
+
+    /// Blocks until the next suspension is detected
+    pub fn wait(&self) -> Option<Suspension> {
+        self.receiver.recv().ok()
+    }
+}
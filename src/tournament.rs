@@ -0,0 +1,231 @@
+use std::error::Error;
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+use chess_core::ai::eval::EvalWeights;
+use chess_core::ai::{ChessAI, Selection, OPENING_VARIETY_MOVES};
+
+use crate::engine_match::MatchEngine;
+use crate::eval_weights;
+use crate::uci_engine::{UciEngine, UciLimit};
+
+/// Depth an engine searches to when its spec gives neither a depth nor a
+/// move time
+pub const DEFAULT_DEPTH: u32 = 3;
+
+/// Where an engine's moves come from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EngineKind {
+    /// The built-in engine
+    Builtin { depth: u32, selection: Selection, move_time: Option<u64>, weights: Option<PathBuf> },
+    /// An external UCI engine run from this path
+    Uci { path: PathBuf, limit: UciLimit },
+}
+
+/// One entrant of a tournament, written on the command line as
+/// comma-separated `key=value` pairs: `depth=4`, `level=beginner`,
+/// `weights=tuned.json` or `movetime=500` for the built-in engine, or
+/// `uci=/usr/bin/stockfish` with `depth` or `movetime` for an external one,
+/// each optionally with `name=...`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EngineSpec {
+    pub name: Option<String>,
+    pub kind: EngineKind,
+}
+
+impl FromStr for EngineSpec {
+    type Err = String;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let (mut name, mut uci, mut depth, mut move_time, mut weights) = (None, None, None, None, None);
+        let mut selection = Selection::Best;
+        for pair in spec.split(',').map(str::trim).filter(|pair| !pair.is_empty()) {
+            let (key, value) = pair.split_once('=').ok_or_else(|| format!("expected key=value, found \"{}\"", pair))?;
+            let number = || value.parse::<u64>().map_err(|_| format!("{} must be a number, not \"{}\"", key, value));
+            match key {
+                "name" => name = Some(value.to_string()),
+                "uci" => uci = Some(PathBuf::from(value)),
+                "depth" => depth = Some(number()?.clamp(1, u64::from(u32::MAX)) as u32),
+                "movetime" => move_time = Some(number()?.max(1)),
+                "weights" => weights = Some(PathBuf::from(value)),
+                "level" => {
+                    selection = match value {
+                        "best" => Selection::Best,
+                        "beginner" => Selection::Beginner,
+                        "random" => Selection::Random,
+                        _ => return Err(format!("unknown level \"{}\" (best, beginner or random)", value)),
+                    }
+                }
+                _ => return Err(format!("unknown key \"{}\"", key)),
+            }
+        }
+
+        let kind = match uci {
+            Some(path) => {
+                if weights.is_some() || selection != Selection::Best {
+                    return Err("weights and level are only for the built-in engine".to_string());
+                }
+                let limit = match (depth, move_time) {
+                    (Some(_), Some(_)) => return Err("give an external engine a depth or a movetime, not both".into()),
+                    (_, Some(ms)) => UciLimit::MoveTime(ms),
+                    (depth, None) => UciLimit::Depth(depth.unwrap_or(DEFAULT_DEPTH)),
+                };
+                EngineKind::Uci { path, limit }
+            }
+            None => EngineKind::Builtin { depth: depth.unwrap_or(DEFAULT_DEPTH), selection, move_time, weights },
+        };
+        Ok(EngineSpec { name, kind })
+    }
+}
+
+/// A tournament engine, started and ready to play
+pub enum Entrant {
+    Builtin { depth: u32, selection: Selection, move_time: Option<u64>, weights: EvalWeights },
+    // Boxed, as the running process is much bigger than the other kind
+    Uci(Box<UciEngine>),
+}
+
+impl Entrant {
+    /// Starts the engine `spec` describes, evaluating with `default_weights`
+    /// if it's built in and names no weights of its own. Returns it with
+    /// its name.
+    pub fn start(spec: &EngineSpec, default_weights: &EvalWeights) -> Result<(String, Entrant), Box<dyn Error>> {
+        let (name, entrant) = match &spec.kind {
+            EngineKind::Builtin { depth, selection, move_time, weights } => {
+                let mut name = match selection {
+                    Selection::Random => "Random".to_string(),
+                    Selection::Beginner => format!("Depth {} beginner", depth),
+                    Selection::Best => format!("Depth {}", depth),
+                };
+                if let Some(ms) = move_time {
+                    name.push_str(&format!(" {}ms", ms));
+                }
+                if let Some(path) = weights {
+                    name.push_str(&format!(" with {}", path.display()));
+                }
+                let weights = weights.as_deref().map(eval_weights::load).transpose()?;
+                let weights = weights.unwrap_or_else(|| default_weights.clone());
+                (name, Entrant::Builtin { depth: *depth, selection: *selection, move_time: *move_time, weights })
+            }
+            EngineKind::Uci { path, limit } => {
+                let engine = UciEngine::start(path, *limit)?;
+                let name = match limit {
+                    UciLimit::Depth(depth) => format!("{} depth {}", engine.name, depth),
+                    UciLimit::MoveTime(ms) => format!("{} {}ms", engine.name, ms),
+                };
+                (name, Entrant::Uci(Box::new(engine)))
+            }
+        };
+        Ok((spec.name.clone().unwrap_or(name), entrant))
+    }
+
+    /// The engine to play one game with: a built-in engine is made afresh
+    /// for each game in `built`, seeded with `seed`
+    pub fn engine<'a>(&'a self, seed: u64, built: &'a mut Option<ChessAI>) -> &'a dyn MatchEngine {
+        match self {
+            Entrant::Builtin { depth, selection, move_time, weights } => {
+                let mut ai = ChessAI::new(*depth)
+                    .with_selection(*selection)
+                    .with_variety(OPENING_VARIETY_MOVES)
+                    .with_seed(seed)
+                    .with_weights(weights.clone());
+                if let Some(ms) = move_time {
+                    ai = ai.with_time_limit(Duration::from_millis(*ms));
+                }
+                built.insert(ai)
+            }
+            Entrant::Uci(engine) => engine.as_ref(),
+        }
+    }
+}
+
+/// Who plays whom in a tournament
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// Every engine against every other
+    RoundRobin,
+    /// The first engine against each of the others, which don't meet
+    Gauntlet,
+}
+
+/// The pairs of engine indices that meet among `engines` engines, the
+/// first of each taking White in the first game between them
+pub fn pairings(engines: usize, format: Format) -> Vec<(usize, usize)> {
+    match format {
+        Format::RoundRobin => (0..engines).flat_map(|a| (a + 1..engines).map(move |b| (a, b))).collect(),
+        Format::Gauntlet => (1..engines).map(|b| (0, b)).collect(),
+    }
+}
+
+/// Every engine's score against every other so far
+pub struct Crosstable {
+    names: Vec<String>,
+    // Half points scored by one engine against another, and games played
+    // between them, by their indices
+    half_points: Vec<Vec<u32>>,
+    games: Vec<Vec<u32>>,
+}
+
+impl Crosstable {
+    pub fn new(names: Vec<String>) -> Self {
+        let size = names.len();
+        Crosstable { names, half_points: vec![vec![0; size]; size], games: vec![vec![0; size]; size] }
+    }
+
+    /// Records the PGN `result` of a game between `white` and `black`.
+    /// Unfinished games ("*") count as played but score nothing.
+    pub fn record(&mut self, white: usize, black: usize, result: &str) {
+        let (white_half_points, black_half_points) = match result {
+            "1-0" => (2, 0),
+            "0-1" => (0, 2),
+            "1/2-1/2" => (1, 1),
+            _ => (0, 0),
+        };
+        self.half_points[white][black] += white_half_points;
+        self.half_points[black][white] += black_half_points;
+        self.games[white][black] += 1;
+        self.games[black][white] += 1;
+    }
+
+    fn total(&self, engine: usize) -> (u32, u32) {
+        (self.half_points[engine].iter().sum(), self.games[engine].iter().sum())
+    }
+}
+
+fn points(half_points: u32) -> String {
+    if half_points % 2 == 1 { format!("{}.5", half_points / 2) } else { (half_points / 2).to_string() }
+}
+
+/// The table with the engines ranked by score, each row giving that
+/// engine's points against the engine of each numbered column
+impl fmt::Display for Crosstable {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut ranked: Vec<usize> = (0..self.names.len()).collect();
+        ranked.sort_by_key(|&engine| std::cmp::Reverse(self.total(engine).0));
+        let name_width = self.names.iter().map(|name| name.chars().count()).max().unwrap_or(0).max("Engine".len());
+        // Wide enough for "12.5/16"
+        const CELL: usize = 8;
+
+        write!(f, "{:>3}  {:<name_width$}", "", "Engine")?;
+        for rank in 1..=ranked.len() {
+            write!(f, "{:>CELL$}", rank)?;
+        }
+        writeln!(f, "{:>CELL$}", "Total")?;
+        for (rank, &engine) in ranked.iter().enumerate() {
+            write!(f, "{:>3}  {:<name_width$}", rank + 1, self.names[engine])?;
+            for &opponent in &ranked {
+                let cell = match self.games[engine][opponent] {
+                    _ if opponent == engine => "x".to_string(),
+                    0 => "-".to_string(),
+                    games => format!("{}/{}", points(self.half_points[engine][opponent]), games),
+                };
+                write!(f, "{:>CELL$}", cell)?;
+            }
+            let (half_points, games) = self.total(engine);
+            writeln!(f, "{:>CELL$}", format!("{}/{}", points(half_points), games))?;
+        }
+        Ok(())
+    }
+}
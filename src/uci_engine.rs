@@ -0,0 +1,196 @@
+use std::cell::RefCell;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+use chess_core::ai::MATE_SCORE;
+use chess_core::game::{Board, Move, MoveGenerator};
+
+use crate::engine_match::MatchEngine;
+
+/// How an external engine is told to search each move
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UciLimit {
+    /// `go depth N`
+    Depth(u32),
+    /// `go movetime N`, in milliseconds
+    MoveTime(u64),
+}
+
+struct Process {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+}
+
+impl Process {
+    /// Starts the engine at `path` and waits for it to be ready, returning
+    /// it with the name it gives in its `id name` line
+    fn start(path: &Path) -> io::Result<(Process, Option<String>)> {
+        let mut child = Command::new(path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|err| io::Error::new(err.kind(), format!("{}: {}", path.display(), err)))?;
+        let (Some(stdin), Some(stdout)) = (child.stdin.take(), child.stdout.take()) else {
+            return Err(io::Error::other("no pipes to the engine"));
+        };
+        let mut process = Process { child, stdin, stdout: BufReader::new(stdout) };
+
+        process.send("uci")?;
+        let mut name = None;
+        process.read_until("uciok", |line| {
+            if let Some(id) = line.strip_prefix("id name ") {
+                name = Some(id.trim().to_string());
+            }
+        })?;
+        process.send("isready")?;
+        process.read_until("readyok", |_| {})?;
+        Ok((process, name))
+    }
+
+    fn send(&mut self, command: &str) -> io::Result<()> {
+        log::debug!("uci < {}", command);
+        writeln!(self.stdin, "{}", command)?;
+        self.stdin.flush()
+    }
+
+    fn read_line(&mut self) -> io::Result<String> {
+        let mut line = String::new();
+        if self.stdout.read_line(&mut line)? == 0 {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "the engine exited"));
+        }
+        log::debug!("uci > {}", line.trim_end());
+        Ok(line.trim().to_string())
+    }
+
+    // Reads lines up to the one starting with `token`, passing each to `on_line`
+    fn read_until(&mut self, token: &str, mut on_line: impl FnMut(&str)) -> io::Result<String> {
+        loop {
+            let line = self.read_line()?;
+            if line.split_whitespace().next() == Some(token) {
+                return Ok(line);
+            }
+            on_line(&line);
+        }
+    }
+
+    fn search(&mut self, limit: UciLimit, initial: &Board, moves: &[Move], board: &Board) -> io::Result<(Move, i32)> {
+        let mut position = format!("position fen {}", initial.to_fen());
+        if !moves.is_empty() {
+            position.push_str(" moves");
+            for mv in moves {
+                position.push(' ');
+                position.push_str(&mv.to_uci());
+            }
+        }
+        self.send(&position)?;
+        self.send(&match limit {
+            UciLimit::Depth(depth) => format!("go depth {}", depth),
+            UciLimit::MoveTime(ms) => format!("go movetime {}", ms),
+        })?;
+
+        let mut score = 0;
+        let line = self.read_until("bestmove", |line| {
+            if let Some(reported) = info_score(line) {
+                score = reported;
+            }
+        })?;
+        let uci = line.split_whitespace().nth(1).unwrap_or("(none)");
+        match MoveGenerator::find_uci_move(board, uci) {
+            Some(mv) => Ok((mv, score)),
+            None => Err(io::Error::new(io::ErrorKind::InvalidData, format!("illegal move {}", uci))),
+        }
+    }
+}
+
+impl Drop for Process {
+    fn drop(&mut self) {
+        if self.send("quit").is_err() {
+            let _ = self.child.kill();
+        }
+        let _ = self.child.wait();
+    }
+}
+
+/// An external engine speaking UCI, run as a child process for as long as
+/// this lives. An engine that fails is started again, so nothing it left
+/// unread, such as a late `bestmove`, reaches the next search.
+pub struct UciEngine {
+    /// The name the engine gives in its `id name` line
+    pub name: String,
+    path: PathBuf,
+    limit: UciLimit,
+    // None once the engine failed and couldn't be started again; it then
+    // has no moves, and forfeits every game
+    process: RefCell<Option<Process>>,
+}
+
+impl UciEngine {
+    /// Starts the engine at `path` and waits for it to be ready
+    pub fn start(path: &Path, limit: UciLimit) -> io::Result<UciEngine> {
+        let (process, name) = Process::start(path)?;
+        let name =
+            name.unwrap_or_else(|| path.file_stem().map_or_else(String::new, |stem| stem.to_string_lossy().into()));
+        Ok(UciEngine { name, path: path.to_path_buf(), limit, process: RefCell::new(Some(process)) })
+    }
+
+    // Replaces the engine's process after `err`, or drops it if it won't start again
+    fn restart(&self, process: &mut Option<Process>, err: io::Error) {
+        log::warn!("{}: {}; restarting it", self.name, err);
+        if let Some(failed) = process.as_mut() {
+            let _ = failed.child.kill();
+        }
+        *process = match Process::start(&self.path) {
+            Ok((fresh, _)) => Some(fresh),
+            Err(err) => {
+                log::error!("{}: couldn't be restarted, so it forfeits its games: {}", self.name, err);
+                None
+            }
+        };
+    }
+}
+
+impl MatchEngine for UciEngine {
+    fn new_game(&self) {
+        let mut process = self.process.borrow_mut();
+        let Some(running) = process.as_mut() else {
+            return;
+        };
+        let ready = running.send("ucinewgame").and_then(|()| running.send("isready"));
+        if let Err(err) = ready.and_then(|()| running.read_until("readyok", |_| {})) {
+            self.restart(&mut process, err);
+        }
+    }
+
+    // A failing engine has no move, and forfeits the game
+    fn choose(&self, initial: &Board, moves: &[Move], board: &Board) -> Option<(Move, i32)> {
+        let mut process = self.process.borrow_mut();
+        match process.as_mut()?.search(self.limit, initial, moves, board) {
+            Ok(found) => Some(found),
+            Err(err) => {
+                self.restart(&mut process, err);
+                None
+            }
+        }
+    }
+}
+
+// The score in an `info` line, in centipawns, with mates scored as the
+// built-in engine scores them
+fn info_score(line: &str) -> Option<i32> {
+    let mut words = line.split_whitespace();
+    if words.next() != Some("info") {
+        return None;
+    }
+    let mut words = words.skip_while(|&word| word != "score").skip(1);
+    let kind = words.next()?;
+    let value: i32 = words.next()?.parse().ok()?;
+    match kind {
+        "cp" => Some(value),
+        "mate" if value > 0 => Some(MATE_SCORE - (2 * value - 1)),
+        "mate" => Some(-MATE_SCORE - 2 * value),
+        _ => None,
+    }
+}
@@ -1,231 +1,3480 @@
-use crate::ai::ChessAI;
-use crate::game::{Board, Color, Move, MoveGenerator, PieceType, Position};
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use ratatui::layout::Rect;
+
+use chess_core::ai::eval::EvalWeights;
+use chess_core::ai::rng::Rng;
+use chess_core::ai::script::BotScript;
+use chess_core::ai::{ChessAI, SearchProgress, Selection, MATE_SCORE, OPENING_VARIETY_MOVES};
+use chess_core::game::clock::LOW_TIME_MS;
+use chess_core::game::draw::DrawClaim;
+use chess_core::game::eco::{self, Opening};
+use chess_core::game::endgame::{Endgame, EndgameGoal};
+use chess_core::game::fen::{FenError, STARTING_FEN};
+use chess_core::game::marks::Marks;
+use chess_core::game::odds::MaterialOdds;
+use chess_core::game::pgn::{MoveAnnotation, PgnError, PgnGame, MOVE_NAGS};
+use chess_core::game::puzzle::{self, PuzzleFilter};
+use chess_core::game::san::{self, Language};
+use chess_core::game::tree::{MoveTree, NodeId};
+use chess_core::game::{
+    Board, Clock, ClockMode, Color, Game, GameEvent, GameOverReason, GameState, Move, MoveGenerator, PieceType, Position,
+    TimeControl,
+};
+use crate::clipboard::Clipboard;
+use crate::online::OnlineSession;
+use crate::puzzle_db::PuzzleDatabase;
+use crate::saves::{self, SavedGame, SavedMode};
+use crate::suspend::{Suspension, SuspendWatcher};
+use crate::painter::art::{CustomArt, PieceArt};
+use crate::painter::svg::SvgOptions;
+use crate::painter::BoardPainter;
+use crate::painter::theme::{Palette, Theme};
+use crate::config::{self, Settings, MAX_AI_DEPTH};
+use crate::sound::{Cue, Sound};
+use crate::profiles::{ai_rating, Profiles};
+use crate::stats::{GameStats, Outcome, StatsMode};
+
+use super::bracket::{Bracket, BracketSetup};
+use super::commands;
+use super::events::{EventKind, EventLog};
+use super::files::FileBrowser;
+use super::keymap::Keymap;
+use super::macros::Macros;
+use super::puzzles::{PuzzleRun, PuzzleSource, PuzzleStatus, Verdict};
+use super::tutorial::{LessonStatus, Tutorial};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MenuItem {
+    TwoPlayer,
+    VsAI,
+    Kids,
+    CustomBot,
+    StartFromFen,
+    Tournament,
+    TimeControl,
+    Handicap,
+    HotSeat,
+    Learn,
+    Puzzles,
+    Endgames,
+    Profiles,
+    Stats,
+    Settings,
+    ResumeGame,
+    Archive,
+    Online,
+    Quit,
+}
+
+impl MenuItem {
+    pub fn label(&self) -> &'static str {
+        match self {
+            MenuItem::TwoPlayer => "Two Player",
+            MenuItem::VsAI => "Play vs AI",
+            MenuItem::Kids => "Kids Mode",
+            MenuItem::CustomBot => "Custom Bot",
+            MenuItem::StartFromFen => "Start from FEN",
+            MenuItem::Tournament => "Tournament",
+            MenuItem::TimeControl => "Time Control",
+            MenuItem::Handicap => "Handicap",
+            MenuItem::HotSeat => "Hot-seat Rotation",
+            MenuItem::Learn => "Learn Chess",
+            MenuItem::Puzzles => "Puzzles",
+            MenuItem::Endgames => "Endgame Practice",
+            MenuItem::Profiles => "Player",
+            MenuItem::Stats => "Statistics",
+            MenuItem::Settings => "Settings",
+            MenuItem::ResumeGame => "Resume Game",
+            MenuItem::Archive => "Game Archive",
+            MenuItem::Online => "Play Online (Lichess)",
+            MenuItem::Quit => "Quit",
+        }
+    }
+}
+
+pub const MENU_ITEMS: [MenuItem; 19] = [
+    MenuItem::TwoPlayer,
+    MenuItem::VsAI,
+    MenuItem::Kids,
+    MenuItem::CustomBot,
+    MenuItem::Learn,
+    MenuItem::Puzzles,
+    MenuItem::Endgames,
+    MenuItem::StartFromFen,
+    MenuItem::Tournament,
+    MenuItem::TimeControl,
+    MenuItem::Handicap,
+    MenuItem::HotSeat,
+    MenuItem::Profiles,
+    MenuItem::Stats,
+    MenuItem::Settings,
+    MenuItem::ResumeGame,
+    MenuItem::Archive,
+    MenuItem::Online,
+    MenuItem::Quit,
+];
+
+// Positions kept on the Start from FEN screen, most recent first
+const RECENT_POSITIONS: usize = 10;
+
+// Kids mode opponent: a shallow, noisy search that waits before replying
+const KIDS_AI_DEPTH: u32 = 1;
+const KIDS_AI_NOISE: i32 = 300;
+const KIDS_AI_DELAY: Duration = Duration::from_millis(1500);
+
+// Pause before a puzzle's reply, so the player's own move is seen landing
+const PUZZLE_REPLY_DELAY: Duration = Duration::from_millis(600);
+
+// Presets offered in the time control menu, after "Untimed" and before "Custom"
+pub const TIME_CONTROLS: [TimeControl; 12] = [
+    TimeControl::new(1, 0),
+    TimeControl::new(2, 1),
+    TimeControl::new(3, 0),
+    TimeControl::new(3, 2),
+    TimeControl::new(5, 0),
+    TimeControl::new(5, 3),
+    TimeControl::delay(5, 2),
+    TimeControl::new(10, 0),
+    TimeControl::new(10, 5),
+    TimeControl::bronstein(10, 5),
+    TimeControl::new(15, 10),
+    TimeControl::new(30, 0),
+];
+
+// Limits for the custom time control, adjusted a minute or a second at a time
+pub const CUSTOM_MAX_MINUTES: u64 = 180;
+pub const CUSTOM_MAX_INCREMENT: u64 = 60;
+
+/// Odds the computer gives the player in games against it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Handicap {
+    pub material: MaterialOdds,
+    // The player's clock starts with this many times the normal time
+    pub time_factor: u64,
+}
+
+impl Default for Handicap {
+    fn default() -> Self {
+        Handicap {
+            material: MaterialOdds::None,
+            time_factor: 1,
+        }
+    }
+}
+
+impl Handicap {
+    pub fn label(&self) -> String {
+        match (self.material, self.time_factor) {
+            (MaterialOdds::None, 1) => "None".to_string(),
+            (material, 1) => material.label().to_string(),
+            (MaterialOdds::None, factor) => format!("{}× time", factor),
+            (material, factor) => format!("{}, {}× time", material.label(), factor),
+        }
+    }
+}
+
+pub const TIME_ODDS: [u64; 4] = [1, 2, 3, 5];
+
+// Rows of the handicap menu
+pub const HANDICAP_ROWS: usize = 2;
+
+/// What a pawn can promote to, in the order the promotion popup lists them
+pub const PROMOTION_PIECES: [PieceType; 4] = [PieceType::Queen, PieceType::Rook, PieceType::Bishop, PieceType::Knight];
+
+/// What blindfold mode hides: the pieces always, and the moves too
+/// unless only the last one is shown
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Blindfold {
+    #[default]
+    Off,
+    LastMove,
+    Hidden,
+}
+
+impl Blindfold {
+    pub const ALL: [Blindfold; 3] = [Blindfold::Off, Blindfold::LastMove, Blindfold::Hidden];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Blindfold::Off => "Off",
+            Blindfold::LastMove => "Show the last move",
+            Blindfold::Hidden => "Hide everything",
+        }
+    }
+
+    /// Name used in the config file
+    pub fn name(self) -> &'static str {
+        match self {
+            Blindfold::Off => "off",
+            Blindfold::LastMove => "last_move",
+            Blindfold::Hidden => "hidden",
+        }
+    }
+
+    pub fn named(name: &str) -> Option<Blindfold> {
+        Blindfold::ALL.into_iter().find(|blindfold| blindfold.name() == name)
+    }
+}
+
+/// Rows of the settings screen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SettingsRow {
+    AiDepth,
+    AdaptiveAi,
+    AiDelay,
+    Palette,
+    PieceArt,
+    AutoQueen,
+    Sound,
+    AnimateMoves,
+    AutoFlip,
+    Coordinates,
+    Blindfold,
+    ScreenReader,
+}
+
+impl SettingsRow {
+    pub fn label(&self) -> &'static str {
+        match self {
+            SettingsRow::AiDepth => "AI strength",
+            SettingsRow::AdaptiveAi => "Adapt AI strength to results",
+            SettingsRow::AiDelay => "AI reply delay",
+            SettingsRow::Palette => "Colors",
+            SettingsRow::PieceArt => "Pieces",
+            SettingsRow::AutoQueen => "Always promote to queen",
+            SettingsRow::Sound => "Sound effects",
+            SettingsRow::AnimateMoves => "Animate moves",
+            SettingsRow::AutoFlip => "Face Black's side when playing Black",
+            SettingsRow::Coordinates => "Board coordinates",
+            SettingsRow::Blindfold => "Blindfold",
+            SettingsRow::ScreenReader => "Screen reader mode",
+        }
+    }
+}
+
+pub const SETTINGS_ROWS: [SettingsRow; 12] = [
+    SettingsRow::AiDepth,
+    SettingsRow::AdaptiveAi,
+    SettingsRow::AiDelay,
+    SettingsRow::Palette,
+    SettingsRow::PieceArt,
+    SettingsRow::AutoQueen,
+    SettingsRow::Sound,
+    SettingsRow::AnimateMoves,
+    SettingsRow::AutoFlip,
+    SettingsRow::Coordinates,
+    SettingsRow::Blindfold,
+    SettingsRow::ScreenReader,
+];
+
+pub const AI_DELAYS_MS: [u64; 5] = [0, 500, 1000, 2000, 3000];
+
+// Longest the main loop waits for input before redrawing
+const FRAME_INTERVAL: Duration = Duration::from_millis(100);
+// How long a played piece takes to slide to its square, and the redraw
+// rate meanwhile
+const MOVE_ANIMATION: Duration = Duration::from_millis(150);
+const ANIMATION_FRAME: Duration = Duration::from_millis(16);
+// How long a notification stays in the status bar
+const NOTIFICATION_DURATION: Duration = Duration::from_secs(4);
+// How long a peek shows the pieces in blindfold mode
+const PEEK_DURATION: Duration = Duration::from_millis(1500);
+
+// Shallow enough to evaluate history positions without a visible pause
+const TOOLTIP_EVAL_DEPTH: u32 = 2;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum GameMode {
     Menu,
+    TimeControlMenu,
+    HandicapMenu,
+    EndgameMenu,
+    FenMenu,
+    TournamentMenu,
+    SettingsMenu,
     TwoPlayer,
     VsAI,
+    SavedGames,
+    Archive,
+    Profiles,
+    Stats,
+    OnlineLobby,
+    Online,
+    Puzzle,
+    Tutorial,
     GameOver,
 }
 
+/// Tabs of the lower side panel
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum GameOverReason {
-    Checkmate(Color), // Winner
-    Stalemate,
-    InsufficientMaterial,
-    FiftyMoveRule,
+pub enum SidePanelTab {
+    Moves,
+    Info,
+    Events,
 }
 
-pub struct App {
-    pub board: Board,
+/// A move as listed in the history panel
+pub struct HistoryRow {
+    /// The position the move leads to
+    pub node: NodeId,
+    /// 0 for the game's moves, 1 for variations on them, and so on
+    pub depth: usize,
+    pub number: u32,
+    pub color: Color,
+    /// In English, localized when displayed
+    pub san: String,
+}
+
+// `game`, subscribed to send its events to `sender`
+fn forwarding(mut game: GameState, sender: &Sender<GameEvent>) -> GameState {
+    let sender = sender.clone();
+    game.subscribe(Box::new(move |event| {
+        // The App holding the receiver outlives its games
+        let _ = sender.send(*event);
+    }));
+    game
+}
+
+fn side_name(color: Color) -> &'static str {
+    match color {
+        Color::White => "White",
+        Color::Black => "Black",
+    }
+}
+
+fn piece_name(piece_type: PieceType) -> &'static str {
+    match piece_type {
+        PieceType::Pawn => "pawn",
+        PieceType::Knight => "knight",
+        PieceType::Bishop => "bishop",
+        PieceType::Rook => "rook",
+        PieceType::Queen => "queen",
+        PieceType::King => "king",
+    }
+}
+
+/// The computer's move from a background search, with what the search took
+struct AiReply {
+    mv: Option<Move>,
+    depth: u32,
+    nodes: u64,
+    time: Duration,
+}
+
+/// What a background search sends back: how it's going, then its move
+enum AiMessage {
+    Progress(SearchInfo),
+    /// A bot's code failed, and its rules picked the move instead
+    ScriptFailed(String),
+    Reply(AiReply),
+}
+
+/// How far the computer's search has got, for the search panel
+#[derive(Debug, Clone)]
+pub struct SearchInfo {
+    pub depth: u32,
+    /// The depth the search goes to
+    pub max_depth: u32,
+    pub nodes: u64,
+    pub nodes_per_second: f64,
+    /// The best move in SAN and its score from White's point of view, as
+    /// of the last depth searched to the end
+    pub best: Option<(String, i32)>,
+}
+
+impl SearchInfo {
+    fn new(board: &Board, progress: &SearchProgress, max_depth: u32) -> SearchInfo {
+        let best = progress.best.map(|(mv, score)| {
+            let score = if board.current_player == Color::White { score } else { -score };
+            (san::to_san(board, &mv), score)
+        });
+        SearchInfo {
+            depth: progress.depth,
+            max_depth,
+            nodes: progress.nodes,
+            nodes_per_second: progress.nodes_per_second(),
+            best,
+        }
+    }
+}
+
+/// A game open in a tab, with everything that belongs to it: the position
+/// and moves, the computer's state and search, the clock, the menu choices
+/// it was started with and what the screen is showing of it.
+pub struct Session {
+    // Position, moves, clock and result
+    pub game: GameState,
+    // Events from the game, which subscribes with game_event_sender
+    game_events: Receiver<GameEvent>,
+    game_event_sender: Sender<GameEvent>,
     pub cursor: Position,
     pub selected_piece: Option<Position>,
     pub legal_moves: Vec<Move>,
-    pub move_history: Vec<Move>,
-    pub captured_white: Vec<PieceType>,
-    pub captured_black: Vec<PieceType>,
+    // English SAN of each move in move_history, localized when displayed
+    pub san_history: Vec<String>,
+    // The game's moves as the main line, with variations tried while reviewing
+    pub move_tree: MoveTree,
+    // Where the game's latest move is in move_tree
+    live_node: NodeId,
     pub mode: GameMode,
-    pub menu_selection: usize,
     pub ai: Option<ChessAI>,
     pub ai_color: Option<Color>,
-    pub game_over_reason: Option<GameOverReason>,
+    // When a delayed AI reply, or a puzzle's reply, is due
+    pub ai_move_due: Option<Instant>,
+    // The AI's reply being searched on a background thread, so the screen
+    // and the clocks keep running while it thinks
+    ai_search: Option<Receiver<AiMessage>>,
+    // When the search in ai_search began
+    search_started: Option<Instant>,
+    // How the computer's current or last search went
+    pub search_info: Option<SearchInfo>,
+    // The player's next move, queued while the computer thinks
+    pub premove: Option<Move>,
+    // Kids mode: big hints, capture scoring, free takebacks, gentle AI
+    pub kids_mode: bool,
+    // The custom bot playing the computer's side, if it's that and not the engine
+    pub bot: Option<BotScript>,
+    // Which of the tournament's games this is, if it's one
+    tournament_game: Option<usize>,
+    // The puzzles being solved, in puzzle mode
+    pub puzzles: Option<PuzzleRun>,
+    // The endgame being practised against the computer, if any
+    pub endgame: Option<Endgame>,
+    // The player's rating when the game against the computer began, for
+    // the PGN tags
+    game_rating: Option<i32>,
+    // Chosen in the menu for new local games; None plays untimed
+    pub time_control: Option<TimeControl>,
+    // Chosen in the menu for new games against the computer
+    pub handicap: Handicap,
+    // Since when the side to move has been charged; None until the clock starts
+    clock_tick: Option<Instant>,
     pub promotion_menu: Option<Position>,
-    pub should_quit: bool,
+    // Highlighted row of the promotion popup, into PROMOTION_PIECES
+    pub promotion_selection: usize,
+    // Ctrl+N was pressed and a fresh game is waiting for confirmation
+    pub restart_pending: bool,
+    // The board is drawn from Black's side, rank 1 at the top
+    pub flipped: bool,
+    // Waiting for the keyboard to change hands in a hot-seat game
+    pub handoff_pending: bool,
+    // Position shown instead of the live game; the move leading to it is
+    // the one selected in the history panel
+    review: Option<NodeId>,
+    // Text of the comment being written on the reviewed move
+    pub comment_editor: Option<String>,
+    // Where the arrow being drawn starts, until its end square is picked
+    arrow_start: Option<Position>,
+    // One-off feedback shown in the status panel, e.g. where an export went
+    pub status_message: Option<String>,
+    // When a notification in status_message goes away
+    status_expires: Option<Instant>,
+    // What happened in the game, in words
+    pub events: EventLog,
+    // When a blindfold peek stops showing the pieces
+    peek_until: Option<Instant>,
+    // What to play for the last move, if sound is on
+    sound_pending: Option<Cue>,
+    // The move sliding into place and when it started
+    animation: Option<(Move, Instant)>,
+    // Identity of the local game being autosaved; None for online games
+    pub game_id: Option<String>,
+    pub game_created: u64,
+    // Tags of the finished game being looked through, kept for copying it
+    reviewed_tags: Vec<(String, String)>,
 }
 
-impl App {
-    pub fn new() -> Self {
-        App {
-            board: Board::new(),
+impl Session {
+    fn new() -> Session {
+        let (game_event_sender, game_events) = mpsc::channel();
+        Session {
+            game: forwarding(GameState::new(Board::new()), &game_event_sender),
+            game_events,
+            game_event_sender,
             cursor: Position::new(6, 4), // Start at white king's pawn
             selected_piece: None,
             legal_moves: Vec::new(),
-            move_history: Vec::new(),
-            captured_white: Vec::new(),
-            captured_black: Vec::new(),
+            san_history: Vec::new(),
+            move_tree: MoveTree::new(),
+            live_node: MoveTree::ROOT,
             mode: GameMode::Menu,
-            menu_selection: 0,
             ai: None,
             ai_color: None,
-            game_over_reason: None,
+            ai_move_due: None,
+            ai_search: None,
+            search_started: None,
+            search_info: None,
+            premove: None,
+            kids_mode: false,
+            bot: None,
+            tournament_game: None,
+            puzzles: None,
+            endgame: None,
+            game_rating: None,
+            time_control: None,
+            handicap: Handicap::default(),
+            clock_tick: None,
             promotion_menu: None,
+            promotion_selection: 0,
+            restart_pending: false,
+            flipped: false,
+            handoff_pending: false,
+            review: None,
+            comment_editor: None,
+            arrow_start: None,
+            status_message: None,
+            status_expires: None,
+            events: EventLog::new(),
+            peek_until: None,
+            sound_pending: None,
+            animation: None,
+            game_id: None,
+            game_created: 0,
+            reviewed_tags: Vec::new(),
+        }
+    }
+}
+
+pub struct App {
+    pub menu_selection: usize,
+    // The hot-seat tournament, once it's started, and the players being
+    // entered for one before that
+    pub tournament: Option<Bracket>,
+    pub tournament_setup: BracketSetup,
+    // A Lichess puzzle database file from the config, and which of its
+    // puzzles to use
+    pub puzzle_database: Option<(PathBuf, PuzzleFilter)>,
+    // The tutorial's lessons, kept so it carries on where it was left
+    pub tutorial: Option<Tutorial>,
+    // Results of finished games, read when the stats screen opens
+    pub stats: GameStats,
+    // The players on this machine and their ratings against the computer
+    pub profiles: Profiles,
+    // Name of the profile being created on the profiles screen
+    pub profile_name: Option<String>,
+    // The Start from FEN screen: the FEN typed, why it was turned down,
+    // whether the computer takes the side not to move, and the positions
+    // started from before
+    pub fen_input: String,
+    pub fen_error: Option<String>,
+    pub fen_vs_ai: bool,
+    pub recent_positions: Vec<String>,
+    pub custom_time_control: TimeControl,
+    pub side_panel_tab: SidePanelTab,
+    // Two-player games turn the board to the side to move, with a pause
+    // between turns while the keyboard changes hands
+    pub hot_seat: bool,
+    // The ? overlay listing the keys for the current screen
+    pub show_help: bool,
+    // Text typed on the command line: a command or a move
+    pub command_line: Option<String>,
+    // The Ctrl+O listing a game file is being picked from
+    pub file_browser: Option<FileBrowser>,
+    // Scores (White's view) of positions shown in tooltips, by Zobrist hash
+    pub position_evals: HashMap<u64, i32>,
+    // Move list area as last drawn, for mapping mouse rows to moves
+    pub history_area: Cell<Rect>,
+    // First move shown in the history list, kept between frames so the list
+    // only scrolls when the selection leaves it
+    history_offset: Cell<usize>,
+    // Piece letters used when showing moves
+    pub language: Language,
+    pub macros: Macros,
+    // Changed on the settings screen and saved to the config file
+    pub settings: Settings,
+    // Makes the computer's choices among equally good moves repeatable
+    pub ai_seed: Option<u64>,
+    // The evaluation weights the computer plays with, from --eval-weights
+    pub ai_weights: EvalWeights,
+    // The bot script loaded with --bot, for the menu's custom bot
+    pub custom_bot: Option<BotScript>,
+    // Colors in use: the chosen palette with the config file's own colors on top
+    pub custom_colors: Vec<(String, ratatui::style::Color)>,
+    pub theme: Theme,
+    // Plain ASCII drawing, for terminals without Unicode glyphs
+    pub ascii: bool,
+    // Piece art from the file named in the config, if there is one
+    pub custom_art: Option<CustomArt>,
+    pub keymap: Keymap,
+    pub online: Option<OnlineSession>,
+    pub saved_games: Vec<SavedGame>,
+    // Finished games, read when the archive screen opens
+    pub archive: Vec<PgnGame>,
+    // Words the archive list is narrowed to, and whether they're being typed
+    pub archive_query: String,
+    pub archive_searching: bool,
+    // Every open game, in tab order, and which of them is on screen
+    sessions: Vec<Session>,
+    active: usize,
+    suspend_watcher: SuspendWatcher,
+    clipboard: Clipboard,
+    pub should_quit: bool,
+    // Asking whether to quit with a game unfinished
+    pub quit_pending: bool,
+}
+
+impl App {
+    pub fn new() -> Self {
+        App {
+            menu_selection: 0,
+            tournament: None,
+            tournament_setup: BracketSetup::default(),
+            puzzle_database: None,
+            tutorial: None,
+            stats: GameStats::default(),
+            profiles: Profiles::default(),
+            profile_name: None,
+            fen_input: String::new(),
+            fen_error: None,
+            fen_vs_ai: false,
+            recent_positions: Vec::new(),
+            custom_time_control: TimeControl::new(10, 0),
+            side_panel_tab: SidePanelTab::Moves,
+            hot_seat: false,
+            show_help: false,
+            command_line: None,
+            file_browser: None,
+            position_evals: HashMap::new(),
+            history_area: Cell::new(Rect::default()),
+            history_offset: Cell::new(0),
+            language: system_language(),
+            macros: Macros::default(),
+            settings: Settings::default(),
+            ai_seed: None,
+            ai_weights: EvalWeights::default(),
+            custom_bot: None,
+            custom_colors: Vec::new(),
+            theme: Theme::default(),
+            ascii: false,
+            custom_art: None,
+            keymap: Keymap::default(),
+            online: None,
+            saved_games: Vec::new(),
+            archive: Vec::new(),
+            archive_query: String::new(),
+            archive_searching: false,
+            sessions: vec![Session::new()],
+            active: 0,
+            suspend_watcher: SuspendWatcher::spawn(),
+            clipboard: Clipboard::default(),
             should_quit: false,
+            quit_pending: false,
         }
     }
 
     pub fn start_two_player(&mut self) {
-        self.board = Board::new();
-        self.cursor = Position::new(6, 4);
-        self.selected_piece = None;
-        self.legal_moves = Vec::new();
-        self.move_history = Vec::new();
-        self.captured_white = Vec::new();
-        self.captured_black = Vec::new();
-        self.mode = GameMode::TwoPlayer;
-        self.ai = None;
-        self.ai_color = None;
-        self.game_over_reason = None;
-        self.promotion_menu = None;
+        self.set_game(GameState::new(Board::new()).with_clock(self.session().time_control.map(Clock::new)));
+        let session = self.session_mut();
+        session.cursor = Position::new(6, 4);
+        session.selected_piece = None;
+        session.legal_moves = Vec::new();
+        self.clear_moves();
+        let session = self.session_mut();
+        session.mode = GameMode::TwoPlayer;
+        session.ai = None;
+        session.ai_color = None;
+        session.ai_move_due = None;
+        session.ai_search = None;
+        session.search_info = None;
+        session.kids_mode = false;
+        session.bot = None;
+        session.tournament_game = None;
+        session.endgame = None;
+        session.clock_tick = None;
+        session.promotion_menu = None;
+        session.handoff_pending = false;
+        self.begin_saved_game();
+        self.orient_board();
     }
 
     pub fn start_vs_ai(&mut self) {
-        self.board = Board::new();
-        self.cursor = Position::new(6, 4);
-        self.selected_piece = None;
-        self.legal_moves = Vec::new();
-        self.move_history = Vec::new();
-        self.captured_white = Vec::new();
-        self.captured_black = Vec::new();
-        self.mode = GameMode::VsAI;
-        self.ai = Some(ChessAI::new(3)); // Depth 3 for reasonable speed
-        self.ai_color = Some(Color::Black);
-        self.game_over_reason = None;
-        self.promotion_menu = None;
+        self.set_game(GameState::new(Board::new()).with_clock(self.session().time_control.map(Clock::new)));
+        let session = self.session_mut();
+        session.cursor = Position::new(6, 4);
+        session.selected_piece = None;
+        session.legal_moves = Vec::new();
+        self.clear_moves();
+        self.session_mut().mode = GameMode::VsAI;
+        self.session_mut().ai = Some(ChessAI::new(self.settings.ai_depth).with_selection(self.settings.ai_selection));
+        let session = self.session_mut();
+        session.ai_color = Some(Color::Black);
+        session.ai_move_due = None;
+        session.ai_search = None;
+        session.search_info = None;
+        session.kids_mode = false;
+        session.bot = None;
+        session.tournament_game = None;
+        session.endgame = None;
+        session.clock_tick = None;
+        session.promotion_menu = None;
+        session.handoff_pending = false;
+        self.begin_saved_game();
+        self.session_mut().game_rating = self.profiles.active().map(|profile| profile.rating);
+        self.apply_handicap();
+        self.orient_board();
     }
 
-    pub fn move_cursor(&mut self, row_offset: i32, col_offset: i32) {
-        let new_row = (self.cursor.row as i32 + row_offset).clamp(0, 7) as usize;
-        let new_col = (self.cursor.col as i32 + col_offset).clamp(0, 7) as usize;
-        self.cursor = Position::new(new_row, new_col);
+    pub fn start_kids_mode(&mut self) {
+        self.start_vs_ai();
+        self.session_mut().ai = Some(ChessAI::new(KIDS_AI_DEPTH).with_noise(KIDS_AI_NOISE));
+        self.session_mut().kids_mode = true;
+        // No pressure on the clock for kids, and the gentle AI is handicap enough
+        self.set_game(GameState::new(Board::new()));
     }
 
-    pub fn select_square(&mut self) {
-        if let Some(_promo_pos) = self.promotion_menu {
-            // Already handled in promotion selection
+    /// Starts a game against the bot loaded with --bot, the player taking
+    /// White. It's an experiment, so it isn't rated or saved.
+    pub fn start_custom_bot(&mut self) {
+        let Some(bot) = self.custom_bot.clone() else {
+            self.notify("No custom bot loaded: start with --bot FILE".to_string());
             return;
+        };
+        self.start_vs_ai();
+        let session = self.session_mut();
+        session.bot = Some(bot);
+        session.game_id = None;
+        session.game_rating = None;
+    }
+
+    pub fn open_endgame_menu(&mut self) {
+        self.session_mut().mode = GameMode::EndgameMenu;
+        self.menu_selection = 0;
+    }
+
+    /// Sets up `endgame` against the computer at full strength. It's
+    /// practice, so it isn't saved and has no clock.
+    pub fn start_endgame(&mut self, endgame: Endgame) {
+        self.start_vs_ai();
+        let session = self.session_mut();
+        session.ai = Some(ChessAI::new(MAX_AI_DEPTH));
+        session.ai_color = Some(endgame.player.opposite());
+        session.endgame = Some(endgame);
+        self.set_game(GameState::new(endgame.board()));
+        self.session_mut().game_id = None;
+        self.session_mut().cursor = match endgame.player {
+            Color::White => Position::new(6, 4),
+            Color::Black => Position::new(1, 4),
+        };
+        self.orient_board();
+        if self.session().game.board().current_player != endgame.player {
+            self.schedule_ai_move();
         }
+    }
 
-        if let Some(_selected_pos) = self.selected_piece {
-            // Try to make a move
-            if let Some(mv) = self.legal_moves.iter().find(|m| m.to == self.cursor) {
-                // Check if this is a promotion move
-                if mv.piece.piece_type == PieceType::Pawn {
-                    let promotion_row = if mv.piece.color == Color::White { 0 } else { 7 };
-                    if mv.to.row == promotion_row {
-                        self.promotion_menu = Some(mv.to);
+    /// Sets the endgame being practised up again from the start
+    pub fn retry_endgame(&mut self) {
+        if let Some(endgame) = self.session().endgame {
+            self.start_endgame(endgame);
+        }
+    }
+
+    /// Whether the endgame being practised was won or drawn as its goal
+    /// asks, once the game is over
+    pub fn endgame_passed(&self) -> Option<bool> {
+        let endgame = self.session().endgame?;
+        let reason = self.session().game.result()?;
+        Some(match (endgame.goal, reason) {
+            (EndgameGoal::Win, GameOverReason::Checkmate(winner)) => winner == endgame.player,
+            (EndgameGoal::Win, _) => false,
+            (EndgameGoal::Draw, reason) => reason.result() == "1/2-1/2",
+        })
+    }
+
+    /// Starts the tactics trainer, or carries on with it. Puzzles come from
+    /// the Lichess database named in the config if there is one, which is
+    /// read the first time, along with the progress kept from earlier
+    /// sessions.
+    pub fn start_puzzles(&mut self) {
+        if self.session().puzzles.is_none() {
+            let source = match &self.puzzle_database {
+                Some((path, filter)) => match PuzzleDatabase::open(path, filter) {
+                    Ok(database) => PuzzleSource::Database(database),
+                    Err(err) => {
+                        self.notify(format!("Couldn't read {}: {}", path.display(), err));
                         return;
                     }
-                }
+                },
+                None => PuzzleSource::Builtin(puzzle::builtin()),
+            };
+            self.session_mut().puzzles = PuzzleRun::new(source, saves::load_puzzle_progress());
+        }
+        if self.session().puzzles.is_none() {
+            self.notify("No puzzles match the config's puzzle filter".to_string());
+            return;
+        }
+        self.set_up_puzzle();
+    }
 
-                self.execute_move(*mv);
-            } else {
-                // Deselect or select a different piece
-                self.try_select_piece();
-            }
-        } else {
-            // Try to select a piece
-            self.try_select_piece();
+    /// Puts the current puzzle's position on the board, for the player to
+    /// find the solution from
+    fn set_up_puzzle(&mut self) {
+        let Some(puzzle) = self.session().puzzles.as_ref().map(|run| run.puzzle().clone()) else {
+            return;
+        };
+        self.set_game(GameState::new(puzzle.board.clone()));
+        self.session_mut().selected_piece = None;
+        self.session_mut().legal_moves = Vec::new();
+        self.clear_moves();
+        let session = self.session_mut();
+        session.status_message = None;
+        session.mode = GameMode::Puzzle;
+        session.ai = None;
+        session.ai_color = Some(puzzle.solver().opposite());
+        session.ai_move_due = None;
+        session.ai_search = None;
+        session.search_info = None;
+        session.kids_mode = false;
+        session.bot = None;
+        session.tournament_game = None;
+        session.clock_tick = None;
+        session.promotion_menu = None;
+        session.handoff_pending = false;
+        // Puzzles aren't games to resume
+        self.session_mut().game_id = None;
+        // The opponent's move that sets the puzzle is seen being played
+        if let Some(setup) = puzzle.setup {
+            self.record_move(setup);
+            self.dispatch_game_events();
         }
+        self.session_mut().cursor = match self.session().game.board().current_player {
+            Color::White => Position::new(6, 4),
+            Color::Black => Position::new(1, 4),
+        };
+        self.orient_board();
     }
 
-    pub fn execute_promotion(&mut self, piece_type: PieceType) {
-        if let (Some(_selected_pos), Some(promo_pos)) = (self.selected_piece, self.promotion_menu) {
-            if let Some(mv) = self.legal_moves.iter().find(|m| m.to == promo_pos) {
-                let promo_move = Move {
-                    from: mv.from,
-                    to: mv.to,
-                    move_type: crate::game::MoveType::Promotion(piece_type),
-                    piece: mv.piece,
-                    captured: mv.captured,
-                };
-                self.execute_move(promo_move);
-                self.promotion_menu = None;
-            }
+    /// Starts the puzzle on the board over
+    pub fn retry_puzzle(&mut self) {
+        if let (GameMode::Puzzle, Some(run)) = (self.session().mode, self.session_mut().puzzles.as_mut()) {
+            run.retry();
+            self.set_up_puzzle();
         }
     }
 
-    fn try_select_piece(&mut self) {
-        if let Some(piece) = self.board.get_piece(self.cursor) {
-            if piece.color == self.board.current_player {
-                self.selected_piece = Some(self.cursor);
-                self.legal_moves = MoveGenerator::generate_legal_moves(&self.board, self.cursor);
-            } else {
-                self.selected_piece = None;
-                self.legal_moves = Vec::new();
+    pub fn next_puzzle(&mut self) {
+        if let (GameMode::Puzzle, Some(run)) = (self.session().mode, self.session_mut().puzzles.as_mut()) {
+            run.next();
+            self.set_up_puzzle();
+        }
+    }
+
+    /// Opens the tutorial at the lesson last left, or the first
+    pub fn start_tutorial(&mut self) {
+        self.tutorial.get_or_insert_with(Tutorial::new);
+        self.set_up_lesson();
+    }
+
+    /// Puts the current lesson's position on the board, playing Black's
+    /// move that sets it up if it has one
+    fn set_up_lesson(&mut self) {
+        let Some((board, setup)) = self.tutorial.as_ref().map(Tutorial::board) else {
+            return;
+        };
+        self.set_game(GameState::new(board));
+        self.session_mut().selected_piece = None;
+        self.session_mut().legal_moves = Vec::new();
+        self.clear_moves();
+        let session = self.session_mut();
+        session.status_message = None;
+        session.mode = GameMode::Tutorial;
+        session.ai = None;
+        session.ai_color = None;
+        session.ai_move_due = None;
+        session.ai_search = None;
+        session.search_info = None;
+        session.kids_mode = false;
+        session.bot = None;
+        session.tournament_game = None;
+        session.clock_tick = None;
+        session.promotion_menu = None;
+        session.handoff_pending = false;
+        session.game_id = None;
+        if let Some(setup) = setup {
+            self.play_lesson_move(setup);
+        }
+        self.session_mut().cursor = Position::new(6, 4);
+        self.session_mut().flipped = false;
+    }
+
+    /// Starts the lesson on the board over
+    pub fn retry_lesson(&mut self) {
+        if let (GameMode::Tutorial, Some(tutorial)) = (self.session().mode, self.tutorial.as_mut()) {
+            tutorial.restart();
+            self.set_up_lesson();
+        }
+    }
+
+    pub fn next_lesson(&mut self) {
+        if let (GameMode::Tutorial, Some(tutorial)) = (self.session().mode, self.tutorial.as_mut()) {
+            tutorial.next();
+            self.set_up_lesson();
+        }
+    }
+
+    /// Plays a move on the lesson's board. Lessons aren't games, so it
+    /// isn't kept in the move tree, and in lessons where Black doesn't move
+    /// White keeps the turn.
+    fn play_lesson_move(&mut self, mv: Move) {
+        if self.session_mut().game.play(mv).is_err() {
+            return;
+        }
+        self.dispatch_game_events();
+        self.session_mut().selected_piece = None;
+        self.session_mut().legal_moves = Vec::new();
+        if mv.piece.color == Color::Black {
+            return;
+        }
+
+        let Some(tutorial) = self.tutorial.as_mut() else {
+            return;
+        };
+        let status = tutorial.check(self.sessions[self.active].game.board(), &mv);
+        if tutorial.lesson().goal.keeps_turn() {
+            self.session_mut().game.set_side_to_move(Color::White);
+        }
+        match status {
+            LessonStatus::Trying => {}
+            LessonStatus::Passed => {
+                self.queue_cue(Cue { sound: Sound::GameEnd, alert: false });
+                self.notify("Well done!".to_string());
             }
-        } else {
-            self.selected_piece = None;
-            self.legal_moves = Vec::new();
+            LessonStatus::Failed => self.notify("Not quite: try again".to_string()),
         }
     }
 
-    fn execute_move(&mut self, mv: Move) {
-        // Track captured pieces
-        if let Some(captured) = mv.captured {
-            match captured.color {
-                Color::White => self.captured_white.push(captured.piece_type),
-                Color::Black => self.captured_black.push(captured.piece_type),
+    /// Plays the player's move in a puzzle and checks it against the
+    /// solution. The opponent's reply follows after a pause.
+    fn play_puzzle_move(&mut self, mv: Move) {
+        let session = self.session_mut();
+        let Some(run) = session.puzzles.as_mut().filter(|run| run.status == PuzzleStatus::Solving) else {
+            return;
+        };
+        let verdict = run.check(session.game.board(), &mv);
+        let solution_san = match verdict {
+            Verdict::Failed(expected) => Some(san::to_san(session.game.board(), &expected)),
+            _ => None,
+        };
+        self.record_move(mv);
+        self.dispatch_game_events();
+        self.session_mut().selected_piece = None;
+        self.session_mut().legal_moves = Vec::new();
+        match verdict {
+            Verdict::Reply => self.session_mut().ai_move_due = Some(Instant::now() + PUZZLE_REPLY_DELAY),
+            Verdict::Solved => {
+                self.queue_cue(Cue { sound: Sound::GameEnd, alert: false });
+                self.notify("Solved!".to_string());
+            }
+            Verdict::Failed(_) => {
+                self.show_message(solution_san.map(|san| format!("Not the solution: {} was", san)));
+            }
+        }
+        if !matches!(verdict, Verdict::Reply) {
+            if let Some(run) = &self.session().puzzles {
+                let _ = saves::save_puzzle_progress(run.progress());
             }
         }
+    }
 
-        self.move_history.push(mv);
-        self.board.make_move(&mv);
-        self.selected_piece = None;
-        self.legal_moves = Vec::new();
+    /// Plays the opponent's reply from the puzzle's solution
+    fn play_puzzle_reply(&mut self) {
+        let Some(reply) = self.session().puzzles.as_ref().and_then(PuzzleRun::reply) else {
+            return;
+        };
+        self.record_move(reply);
+        self.dispatch_game_events();
+        if self.session().review.is_none() {
+            self.session_mut().selected_piece = None;
+            self.session_mut().legal_moves = Vec::new();
+        }
+    }
 
-        self.check_game_over();
+    /// Sets up the handicap chosen in the menu: the computer's odds piece
+    /// comes off the board and the player's clock gets extra time
+    fn apply_handicap(&mut self) {
+        let Some(ai_color) = self.session().ai_color else {
+            return;
+        };
+        self.set_position(self.session().handicap.material.starting_board(ai_color));
+        let time_factor = self.session().handicap.time_factor;
+        if let Some(clock) = self.session_mut().game.clock_mut() {
+            let player = ai_color.opposite();
+            clock.set_remaining(player, clock.control.initial_ms * time_factor);
+        }
+    }
 
-        // If playing against AI and it's AI's turn, make AI move
-        if self.mode == GameMode::VsAI && self.game_over_reason.is_none() {
-            if Some(self.board.current_player) == self.ai_color {
-                self.make_ai_move();
+    /// Whether the current or just-finished game can be restarted with Ctrl+N.
+    /// Online games are paired by Lichess, so only local games qualify.
+    pub fn can_restart(&self) -> bool {
+        self.session().game_id.is_some()
+    }
+
+    pub fn request_restart(&mut self) {
+        self.session_mut().restart_pending = self.can_restart();
+    }
+
+    pub fn cancel_restart(&mut self) {
+        self.session_mut().restart_pending = false;
+    }
+
+    /// Starts a fresh game with the same mode, AI side and AI strength,
+    /// skipping the menu
+    pub fn restart_game(&mut self) {
+        self.session_mut().restart_pending = false;
+        if !self.can_restart() {
+            return;
+        }
+
+        let ai_level = self.session().ai.as_ref().map(|ai| (ai.selection, ai.depth));
+        let ai_color = self.session().ai_color;
+        let control = self.session().game.clock().map(|clock| clock.control);
+        match ai_level {
+            Some(_) if self.session().kids_mode => self.start_kids_mode(),
+            Some(_) if self.session().bot.is_some() => {
+                self.start_custom_bot();
+                self.session_mut().ai_color = ai_color;
+            }
+            Some((selection, depth)) => {
+                self.start_vs_ai();
+                // Adaptive strength has already picked the level for the next game
+                if !self.settings.adaptive_ai {
+                    self.session_mut().ai = Some(ChessAI::new(depth).with_selection(selection));
+                }
+                self.session_mut().ai_color = ai_color;
             }
+            None => self.start_two_player(),
+        }
+        self.set_game(GameState::new(self.session().game.initial().clone()).with_clock(control.map(Clock::new)));
+        if self.session().ai.is_some() && !self.session().kids_mode {
+            self.apply_handicap();
+        }
+        self.orient_board();
+
+        if self.session().ai_color == Some(Color::White) {
+            self.schedule_ai_move();
         }
     }
 
-    pub fn make_ai_move(&mut self) {
-        if let Some(ref ai) = self.ai {
-            if let Some(mv) = ai.get_best_move(&self.board) {
-                // Track captured pieces
-                if let Some(captured) = mv.captured {
-                    match captured.color {
-                        Color::White => self.captured_white.push(captured.piece_type),
-                        Color::Black => self.captured_black.push(captured.piece_type),
-                    }
-                }
+    /// Plays the finished game's settings again from the game over
+    /// screen, with no confirmation as there's nothing left to lose.
+    /// Against the computer the player can take the other color.
+    pub fn rematch(&mut self, switch_colors: bool) {
+        if self.session().endgame.is_some() {
+            self.retry_endgame();
+            return;
+        }
+        if self.session().game.result().is_none() || !self.can_restart() {
+            return;
+        }
+        // The kids' AI always plays Black
+        if switch_colors && !self.session().kids_mode {
+            self.session_mut().ai_color = self.session().ai_color.map(|color| color.opposite());
+        }
+        self.restart_game();
+    }
+
+    fn begin_saved_game(&mut self) {
+        self.session_mut().game_id = Some(saves::new_id());
+        self.session_mut().game_created = saves::now();
+    }
+
+    // Sets `board` up to play from, keeping the clock
+    fn set_position(&mut self, board: Board) {
+        self.set_game(GameState::new(board).with_clock(self.session().game.clock().copied()));
+    }
+
+    /// Writes the current local game to disk. Called after every move so
+    /// games survive restarts and can be continued later.
+    fn autosave(&self) {
+        let Some(id) = self.session().game_id.clone() else {
+            return;
+        };
+        let mode = match self.session().ai {
+            Some(_) if self.session().kids_mode => SavedMode::Kids,
+            Some(_) => SavedMode::VsAi,
+            None => SavedMode::TwoPlayer,
+        };
+        let game = SavedGame {
+            id,
+            mode,
+            ai_color: self.session().ai_color.map(Into::into),
+            ai_depth: self.session().ai.as_ref().map(|ai| ai.depth),
+            ai_selection: self.session().ai.as_ref().map_or(Selection::Best, |ai| ai.selection),
+            created: self.session().game_created,
+            updated: saves::now(),
+            initial_fen: self.session().game.initial().to_fen(),
+            moves: self.session().game.moves().iter().map(|mv| mv.to_uci()).collect(),
+            fen: self.session().game.board().to_fen(),
+            result: self.session().game.result().map(|reason| reason.result().to_string()),
+            clock: self.session().game.clock().copied(),
+        };
+        // A failed save must not interrupt play; the next move retries it
+        let _ = saves::save(&game);
+    }
+
+    /// The most recent move, highlighted on the board so the opponent's
+    /// reply is easy to spot
+    pub fn last_move(&self) -> Option<&Move> {
+        self.session().game.last_move()
+    }
 
-                self.move_history.push(mv);
-                self.board.make_move(&mv);
-                self.check_game_over();
+    /// The named opening the game has reached, if any
+    pub fn opening(&self) -> Option<Opening> {
+        eco::classify(self.session().game.initial(), self.session().game.moves())
+    }
+
+    /// The game so far, with players named after the mode and the active
+    /// profile, and rated in games against the computer
+    pub fn to_game(&self) -> Game {
+        let player = self.profiles.active().map_or("Player", |profile| profile.name.as_str());
+        let computer = self.session().bot.as_ref().map_or("Computer", |bot| bot.name.as_str());
+        let tournament_game = self.tournament.as_ref().zip(self.session().tournament_game);
+        let (white, black) = match self.session().ai_color {
+            Some(Color::White) => (computer, player),
+            Some(Color::Black) => (player, computer),
+            None => tournament_game.and_then(|(bracket, index)| bracket.names(index)).unwrap_or(("White", "Black")),
+        };
+        let started = if self.session().game_created > 0 { self.session().game_created } else { saves::now() };
+        let mut game = self.session().game.to_record();
+        let event = if tournament_game.is_some() { "Hot-seat tournament" } else { "Casual game" };
+        game.tags = vec![
+            ("Event".to_string(), event.to_string()),
+            ("Site".to_string(), "Terminal".to_string()),
+            ("Date".to_string(), saves::pgn_date(started)),
+        ];
+        if let Some((bracket, index)) = tournament_game {
+            game.tags.push(("Round".to_string(), bracket.pairings[index].round.to_string()));
+        }
+        game.tags.push(("White".to_string(), white.to_string()));
+        game.tags.push(("Black".to_string(), black.to_string()));
+        let session = self.session();
+        if let (Some(ai), Some(ai_color)) = (&session.ai, session.ai_color) {
+            // The kids' AI plays below its depth, and a bot not at all by
+            // it, so neither has a rating to give
+            let ai_elo = (!session.kids_mode && session.bot.is_none()).then(|| ai_rating(ai.selection, ai.depth));
+            let player_elo = session.game_rating.filter(|_| self.profiles.active.is_some());
+            let (white_elo, black_elo) = match ai_color {
+                Color::White => (ai_elo, player_elo),
+                Color::Black => (player_elo, ai_elo),
+            };
+            for (tag, elo) in [("WhiteElo", white_elo), ("BlackElo", black_elo)] {
+                if let Some(elo) = elo {
+                    game.tags.push((tag.to_string(), elo.to_string()));
+                }
             }
         }
+        if matches!(self.session().game.result(), Some(GameOverReason::Review { .. })) {
+            game.tags = self.session().reviewed_tags.clone();
+        }
+        game.annotations = self.mainline_annotations();
+        game
     }
 
-    fn check_game_over(&mut self) {
-        if MoveGenerator::is_checkmate(&self.board, self.board.current_player) {
-            self.game_over_reason = Some(GameOverReason::Checkmate(self.board.current_player.opposite()));
-            self.mode = GameMode::GameOver;
-        } else if MoveGenerator::is_stalemate(&self.board, self.board.current_player) {
-            self.game_over_reason = Some(GameOverReason::Stalemate);
-            self.mode = GameMode::GameOver;
-        } else if MoveGenerator::is_insufficient_material(&self.board) {
-            self.game_over_reason = Some(GameOverReason::InsufficientMaterial);
-            self.mode = GameMode::GameOver;
-        } else if self.board.halfmove_clock >= 50 {
-            self.game_over_reason = Some(GameOverReason::FiftyMoveRule);
-            self.mode = GameMode::GameOver;
+    /// The comments, NAGs and marks on the game's own moves, leaving out the
+    /// unannotated ones at the end
+    fn mainline_annotations(&self) -> Vec<MoveAnnotation> {
+        let mut annotations = Vec::new();
+        let mut node = MoveTree::ROOT;
+        while let Some(next) = self.session().move_tree.main_child(node) {
+            annotations.push(self.session().move_tree.annotation(next).clone());
+            node = next;
         }
+        while annotations.last().is_some_and(MoveAnnotation::is_empty) {
+            annotations.pop();
+        }
+        annotations
     }
 
-    pub fn deselect(&mut self) {
-        self.selected_piece = None;
-        self.legal_moves = Vec::new();
-        self.promotion_menu = None;
+    /// Writes the game as JSON to the exports directory
+    pub fn export_json(&mut self) {
+        let name = format!("{}.json", self.session().game_id.clone().unwrap_or_else(saves::new_id));
+        let written = serde_json::to_string_pretty(&self.to_game())
+            .map_err(std::io::Error::from)
+            .and_then(|json| saves::write_export(&name, &json));
+        self.notify(match written {
+            Ok(path) => format!("Exported to {}", path.display()),
+            Err(err) => format!("Export failed: {}", err),
+        });
     }
 
-    pub fn return_to_menu(&mut self) {
-        self.mode = GameMode::Menu;
+    /// The game as PGN, with its variations and annotations
+    pub fn pgn(&self) -> String {
+        PgnGame::from(self.to_game()).to_pgn_with_variations(&self.session().move_tree)
+    }
+
+    /// Copies the game as PGN, ready to paste into an analysis board
+    pub fn copy_pgn(&mut self) {
+        let pgn = self.pgn();
+        let message = match self.clipboard.copy(&pgn) {
+            Ok(()) => "Copied PGN to the clipboard".to_string(),
+            Err(err) => format!("Copy failed: {}", err),
+        };
+        self.notify(message);
+    }
+
+    pub fn copy_fen(&mut self) {
+        let fen = self.session().game.board().to_fen();
+        let message = match self.clipboard.copy(&fen) {
+            Ok(()) => "Copied FEN to the clipboard".to_string(),
+            Err(err) => format!("Copy failed: {}", err),
+        };
+        self.notify(message);
+    }
+
+    /// Sets up the position on the clipboard as a new two-player game, where
+    /// both sides can be moved freely to study it
+    pub fn paste_fen(&mut self) {
+        if self.session().mode == GameMode::Online {
+            return;
+        }
+        let fen = match self.clipboard.paste() {
+            Ok(text) => text.trim().to_string(),
+            Err(err) => {
+                self.notify(format!("Paste failed: {}", err));
+                return;
+            }
+        };
+        if let Err(err) = self.start_from_fen(&fen) {
+            self.notify(format!("Clipboard doesn't hold a FEN: {}", err));
+        }
+    }
+
+    /// Starts a two-player game from any position
+    pub fn start_from_fen(&mut self, fen: &str) -> Result<(), FenError> {
+        let board = Board::from_fen(fen)?;
+        self.start_two_player();
+        self.set_position(board);
+        self.orient_board();
+        self.check_game_over();
+        self.dispatch_game_events();
+        self.autosave();
+        Ok(())
+    }
+
+    /// Starts a game against the computer from any position, the computer
+    /// taking the side not to move
+    pub fn start_vs_ai_from_fen(&mut self, fen: &str) -> Result<(), FenError> {
+        let board = Board::from_fen(fen)?;
+        self.start_vs_ai();
+        self.session_mut().ai_color = Some(board.current_player.opposite());
+        self.set_position(board);
+        self.session_mut().cursor = match self.session().game.board().current_player {
+            Color::White => Position::new(6, 4),
+            Color::Black => Position::new(1, 4),
+        };
+        self.orient_board();
+        self.check_game_over();
+        self.dispatch_game_events();
+        self.autosave();
+        Ok(())
+    }
+
+    pub fn open_fen_menu(&mut self) {
+        self.session_mut().mode = GameMode::FenMenu;
         self.menu_selection = 0;
+        self.fen_input.clear();
+        self.fen_error = None;
+        self.recent_positions = saves::load_data("positions.json");
     }
 
-    pub fn quit(&mut self) {
-        self.should_quit = true;
+    /// Highlights the FEN row or a recent position on the Start from FEN
+    /// screen, copying the position into the FEN to start from or edit
+    pub fn select_fen_row(&mut self, row: usize) {
+        if row > self.recent_positions.len() {
+            return;
+        }
+        self.menu_selection = row;
+        if let Some(fen) = row.checked_sub(1).and_then(|index| self.recent_positions.get(index)) {
+            self.fen_input = fen.clone();
+        }
+        self.fen_error = None;
     }
-}
 
-impl Default for App {
-    fn default() -> Self {
-        Self::new()
+    /// Replaces the FEN on the Start from FEN screen with the clipboard's text
+    pub fn paste_fen_input(&mut self) {
+        match self.clipboard.paste() {
+            Ok(text) => {
+                self.fen_input = text.trim().to_string();
+                self.menu_selection = 0;
+                self.fen_error = None;
+            }
+            Err(err) => self.fen_error = Some(format!("Paste failed: {}", err)),
+        }
+    }
+
+    /// Starts the game set up on the Start from FEN screen, or says there
+    /// what's wrong with the FEN
+    pub fn start_fen_menu_game(&mut self) {
+        let fen = self.fen_input.trim().to_string();
+        if fen.is_empty() {
+            self.fen_error = Some("Type a FEN or pick a recent position".to_string());
+            return;
+        }
+        let started = if self.fen_vs_ai { self.start_vs_ai_from_fen(&fen) } else { self.start_from_fen(&fen) };
+        if let Err(err) = started {
+            self.fen_error = Some(format!("Not a valid FEN: {}", err));
+            return;
+        }
+        self.fen_error = None;
+        self.recent_positions.retain(|recent| *recent != fen);
+        self.recent_positions.insert(0, fen);
+        self.recent_positions.truncate(RECENT_POSITIONS);
+        // Like autosaves, a failed write only loses the list
+        let _ = saves::save_data("positions.json", &self.recent_positions);
+    }
+
+    /// Opens the tournament screen: the standings and the next game of the
+    /// tournament under way, or the players to enter for a new one
+    pub fn open_tournament(&mut self) {
+        self.session_mut().mode = GameMode::TournamentMenu;
+        self.menu_selection = 0;
+        self.tournament_setup.error = None;
     }
+
+    /// Starts the tournament between the players entered, or says what's missing
+    pub fn start_tournament(&mut self) {
+        if let Some(bracket) = self.tournament_setup.start() {
+            self.tournament = Some(bracket);
+            self.menu_selection = 0;
+        }
+    }
+
+    /// Drops the tournament under way, back to entering the players, who
+    /// are kept for another one
+    pub fn end_tournament(&mut self) {
+        self.tournament = None;
+        self.menu_selection = 0;
+    }
+
+    /// Starts the tournament's next game between its two players, taking
+    /// turns at the keyboard. It's only played in this session, so it
+    /// isn't saved to resume.
+    pub fn start_tournament_game(&mut self) {
+        let Some(index) = self.tournament.as_ref().and_then(Bracket::next_game) else {
+            return;
+        };
+        self.start_two_player();
+        self.session_mut().tournament_game = Some(index);
+        self.session_mut().game_id = None;
+    }
+
+    /// Whether the game on screen is one of the tournament's
+    pub fn is_tournament_game(&self) -> bool {
+        self.session().tournament_game.is_some() && self.tournament.is_some()
+    }
+
+    // Enters the result of the tournament game that just ended and says
+    // what comes next
+    fn record_tournament_result(&mut self) {
+        let (Some(index), Some(reason)) = (self.session().tournament_game, self.session().game.result()) else {
+            return;
+        };
+        let Some(bracket) = self.tournament.as_mut() else {
+            return;
+        };
+        bracket.record(index, reason.result());
+        let message = match (bracket.winner(), bracket.next_game()) {
+            (Some(winner), _) => format!("{} wins the tournament", bracket.players[winner]),
+            (None, None) => "The tournament ends in a tie for first".to_string(),
+            (None, Some(next)) => {
+                let (white, black) = bracket.names(next).unwrap_or_default();
+                format!("Next up: {} vs {}", white, black)
+            }
+        };
+        self.session_mut().events.push(EventKind::Result, message.clone());
+        self.notify(message);
+    }
+
+    /// Starts a two-player game from a PGN game's moves, with their
+    /// comments and NAGs, to carry on from its last position. A game with
+    /// an illegal move isn't started.
+    pub fn start_from_pgn(&mut self, game: &PgnGame) -> Result<(), PgnError> {
+        game.validate()?;
+        self.start_from_fen(&game.initial_board.to_fen()).map_err(PgnError::InvalidFen)?;
+        for (index, &mv) in game.moves.iter().enumerate() {
+            self.record_move(mv);
+            if let Some(annotation) = game.annotations.get(index) {
+                let session = self.session_mut();
+                *session.move_tree.annotation_mut(session.live_node) = annotation.clone();
+            }
+        }
+        // The game's own moves are only being caught up with
+        self.skip_game_events();
+        self.session_mut().cursor = match self.session().game.board().current_player {
+            Color::White => Position::new(6, 4),
+            Color::Black => Position::new(1, 4),
+        };
+        self.orient_board();
+        self.check_game_over();
+        self.dispatch_game_events();
+        self.autosave();
+        Ok(())
+    }
+
+    /// Writes the current position as an SVG image to the exports directory
+    pub fn export_svg(&mut self) {
+        let image = BoardPainter::new(self.session().game.board())
+            .last_move(self.last_move())
+            .show_check(true)
+            .flip(self.session().flipped)
+            .to_svg(&SvgOptions::default());
+        let id = self.session().game_id.clone().unwrap_or_else(saves::new_id);
+        let name = format!("{}-{}.svg", id, self.session().game.moves().len());
+        self.notify(match saves::write_export(&name, &image) {
+            Ok(path) => format!("Saved image to {}", path.display()),
+            Err(err) => format!("Export failed: {}", err),
+        });
+    }
+
+    /// Keeps the result of the game that just ended: in the stats, and the
+    /// game itself in the archive
+    fn finish_game(&mut self) {
+        if let Some(reason) = self.session().game.result() {
+            log::info!("game over: {}", reason.describe());
+            self.session_mut().events.push(EventKind::Result, format!("Game over: {}", reason.describe()));
+        }
+        self.record_stats();
+        self.record_tournament_result();
+        self.archive_game();
+    }
+
+    fn archive_game(&mut self) {
+        if self.session().game.moves().is_empty() {
+            return;
+        }
+        let id = self.session().game_id.clone().unwrap_or_else(saves::new_id);
+        // A failed write only leaves the game out of the archive
+        let _ = saves::archive(&id, &self.pgn());
+    }
+
+    pub fn open_archive(&mut self) {
+        self.archive = saves::list_archive();
+        self.archive_query.clear();
+        self.archive_searching = false;
+        self.session_mut().mode = GameMode::Archive;
+        self.menu_selection = 0;
+    }
+
+    /// The archived games the search leaves in the list
+    pub fn archive_matches(&self) -> Vec<&PgnGame> {
+        self.archive.iter().filter(|game| saves::archive_matches(game, &self.archive_query)).collect()
+    }
+
+    /// Opens the highlighted archived game to look through
+    pub fn open_archived_game(&mut self) {
+        let Some(game) = self.archive_matches().get(self.menu_selection).map(|&game| game.clone()) else {
+            return;
+        };
+        self.review_game(game, true);
+    }
+
+    /// Opens a game from a file: a finished one to look through, and one
+    /// still in progress to play on from its last position
+    pub fn open_game(&mut self, game: &PgnGame) -> Result<(), PgnError> {
+        if game.result == "*" {
+            self.start_from_pgn(game)
+        } else {
+            self.review_game(game.clone(), false);
+            Ok(())
+        }
+    }
+
+    /// Sets up a finished game at its starting position, to step through
+    /// its moves. It's only for looking at, so it isn't saved.
+    fn review_game(&mut self, game: PgnGame, archived: bool) {
+        self.start_two_player();
+        self.session_mut().game_id = None;
+        self.set_game(GameState::new(game.initial_board.clone()));
+        for (index, &mv) in game.moves.iter().enumerate() {
+            self.record_move(mv);
+            if let Some(annotation) = game.annotations.get(index) {
+                let session = self.session_mut();
+                *session.move_tree.annotation_mut(session.live_node) = annotation.clone();
+            }
+        }
+        let winner = match game.result.as_str() {
+            "1-0" => Some(Color::White),
+            "0-1" => Some(Color::Black),
+            _ => None,
+        };
+        self.session_mut().game.end(GameOverReason::Review { winner, archived });
+        // Looking through a game doesn't replay it
+        self.skip_game_events();
+        let session = self.session_mut();
+        session.reviewed_tags = game.tags;
+        session.mode = GameMode::GameOver;
+        session.flipped = false;
+        self.review_start();
+    }
+
+    /// Whether the game on the board was opened from the archive
+    pub fn is_archived_game(&self) -> bool {
+        matches!(self.session().game.result(), Some(GameOverReason::Review { archived: true, .. }))
+    }
+
+    pub fn open_stats(&mut self) {
+        self.stats = GameStats::load();
+        self.session_mut().mode = GameMode::Stats;
+    }
+
+    /// Adds the game that just ended to the stats file, from the player's
+    /// side: against the computer or online the player's own, and White's
+    /// in two-player games. A game against the computer also rates the
+    /// active profile, and with adaptive strength on moves the computer's
+    /// level for the next game.
+    fn record_stats(&mut self) {
+        let Some(reason) = self.session().game.result() else {
+            return;
+        };
+        // A position set up already finished isn't a game played
+        if self.session().game.moves().is_empty() {
+            return;
+        }
+        let online_color = self.online.as_ref().and_then(|session| session.game.as_ref()).map(|game| game.color);
+        let (mode, player) = match (reason, self.session().ai_color) {
+            (GameOverReason::Remote { .. }, _) => (StatsMode::Online, online_color.unwrap_or(Color::White)),
+            (_, Some(ai_color)) if self.session().endgame.is_some() => (StatsMode::Endgame, ai_color.opposite()),
+            (_, Some(ai_color)) if self.session().kids_mode => (StatsMode::Kids, ai_color.opposite()),
+            (_, Some(ai_color)) => (StatsMode::VsAi, ai_color.opposite()),
+            (_, None) => (StatsMode::TwoPlayer, Color::White),
+        };
+        let ai_level = match mode {
+            StatsMode::VsAi if self.session().bot.is_none() => {
+                self.session().ai.as_ref().map(|ai| (ai.selection, ai.depth))
+            }
+            _ => None,
+        };
+        let outcome = match (reason.result(), player) {
+            ("1-0", Color::White) | ("0-1", Color::Black) => Outcome::Win,
+            ("1-0", Color::Black) | ("0-1", Color::White) => Outcome::Loss,
+            _ => Outcome::Draw,
+        };
+
+        let mut stats = GameStats::load();
+        stats.record(mode, ai_level, outcome, self.session().game.moves().len());
+        // Like autosaves, a failed write mustn't get in the way of the game
+        let _ = stats.save();
+
+        let Some((selection, depth)) = ai_level else {
+            return;
+        };
+        let score = match outcome {
+            Outcome::Win => 1.0,
+            Outcome::Loss => 0.0,
+            Outcome::Draw => 0.5,
+        };
+        let mut notes = Vec::new();
+        if let Some((rating, change)) = self.profiles.rate_game(ai_rating(selection, depth), score) {
+            let _ = self.profiles.save();
+            notes.push(format!("Rating: {} ({:+})", rating, change));
+        }
+        if let Some(note) = self.adapt_ai_level((selection, depth), outcome) {
+            notes.push(note);
+        }
+        if !notes.is_empty() {
+            self.notify(notes.join("; "));
+        }
+    }
+
+    // With adaptive strength on, sets the level for the next game one up
+    // from `level` after a win and one down after a loss, and says so.
+    // Draws leave it where it is.
+    fn adapt_ai_level(&mut self, level: (Selection, u32), outcome: Outcome) -> Option<String> {
+        if !self.settings.adaptive_ai {
+            return None;
+        }
+        let steps = match outcome {
+            Outcome::Win => 1,
+            Outcome::Loss => -1,
+            Outcome::Draw => 0,
+        };
+        let next = config::step_ai_level(level, steps);
+        if next == (self.settings.ai_selection, self.settings.ai_depth) {
+            return None;
+        }
+        (self.settings.ai_selection, self.settings.ai_depth) = next;
+        let label = config::ai_level_label(next.0, next.1).to_lowercase();
+        log::info!("adaptive strength: next game at {}", label);
+        if let Err(err) = config::save_settings(&self.settings) {
+            return Some(format!("Couldn't save settings: {}", err));
+        }
+        Some(format!("Next game: {}", label))
+    }
+
+    pub fn open_profiles(&mut self) {
+        self.session_mut().mode = GameMode::Profiles;
+        self.menu_selection = self.profiles.active.unwrap_or(0);
+        self.profile_name = None;
+    }
+
+    /// Plays as the highlighted profile, or starts naming a new one on
+    /// the row after them
+    pub fn choose_profile(&mut self) {
+        if self.menu_selection < self.profiles.profiles.len() {
+            self.profiles.active = Some(self.menu_selection);
+            let _ = self.profiles.save();
+            self.return_to_menu();
+        } else {
+            self.profile_name = Some(String::new());
+        }
+    }
+
+    /// Adds the profile named on the profiles screen and plays as it
+    pub fn create_profile(&mut self) {
+        let Some(name) = self.profile_name.take() else {
+            return;
+        };
+        match self.profiles.add(&name) {
+            Ok(()) => {
+                let _ = self.profiles.save();
+                self.menu_selection = self.profiles.profiles.len() - 1;
+                self.session_mut().status_message = None;
+            }
+            Err(message) => {
+                // The name stays to be fixed
+                self.profile_name = Some(name);
+                self.notify(message);
+            }
+        }
+    }
+
+    pub fn delete_profile(&mut self) {
+        if self.menu_selection < self.profiles.profiles.len() {
+            self.profiles.remove(self.menu_selection);
+            let _ = self.profiles.save();
+        }
+    }
+
+    /// Plays on unrated, without a profile
+    pub fn play_as_guest(&mut self) {
+        self.profiles.active = None;
+        let _ = self.profiles.save();
+        self.return_to_menu();
+    }
+
+    pub fn open_saved_games(&mut self) {
+        self.saved_games = saves::list_ongoing();
+        self.session_mut().mode = GameMode::SavedGames;
+        self.menu_selection = 0;
+    }
+
+    pub fn resume_saved_game(&mut self) {
+        let Some(saved) = self.saved_games.get(self.menu_selection).cloned() else {
+            return;
+        };
+        let Ok(board) = Board::from_fen(&saved.initial_fen) else {
+            return;
+        };
+
+        self.set_game(GameState::new(board).with_clock(saved.clock));
+        self.session_mut().selected_piece = None;
+        self.session_mut().legal_moves = Vec::new();
+        self.clear_moves();
+        self.session_mut().promotion_menu = None;
+        self.session_mut().ai_color = saved.ai_color.map(Into::into);
+        self.session_mut().ai = match saved.mode {
+            SavedMode::VsAi => Some(
+                ChessAI::new(saved.ai_depth.unwrap_or(self.settings.ai_depth)).with_selection(saved.ai_selection),
+            ),
+            SavedMode::Kids => Some(ChessAI::new(KIDS_AI_DEPTH).with_noise(KIDS_AI_NOISE)),
+            SavedMode::TwoPlayer => None,
+        };
+        self.session_mut().mode = match saved.mode {
+            SavedMode::VsAi | SavedMode::Kids => GameMode::VsAI,
+            SavedMode::TwoPlayer => GameMode::TwoPlayer,
+        };
+        let session = self.session_mut();
+        session.kids_mode = saved.mode == SavedMode::Kids;
+        session.bot = None;
+        session.tournament_game = None;
+        session.handoff_pending = false;
+        session.ai_move_due = None;
+        session.ai_search = None;
+        session.search_info = None;
+        session.game_id = Some(saved.id);
+        session.game_created = saved.created;
+        self.session_mut().game_rating = self.profiles.active().map(|profile| profile.rating);
+
+        for uci in &saved.moves {
+            match MoveGenerator::find_uci_move(self.session().game.board(), uci) {
+                Some(mv) => self.record_move(mv),
+                None => break,
+            }
+        }
+        self.session_mut().cursor = match self.session().game.board().current_player {
+            Color::White => Position::new(6, 4),
+            Color::Black => Position::new(1, 4),
+        };
+        self.orient_board();
+
+        self.session_mut().clock_tick = None;
+        self.start_turn_clock();
+
+        // The saved moves were played before
+        self.skip_game_events();
+        self.check_game_over();
+        self.dispatch_game_events();
+        let session = self.session();
+        if session.mode == GameMode::VsAI && Some(session.game.board().current_player) == session.ai_color {
+            self.schedule_ai_move();
+        }
+    }
+
+    pub fn delete_saved_game(&mut self) {
+        if self.menu_selection < self.saved_games.len() {
+            let saved = self.saved_games.remove(self.menu_selection);
+            let _ = saves::delete(&saved.id);
+            self.menu_selection = self.menu_selection.min(self.saved_games.len().saturating_sub(1));
+        }
+    }
+
+    /// Moves the cursor as seen on screen, so up is towards the top of
+    /// the board whichever way it faces
+    pub fn move_cursor(&mut self, row_offset: i32, col_offset: i32) {
+        let (row_offset, col_offset) = if self.session().flipped {
+            (-row_offset, -col_offset)
+        } else {
+            (row_offset, col_offset)
+        };
+        let new_row = (self.session().cursor.row as i32 + row_offset).clamp(0, 7) as usize;
+        let new_col = (self.session().cursor.col as i32 + col_offset).clamp(0, 7) as usize;
+        self.session_mut().cursor = Position::new(new_row, new_col);
+    }
+
+    pub fn select_square(&mut self) {
+        // The latest move's position is the live game's
+        if self.session().review == Some(self.session().live_node) {
+            self.session_mut().review = None;
+        }
+        if let Some(_promo_pos) = self.session().promotion_menu {
+            // Already handled in promotion selection
+            return;
+        }
+
+        if let Some(selected_pos) = self.session().selected_piece {
+            // Try to make a move
+            if let Some(mv) = self.session().legal_moves.iter().find(|m| m.to == self.session().cursor) {
+                // Check if this is a promotion move
+                if mv.piece.piece_type == PieceType::Pawn {
+                    let promotion_row = if mv.piece.color == Color::White { 0 } else { 7 };
+                    if mv.to.row == promotion_row {
+                        self.session_mut().promotion_menu = Some(mv.to);
+                        self.session_mut().promotion_selection = 0;
+                        if self.settings.auto_queen {
+                            self.execute_promotion(PieceType::Queen);
+                        }
+                        return;
+                    }
+                }
+
+                self.play(*mv);
+            } else {
+                // Deselect or select a different piece
+                self.try_select_piece();
+                // Anywhere but another piece to pick is somewhere it can't go
+                if self.session().selected_piece.is_none() && self.session().cursor != selected_pos {
+                    self.notify("Illegal move".to_string());
+                }
+            }
+        } else {
+            // Try to select a piece
+            self.try_select_piece();
+        }
+    }
+
+    pub fn execute_promotion(&mut self, piece_type: PieceType) {
+        if let (Some(_selected_pos), Some(promo_pos)) = (self.session().selected_piece, self.session().promotion_menu) {
+            if let Some(mv) = self.session().legal_moves.iter().find(|m| m.to == promo_pos) {
+                let promo_move = Move {
+                    from: mv.from,
+                    to: mv.to,
+                    move_type: chess_core::game::MoveType::Promotion(piece_type),
+                    piece: mv.piece,
+                    captured: mv.captured,
+                };
+                self.session_mut().promotion_menu = None;
+                self.play(promo_move);
+            }
+        }
+    }
+
+    fn is_human_turn(&self) -> bool {
+        match self.session().mode {
+            GameMode::Online => self
+                .online
+                .as_ref()
+                .and_then(|session| session.game.as_ref())
+                .is_some_and(|game| game.color == self.session().game.board().current_player && !game.is_finished()),
+            GameMode::VsAI => Some(self.session().game.board().current_player) != self.session().ai_color,
+            GameMode::Puzzle => {
+                Some(self.session().game.board().current_player) != self.session().ai_color
+                    && self.session().ai_move_due.is_none()
+                    && self.session().puzzles.as_ref().is_some_and(|run| run.status == PuzzleStatus::Solving)
+            }
+            GameMode::Tutorial => self.tutorial.as_ref().is_some_and(|tutorial| tutorial.status == LessonStatus::Trying),
+            _ => true,
+        }
+    }
+
+    /// Makes a move in the game, or in the reviewed position as a variation,
+    /// or queues it as a premove while the computer thinks
+    fn play(&mut self, mv: Move) {
+        match self.session().review {
+            Some(node) => self.play_variation(node, mv),
+            None if self.session().mode == GameMode::Puzzle => self.play_puzzle_move(mv),
+            None if self.session().mode == GameMode::Tutorial => self.play_lesson_move(mv),
+            None if self.can_premove() => {
+                let session = self.session_mut();
+                session.premove = Some(mv);
+                session.selected_piece = None;
+                session.legal_moves = Vec::new();
+                self.notify(format!("Premove {} queued", mv.to_uci()));
+            }
+            None => self.execute_move(mv),
+        }
+    }
+
+    /// Whether it's the computer's turn, so the player's moves are premoves
+    fn can_premove(&self) -> bool {
+        self.session().mode == GameMode::VsAI
+            && self.session().game.result().is_none()
+            && Some(self.session().game.board().current_player) == self.session().ai_color
+    }
+
+    /// The position as if it were the player's turn already, to pick
+    /// premoves in. The computer's reply can still rule them out.
+    fn premove_board(&self) -> Board {
+        let mut board = self.session().game.board().clone();
+        board.current_player = board.current_player.opposite();
+        board.en_passant_target = None;
+        board
+    }
+
+    /// Plays the queued premove now it's the player's turn, if it's still legal
+    fn play_premove(&mut self) {
+        let Some(premove) = self.session_mut().premove.take() else {
+            return;
+        };
+        if self.session().game.result().is_some() {
+            return;
+        }
+        match MoveGenerator::find_uci_move(self.session().game.board(), &premove.to_uci()) {
+            Some(mv) => self.execute_move(mv),
+            None => self.notify(format!("Premove {} cancelled: no longer legal", premove.to_uci())),
+        }
+    }
+
+    /// Shows a notification in the status bar for a few seconds
+    pub fn notify(&mut self, message: String) {
+        self.session_mut().status_message = Some(message);
+        self.session_mut().status_expires = Some(Instant::now() + NOTIFICATION_DURATION);
+    }
+
+    /// Shows a message in the status bar until something replaces it
+    pub fn show_message(&mut self, message: Option<String>) {
+        self.session_mut().status_message = message;
+        self.session_mut().status_expires = None;
+    }
+
+    /// Opens the command line, where moves can be typed too
+    pub fn open_command_line(&mut self) {
+        self.command_line = Some(String::new());
+        self.session_mut().status_message = None;
+    }
+
+    /// Closes the command line after a command. In screen reader mode it
+    /// stays open for the next move while a game is on screen.
+    pub fn finish_command_line(&mut self) {
+        self.command_line = (self.settings.screen_reader && is_game_screen(self.session().mode)).then(String::new);
+    }
+
+    /// Opens the command line ready to save the game, with a file name
+    /// from today's date to change or keep
+    pub fn open_save_prompt(&mut self) {
+        let date = saves::pgn_date(saves::now()).replace('.', "-");
+        self.command_line = Some(format!("save game-{}.pgn", date));
+        self.notify("Enter to save; a name ending in .json saves JSON".to_string());
+    }
+
+    /// Lists the working directory's game files to load one from
+    pub fn open_file_browser(&mut self) {
+        if self.session().mode == GameMode::Online {
+            self.notify("Not during an online game".to_string());
+            return;
+        }
+        let opened = std::env::current_dir().and_then(|dir| FileBrowser::open(&dir));
+        match opened {
+            Ok(browser) => self.file_browser = Some(browser),
+            Err(err) => self.notify(format!("Couldn't list the files: {}", err)),
+        }
+    }
+
+    /// Goes into the directory highlighted in the file browser, or loads
+    /// the game file
+    pub fn choose_file(&mut self) {
+        let Some(browser) = self.file_browser.as_mut() else {
+            return;
+        };
+        match browser.choose() {
+            Ok(None) => {}
+            Ok(Some(path)) => {
+                self.file_browser = None;
+                let message = commands::load_game(self, &path).unwrap_or_else(|message| message);
+                self.notify(message);
+            }
+            Err(err) => self.notify(format!("Couldn't open the directory: {}", err)),
+        }
+    }
+
+    /// Plays a typed move, written in SAN ("Nf3", in the UI language or
+    /// English) or coordinates ("g1f3", "e7e8q")
+    pub fn play_typed_move(&mut self, text: &str) -> Result<(), String> {
+        if self.session().game.result().is_some() {
+            return Err("The game is over".to_string());
+        }
+        if self.session().review == Some(self.session().live_node) {
+            self.session_mut().review = None;
+        }
+        // Either side can be moved in a reviewed position
+        let reviewed = self.session().review.map(|node| self.board_at(node));
+        let premove_board = (reviewed.is_none() && self.can_premove()).then(|| self.premove_board());
+        let (board, can_move) = match (&reviewed, &premove_board) {
+            (Some(board), _) | (None, Some(board)) => (board, true),
+            (None, None) => (self.session().game.board(), self.is_human_turn()),
+        };
+        if !can_move {
+            return Err("It's not your turn".to_string());
+        }
+
+        let coordinates = text.replace('-', "").to_ascii_lowercase();
+        let mv = match MoveGenerator::find_uci_move(board, &coordinates) {
+            Some(mv) => mv,
+            None => san::parse_localized_san(board, text, self.language)
+                .or_else(|err| san::parse_san(board, text).map_err(|_| err))
+                .map_err(|err| format!("Move not played: {}", err))?,
+        };
+        let session = self.session_mut();
+        session.selected_piece = None;
+        session.legal_moves = Vec::new();
+        session.promotion_menu = None;
+        self.play(mv);
+        Ok(())
+    }
+
+    fn try_select_piece(&mut self) {
+        if self.session().review.is_none() && self.can_premove() {
+            let (board, cursor) = (self.session().game.board(), self.session().cursor);
+            let player = board.current_player.opposite();
+            if board.get_piece(cursor).is_some_and(|piece| piece.color == player) {
+                let legal_moves = MoveGenerator::generate_legal_moves(&self.premove_board(), cursor);
+                self.session_mut().selected_piece = Some(cursor);
+                self.session_mut().legal_moves = legal_moves;
+            } else {
+                self.session_mut().selected_piece = None;
+                self.session_mut().legal_moves = Vec::new();
+            }
+            return;
+        }
+
+        // Either side can be moved in a reviewed position
+        let reviewed = self.session().review.map(|node| self.board_at(node));
+        let (board, can_move) = match &reviewed {
+            Some(board) => (board, true),
+            None => (self.session().game.board(), self.is_human_turn()),
+        };
+        let cursor = self.session().cursor;
+        if let Some(piece) = board.get_piece(cursor) {
+            if piece.color == board.current_player && can_move {
+                let legal_moves = MoveGenerator::generate_legal_moves(board, cursor);
+                self.session_mut().selected_piece = Some(cursor);
+                self.session_mut().legal_moves = legal_moves;
+            } else if piece.color == board.current_player && self.session().game.result().is_none() {
+                self.session_mut().selected_piece = None;
+                self.session_mut().legal_moves = Vec::new();
+                self.notify("It's not your turn".to_string());
+            } else {
+                self.session_mut().selected_piece = None;
+                self.session_mut().legal_moves = Vec::new();
+            }
+        } else {
+            self.session_mut().selected_piece = None;
+            self.session_mut().legal_moves = Vec::new();
+        }
+    }
+
+    /// Empties the game's moves, variations included, before a new game
+    fn clear_moves(&mut self) {
+        let session = self.session_mut();
+        session.san_history = Vec::new();
+        session.move_tree = MoveTree::new();
+        session.live_node = MoveTree::ROOT;
+        session.review = None;
+        session.premove = None;
+    }
+
+    fn record_move(&mut self, mv: Move) {
+        let san = san::to_san(self.session().game.board(), &mv);
+        if self.session_mut().game.play(mv).is_err() {
+            return;
+        }
+        let session = self.session_mut();
+        session.san_history.push(san);
+        session.live_node = session.move_tree.add_main(session.live_node, mv);
+        session.review = None;
+        session.arrow_start = None;
+        self.session_mut().status_message = None;
+    }
+
+    // Lists the game's moves again after some were taken back, dropping
+    // the variations tried on them
+    fn rebuild_history(&mut self) {
+        let mut board = self.session().game.initial().clone();
+        self.clear_moves();
+        let session = self.session_mut();
+        for &mv in session.game.moves() {
+            session.san_history.push(san::to_san(&board, &mv));
+            board.make_move(&mv);
+            session.live_node = session.move_tree.add_main(session.live_node, mv);
+        }
+    }
+
+    fn execute_move(&mut self, mv: Move) {
+        self.tick_clock();
+        if self.session().game.result().is_some() {
+            return;
+        }
+        self.record_move(mv);
+        self.press_clock(mv.piece.color);
+        self.session_mut().selected_piece = None;
+        self.session_mut().legal_moves = Vec::new();
+        self.check_game_over();
+        self.dispatch_game_events();
+        self.autosave();
+
+        if self.session().mode == GameMode::TwoPlayer && self.hot_seat {
+            self.orient_board();
+            self.session_mut().handoff_pending = true;
+        }
+
+        // If playing against AI and it's AI's turn, make AI move
+        if self.session().mode == GameMode::VsAI
+            && self.session().game.result().is_none()
+            && Some(self.session().game.board().current_player) == self.session().ai_color
+        {
+            self.schedule_ai_move();
+        }
+    }
+
+    /// Starts the AI's reply now, or after a pause in kids mode so the
+    /// child can see their own move land first, or one from the settings
+    fn schedule_ai_move(&mut self) {
+        let delay = if self.session().kids_mode {
+            KIDS_AI_DELAY
+        } else {
+            Duration::from_millis(self.settings.ai_delay_ms)
+        };
+        if delay.is_zero() {
+            self.make_ai_move();
+        } else {
+            self.session_mut().ai_move_due = Some(Instant::now() + delay);
+        }
+    }
+
+    /// Undoes the last move, and the AI's reply to it, so the player can
+    /// try again. Kids mode allows this at any time, even after the game ends.
+    pub fn take_back(&mut self) {
+        if !self.session().kids_mode || self.session().game.moves().is_empty() {
+            return;
+        }
+
+        self.session_mut().game.undo();
+        // Keep undoing until it's the player's turn again
+        while self.session().game.last_move().is_some_and(|mv| Some(mv.piece.color) != self.session().ai_color) {
+            self.session_mut().game.undo();
+        }
+
+        self.rebuild_history();
+        let session = self.session_mut();
+        session.selected_piece = None;
+        session.legal_moves = Vec::new();
+        session.promotion_menu = None;
+        session.ai_move_due = None;
+        session.ai_search = None;
+        session.mode = GameMode::VsAI;
+        self.autosave();
+        let text = format!("Moves taken back; {} to move", side_name(self.session().game.board().current_player));
+        self.session_mut().events.push(EventKind::Move, text);
+
+        if Some(self.session().game.board().current_player) == self.session().ai_color {
+            self.schedule_ai_move();
+        }
+    }
+
+    /// Starts searching for the AI's reply; `update` plays it once found
+    pub fn make_ai_move(&mut self) {
+        if let Some(bot) = self.session().bot.clone() {
+            self.make_bot_move(bot);
+            return;
+        }
+        let Some(ai) = &self.session().ai else {
+            return;
+        };
+        let mut engine = ChessAI::new(ai.depth)
+            .with_noise(ai.noise)
+            .with_selection(ai.selection)
+            .with_variety(OPENING_VARIETY_MOVES)
+            .with_weights(self.ai_weights.clone())
+            .with_history(self.session().game.initial(), self.session().game.moves());
+        engine.time_limit = ai.time_limit;
+        engine.seed = self.ai_seed;
+        let board = self.session().game.board().clone();
+        log::debug!("AI search started at depth {}: {}", engine.depth, board.to_fen());
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let started = Instant::now();
+            let best = engine.search_with_progress(&board, |progress| {
+                let _ = sender.send(AiMessage::Progress(SearchInfo::new(&board, progress, engine.depth)));
+            });
+            let mv = best.map(|(mv, _)| mv);
+            let reply = AiReply { mv, depth: engine.depth, nodes: engine.nodes(), time: started.elapsed() };
+            log::info!(
+                "AI search finished: {} at depth {}, {} nodes in {:.3} s",
+                mv.map_or("no move".to_string(), |mv| mv.to_uci()),
+                reply.depth,
+                reply.nodes,
+                reply.time.as_secs_f64()
+            );
+            let _ = sender.send(AiMessage::Reply(reply));
+        });
+        let session = self.session_mut();
+        session.ai_search = Some(receiver);
+        session.search_started = Some(Instant::now());
+        session.search_info = None;
+    }
+
+    // Has `bot` pick the computer's move, in the background like a search
+    // as its rules may search too
+    fn make_bot_move(&mut self, bot: BotScript) {
+        let initial = self.session().game.initial().clone();
+        let board = self.session().game.board().clone();
+        let played = self.session().game.moves().to_vec();
+        let seed = self.ai_seed.map_or_else(|| Rng::from_time().next_u64(), |seed| seed ^ played.len() as u64);
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let started = Instant::now();
+            let mv = bot.choose(&initial, &board, &played, seed).unwrap_or_else(|error| {
+                log::warn!("{}'s code failed: {}", bot.name, error);
+                let _ = sender.send(AiMessage::ScriptFailed(format!("{}'s code failed: {}", bot.name, error)));
+                bot.choose_by_rules(&board, seed)
+            });
+            log::info!("{} picked {}", bot.name, mv.map_or("no move".to_string(), |mv| mv.to_uci()));
+            let _ = sender.send(AiMessage::Reply(AiReply { mv, depth: 0, nodes: 0, time: started.elapsed() }));
+        });
+        let session = self.session_mut();
+        session.ai_search = Some(receiver);
+        session.search_started = Some(Instant::now());
+        session.search_info = None;
+    }
+
+    fn play_ai_move(&mut self, mv: Move) {
+        // The search time comes off the computer's clock
+        self.tick_clock();
+        if self.session().game.result().is_some() {
+            return;
+        }
+        self.record_move(mv);
+        self.press_clock(mv.piece.color);
+        self.check_game_over();
+        self.dispatch_game_events();
+        self.autosave();
+        // A piece picked for a premove may have nowhere to go now
+        if self.session().review.is_none() {
+            self.session_mut().selected_piece = None;
+            self.session_mut().legal_moves = Vec::new();
+        }
+        self.play_premove();
+    }
+
+    /// Shows `mv`, just played: slides it into place, and describes it in
+    /// screen reader mode
+    fn show_move(&mut self, mv: Move) {
+        if self.settings.animate_moves {
+            self.session_mut().animation = Some((mv, Instant::now()));
+        }
+        let Some(san) = self.session().san_history.last() else {
+            return;
+        };
+        let side = self.session().game.board().current_player;
+        let mut text = format!("{} plays {}", side_name(mv.piece.color), san);
+        if let Some(captured) = mv.captured {
+            text.push_str(&format!(", taking a {}", piece_name(captured.piece_type)));
+        }
+        if MoveGenerator::is_checkmate(self.session().game.board(), side) {
+            text.push_str("; checkmate");
+        } else if MoveGenerator::is_stalemate(self.session().game.board(), side) {
+            text.push_str("; stalemate");
+        } else {
+            text.push_str(&format!("; {} to move", side_name(side)));
+            if MoveGenerator::is_in_check(self.session().game.board(), side) {
+                text.push_str(&format!("; {} is in check", side_name(side)));
+            }
+        }
+        log::debug!("{}", text);
+        self.session_mut().events.push(EventKind::Move, text);
+    }
+
+    /// Logs what a command answered, for screen reader mode, where the
+    /// event log is read instead of the status line
+    pub fn narrate(&mut self, text: String) {
+        if self.settings.screen_reader {
+            self.session_mut().events.push(EventKind::Message, text);
+        }
+    }
+
+    /// The move still sliding into place, if any, and how far along it is
+    /// from 0 to 1
+    pub fn animation(&self) -> Option<(Move, f32)> {
+        let (mv, started) = self.session().animation?;
+        let progress = started.elapsed().as_secs_f32() / MOVE_ANIMATION.as_secs_f32();
+        // A new game or a takeback ends it early
+        (progress < 1.0 && self.last_move() == Some(&mv)).then_some((mv, progress))
+    }
+
+    // Puts `game` on screen in place of the game there, whose events
+    // not yet dispatched go with it
+    fn set_game(&mut self, game: GameState) {
+        let session = self.session_mut();
+        session.game = forwarding(game, &session.game_event_sender);
+        self.skip_game_events();
+    }
+
+    // Passes over the events so far, for moves that were only caught up with
+    fn skip_game_events(&mut self) {
+        self.session().game_events.try_iter().for_each(drop);
+    }
+
+    /// Reacts to what has happened in the game since the last call:
+    /// shows and logs moves, sends the player's to Lichess, picks their
+    /// sounds and wraps up a game that has ended
+    fn dispatch_game_events(&mut self) {
+        let events: Vec<GameEvent> = self.session().game_events.try_iter().collect();
+        for event in events {
+            match event {
+                GameEvent::MovePlayed(mv) => {
+                    self.show_move(mv);
+                    self.send_online_move(mv);
+                    let alert = self.is_opponent_move(mv.piece.color);
+                    self.queue_cue(Cue { sound: Sound::Move, alert });
+                }
+                // A check drowns out a capture, and the end of the game both
+                GameEvent::Captured(_) => self.replace_sound(Sound::Capture),
+                GameEvent::Check(_) => self.replace_sound(Sound::Check),
+                GameEvent::Promotion { color, piece_type } => {
+                    log::debug!("{} promotes to a {}", side_name(color), piece_name(piece_type));
+                }
+                GameEvent::Clock { color, .. } => {
+                    let text = format!("{} has under {} seconds left", side_name(color), LOW_TIME_MS / 1000);
+                    self.session_mut().events.push(EventKind::Clock, text);
+                }
+                GameEvent::GameEnded(reason) => self.on_game_ended(reason),
+            }
+        }
+    }
+
+    // Whether `color` is the side the player here doesn't move, whose moves
+    // call for attention
+    fn is_opponent_move(&self, color: Color) -> bool {
+        match self.session().mode {
+            GameMode::Online => {
+                let game = self.online.as_ref().and_then(|session| session.game.as_ref());
+                game.is_some_and(|game| game.color != color)
+            }
+            GameMode::Tutorial => color == Color::Black,
+            _ => Some(color) == self.session().ai_color,
+        }
+    }
+
+    // Sends the player's move in an online game. Moves caught up with from
+    // the server's list are there already.
+    fn send_online_move(&mut self, mv: Move) {
+        if self.session().mode != GameMode::Online {
+            return;
+        }
+        let played = self.session().game.moves().len();
+        let Some(session) = self.online.as_mut() else {
+            return;
+        };
+        if session.game.as_ref().map_or(0, |game| game.moves.len()) < played {
+            session.send_move(mv.to_uci());
+        }
+    }
+
+    fn on_game_ended(&mut self, reason: GameOverReason) {
+        self.session_mut().mode = GameMode::GameOver;
+        self.finish_game();
+        // The player's own resignation or claim needs no alert
+        let alert = !matches!(
+            reason,
+            GameOverReason::Resignation(_) | GameOverReason::DrawClaimed(_) | GameOverReason::DrawAgreed
+        );
+        self.queue_cue(Cue { sound: Sound::GameEnd, alert });
+        let session = self.session_mut();
+        session.selected_piece = None;
+        session.legal_moves = Vec::new();
+        session.promotion_menu = None;
+        session.ai_move_due = None;
+        session.ai_search = None;
+    }
+
+    // Makes the sound queued for the move just played `sound` instead
+    fn replace_sound(&mut self, sound: Sound) {
+        if let Some(cue) = self.session_mut().sound_pending.as_mut() {
+            cue.sound = sound;
+        }
+    }
+
+    fn queue_cue(&mut self, cue: Cue) {
+        if self.settings.sound {
+            self.session_mut().sound_pending = Some(cue);
+        }
+    }
+
+    /// The sound to play for what happened since the last call
+    pub fn take_sound(&mut self) -> Option<Cue> {
+        self.session_mut().sound_pending.take()
+    }
+
+    /// Whether the AI is waiting to reply or searching for its move
+    pub fn ai_thinking(&self) -> bool {
+        self.session().ai_move_due.is_some() || self.session().ai_search.is_some()
+    }
+
+    /// How long the computer has been searching for its move, while it is
+    pub fn search_time(&self) -> Option<Duration> {
+        self.session().ai_search.as_ref()?;
+        self.session().search_started.map(|started| started.elapsed())
+    }
+
+    /// Charges the side to move for the time since the last tick, ending the
+    /// game if its flag falls
+    fn tick_clock(&mut self) {
+        if self.running_clock().is_none() {
+            return;
+        }
+        let (Some(_), Some(tick)) = (self.session().game.clock(), self.session().clock_tick) else {
+            return;
+        };
+        let now = Instant::now();
+        self.session_mut().clock_tick = Some(now);
+
+        let flagged = self.session_mut().game.charge_clock((now - tick).as_millis() as u64);
+        self.dispatch_game_events();
+        if flagged {
+            self.autosave();
+        }
+    }
+
+    /// Ends a side's turn on the clock: adds its increment and starts the
+    /// opponent's time
+    fn press_clock(&mut self, mover: Color) {
+        let ticking = self.session().clock_tick.is_some();
+        let Some(clock) = self.session_mut().game.clock_mut() else {
+            return;
+        };
+        if ticking {
+            clock.complete_move(mover);
+        }
+        self.start_turn_clock();
+    }
+
+    /// Starts timing the side to move. As on most servers, clocks only start
+    /// once both sides have made a move.
+    fn start_turn_clock(&mut self) {
+        if self.session().game.clock().is_some() && self.session().game.moves().len() >= 2 {
+            self.session_mut().clock_tick = Some(Instant::now());
+        }
+    }
+
+    /// The side whose clock is running, if any
+    pub fn running_clock(&self) -> Option<Color> {
+        let session = self.session();
+        let playing = matches!(session.mode, GameMode::TwoPlayer | GameMode::VsAI);
+        (playing && session.game.clock().is_some() && session.clock_tick.is_some())
+            .then_some(session.game.board().current_player)
+    }
+
+    /// How long the main loop may wait for input before it has to update:
+    /// a frame, or less if a flag falls or the AI's reply comes due sooner
+    pub fn poll_timeout(&self) -> Duration {
+        let now = Instant::now();
+        let mut timeout = FRAME_INTERVAL;
+        if self.animation().is_some() {
+            timeout = ANIMATION_FRAME;
+        }
+        if let Some(due) = self.session().ai_move_due {
+            timeout = timeout.min(due.saturating_duration_since(now));
+        }
+        let (clock, tick) = (self.session().game.clock(), self.session().clock_tick);
+        if let (Some(color), Some(clock), Some(tick)) = (self.running_clock(), clock, tick) {
+            let remaining = Duration::from_millis(clock.remaining(color) + clock.delay_remaining());
+            timeout = timeout.min(remaining.saturating_sub(now - tick));
+        }
+        timeout
+    }
+
+    pub fn open_handicap_menu(&mut self) {
+        self.session_mut().mode = GameMode::HandicapMenu;
+        self.menu_selection = 0;
+    }
+
+    /// Changes the handicap setting on the selected row of the handicap
+    /// menu by `step` choices
+    pub fn cycle_handicap(&mut self, step: isize) {
+        let handicap = &mut self.sessions[self.active].handicap;
+        match self.menu_selection {
+            0 => handicap.material = cycle(&MaterialOdds::ALL, handicap.material, step),
+            _ => handicap.time_factor = cycle(&TIME_ODDS, handicap.time_factor, step),
+        }
+    }
+
+    pub fn open_settings_menu(&mut self) {
+        self.session_mut().mode = GameMode::SettingsMenu;
+        self.menu_selection = 0;
+    }
+
+    /// Changes the setting on the selected row of the settings screen by
+    /// `step` choices; on/off settings just flip
+    pub fn change_setting(&mut self, step: isize) {
+        let settings = &mut self.settings;
+        match SETTINGS_ROWS[self.menu_selection] {
+            SettingsRow::AiDepth => {
+                let level = (settings.ai_selection, settings.ai_depth);
+                (settings.ai_selection, settings.ai_depth) = cycle(&config::ai_levels(), level, step);
+            }
+            SettingsRow::AdaptiveAi => settings.adaptive_ai = !settings.adaptive_ai,
+            SettingsRow::AiDelay => settings.ai_delay_ms = cycle(&AI_DELAYS_MS, settings.ai_delay_ms, step),
+            SettingsRow::Palette => {
+                settings.palette = cycle(&Palette::ALL, settings.palette, step);
+                self.apply_palette();
+            }
+            SettingsRow::PieceArt => {
+                // Custom art is only offered once it's been loaded
+                let arts: Vec<PieceArt> = PieceArt::ALL
+                    .into_iter()
+                    .filter(|&art| art != PieceArt::Custom || self.custom_art.is_some())
+                    .collect();
+                settings.piece_art = cycle(&arts, settings.piece_art, step);
+            }
+            SettingsRow::AutoQueen => settings.auto_queen = !settings.auto_queen,
+            SettingsRow::Sound => settings.sound = !settings.sound,
+            SettingsRow::AnimateMoves => settings.animate_moves = !settings.animate_moves,
+            SettingsRow::AutoFlip => settings.auto_flip = !settings.auto_flip,
+            SettingsRow::Coordinates => settings.coordinates = !settings.coordinates,
+            SettingsRow::Blindfold => settings.blindfold = cycle(&Blindfold::ALL, settings.blindfold, step),
+            SettingsRow::ScreenReader => settings.screen_reader = !settings.screen_reader,
+        }
+    }
+
+    /// Saves the settings to the config file and returns to the main menu
+    pub fn close_settings(&mut self) {
+        if let Err(err) = config::save_settings(&self.settings) {
+            self.notify(format!("Couldn't save settings: {}", err));
+        }
+        self.close_settings_menu(MenuItem::Settings);
+    }
+
+    /// Leaves a settings screen for the main menu, keeping its entry selected
+    pub fn close_settings_menu(&mut self, item: MenuItem) {
+        self.session_mut().mode = GameMode::Menu;
+        self.menu_selection = MENU_ITEMS.iter().position(|&entry| entry == item).unwrap_or(0);
+    }
+
+    pub fn open_time_control_menu(&mut self) {
+        self.session_mut().mode = GameMode::TimeControlMenu;
+        // Start on the current choice: "Untimed", a preset, or "Custom"
+        self.menu_selection = match self.session().time_control {
+            None => 0,
+            Some(control) => TIME_CONTROLS
+                .iter()
+                .position(|&preset| preset == control)
+                .map_or(TIME_CONTROLS.len() + 1, |index| index + 1),
+        };
+    }
+
+    /// Entries of the time control menu: "Untimed", the presets, then "Custom"
+    pub fn time_control_items(&self) -> Vec<String> {
+        let label = |control: &TimeControl| match control.mode {
+            ClockMode::Increment => control.to_string(),
+            mode => format!("{} ({})", control, mode.label()),
+        };
+        let mut items = vec!["Untimed".to_string()];
+        items.extend(
+            TIME_CONTROLS
+                .iter()
+                .map(|control| format!("{} {}", control.category(), label(control))),
+        );
+        items.push(format!("Custom {}", label(&self.custom_time_control)));
+        items
+    }
+
+    pub fn select_time_control(&mut self) {
+        self.session_mut().time_control = match self.menu_selection {
+            0 => None,
+            n if n <= TIME_CONTROLS.len() => Some(TIME_CONTROLS[n - 1]),
+            _ => Some(self.custom_time_control),
+        };
+        self.close_settings_menu(MenuItem::TimeControl);
+    }
+
+    /// Changes the custom time control's minutes and increment seconds
+    pub fn adjust_custom_time_control(&mut self, minutes: i64, increment: i64) {
+        let control = self.custom_time_control;
+        let new_minutes = (control.initial_ms / 60_000).saturating_add_signed(minutes);
+        let new_increment = (control.increment_ms / 1000).saturating_add_signed(increment);
+        self.custom_time_control = TimeControl {
+            mode: control.mode,
+            ..TimeControl::new(new_minutes.clamp(1, CUSTOM_MAX_MINUTES), new_increment.min(CUSTOM_MAX_INCREMENT))
+        };
+    }
+
+    /// Switches the custom time control between increment and the delay modes
+    pub fn cycle_custom_clock_mode(&mut self) {
+        self.custom_time_control.mode = self.custom_time_control.mode.next();
+    }
+
+    fn check_game_over(&mut self) {
+        // Lichess adjudicates online games; its verdict arrives with the game
+        // state. Puzzles end with their solution.
+        if matches!(self.session().mode, GameMode::Online | GameMode::Puzzle) {
+            return;
+        }
+
+        self.session_mut().game.adjudicate();
+    }
+
+    /// The draw the player to move could claim now, if any. Lichess games
+    /// are claimed there.
+    pub fn claimable_draw(&self) -> Option<DrawClaim> {
+        let playing = matches!(self.session().mode, GameMode::TwoPlayer | GameMode::VsAI);
+        if !playing || self.session().game.result().is_some() || !self.is_human_turn() {
+            return None;
+        }
+        self.session().game.claimable_draw()
+    }
+
+    /// Claims a draw by threefold repetition or the fifty-move rule for the
+    /// player to move, if the rules allow one
+    pub fn claim_draw(&mut self) -> Result<(), String> {
+        if self.session().game.result().is_some() {
+            return Err("The game is already over".to_string());
+        }
+        if self.session().mode == GameMode::Online {
+            return Err("Claim draws on Lichess".to_string());
+        }
+        if !self.is_human_turn() {
+            return Err("Only the player to move can claim a draw".to_string());
+        }
+        let Some(claim) = self.claimable_draw() else {
+            return Err(format!(
+                "No draw to claim: the position has occurred {} times, and it's {} moves since a capture or pawn move",
+                self.session().game.repetitions(),
+                self.session().game.board().halfmove_clock / 2
+            ));
+        };
+        self.end_game(GameOverReason::DrawClaimed(claim));
+        Ok(())
+    }
+
+    pub fn open_online_lobby(&mut self) {
+        if self.online.is_none() {
+            if let Ok(token) = std::env::var("LICHESS_TOKEN") {
+                self.online = Some(OnlineSession::connect(token));
+            }
+        }
+        self.session_mut().mode = GameMode::OnlineLobby;
+        self.menu_selection = 0;
+    }
+
+    /// Entries of the online lobby list: two seeks, then one per incoming
+    /// challenge, then an optional resume entry, then "Back"
+    pub fn online_lobby_items(&self) -> Vec<String> {
+        let mut items = vec![
+            format!("Seek casual {}+{}", crate::online::SEEK_MINUTES, crate::online::SEEK_INCREMENT),
+            format!("Seek rated {}+{}", crate::online::SEEK_MINUTES, crate::online::SEEK_INCREMENT),
+        ];
+        if let Some(session) = &self.online {
+            for challenge in &session.challenges {
+                let challenger = challenge.challenger.as_ref().map_or("Anonymous".to_string(), |c| {
+                    match c.rating {
+                        Some(rating) => format!("{} ({})", c.name, rating),
+                        None => c.name.clone(),
+                    }
+                });
+                items.push(format!(
+                    "Accept {} {} {} challenge from {}",
+                    if challenge.rated { "rated" } else { "casual" },
+                    challenge.speed,
+                    challenge.variant.key,
+                    challenger
+                ));
+            }
+            if let Some(game) = session.game.as_ref().filter(|_| session.has_active_game()) {
+                items.push(format!("Resume game vs {}", game.opponent_name()));
+            }
+        }
+        items.push("Back".to_string());
+        items
+    }
+
+    pub fn select_online_lobby_item(&mut self) {
+        let item_count = self.online_lobby_items().len();
+        if self.menu_selection + 1 == item_count {
+            self.return_to_menu();
+            return;
+        }
+
+        let Some(session) = self.online.as_mut() else {
+            return;
+        };
+        let challenge_count = session.challenges.len();
+        match self.menu_selection {
+            0 => session.seek(false),
+            1 => session.seek(true),
+            n if n < 2 + challenge_count => session.accept_challenge(n - 2),
+            _ => self.start_online_game(),
+        }
+    }
+
+    pub fn decline_online_challenge(&mut self) {
+        if let Some(session) = self.online.as_mut() {
+            let challenge_count = session.challenges.len();
+            if (2..2 + challenge_count).contains(&self.menu_selection) {
+                session.decline_challenge(self.menu_selection - 2);
+            }
+        }
+    }
+
+    /// Processes background events. Called once per frame from the main loop.
+    pub fn update(&mut self) {
+        if self.session().status_expires.is_some_and(|expires| Instant::now() >= expires) {
+            self.session_mut().status_message = None;
+            self.session_mut().status_expires = None;
+        }
+        if let Some(suspension) = self.suspend_watcher.poll() {
+            self.handle_resume(suspension);
+        }
+        self.tick_clock();
+
+        if self.session().ai_move_due.is_some_and(|due| Instant::now() >= due) {
+            self.session_mut().ai_move_due = None;
+            match self.session().mode {
+                GameMode::VsAI => self.make_ai_move(),
+                GameMode::Puzzle => self.play_puzzle_reply(),
+                _ => {}
+            }
+        }
+
+        while let Some(search) = &self.session().ai_search {
+            match search.try_recv() {
+                Ok(AiMessage::Progress(info)) => self.session_mut().search_info = Some(info),
+                Ok(AiMessage::ScriptFailed(message)) => {
+                    self.session_mut().events.push(EventKind::Message, message.clone());
+                    self.notify(message);
+                }
+                Ok(AiMessage::Reply(reply)) => {
+                    self.session_mut().ai_search = None;
+                    let text = match &self.session().bot {
+                        Some(bot) => format!("{} picked its move in {:.1} s", bot.name, reply.time.as_secs_f64()),
+                        None => format!(
+                            "Computer searched {} plies, {} positions in {:.1} s",
+                            reply.depth,
+                            reply.nodes,
+                            reply.time.as_secs_f64()
+                        ),
+                    };
+                    self.session_mut().events.push(EventKind::Search, text);
+                    match (reply.mv, self.session().mode) {
+                        (Some(mv), GameMode::VsAI) => self.play_ai_move(mv),
+                        (None, _) => log::warn!("AI search found no move in\n{}", self.session().game.board()),
+                        (Some(_), mode) => log::debug!("AI move dropped, the game is now {:?}", mode),
+                    }
+                }
+                Err(TryRecvError::Disconnected) => {
+                    log::warn!("AI search thread ended without a reply");
+                    self.session_mut().ai_search = None;
+                }
+                Err(TryRecvError::Empty) => break,
+            }
+        }
+
+        let Some(session) = self.online.as_mut() else {
+            return;
+        };
+        let game_started = session.poll();
+
+        if game_started && self.session().mode == GameMode::OnlineLobby {
+            self.start_online_game();
+        } else if self.session().mode == GameMode::Online {
+            self.sync_online_game();
+        }
+
+        let item_count = self.online_lobby_items().len();
+        if self.session().mode == GameMode::OnlineLobby && self.menu_selection >= item_count {
+            self.menu_selection = item_count - 1;
+        }
+    }
+
+    /// Called after the machine wakes from suspend (or the process was stopped)
+    fn handle_resume(&mut self, suspension: Suspension) {
+        // Nobody loses on time to a sleeping laptop
+        if let Some(tick) = self.session().clock_tick {
+            self.session_mut().clock_tick = Some(tick + suspension.monotonic.min(tick.elapsed()));
+        }
+        // A pending AI reply restarts its wait rather than landing instantly
+        if self.session().ai_move_due.is_some() {
+            self.session_mut().ai_move_due = Some(Instant::now() + KIDS_AI_DELAY);
+        }
+        // Where `Instant` counted the time asleep, a search with a time
+        // limit finds its budget spent and would answer almost unsearched,
+        // so it starts over. The old search stops at its limit unheard.
+        let budget = self.session().ai.as_ref().and_then(|ai| ai.time_limit);
+        if self.session().ai_search.is_some() && budget.is_some_and(|budget| suspension.monotonic >= budget) {
+            log::info!("Restarting the AI search after {:.1} s suspended", suspension.monotonic.as_secs_f64());
+            self.session_mut().ai_search = None;
+            self.make_ai_move();
+        }
+        if let Some(session) = self.online.as_mut() {
+            session.resync();
+        }
+    }
+
+    fn start_online_game(&mut self) {
+        let Some(game) = self.online.as_ref().and_then(|session| session.game.as_ref()) else {
+            return;
+        };
+        let color = game.color;
+        self.reset_online_board();
+        self.session_mut().mode = GameMode::Online;
+        self.session_mut().flipped = self.settings.auto_flip && color == Color::Black;
+        self.session_mut().cursor = match color {
+            Color::White => Position::new(6, 4),
+            Color::Black => Position::new(1, 4),
+        };
+        self.sync_online_game();
+    }
+
+    fn reset_online_board(&mut self) {
+        let initial_fen = self
+            .online
+            .as_ref()
+            .and_then(|session| session.game.as_ref())
+            .map(|game| game.initial_fen.clone())
+            .unwrap_or_default();
+        // Lichess reports standard games as "startpos" rather than a FEN
+        let initial_fen = if initial_fen == "startpos" { STARTING_FEN } else { initial_fen.as_str() };
+        // Lichess keeps the time for online games
+        self.set_game(GameState::new(Board::from_fen(initial_fen).unwrap_or_default()));
+        self.session_mut().selected_piece = None;
+        self.session_mut().legal_moves = Vec::new();
+        self.clear_moves();
+        let session = self.session_mut();
+        session.ai = None;
+        session.ai_color = None;
+        session.ai_search = None;
+        session.search_info = None;
+        session.clock_tick = None;
+        session.promotion_menu = None;
+        session.game_id = None;
+    }
+
+    /// Brings the local board in line with the server's move list
+    fn sync_online_game(&mut self) {
+        let Some(game) = self.online.as_ref().and_then(|session| session.game.as_ref()) else {
+            return;
+        };
+        let server_moves = game.moves.clone();
+        let finished = game.is_finished();
+        let winner = game.winner;
+        let reason = game.end_reason();
+
+        let moves = self.session().game.moves();
+        let diverged = server_moves.len() < moves.len()
+            || moves.iter().zip(&server_moves).any(|(mv, uci)| mv.to_uci() != *uci);
+        if diverged {
+            self.reset_online_board();
+        }
+
+        for uci in &server_moves[self.session().game.moves().len()..] {
+            match MoveGenerator::find_uci_move(self.session().game.board(), uci) {
+                Some(mv) => {
+                    self.record_move(mv);
+                    self.dispatch_game_events();
+                }
+                None => break,
+            }
+            self.session_mut().selected_piece = None;
+            self.session_mut().legal_moves = Vec::new();
+        }
+
+        if finished && self.session().game.result().is_none() {
+            self.session_mut().game.end(GameOverReason::Remote { winner, reason });
+            self.dispatch_game_events();
+        }
+    }
+
+    pub fn resign_online_game(&mut self) {
+        if let Some(session) = self.online.as_mut() {
+            session.resign();
+        }
+    }
+
+    /// Gives up the game: the player's side against the computer, the
+    /// side to move in a two-player game
+    pub fn resign(&mut self) -> Result<(), String> {
+        if self.session().game.result().is_some() {
+            return Err("The game is already over".to_string());
+        }
+        if self.session().mode == GameMode::Online {
+            self.resign_online_game();
+            return Ok(());
+        }
+        let loser = match self.session().ai_color {
+            Some(ai_color) => ai_color.opposite(),
+            None => self.session().game.board().current_player,
+        };
+        self.end_game(GameOverReason::Resignation(loser));
+        Ok(())
+    }
+
+    // Ends the game on the spot, for reasons other than the position on
+    // the board: a resignation or a claimed draw
+    fn end_game(&mut self, reason: GameOverReason) {
+        self.session_mut().game.end(reason);
+        self.session_mut().review = None;
+        self.dispatch_game_events();
+        self.autosave();
+    }
+
+    /// Changes how deep the computer opponent searches, for the rest of
+    /// this game. It plays its best move from then on, even if it was on
+    /// the beginner or random level.
+    pub fn set_ai_depth(&mut self, depth: u32) -> Result<(), String> {
+        if !(1..=MAX_AI_DEPTH).contains(&depth) {
+            return Err(format!("Depth must be between 1 and {}", MAX_AI_DEPTH));
+        }
+        if self.session().bot.is_some() {
+            return Err("The custom bot plays by its script, not a depth".to_string());
+        }
+        match self.session_mut().ai.as_mut() {
+            Some(ai) => {
+                ai.depth = depth;
+                ai.selection = Selection::Best;
+                Ok(())
+            }
+            None => Err("There's no computer opponent in this game".to_string()),
+        }
+    }
+
+    /// Moves the computer `delta` levels up, or down, from its next move,
+    /// and says where it is now. Below depth 1 come the beginner and random
+    /// levels.
+    pub fn step_ai_depth(&mut self, delta: i32) {
+        let Some(ai) = self.session_mut().ai.as_mut() else {
+            self.notify("There's no computer opponent in this game".to_string());
+            return;
+        };
+        (ai.selection, ai.depth) = config::step_ai_level((ai.selection, ai.depth), delta);
+        let message = match ai.selection {
+            Selection::Best => format!("The computer now searches {} plies deep", ai.depth),
+            Selection::Beginner => "The computer now plays like a beginner".to_string(),
+            Selection::Random => "The computer now plays random moves".to_string(),
+        };
+        self.notify(message);
+    }
+
+    /// Limits how long the computer thinks about each move, for the rest of
+    /// this game; None lets it search to its full depth
+    pub fn set_ai_time_limit(&mut self, limit: Option<Duration>) -> Result<(), String> {
+        match self.session_mut().ai.as_mut() {
+            Some(ai) => {
+                ai.time_limit = limit;
+                Ok(())
+            }
+            None => Err("There's no computer opponent in this game".to_string()),
+        }
+    }
+
+    /// Turns the board so the player's pieces are at the bottom. Games
+    /// against the computer where it plays White are seen from Black's
+    /// side, and hot-seat games from the side to move.
+    fn orient_board(&mut self) {
+        self.session_mut().flipped = if self.session().mode == GameMode::TwoPlayer && self.hot_seat {
+            self.session().game.board().current_player == Color::Black
+        } else {
+            self.settings.auto_flip && self.session().ai_color == Some(Color::White)
+        };
+    }
+
+    /// Colors the interface with the chosen palette and the config file's own colors
+    pub fn apply_palette(&mut self) {
+        self.theme = self.settings.palette.theme();
+        for (name, color) in &self.custom_colors {
+            self.theme.set(name, *color);
+        }
+    }
+
+    pub fn toggle_hot_seat(&mut self) {
+        self.hot_seat = !self.hot_seat;
+    }
+
+    /// The next player has the keyboard; show the board again
+    pub fn finish_handoff(&mut self) {
+        self.session_mut().handoff_pending = false;
+    }
+
+    pub fn toggle_help(&mut self) {
+        self.show_help = !self.show_help;
+    }
+
+    pub fn toggle_flip(&mut self) {
+        self.session_mut().flipped = !self.session().flipped;
+    }
+
+    /// Moves blindfold mode on to its next setting for the rest of the session
+    pub fn cycle_blindfold(&mut self) {
+        self.settings.blindfold = cycle(&Blindfold::ALL, self.settings.blindfold, 1);
+        self.session_mut().peek_until = None;
+        self.notify(format!("Blindfold: {}", self.settings.blindfold.label()));
+    }
+
+    /// Shows the pieces for a moment in blindfold mode
+    pub fn peek(&mut self) {
+        if self.settings.blindfold != Blindfold::Off {
+            self.session_mut().peek_until = Some(Instant::now() + PEEK_DURATION);
+        }
+    }
+
+    /// Whether blindfold mode is hiding the pieces right now. A finished
+    /// game shows them again.
+    pub fn pieces_hidden(&self) -> bool {
+        let peeking = self.session().peek_until.is_some_and(|until| Instant::now() < until);
+        self.settings.blindfold != Blindfold::Off
+            && !matches!(self.session().mode, GameMode::GameOver | GameMode::Tutorial)
+            && !peeking
+    }
+
+    /// The game on screen, with everything that goes with it
+    pub fn session(&self) -> &Session {
+        &self.sessions[self.active]
+    }
+
+    pub fn session_mut(&mut self) -> &mut Session {
+        &mut self.sessions[self.active]
+    }
+
+    /// How many games are open, one per tab
+    pub fn tab_count(&self) -> usize {
+        self.sessions.len()
+    }
+
+    /// The open games' names, in tab order, and which is on screen
+    pub fn tab_titles(&self) -> (Vec<String>, usize) {
+        let titles = self
+            .sessions
+            .iter()
+            .map(|session| {
+                let bot = session.bot.as_ref().map(|bot| bot.name.as_str());
+                let endgame = session.endgame.is_some();
+                game_title(session.mode, session.ai.as_ref(), session.kids_mode, bot, endgame, session.game.result())
+            })
+            .collect();
+        (titles, self.active)
+    }
+
+    /// Keeps the game on screen open in its tab and goes to the menu to
+    /// start another one in a new tab
+    pub fn new_tab(&mut self) {
+        if self.session().mode == GameMode::Online {
+            self.notify("Online games can't share the screen".to_string());
+            return;
+        }
+        self.pause_clock();
+        // The menu starts with the choices the last game was started with
+        let mut session = Session::new();
+        session.time_control = self.session().time_control;
+        session.handicap = self.session().handicap;
+        self.sessions.push(session);
+        self.active = self.sessions.len() - 1;
+        self.return_to_menu();
+    }
+
+    /// Shows the next open game. A tab left on the menu or another screen
+    /// outside a game is closed on the way. Returns false when there's no
+    /// other game to show.
+    pub fn next_tab(&mut self) -> bool {
+        if self.sessions.len() < 2 {
+            return false;
+        }
+        if self.session().mode == GameMode::Online {
+            self.notify("Finish the online game first".to_string());
+            return true;
+        }
+        self.pause_clock();
+        let left = self.active;
+        self.active = if is_game_screen(self.sessions[left].mode) {
+            (left + 1) % self.sessions.len()
+        } else {
+            self.sessions.remove(left);
+            left % self.sessions.len()
+        };
+
+        self.history_offset.set(0);
+        if self.session().game.result().is_none() {
+            self.start_turn_clock();
+        }
+        true
+    }
+
+    /// Closes the game on screen and shows the next one
+    pub fn close_tab(&mut self) {
+        if self.sessions.len() < 2 || self.session().mode == GameMode::Online {
+            return;
+        }
+        // The autosave keeps it, to resume later
+        self.session_mut().mode = GameMode::Menu;
+        self.next_tab();
+    }
+
+    // Charges the time used so far and stops the clock while its game is
+    // out of sight
+    fn pause_clock(&mut self) {
+        self.tick_clock();
+        self.session_mut().clock_tick = None;
+    }
+
+    pub fn toggle_side_panel_tab(&mut self) {
+        self.side_panel_tab = match self.side_panel_tab {
+            SidePanelTab::Moves => SidePanelTab::Info,
+            SidePanelTab::Info => SidePanelTab::Events,
+            SidePanelTab::Events => SidePanelTab::Moves,
+        };
+    }
+
+    pub fn deselect(&mut self) {
+        let session = self.session_mut();
+        session.selected_piece = None;
+        session.legal_moves = Vec::new();
+        session.promotion_menu = None;
+        session.review = None;
+        session.arrow_start = None;
+        session.premove = None;
+    }
+
+    /// The position being reviewed instead of the live game, if any
+    pub fn review_node(&self) -> Option<NodeId> {
+        self.session().review
+    }
+
+    /// The moves of the game and the variations tried from its positions,
+    /// one row per move in the order PGN writes them
+    pub fn history_rows(&self) -> Vec<HistoryRow> {
+        let mut rows = Vec::new();
+        let board = self.session().game.initial().clone();
+        self.push_history_rows(&mut rows, MoveTree::ROOT, board, 0);
+        rows
+    }
+
+    fn push_history_rows(&self, rows: &mut Vec<HistoryRow>, mut node: NodeId, mut board: Board, depth: usize) {
+        let row = |node: NodeId, board: &Board, depth: usize| {
+            let mv = *self.session().move_tree.mv(node).expect("only the root has no move");
+            // The game's own moves are already in SAN
+            let san = match depth {
+                0 => self.session().san_history[self.session().move_tree.depth(node) - 1].clone(),
+                _ => san::to_san(board, &mv),
+            };
+            HistoryRow {
+                node,
+                depth,
+                number: board.fullmove_number,
+                color: board.current_player,
+                san,
+            }
+        };
+        while let Some(next) = self.session().move_tree.main_child(node) {
+            rows.push(row(next, &board, depth));
+            for &variation in &self.session().move_tree.children(node)[1..] {
+                rows.push(row(variation, &board, depth + 1));
+                let mut after = board.clone();
+                after.make_move(self.session().move_tree.mv(variation).expect("only the root has no move"));
+                self.push_history_rows(rows, variation, after, depth + 1);
+            }
+            board.make_move(self.session().move_tree.mv(next).expect("only the root has no move"));
+            node = next;
+        }
+    }
+
+    /// The history row of the reviewed position's move, if any
+    pub fn selected_history(&self) -> Option<usize> {
+        let node = self.session().review?;
+        self.history_rows().iter().position(|row| row.node == node)
+    }
+
+    /// Selects a history row and evaluates the position after its move
+    pub fn select_history(&mut self, index: Option<usize>) {
+        let node = index.and_then(|index| self.history_rows().get(index).map(|row| row.node));
+        self.review(node);
+    }
+
+    /// Shows the position at `node`, or with None the live game again, and
+    /// evaluates it for the tooltip
+    fn review(&mut self, node: Option<NodeId>) {
+        if node != self.session().review {
+            let session = self.session_mut();
+            session.selected_piece = None;
+            session.legal_moves = Vec::new();
+            session.promotion_menu = None;
+            session.arrow_start = None;
+        }
+        self.session_mut().review = node;
+        if let Some(node) = node {
+            let board = self.board_at(node);
+            self.position_evals
+                .entry(board.zobrist_hash())
+                .or_insert_with(|| evaluate(&board));
+        }
+    }
+
+    /// Moves `delta` moves back or forward along the reviewed line.
+    /// Stepping back from the live game starts at its latest move and can
+    /// go on to the starting position; stepping past the latest move
+    /// returns to the live game, while a variation just stops at its end.
+    pub fn step_history(&mut self, delta: isize) {
+        if self.session().game.moves().is_empty() {
+            return;
+        }
+        let (mut node, mut steps) = match self.session().review {
+            Some(node) => (node, delta),
+            None if delta < 0 => (self.session().live_node, delta + 1),
+            None => return,
+        };
+        self.side_panel_tab = SidePanelTab::Moves;
+        while steps < 0 {
+            let Some(parent) = self.session().move_tree.parent(node) else {
+                break;
+            };
+            node = parent;
+            steps += 1;
+        }
+        while steps > 0 {
+            match self.session().move_tree.main_child(node) {
+                Some(child) => node = child,
+                None if node == self.session().live_node => return self.review(None),
+                None => break,
+            }
+            steps -= 1;
+        }
+        self.review(Some(node));
+    }
+
+    /// Moves the history selection by a list's height of moves in `direction`
+    pub fn page_history(&mut self, direction: isize) {
+        let rows = (self.history_area.get().height as isize).max(1);
+        self.step_history(direction * rows);
+    }
+
+    /// Jumps back to the position the game started from
+    pub fn review_start(&mut self) {
+        if !self.session().game.moves().is_empty() {
+            self.side_panel_tab = SidePanelTab::Moves;
+            self.review(Some(MoveTree::ROOT));
+        }
+    }
+
+    /// Stops reviewing and shows the live game again
+    pub fn return_to_live(&mut self) {
+        self.review(None);
+    }
+
+    /// Switches the reviewed move to the next of the moves tried in the
+    /// same position: from the game's move to its variations and round again
+    pub fn next_variation(&mut self) {
+        let Some(parent) = self.session().review.and_then(|node| self.session().move_tree.parent(node)) else {
+            return;
+        };
+        let siblings = self.session().move_tree.children(parent);
+        let index = siblings.iter().position(|&node| Some(node) == self.session().review).unwrap_or(0);
+        let next = siblings[(index + 1) % siblings.len()];
+        self.review(Some(next));
+    }
+
+    /// The reviewed move, which annotations go on
+    fn annotated_node(&self) -> Option<NodeId> {
+        self.session().review.filter(|&node| node != MoveTree::ROOT)
+    }
+
+    /// Gives the reviewed move the next judgement: !, ?, !!, ??, !?, ?!,
+    /// then none again
+    pub fn cycle_nag(&mut self) {
+        let Some(node) = self.annotated_node() else {
+            return;
+        };
+        let annotation = self.session_mut().move_tree.annotation_mut(node);
+        let index = MOVE_NAGS.iter().position(|&(nag, _)| Some(nag) == annotation.nag);
+        annotation.nag = match index {
+            None => Some(MOVE_NAGS[0].0),
+            Some(index) => MOVE_NAGS.get(index + 1).map(|&(nag, _)| nag),
+        };
+    }
+
+    /// Starts writing a comment on the reviewed move, beginning with the one
+    /// it already has
+    pub fn open_comment_editor(&mut self) {
+        if let Some(node) = self.annotated_node() {
+            let comment = self.session().move_tree.annotation(node).comment.clone();
+            self.session_mut().comment_editor = Some(comment.unwrap_or_default());
+        }
+    }
+
+    /// Puts the edited comment on the reviewed move; an empty one removes it
+    pub fn save_comment(&mut self) {
+        let (Some(text), Some(node)) = (self.session_mut().comment_editor.take(), self.annotated_node()) else {
+            return;
+        };
+        let text = text.trim();
+        self.session_mut().move_tree.annotation_mut(node).comment = (!text.is_empty()).then(|| text.to_string());
+    }
+
+    /// The position on the board: the reviewed one, or the live game's
+    fn shown_node(&self) -> NodeId {
+        self.session().review.unwrap_or(self.session().live_node)
+    }
+
+    /// The squares and arrows marked on the position on the board. Each
+    /// position keeps its own, so they're gone once a move is made and
+    /// back when reviewing it.
+    /// The marks shown on the board: the squares a lesson asks to visit in
+    /// the tutorial, and the shown position's own otherwise
+    pub fn marks(&self) -> &Marks {
+        match (self.session().mode, &self.tutorial) {
+            (GameMode::Tutorial, Some(tutorial)) => tutorial.marks(),
+            _ => &self.session().move_tree.annotation(self.shown_node()).marks,
+        }
+    }
+
+    /// The start of the arrow being drawn, if any
+    pub fn arrow_start(&self) -> Option<Position> {
+        self.session().arrow_start
+    }
+
+    /// Marks the square under the cursor, or changes its mark's color,
+    /// or removes it after the last color
+    pub fn mark_square(&mut self) {
+        let node = self.shown_node();
+        let session = self.session_mut();
+        session.move_tree.annotation_mut(node).marks.cycle_square(session.cursor);
+    }
+
+    /// Starts an arrow at the cursor, or ends the one being drawn there.
+    /// Drawing an arrow again changes its color, then removes it; ending
+    /// it where it started cancels it.
+    pub fn draw_arrow(&mut self) {
+        let Some(start) = self.session_mut().arrow_start.take() else {
+            self.session_mut().arrow_start = Some(self.session().cursor);
+            self.notify(format!("Drawing an arrow from {}", self.session().cursor.to_algebraic()));
+            return;
+        };
+        self.session_mut().status_message = None;
+        if start != self.session().cursor {
+            let node = self.shown_node();
+            let session = self.session_mut();
+            session.move_tree.annotation_mut(node).marks.cycle_arrow(start, session.cursor);
+        }
+    }
+
+    /// Plays `mv` in the reviewed position, adding it to the move tree as a
+    /// variation instead of to the game
+    fn play_variation(&mut self, node: NodeId, mv: Move) {
+        let child = self.session_mut().move_tree.add(node, mv);
+        self.side_panel_tab = SidePanelTab::Moves;
+        self.review(Some(child));
+    }
+
+    /// The position after the moves leading to `node`, replayed from the
+    /// start of the game
+    pub fn board_at(&self, node: NodeId) -> Board {
+        let mut board = self.session().game.initial().clone();
+        for mv in self.session().move_tree.moves_to(node) {
+            board.make_move(&mv);
+        }
+        board
+    }
+
+    /// The first row shown in a history list `rows` tall: the latest moves,
+    /// or with a selection, where the list was last scrolled to, moved only
+    /// as far as needed to keep the selection visible
+    pub fn history_first_visible(&self, rows: usize) -> usize {
+        let latest = self.history_rows().len().saturating_sub(rows);
+        let Some(index) = self.selected_history() else {
+            return latest;
+        };
+        let offset = self.history_offset.get().min(latest);
+        if index < offset {
+            index
+        } else if index >= offset + rows {
+            index + 1 - rows
+        } else {
+            offset
+        }
+    }
+
+    /// Where the history list ended up scrolled to when drawn
+    pub fn remember_history_offset(&self, offset: usize) {
+        self.history_offset.set(offset);
+    }
+
+    /// The history row drawn at a terminal cell, if any
+    pub fn history_index_at(&self, column: u16, row: u16) -> Option<usize> {
+        let area = self.history_area.get();
+        let inside = column >= area.x && column < area.right() && row >= area.y && row < area.bottom();
+        if self.side_panel_tab != SidePanelTab::Moves || !inside {
+            return None;
+        }
+        let index = self.history_first_visible(area.height as usize) + (row - area.y) as usize;
+        (index < self.history_rows().len()).then_some(index)
+    }
+
+    pub fn return_to_menu(&mut self) {
+        let session = self.session_mut();
+        session.status_message = None;
+        session.endgame = None;
+        session.mode = GameMode::Menu;
+        self.menu_selection = 0;
+    }
+
+    /// Quits, first asking to make sure while a game is unfinished, here or
+    /// in another tab
+    pub fn quit(&mut self) {
+        let unfinished = self
+            .sessions
+            .iter()
+            .any(|session| is_unfinished(session.mode, session.game.moves(), session.game.result()));
+        if unfinished && !self.quit_pending {
+            self.quit_pending = true;
+        } else {
+            self.should_quit = true;
+        }
+    }
+
+    pub fn cancel_quit(&mut self) {
+        self.quit_pending = false;
+    }
+
+    /// Whether the game on screen can be saved to a file before quitting
+    pub fn can_save_and_quit(&self) -> bool {
+        is_unfinished(self.session().mode, self.session().game.moves(), self.session().game.result())
+    }
+
+    /// A PGN file in the working directory to save the game to before
+    /// quitting, named after today's date and not already taken
+    pub fn quit_save_name(&self) -> String {
+        let date = saves::pgn_date(saves::now()).replace('.', "-");
+        (1..)
+            .map(|n| match n {
+                1 => format!("game-{}.pgn", date),
+                n => format!("game-{}-{}.pgn", date, n),
+            })
+            .find(|name| !Path::new(name).exists())
+            .expect("some name is free")
+    }
+
+    /// Saves the game on screen to a PGN file, then quits. If it can't be
+    /// saved, the game stays open with the reason.
+    pub fn save_and_quit(&mut self) {
+        if !self.can_save_and_quit() {
+            return;
+        }
+        match commands::save_game(self, Path::new(&self.quit_save_name())) {
+            Ok(_) => self.should_quit = true,
+            Err(message) => {
+                self.quit_pending = false;
+                self.notify(message);
+            }
+        }
+    }
+}
+
+/// Whether a game on `mode` has been started and not finished, so quitting
+/// would walk away from it
+fn is_unfinished(mode: GameMode, moves: &[Move], game_over_reason: Option<GameOverReason>) -> bool {
+    matches!(mode, GameMode::TwoPlayer | GameMode::VsAI | GameMode::Online)
+        && !moves.is_empty()
+        && game_over_reason.is_none()
+}
+
+/// Whether `mode` shows a game, so a tab left on it stays open
+fn is_game_screen(mode: GameMode) -> bool {
+    matches!(mode, GameMode::TwoPlayer | GameMode::VsAI | GameMode::Puzzle | GameMode::Tutorial | GameMode::GameOver)
+}
+
+/// A tab's name, after the kind of game and its result once it's over
+fn game_title(
+    mode: GameMode,
+    ai: Option<&ChessAI>,
+    kids_mode: bool,
+    bot: Option<&str>,
+    endgame: bool,
+    game_over_reason: Option<GameOverReason>,
+) -> String {
+    let name = match (mode, ai) {
+        (GameMode::Puzzle, _) => "Puzzles".to_string(),
+        (GameMode::Tutorial, _) => "Lesson".to_string(),
+        (GameMode::Online, _) => "Online".to_string(),
+        (_, Some(_)) if endgame => "Endgame".to_string(),
+        (_, Some(_)) if kids_mode => "Kids".to_string(),
+        (_, Some(_)) if bot.is_some() => format!("vs {}", bot.unwrap_or_default()),
+        (_, Some(ai)) => format!("vs AI ({})", ai.depth),
+        (GameMode::TwoPlayer | GameMode::GameOver, None) => "Two Player".to_string(),
+        _ => "New game".to_string(),
+    };
+    match game_over_reason {
+        Some(reason) => format!("{} {}", name, reason.result()),
+        None => name,
+    }
+}
+
+/// A quick engine score for a history tooltip, from White's point of view
+fn evaluate(board: &Board) -> i32 {
+    let score = match ChessAI::new(TOOLTIP_EVAL_DEPTH).search(board) {
+        Some((_, score)) => score,
+        None if MoveGenerator::is_in_check(board, board.current_player) => -MATE_SCORE,
+        None => 0,
+    };
+    if board.current_player == Color::White { score } else { -score }
+}
+
+/// The UI language from the usual locale variables, e.g. "de_DE.UTF-8"
+fn system_language() -> Language {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|value| !value.is_empty())
+        .and_then(|locale| locale.get(..2).and_then(Language::from_code))
+        .unwrap_or_default()
+}
+
+impl Default for App {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The choice `step` places after `current`, wrapping around
+fn cycle<T: Copy + PartialEq>(choices: &[T], current: T, step: isize) -> T {
+    let index = choices.iter().position(|&choice| choice == current).unwrap_or(0);
+    choices[(index as isize + step).rem_euclid(choices.len() as isize) as usize]
 }
@@ -0,0 +1,275 @@
+use crate::profiles::MAX_NAME_LENGTH;
+
+/// The most players a tournament takes; more would need more than an
+/// evening at one keyboard
+pub const MAX_PLAYERS: usize = 16;
+
+/// How a tournament's players are paired up
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BracketFormat {
+    /// Each round's losers drop out, until one player is left. A drawn
+    /// game is replayed with colors swapped until someone wins.
+    #[default]
+    Knockout,
+    /// Everyone plays everyone else once
+    RoundRobin,
+}
+
+impl BracketFormat {
+    pub fn label(self) -> &'static str {
+        match self {
+            BracketFormat::Knockout => "Single elimination",
+            BracketFormat::RoundRobin => "Round robin",
+        }
+    }
+}
+
+/// A tournament game, played or still to play
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pairing {
+    pub round: u32,
+    /// Indices into the tournament's players
+    pub white: usize,
+    pub black: usize,
+    /// "1-0", "0-1" or "1/2-1/2" once it's been played
+    pub result: Option<&'static str>,
+}
+
+/// A player's results so far
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Standing {
+    pub player: usize,
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+    /// Knocked out of a single elimination tournament
+    pub eliminated: bool,
+}
+
+impl Standing {
+    pub fn points(&self) -> f64 {
+        f64::from(self.wins) + f64::from(self.draws) / 2.0
+    }
+
+    pub fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+}
+
+/// The players being entered on the tournament screen, before it starts
+#[derive(Debug, Clone, Default)]
+pub struct BracketSetup {
+    pub players: Vec<String>,
+    /// The name being typed
+    pub name: String,
+    pub format: BracketFormat,
+    /// Why the last name or start was turned down
+    pub error: Option<String>,
+}
+
+impl BracketSetup {
+    /// Adds the name being typed as a player
+    pub fn add_player(&mut self) {
+        let name = self.name.trim().to_string();
+        self.error = if name.is_empty() {
+            Some("Type a player's name".to_string())
+        } else if self.players.iter().any(|player| player.eq_ignore_ascii_case(&name)) {
+            Some(format!("{} is already playing", name))
+        } else if self.players.len() >= MAX_PLAYERS {
+            Some(format!("At most {} players", MAX_PLAYERS))
+        } else {
+            self.players.push(name);
+            self.name.clear();
+            None
+        };
+    }
+
+    pub fn remove_player(&mut self, index: usize) {
+        if index < self.players.len() {
+            self.players.remove(index);
+            self.error = None;
+        }
+    }
+
+    /// Adds `c` to the name being typed, if there's room
+    pub fn type_char(&mut self, c: char) {
+        if self.name.chars().count() < MAX_NAME_LENGTH {
+            self.name.push(c);
+        }
+        self.error = None;
+    }
+
+    /// The tournament between the players entered, in the order entered
+    pub fn start(&mut self) -> Option<Bracket> {
+        if self.players.len() < 2 {
+            self.error = Some("Enter at least two players".to_string());
+            return None;
+        }
+        self.error = None;
+        Some(Bracket::new(self.players.clone(), self.format))
+    }
+}
+
+/// A tournament among players taking turns at the keyboard: who plays whom
+/// in each round, the results so far, and the standings they add up to.
+/// It lasts for the session.
+#[derive(Debug, Clone)]
+pub struct Bracket {
+    pub players: Vec<String>,
+    pub format: BracketFormat,
+    /// Every game so far and those still to play, in the order they're played
+    pub pairings: Vec<Pairing>,
+    /// Players sitting a round out, with the round
+    pub byes: Vec<(u32, usize)>,
+    // Players still in a knockout, in the order entered
+    remaining: Vec<usize>,
+}
+
+impl Bracket {
+    pub fn new(players: Vec<String>, format: BracketFormat) -> Bracket {
+        let mut bracket = Bracket {
+            remaining: (0..players.len()).collect(),
+            players,
+            format,
+            pairings: Vec::new(),
+            byes: Vec::new(),
+        };
+        match format {
+            BracketFormat::Knockout => bracket.pair_knockout_round(1),
+            BracketFormat::RoundRobin => bracket.pair_round_robin(),
+        }
+        bracket
+    }
+
+    // Pairs everyone against everyone by the circle method, so each round
+    // has everyone playing once, bar one player sitting out when there's
+    // an odd number. The player kept in place alternates colors, and as
+    // the others move round they swap between White and Black, so everyone
+    // ends up with White about half the time.
+    fn pair_round_robin(&mut self) {
+        let count = self.players.len();
+        // An odd number of players gets a dummy, whose opponent sits out
+        let seats = count + count % 2;
+        for round in 0..seats - 1 {
+            for board in 0..seats / 2 {
+                let a = if board == 0 { seats - 1 } else { (round + board) % (seats - 1) };
+                let b = (round + seats - 1 - board) % (seats - 1);
+                let round_number = round as u32 + 1;
+                if a >= count || b >= count {
+                    self.byes.push((round_number, a.min(b)));
+                    continue;
+                }
+                let (white, black) = if board > 0 || round % 2 == 1 { (a, b) } else { (b, a) };
+                self.pairings.push(Pairing { round: round_number, white, black, result: None });
+            }
+        }
+    }
+
+    // Pairs the players still in, in the order entered. With an odd number
+    // left, the first who hasn't sat out yet goes through without playing.
+    fn pair_knockout_round(&mut self, round: u32) {
+        let mut players = self.remaining.clone();
+        if players.len() % 2 == 1 {
+            let had_bye = |player: &usize| self.byes.iter().any(|&(_, bye)| bye == *player);
+            let sitting_out = players.iter().position(|player| !had_bye(player)).unwrap_or(0);
+            self.byes.push((round, players.remove(sitting_out)));
+        }
+        for pair in players.chunks(2) {
+            self.pairings.push(Pairing { round, white: pair[0], black: pair[1], result: None });
+        }
+    }
+
+    /// The index of the next game to play, or None once the tournament is over
+    pub fn next_game(&self) -> Option<usize> {
+        self.pairings.iter().position(|pairing| pairing.result.is_none())
+    }
+
+    /// The round being played, or the last one once it's over
+    pub fn round(&self) -> u32 {
+        let next = self.next_game().map(|index| self.pairings[index].round);
+        next.or_else(|| self.pairings.last().map(|pairing| pairing.round)).unwrap_or(1)
+    }
+
+    /// Records the result of the game `index`, if it hasn't one already,
+    /// and pairs the next knockout round once this one is over
+    pub fn record(&mut self, index: usize, result: &'static str) {
+        let Some(pairing) = self.pairings.get_mut(index) else {
+            return;
+        };
+        if pairing.result.is_some() || !matches!(result, "1-0" | "0-1" | "1/2-1/2") {
+            return;
+        }
+        pairing.result = Some(result);
+        if self.format == BracketFormat::RoundRobin {
+            return;
+        }
+
+        let Pairing { round, white, black, .. } = *pairing;
+        match result {
+            "1-0" => self.remaining.retain(|&player| player != black),
+            "0-1" => self.remaining.retain(|&player| player != white),
+            _ => self.pairings.push(Pairing { round, white: black, black: white, result: None }),
+        }
+        if self.next_game().is_none() && self.remaining.len() > 1 {
+            self.pair_knockout_round(round + 1);
+        }
+    }
+
+    /// Each player's results, best first: by points, then wins, then the
+    /// order entered
+    pub fn standings(&self) -> Vec<Standing> {
+        let mut standings: Vec<Standing> = (0..self.players.len())
+            .map(|player| Standing {
+                player,
+                wins: 0,
+                draws: 0,
+                losses: 0,
+                eliminated: self.format == BracketFormat::Knockout && !self.remaining.contains(&player),
+            })
+            .collect();
+        for pairing in &self.pairings {
+            let (white, black) = (pairing.white, pairing.black);
+            match pairing.result {
+                Some("1-0") => {
+                    standings[white].wins += 1;
+                    standings[black].losses += 1;
+                }
+                Some("0-1") => {
+                    standings[black].wins += 1;
+                    standings[white].losses += 1;
+                }
+                Some(_) => {
+                    standings[white].draws += 1;
+                    standings[black].draws += 1;
+                }
+                None => {}
+            }
+        }
+        standings.sort_by(|a, b| {
+            b.points().total_cmp(&a.points()).then(b.wins.cmp(&a.wins)).then(a.player.cmp(&b.player))
+        });
+        standings
+    }
+
+    /// The tournament's winner once it's over: the last player left in a
+    /// knockout, or the one alone on top of a round robin
+    pub fn winner(&self) -> Option<usize> {
+        if self.next_game().is_some() {
+            return None;
+        }
+        match self.format {
+            BracketFormat::Knockout => (self.remaining.len() == 1).then(|| self.remaining[0]),
+            BracketFormat::RoundRobin => match self.standings().as_slice() {
+                [first, second, ..] if first.points() == second.points() => None,
+                [first, ..] => Some(first.player),
+                [] => None,
+            },
+        }
+    }
+
+    /// The names of the game `index`'s players, White's first
+    pub fn names(&self, index: usize) -> Option<(&str, &str)> {
+        let pairing = self.pairings.get(index)?;
+        Some((&self.players[pairing.white], &self.players[pairing.black]))
+    }
+}
@@ -0,0 +1,224 @@
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+use chess_core::game::pgn::{parse_pgn, PgnGame};
+use chess_core::game::{san, Board, Color, Game, MoveGenerator, PieceType};
+
+use super::app::{App, GameMode};
+
+/// The command line's commands, with their arguments and what they do
+pub const COMMANDS: [(&str, &str, &str); 13] = [
+    ("fen", "[FEN]", "Set up a position, or show the current one"),
+    ("save", "FILE", "Write the game to a PGN file, or JSON if it ends in .json"),
+    ("load", "FILE", "Play on from the first game in a PGN or JSON file"),
+    ("pieces", "", "List where every piece stands"),
+    ("moves", "", "List the legal moves"),
+    ("flip", "", "Flip the board"),
+    ("depth", "N", "Set the computer's search depth"),
+    ("movetime", "SECONDS", "Limit the computer's time per move (off for none)"),
+    ("resign", "", "Resign the game"),
+    ("draw", "", "Claim a draw by repetition or the fifty-move rule"),
+    ("menu", "", "Return to the menu"),
+    ("quit", "", "Quit"),
+    ("help", "", "List the commands"),
+];
+
+/// Runs a line typed on the command line: a command, or otherwise a move.
+/// Returns a message to show afterwards, or why the line didn't work.
+pub fn run(app: &mut App, line: &str) -> Result<Option<String>, String> {
+    let line = line.trim();
+    let (name, arg) = line.split_once(' ').unwrap_or((line, ""));
+    let arg = arg.trim();
+    let online = app.session().mode == GameMode::Online;
+
+    match name {
+        "" => Ok(None),
+        "fen" if arg.is_empty() => Ok(Some(app.session().game.board().to_fen())),
+        "fen" | "load" if online => Err("Not during an online game".to_string()),
+        "fen" => {
+            app.start_from_fen(arg).map_err(|err| format!("Bad FEN: {}", err))?;
+            Ok(None)
+        }
+        "save" | "load" if arg.is_empty() => Err(format!("Usage: {} FILE", name)),
+        "save" => save_game(app, Path::new(arg)).map(Some),
+        "load" => load_game(app, Path::new(arg)).map(Some),
+        "pieces" => Ok(Some(describe_pieces(app.session().game.board()))),
+        "moves" => {
+            let board = app.session().game.board();
+            let moves = MoveGenerator::generate_all_legal_moves(board);
+            let sans: Vec<String> = moves.iter().map(|mv| san::to_san(board, mv)).collect();
+            if sans.is_empty() {
+                Ok(Some("No legal moves".to_string()))
+            } else {
+                Ok(Some(format!("Legal moves: {}", sans.join(", "))))
+            }
+        }
+        "flip" => {
+            app.toggle_flip();
+            Ok(None)
+        }
+        "depth" => {
+            let depth = arg.parse().map_err(|_| "Usage: depth N".to_string())?;
+            app.set_ai_depth(depth)?;
+            Ok(Some(format!("The computer now searches {} plies deep", depth)))
+        }
+        "movetime" => {
+            if arg == "off" {
+                app.set_ai_time_limit(None)?;
+                return Ok(Some("The computer now searches to its full depth".to_string()));
+            }
+            let seconds: f64 = arg
+                .parse()
+                .ok()
+                .filter(|&seconds: &f64| seconds > 0.0 && seconds.is_finite())
+                .ok_or_else(|| "Usage: movetime SECONDS, or movetime off".to_string())?;
+            app.set_ai_time_limit(Some(Duration::from_secs_f64(seconds)))?;
+            Ok(Some(format!("The computer now thinks for at most {} s a move", seconds)))
+        }
+        "resign" => app.resign().map(|()| None),
+        "draw" => app.claim_draw().map(|()| None),
+        "menu" => {
+            app.return_to_menu();
+            Ok(None)
+        }
+        "quit" => {
+            app.quit();
+            Ok(None)
+        }
+        "help" => {
+            let names: Vec<&str> = COMMANDS.iter().map(|&(name, _, _)| name).collect();
+            Ok(Some(format!("Commands: {}, or type a move", names.join(", "))))
+        }
+        _ => app.play_typed_move(line).map(|()| None),
+    }
+}
+
+/// Where every piece stands, in words: "White: king e1, rooks a1 h1, ..."
+fn describe_pieces(board: &Board) -> String {
+    const NAMES: [(PieceType, &str, &str); 6] = [
+        (PieceType::King, "king", "kings"),
+        (PieceType::Queen, "queen", "queens"),
+        (PieceType::Rook, "rook", "rooks"),
+        (PieceType::Bishop, "bishop", "bishops"),
+        (PieceType::Knight, "knight", "knights"),
+        (PieceType::Pawn, "pawn", "pawns"),
+    ];
+    let sides: Vec<String> = [(Color::White, "White"), (Color::Black, "Black")]
+        .into_iter()
+        .map(|(color, side)| {
+            let pieces = board.get_all_pieces(color);
+            let groups: Vec<String> = NAMES
+                .iter()
+                .filter_map(|&(piece_type, one, many)| {
+                    let squares: Vec<String> = pieces
+                        .iter()
+                        .filter(|(_, piece)| piece.piece_type == piece_type)
+                        .map(|(pos, _)| pos.to_algebraic())
+                        .collect();
+                    match squares.len() {
+                        0 => None,
+                        1 => Some(format!("{} {}", one, squares[0])),
+                        _ => Some(format!("{} {}", many, squares.join(" "))),
+                    }
+                })
+                .collect();
+            format!("{}: {}", side, groups.join(", "))
+        })
+        .collect();
+    sides.join("; ")
+}
+
+// Whether a file holds a game as JSON rather than PGN
+fn is_json(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json"))
+}
+
+/// Writes the game to `path`: as JSON, the form games are exported in, if
+/// the name ends in .json, and otherwise as PGN
+pub fn save_game(app: &App, path: &Path) -> Result<String, String> {
+    let text = if is_json(path) {
+        serde_json::to_string_pretty(&app.to_game()).map_err(|err| err.to_string())?
+    } else {
+        app.pgn()
+    };
+    fs::write(path, text).map_err(|err| format!("Couldn't write {}: {}", path.display(), err))?;
+    Ok(format!("Saved the game to {}", path.display()))
+}
+
+/// Plays on from the game in `path`, the first one if it's a PGN file
+/// holding several
+pub fn load_game(app: &mut App, path: &Path) -> Result<String, String> {
+    if app.session().mode == GameMode::Online {
+        return Err("Not during an online game".to_string());
+    }
+    let game = read_game(path)?;
+    app.start_from_pgn(&game).map_err(|err| format!("Couldn't load {}: {}", path.display(), err))?;
+    Ok(format!("Loaded {}", path.display()))
+}
+
+/// Reads the game in a JSON file, or the first one in a PGN file
+pub fn read_game(path: &Path) -> Result<PgnGame, String> {
+    let text = fs::read_to_string(path).map_err(|err| format!("Couldn't read {}: {}", path.display(), err))?;
+    if is_json(path) {
+        let game = serde_json::from_str::<Game>(&text)
+            .map(PgnGame::from)
+            .map_err(|err| format!("Couldn't read {}: {}", path.display(), err))?;
+        // Unlike PGN, JSON isn't checked move by move as it's read
+        game.validate().map_err(|err| format!("Couldn't read {}: {}", path.display(), err))?;
+        Ok(game)
+    } else {
+        let games = parse_pgn(&text).map_err(|err| format!("Couldn't read {}: {}", path.display(), err))?;
+        games.into_iter().next().ok_or_else(|| format!("No game in {}", path.display()))
+    }
+}
+
+/// Completes the command name, or the file name after `save` and `load`.
+/// Returns the completed line and, when there's more than one way to go
+/// on, the candidates.
+pub fn complete(line: &str) -> (String, Vec<String>) {
+    let Some((name, arg)) = line.split_once(' ') else {
+        let names = COMMANDS.iter().map(|&(name, _, _)| name.to_string());
+        let candidates: Vec<String> = names.filter(|name| name.starts_with(line)).collect();
+        return match candidates.as_slice() {
+            [only] => (format!("{} ", only), Vec::new()),
+            _ => (common_prefix(&candidates).unwrap_or_else(|| line.to_string()), candidates),
+        };
+    };
+    if !matches!(name, "save" | "load") {
+        return (line.to_string(), Vec::new());
+    }
+
+    // Everything up to the last slash is the directory to look in
+    let (dir, prefix) = match arg.rfind('/') {
+        Some(index) => arg.split_at(index + 1),
+        None => ("", arg),
+    };
+    let entries = fs::read_dir(if dir.is_empty() { Path::new(".") } else { Path::new(dir) });
+    let mut candidates: Vec<String> = entries
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| {
+            let file_name = entry.file_name().into_string().ok()?;
+            let slash = if entry.path().is_dir() { "/" } else { "" };
+            (file_name.starts_with(prefix) && !file_name.starts_with('.')).then(|| format!("{}{}", file_name, slash))
+        })
+        .collect();
+    candidates.sort();
+    let completed = match candidates.as_slice() {
+        [only] => only.clone(),
+        _ => common_prefix(&candidates).unwrap_or_else(|| prefix.to_string()),
+    };
+    let shown = if candidates.len() > 1 { candidates } else { Vec::new() };
+    (format!("{} {}{}", name, dir, completed), shown)
+}
+
+/// The longest start all of `words` share, if there are any
+fn common_prefix(words: &[String]) -> Option<String> {
+    let first = words.first()?;
+    let len = words.iter().fold(first.chars().count(), |len, word| {
+        first.chars().zip(word.chars()).take(len).take_while(|(a, b)| a == b).count()
+    });
+    Some(first.chars().take(len).collect())
+}
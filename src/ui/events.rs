@@ -0,0 +1,63 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+// Entries kept; the oldest are dropped past this
+const MAX_EVENTS: usize = 500;
+
+/// What an event log entry is about
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventKind {
+    /// A move played, with any capture and check
+    Move,
+    /// How a game ended
+    Result,
+    /// A clock running low
+    Clock,
+    /// How deep and how long the computer searched
+    Search,
+    /// What a typed command answered
+    Message,
+}
+
+/// One line of the event log
+pub struct Event {
+    /// Since the program started
+    pub at: Duration,
+    pub kind: EventKind,
+    pub text: String,
+}
+
+/// What happened in the games this session, in words, for the event log
+/// panel and screen reader mode
+pub struct EventLog {
+    started: Instant,
+    events: VecDeque<Event>,
+}
+
+impl EventLog {
+    pub fn new() -> EventLog {
+        EventLog { started: Instant::now(), events: VecDeque::new() }
+    }
+
+    pub fn push(&mut self, kind: EventKind, text: String) {
+        if self.events.len() == MAX_EVENTS {
+            self.events.pop_front();
+        }
+        self.events.push_back(Event { at: self.started.elapsed(), kind, text });
+    }
+
+    /// Oldest first
+    pub fn events(&self) -> impl DoubleEndedIterator<Item = &Event> {
+        self.events.iter()
+    }
+}
+
+/// An event's time as "m:ss", or "h:mm:ss" after the first hour
+pub fn format_time(at: Duration) -> String {
+    let seconds = at.as_secs();
+    if seconds < 3600 {
+        format!("{}:{:02}", seconds / 60, seconds % 60)
+    } else {
+        format!("{}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60)
+    }
+}
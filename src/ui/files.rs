@@ -0,0 +1,94 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The kinds of file a game can be loaded from: PGN, or the JSON games
+/// are exported as
+pub const GAME_EXTENSIONS: [&str; 2] = ["pgn", "json"];
+
+/// One line of the file browser
+pub struct FileEntry {
+    pub name: String,
+    pub is_dir: bool,
+}
+
+/// A directory listing to pick a game file from, with its subdirectories
+/// to go into and the parent to go back up to
+pub struct FileBrowser {
+    pub dir: PathBuf,
+    pub entries: Vec<FileEntry>,
+    pub selection: usize,
+}
+
+impl FileBrowser {
+    pub fn open(dir: &Path) -> io::Result<FileBrowser> {
+        let mut browser = FileBrowser { dir: dir.to_path_buf(), entries: Vec::new(), selection: 0 };
+        browser.read()?;
+        Ok(browser)
+    }
+
+    // Lists the directory: ".." first, then the subdirectories and the
+    // game files, each in name order. Hidden ones are left out.
+    fn read(&mut self) -> io::Result<()> {
+        let mut entries: Vec<FileEntry> = fs::read_dir(&self.dir)?
+            .flatten()
+            .filter_map(|entry| {
+                let name = entry.file_name().into_string().ok()?;
+                let is_dir = entry.path().is_dir();
+                let is_game = Path::new(&name)
+                    .extension()
+                    .is_some_and(|ext| GAME_EXTENSIONS.iter().any(|&game| ext.eq_ignore_ascii_case(game)));
+                (!name.starts_with('.') && (is_dir || is_game)).then_some(FileEntry { name, is_dir })
+            })
+            .collect();
+        entries.sort_by(|a, b| b.is_dir.cmp(&a.is_dir).then_with(|| a.name.cmp(&b.name)));
+        if self.dir.parent().is_some() {
+            entries.insert(0, FileEntry { name: "..".to_string(), is_dir: true });
+        }
+        self.entries = entries;
+        self.selection = 0;
+        Ok(())
+    }
+
+    pub fn move_selection(&mut self, delta: isize) {
+        let last = self.entries.len().saturating_sub(1) as isize;
+        self.selection = (self.selection as isize + delta).clamp(0, last) as usize;
+    }
+
+    /// Goes up to the parent directory
+    pub fn up(&mut self) -> io::Result<()> {
+        let Some(parent) = self.dir.parent() else {
+            return Ok(());
+        };
+        let left = self.dir.file_name().map(|name| name.to_string_lossy().into_owned());
+        self.dir = parent.to_path_buf();
+        self.read()?;
+        // Land on the directory just left
+        if let Some(index) = self.entries.iter().position(|entry| Some(&entry.name) == left.as_ref()) {
+            self.selection = index;
+        }
+        Ok(())
+    }
+
+    /// Goes into the highlighted directory, or returns the highlighted file
+    pub fn choose(&mut self) -> io::Result<Option<PathBuf>> {
+        let Some(entry) = self.entries.get(self.selection) else {
+            return Ok(None);
+        };
+        if entry.name == ".." {
+            self.up()?;
+            return Ok(None);
+        }
+        let path = self.dir.join(&entry.name);
+        if !entry.is_dir {
+            return Ok(Some(path));
+        }
+        let previous = std::mem::replace(&mut self.dir, path);
+        if let Err(err) = self.read() {
+            // Stay where we were if it can't be listed
+            self.dir = previous;
+            return Err(err);
+        }
+        Ok(None)
+    }
+}
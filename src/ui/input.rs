@@ -1,42 +1,108 @@
-use crossterm::event::{self, Event, KeyCode, KeyEvent};
-use std::time::Duration;
-
-use super::app::{App, GameMode};
-use crate::game::PieceType;
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers, MouseEvent, MouseEventKind};
+use super::commands;
+use super::bracket::BracketFormat;
+use super::app::{App, GameMode, MenuItem, HANDICAP_ROWS, MENU_ITEMS, PROMOTION_PIECES, SETTINGS_ROWS};
+use crate::profiles::MAX_NAME_LENGTH;
+use chess_core::game::endgame::ENDGAMES;
+use chess_core::game::PieceType;
 
 pub fn handle_input(app: &mut App) -> std::io::Result<()> {
-    if event::poll(Duration::from_millis(100))? {
-        if let Event::Key(key) = event::read()? {
-            match app.mode {
+    if event::poll(app.poll_timeout())? {
+        let event = event::read()?;
+        match &event {
+            Event::Key(key) => log::debug!("key {:?} {:?} on {:?}", key.code, key.modifiers, app.session().mode),
+            Event::Mouse(mouse) if matches!(mouse.kind, MouseEventKind::Down(_)) => {
+                log::debug!("click at {},{} on {:?}", mouse.column, mouse.row, app.session().mode)
+            }
+            _ => {}
+        }
+        match event {
+            // Any key closes the help again
+            Event::Key(_) if app.show_help => app.toggle_help(),
+            Event::Key(key) if app.quit_pending => handle_quit_input(app, key),
+            Event::Key(key) if app.session().comment_editor.is_some() => handle_comment_input(app, key),
+            Event::Key(key) if app.command_line.is_some() => handle_command_line_input(app, key),
+            Event::Key(key) if app.file_browser.is_some() => handle_file_browser_input(app, key),
+            Event::Key(key) if app.profile_name.is_some() => handle_profile_name_input(app, key),
+            Event::Key(key) if app.archive_searching => handle_archive_search_input(app, key),
+            Event::Key(key) if key.code == KeyCode::Char('?') => app.toggle_help(),
+            Event::Key(key) => match app.session().mode {
                 GameMode::Menu => handle_menu_input(app, key),
-                GameMode::TwoPlayer | GameMode::VsAI => handle_game_input(app, key),
+                GameMode::TimeControlMenu => handle_time_control_input(app, key),
+                GameMode::HandicapMenu => handle_handicap_input(app, key),
+                GameMode::EndgameMenu => handle_endgame_menu_input(app, key),
+                GameMode::FenMenu => handle_fen_menu_input(app, key),
+                GameMode::TournamentMenu => handle_tournament_input(app, key),
+                GameMode::SettingsMenu => handle_settings_input(app, key),
+                GameMode::TwoPlayer | GameMode::VsAI | GameMode::Online | GameMode::Puzzle | GameMode::Tutorial => {
+                    handle_game_input(app, key)
+                }
+                GameMode::SavedGames => handle_saved_games_input(app, key),
+                GameMode::Archive => handle_archive_input(app, key),
+                GameMode::Profiles => handle_profiles_input(app, key),
+                GameMode::Stats => handle_stats_input(app, key),
+                GameMode::OnlineLobby => handle_online_lobby_input(app, key),
                 GameMode::GameOver => handle_game_over_input(app, key),
-            }
+            },
+            Event::Mouse(mouse) => handle_mouse(app, mouse),
+            _ => {}
         }
     }
     Ok(())
 }
 
+/// Hovering over or clicking a move in the history panel shows its tooltip
+fn handle_mouse(app: &mut App, mouse: MouseEvent) {
+    let in_game = matches!(
+        app.session().mode,
+        GameMode::TwoPlayer | GameMode::VsAI | GameMode::Online | GameMode::Puzzle | GameMode::GameOver
+    );
+    if !in_game || app.session().handoff_pending {
+        return;
+    }
+    if let MouseEventKind::Moved | MouseEventKind::Down(_) = mouse.kind {
+        let index = app.history_index_at(mouse.column, mouse.row);
+        if index != app.selected_history() {
+            app.select_history(index);
+        }
+    }
+}
+
 fn handle_menu_input(app: &mut App, key: KeyEvent) {
     match key.code {
-        KeyCode::Up | KeyCode::Char('k') => {
-            if app.menu_selection > 0 {
-                app.menu_selection -= 1;
-            }
+        KeyCode::Up | KeyCode::Char('k') if app.menu_selection > 0 => {
+            app.menu_selection -= 1;
         }
-        KeyCode::Down | KeyCode::Char('j') => {
-            if app.menu_selection < 2 {
-                app.menu_selection += 1;
-            }
+        KeyCode::Down | KeyCode::Char('j') if app.menu_selection + 1 < MENU_ITEMS.len() => {
+            app.menu_selection += 1;
         }
         KeyCode::Enter | KeyCode::Char(' ') => {
-            match app.menu_selection {
-                0 => app.start_two_player(),
-                1 => app.start_vs_ai(),
-                2 => app.quit(),
-                _ => {}
+            match MENU_ITEMS[app.menu_selection] {
+                MenuItem::TwoPlayer => app.start_two_player(),
+                MenuItem::VsAI => app.start_vs_ai(),
+                MenuItem::Kids => app.start_kids_mode(),
+                MenuItem::CustomBot => app.start_custom_bot(),
+                MenuItem::StartFromFen => app.open_fen_menu(),
+                MenuItem::Tournament => app.open_tournament(),
+                MenuItem::Learn => app.start_tutorial(),
+                MenuItem::Puzzles => app.start_puzzles(),
+                MenuItem::Endgames => app.open_endgame_menu(),
+                MenuItem::Profiles => app.open_profiles(),
+                MenuItem::Stats => app.open_stats(),
+                MenuItem::TimeControl => app.open_time_control_menu(),
+                MenuItem::Handicap => app.open_handicap_menu(),
+                MenuItem::HotSeat => app.toggle_hot_seat(),
+                MenuItem::Settings => app.open_settings_menu(),
+                MenuItem::ResumeGame => app.open_saved_games(),
+                MenuItem::Archive => app.open_archive(),
+                MenuItem::Online => app.open_online_lobby(),
+                MenuItem::Quit => app.quit(),
             }
         }
+        // Back to the games open in other tabs
+        KeyCode::Tab => {
+            app.next_tab();
+        }
         KeyCode::Char('q') | KeyCode::Esc => {
             app.quit();
         }
@@ -44,10 +110,497 @@ fn handle_menu_input(app: &mut App, key: KeyEvent) {
     }
 }
 
+fn handle_time_control_input(app: &mut App, key: KeyEvent) {
+    let item_count = app.time_control_items().len();
+    let on_custom = app.menu_selection + 1 == item_count;
+    match key.code {
+        KeyCode::Up | KeyCode::Char('k') if app.menu_selection > 0 => {
+            app.menu_selection -= 1;
+        }
+        KeyCode::Down | KeyCode::Char('j') if app.menu_selection + 1 < item_count => {
+            app.menu_selection += 1;
+        }
+        // The custom entry is edited in place: minutes with ←/→, increment
+        // or delay with -/+, and d to switch between increment and delays
+        KeyCode::Left | KeyCode::Char('h') if on_custom => app.adjust_custom_time_control(-1, 0),
+        KeyCode::Right | KeyCode::Char('l') if on_custom => app.adjust_custom_time_control(1, 0),
+        KeyCode::Char('-') if on_custom => app.adjust_custom_time_control(0, -1),
+        KeyCode::Char('+') | KeyCode::Char('=') if on_custom => app.adjust_custom_time_control(0, 1),
+        KeyCode::Char('d') if on_custom => app.cycle_custom_clock_mode(),
+        KeyCode::Enter | KeyCode::Char(' ') => {
+            app.select_time_control();
+        }
+        KeyCode::Esc | KeyCode::Char('m') => {
+            app.return_to_menu();
+        }
+        KeyCode::Char('q') => {
+            app.quit();
+        }
+        _ => {}
+    }
+}
+
+fn handle_endgame_menu_input(app: &mut App, key: KeyEvent) {
+    match key.code {
+        KeyCode::Up | KeyCode::Char('k') if app.menu_selection > 0 => {
+            app.menu_selection -= 1;
+        }
+        KeyCode::Down | KeyCode::Char('j') if app.menu_selection + 1 < ENDGAMES.len() => {
+            app.menu_selection += 1;
+        }
+        KeyCode::Enter | KeyCode::Char(' ') => {
+            app.start_endgame(ENDGAMES[app.menu_selection]);
+        }
+        KeyCode::Esc | KeyCode::Char('m') => {
+            app.return_to_menu();
+        }
+        KeyCode::Char('q') => {
+            app.quit();
+        }
+        _ => {}
+    }
+}
+
+// Letters go into the FEN, so only the arrow keys move around
+fn handle_fen_menu_input(app: &mut App, key: KeyEvent) {
+    match key.code {
+        KeyCode::Up if app.menu_selection > 0 => app.select_fen_row(app.menu_selection - 1),
+        KeyCode::Down => app.select_fen_row(app.menu_selection + 1),
+        KeyCode::Tab => app.fen_vs_ai = !app.fen_vs_ai,
+        KeyCode::Enter => app.start_fen_menu_game(),
+        KeyCode::Esc => app.return_to_menu(),
+        KeyCode::Backspace => {
+            app.fen_input.pop();
+            app.fen_error = None;
+        }
+        KeyCode::Char('v') if key.modifiers.contains(KeyModifiers::CONTROL) => app.paste_fen_input(),
+        KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+            app.fen_input.push(c);
+            app.fen_error = None;
+        }
+        _ => {}
+    }
+}
+
+fn handle_tournament_input(app: &mut App, key: KeyEvent) {
+    if app.tournament.is_some() {
+        match key.code {
+            KeyCode::Enter | KeyCode::Char(' ') => app.start_tournament_game(),
+            KeyCode::Char('n') => app.end_tournament(),
+            KeyCode::Esc | KeyCode::Char('m') => app.return_to_menu(),
+            KeyCode::Char('q') => app.quit(),
+            _ => {}
+        }
+        return;
+    }
+    // Entering the players: letters go into the name, so only the arrow
+    // keys move around, from the name to the players entered
+    let setup = &mut app.tournament_setup;
+    match key.code {
+        KeyCode::Up if app.menu_selection > 0 => app.menu_selection -= 1,
+        KeyCode::Down if app.menu_selection < setup.players.len() => app.menu_selection += 1,
+        KeyCode::Tab => setup.format = match setup.format {
+            BracketFormat::Knockout => BracketFormat::RoundRobin,
+            BracketFormat::RoundRobin => BracketFormat::Knockout,
+        },
+        KeyCode::Enter if !setup.name.trim().is_empty() => setup.add_player(),
+        KeyCode::Enter => app.start_tournament(),
+        KeyCode::Delete if app.menu_selection > 0 => {
+            setup.remove_player(app.menu_selection - 1);
+            app.menu_selection = app.menu_selection.min(setup.players.len());
+        }
+        KeyCode::Backspace => {
+            setup.name.pop();
+            setup.error = None;
+        }
+        KeyCode::Esc => app.return_to_menu(),
+        KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => setup.type_char(c),
+        _ => {}
+    }
+}
+
+fn handle_profiles_input(app: &mut App, key: KeyEvent) {
+    match key.code {
+        KeyCode::Up | KeyCode::Char('k') if app.menu_selection > 0 => {
+            app.menu_selection -= 1;
+        }
+        // The row after the profiles adds a new one
+        KeyCode::Down | KeyCode::Char('j') if app.menu_selection < app.profiles.profiles.len() => {
+            app.menu_selection += 1;
+        }
+        KeyCode::Enter | KeyCode::Char(' ') => {
+            app.choose_profile();
+        }
+        KeyCode::Char('n') => {
+            app.profile_name = Some(String::new());
+        }
+        KeyCode::Char('d') | KeyCode::Delete => {
+            app.delete_profile();
+        }
+        KeyCode::Char('g') => {
+            app.play_as_guest();
+        }
+        KeyCode::Esc | KeyCode::Char('m') => {
+            app.return_to_menu();
+        }
+        KeyCode::Char('q') => {
+            app.quit();
+        }
+        _ => {}
+    }
+}
+
+fn handle_profile_name_input(app: &mut App, key: KeyEvent) {
+    let Some(name) = app.profile_name.as_mut() else {
+        return;
+    };
+    match key.code {
+        KeyCode::Enter => app.create_profile(),
+        KeyCode::Esc => {
+            app.profile_name = None;
+            app.session_mut().status_message = None;
+        }
+        KeyCode::Backspace => {
+            name.pop();
+        }
+        KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) && name.chars().count() < MAX_NAME_LENGTH => {
+            name.push(c)
+        }
+        _ => {}
+    }
+}
+
+fn handle_stats_input(app: &mut App, key: KeyEvent) {
+    match key.code {
+        KeyCode::Enter | KeyCode::Esc | KeyCode::Char(' ') | KeyCode::Char('m') => app.return_to_menu(),
+        KeyCode::Char('q') => app.quit(),
+        _ => {}
+    }
+}
+
+fn handle_handicap_input(app: &mut App, key: KeyEvent) {
+    match key.code {
+        KeyCode::Up | KeyCode::Char('k') if app.menu_selection > 0 => {
+            app.menu_selection -= 1;
+        }
+        KeyCode::Down | KeyCode::Char('j') if app.menu_selection + 1 < HANDICAP_ROWS => {
+            app.menu_selection += 1;
+        }
+        KeyCode::Left | KeyCode::Char('h') => app.cycle_handicap(-1),
+        KeyCode::Right | KeyCode::Char('l') | KeyCode::Char(' ') => app.cycle_handicap(1),
+        KeyCode::Enter | KeyCode::Esc | KeyCode::Char('m') => {
+            app.close_settings_menu(MenuItem::Handicap);
+        }
+        KeyCode::Char('q') => {
+            app.quit();
+        }
+        _ => {}
+    }
+}
+
+fn handle_settings_input(app: &mut App, key: KeyEvent) {
+    match key.code {
+        KeyCode::Up | KeyCode::Char('k') if app.menu_selection > 0 => {
+            app.menu_selection -= 1;
+        }
+        KeyCode::Down | KeyCode::Char('j') if app.menu_selection + 1 < SETTINGS_ROWS.len() => {
+            app.menu_selection += 1;
+        }
+        KeyCode::Left | KeyCode::Char('h') => app.change_setting(-1),
+        KeyCode::Right | KeyCode::Char('l') | KeyCode::Char(' ') => app.change_setting(1),
+        KeyCode::Enter | KeyCode::Esc | KeyCode::Char('m') => {
+            app.close_settings();
+        }
+        KeyCode::Char('q') => {
+            app.quit();
+        }
+        _ => {}
+    }
+}
+
+fn handle_saved_games_input(app: &mut App, key: KeyEvent) {
+    match key.code {
+        KeyCode::Up | KeyCode::Char('k') if app.menu_selection > 0 => {
+            app.menu_selection -= 1;
+        }
+        KeyCode::Down | KeyCode::Char('j') if app.menu_selection + 1 < app.saved_games.len() => {
+            app.menu_selection += 1;
+        }
+        KeyCode::Enter | KeyCode::Char(' ') => {
+            app.resume_saved_game();
+        }
+        KeyCode::Char('d') | KeyCode::Delete => {
+            app.delete_saved_game();
+        }
+        KeyCode::Esc | KeyCode::Char('m') => {
+            app.return_to_menu();
+        }
+        KeyCode::Char('q') => {
+            app.quit();
+        }
+        _ => {}
+    }
+}
+
+fn handle_archive_input(app: &mut App, key: KeyEvent) {
+    match key.code {
+        KeyCode::Up | KeyCode::Char('k') if app.menu_selection > 0 => {
+            app.menu_selection -= 1;
+        }
+        KeyCode::Down | KeyCode::Char('j') if app.menu_selection + 1 < app.archive_matches().len() => {
+            app.menu_selection += 1;
+        }
+        KeyCode::Enter | KeyCode::Char(' ') => {
+            app.open_archived_game();
+        }
+        KeyCode::Char('/') => {
+            app.archive_searching = true;
+        }
+        // Clears the search first, then leaves
+        KeyCode::Esc if !app.archive_query.is_empty() => {
+            app.archive_query.clear();
+            app.menu_selection = 0;
+        }
+        KeyCode::Esc | KeyCode::Char('m') => {
+            app.return_to_menu();
+        }
+        KeyCode::Char('q') => {
+            app.quit();
+        }
+        _ => {}
+    }
+}
+
+fn handle_archive_search_input(app: &mut App, key: KeyEvent) {
+    match key.code {
+        KeyCode::Enter => app.archive_searching = false,
+        KeyCode::Esc => {
+            app.archive_searching = false;
+            app.archive_query.clear();
+        }
+        KeyCode::Backspace => {
+            app.archive_query.pop();
+        }
+        KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => app.archive_query.push(c),
+        _ => {}
+    }
+    app.menu_selection = 0;
+}
+
+fn handle_online_lobby_input(app: &mut App, key: KeyEvent) {
+    match key.code {
+        KeyCode::Up | KeyCode::Char('k') if app.menu_selection > 0 => {
+            app.menu_selection -= 1;
+        }
+        KeyCode::Down | KeyCode::Char('j') if app.menu_selection + 1 < app.online_lobby_items().len() => {
+            app.menu_selection += 1;
+        }
+        KeyCode::Enter | KeyCode::Char(' ') => {
+            app.select_online_lobby_item();
+        }
+        KeyCode::Char('d') => {
+            app.decline_online_challenge();
+        }
+        KeyCode::Esc | KeyCode::Char('m') => {
+            app.return_to_menu();
+        }
+        KeyCode::Char('q') => {
+            app.quit();
+        }
+        _ => {}
+    }
+}
+
+fn is_restart_key(key: KeyEvent) -> bool {
+    key.code == KeyCode::Char('n') && key.modifiers.contains(KeyModifiers::CONTROL)
+}
+
+fn is_export_key(key: KeyEvent) -> bool {
+    key.code == KeyCode::Char('e') && key.modifiers.contains(KeyModifiers::CONTROL)
+}
+
+fn is_image_key(key: KeyEvent) -> bool {
+    key.code == KeyCode::Char('g') && key.modifiers.contains(KeyModifiers::CONTROL)
+}
+
+fn is_save_key(key: KeyEvent) -> bool {
+    key.code == KeyCode::Char('s') && key.modifiers.contains(KeyModifiers::CONTROL)
+}
+
+fn is_open_key(key: KeyEvent) -> bool {
+    key.code == KeyCode::Char('o') && key.modifiers.contains(KeyModifiers::CONTROL)
+}
+
+fn is_new_tab_key(key: KeyEvent) -> bool {
+    key.code == KeyCode::Char('t') && key.modifiers.contains(KeyModifiers::CONTROL)
+}
+
+fn is_close_tab_key(key: KeyEvent) -> bool {
+    key.code == KeyCode::Char('w') && key.modifiers.contains(KeyModifiers::CONTROL)
+}
+
+/// Ctrl+S and Ctrl+O for files and Ctrl+T and Ctrl+W for tabs, during a
+/// game and after it. Returns whether the key was one of them.
+fn handle_file_keys(app: &mut App, key: KeyEvent) -> bool {
+    if is_save_key(key) {
+        app.open_save_prompt();
+    } else if is_open_key(key) {
+        app.open_file_browser();
+    } else if is_new_tab_key(key) {
+        app.new_tab();
+    } else if is_close_tab_key(key) {
+        app.close_tab();
+    } else {
+        return false;
+    }
+    true
+}
+
+/// Answers the Ctrl+N confirmation prompt. Returns false when no prompt is open.
+fn handle_restart_prompt(app: &mut App, key: KeyEvent) -> bool {
+    if !app.session().restart_pending {
+        return false;
+    }
+    match key.code {
+        KeyCode::Char('y') | KeyCode::Char('Y') | KeyCode::Enter => app.restart_game(),
+        _ => app.cancel_restart(),
+    }
+    true
+}
+
+/// Typing into the comment on the reviewed move
+fn handle_comment_input(app: &mut App, key: KeyEvent) {
+    let Some(text) = app.session_mut().comment_editor.as_mut() else {
+        return;
+    };
+    match key.code {
+        KeyCode::Enter => app.save_comment(),
+        KeyCode::Esc => app.session_mut().comment_editor = None,
+        KeyCode::Backspace => {
+            text.pop();
+        }
+        KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => text.push(c),
+        _ => {}
+    }
+}
+
+fn handle_file_browser_input(app: &mut App, key: KeyEvent) {
+    let Some(browser) = app.file_browser.as_mut() else {
+        return;
+    };
+    match key.code {
+        KeyCode::Up | KeyCode::Char('k') => browser.move_selection(-1),
+        KeyCode::Down | KeyCode::Char('j') => browser.move_selection(1),
+        KeyCode::PageUp => browser.move_selection(-10),
+        KeyCode::PageDown => browser.move_selection(10),
+        KeyCode::Backspace | KeyCode::Left | KeyCode::Char('h') => {
+            if let Err(err) = browser.up() {
+                app.notify(format!("Couldn't open the directory: {}", err));
+            }
+        }
+        KeyCode::Enter | KeyCode::Right | KeyCode::Char('l') => app.choose_file(),
+        KeyCode::Esc | KeyCode::Char('q') => app.file_browser = None,
+        _ => {}
+    }
+}
+
+fn handle_quit_input(app: &mut App, key: KeyEvent) {
+    match key.code {
+        KeyCode::Char('y') | KeyCode::Char('q') => app.quit(),
+        KeyCode::Char('s') => app.save_and_quit(),
+        KeyCode::Char('n') | KeyCode::Esc => app.cancel_quit(),
+        _ => {}
+    }
+}
+
+fn handle_command_line_input(app: &mut App, key: KeyEvent) {
+    let Some(text) = app.command_line.as_mut() else {
+        return;
+    };
+    match key.code {
+        KeyCode::Enter => {
+            let line = text.clone();
+            match commands::run(app, &line) {
+                Ok(message) => {
+                    app.finish_command_line();
+                    if let Some(message) = message {
+                        app.narrate(message.clone());
+                        app.notify(message);
+                    }
+                }
+                // The line stays to be fixed
+                Err(message) => {
+                    app.narrate(message.clone());
+                    app.notify(message);
+                }
+            }
+        }
+        KeyCode::Tab => {
+            let (completed, candidates) = commands::complete(text);
+            *text = completed;
+            app.show_message((!candidates.is_empty()).then(|| candidates.join("  ")));
+        }
+        KeyCode::Esc => app.command_line = None,
+        KeyCode::Backspace => {
+            text.pop();
+        }
+        KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => text.push(c),
+        _ => {}
+    }
+}
+
 fn handle_game_input(app: &mut App, key: KeyEvent) {
+    // Any key ends the hot-seat pause once the next player is ready
+    if app.session().handoff_pending {
+        app.finish_handoff();
+        return;
+    }
+    if handle_restart_prompt(app, key) {
+        return;
+    }
+    if is_restart_key(key) {
+        app.request_restart();
+        return;
+    }
+    if is_export_key(key) {
+        app.export_json();
+        return;
+    }
+    if is_image_key(key) {
+        app.export_svg();
+        return;
+    }
+    if handle_file_keys(app, key) {
+        return;
+    }
+
+    // A just-recorded macro is waiting for a function key to bind it to
+    if app.macros.is_awaiting_binding() {
+        match key.code {
+            KeyCode::F(function_key) => app.macros.bind(function_key),
+            KeyCode::Esc => app.macros.discard_pending(),
+            _ => {}
+        }
+        return;
+    }
+
+    if key.code == KeyCode::Char('r') && key.modifiers.contains(KeyModifiers::CONTROL) {
+        app.macros.toggle_recording();
+        return;
+    }
+
     // Handle promotion menu if active
-    if app.promotion_menu.is_some() {
+    if app.session().promotion_menu.is_some() {
         match key.code {
+            KeyCode::Up | KeyCode::Char('k') if app.session().promotion_selection > 0 => {
+                app.session_mut().promotion_selection -= 1;
+            }
+            KeyCode::Down | KeyCode::Char('j') if app.session().promotion_selection + 1 < PROMOTION_PIECES.len() => {
+                app.session_mut().promotion_selection += 1;
+            }
+            KeyCode::Enter | KeyCode::Char(' ') => {
+                app.execute_promotion(PROMOTION_PIECES[app.session().promotion_selection]);
+            }
             KeyCode::Char('q') | KeyCode::Char('Q') => {
                 app.execute_promotion(PieceType::Queen);
             }
@@ -61,44 +614,216 @@ fn handle_game_input(app: &mut App, key: KeyEvent) {
                 app.execute_promotion(PieceType::Knight);
             }
             KeyCode::Esc => {
-                app.promotion_menu = None;
+                app.session_mut().promotion_menu = None;
             }
             _ => {}
         }
         return;
     }
 
-    match key.code {
-        KeyCode::Up | KeyCode::Char('k') => {
-            app.move_cursor(-1, 0);
-        }
-        KeyCode::Down | KeyCode::Char('j') => {
-            app.move_cursor(1, 0);
-        }
-        KeyCode::Left | KeyCode::Char('h') => {
-            app.move_cursor(0, -1);
-        }
-        KeyCode::Right | KeyCode::Char('l') => {
-            app.move_cursor(0, 1);
+    if let KeyCode::F(function_key) = key.code {
+        if !app.macros.is_recording() {
+            if let Some(actions) = app.macros.get(function_key) {
+                for action in actions {
+                    perform_action(app, action);
+                    let in_game = matches!(
+                        app.session().mode,
+                        GameMode::TwoPlayer | GameMode::VsAI | GameMode::Online | GameMode::Puzzle | GameMode::Tutorial
+                    );
+                    if !in_game {
+                        break;
+                    }
+                }
+            }
         }
-        KeyCode::Enter | KeyCode::Char(' ') => {
-            app.select_square();
+        return;
+    }
+
+    if let Some(action) = app.keymap.action(key) {
+        if action != Action::Quit {
+            app.macros.record(action);
         }
-        KeyCode::Esc => {
-            app.deselect();
+        perform_action(app, action);
+    }
+}
+
+/// In-game commands that keys are bound to. Macros record and replay these
+/// rather than raw keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    MoveCursor(i32, i32),
+    Select,
+    Deselect,
+    ToggleSidePanel,
+    NextGame,
+    FlipBoard,
+    CopyFen,
+    PasteFen,
+    CopyPgn,
+    HistoryBack,
+    HistoryForward,
+    HistoryPageBack,
+    HistoryPageForward,
+    HistoryStart,
+    HistoryLive,
+    NextVariation,
+    CycleNag,
+    EditComment,
+    MarkSquare,
+    DrawArrow,
+    CommandLine,
+    Blindfold,
+    Peek,
+    TakeBack,
+    RetryPuzzle,
+    NextPuzzle,
+    Resign,
+    ClaimDraw,
+    AiDeeper,
+    AiShallower,
+    ReturnToMenu,
+    Quit,
+}
+
+impl Action {
+    /// What the action does, for the help
+    pub fn description(&self) -> &'static str {
+        match self {
+            Action::MoveCursor(-1, 0) => "Move cursor up",
+            Action::MoveCursor(1, 0) => "Move cursor down",
+            Action::MoveCursor(0, -1) => "Move cursor left",
+            Action::MoveCursor(0, 1) => "Move cursor right",
+            Action::MoveCursor(..) => "Move cursor",
+            Action::Select => "Select piece / make move",
+            Action::Deselect => "Deselect piece / hide tooltip",
+            Action::ToggleSidePanel => "Switch moves / position info / events",
+            Action::NextGame => "Next open game (moves / info with just one)",
+            Action::FlipBoard => "Flip the board",
+            Action::CopyFen => "Copy FEN",
+            Action::PasteFen => "Set up a pasted FEN",
+            Action::CopyPgn => "Copy PGN",
+            Action::HistoryBack => "Step back through the moves",
+            Action::HistoryForward => "Step forward through the moves",
+            Action::HistoryPageBack => "Page back through the moves",
+            Action::HistoryPageForward => "Page forward through the moves",
+            Action::HistoryStart => "Review from the starting position",
+            Action::HistoryLive => "Back to the live position",
+            Action::NextVariation => "Switch to the next variation",
+            Action::CycleNag => "Mark the reviewed move !, ?, !!, ??, !? or ?!",
+            Action::EditComment => "Comment on the reviewed move",
+            Action::MarkSquare => "Mark the square (again to change color)",
+            Action::DrawArrow => "Start / finish an arrow at the cursor",
+            Action::CommandLine => "Command line / type a move (Nf3 or g1f3)",
+            Action::Blindfold => "Blindfold: hide the pieces / moves / neither",
+            Action::Peek => "Peek at the pieces while blindfolded",
+            Action::TakeBack => "Take back (kids mode)",
+            Action::RetryPuzzle => "Try the puzzle, lesson or endgame again",
+            Action::NextPuzzle => "Next puzzle or lesson",
+            Action::Resign => "Resign (online)",
+            Action::ClaimDraw => "Claim a draw by repetition or the fifty-move rule",
+            Action::AiDeeper => "Computer searches a ply deeper from its next move",
+            Action::AiShallower => "Computer searches a ply shallower from its next move",
+            Action::ReturnToMenu => "Return to menu",
+            Action::Quit => "Quit",
         }
-        KeyCode::Char('m') => {
-            app.return_to_menu();
+    }
+}
+
+fn perform_action(app: &mut App, action: Action) {
+    match action {
+        Action::MoveCursor(row_offset, col_offset) => app.move_cursor(row_offset, col_offset),
+        Action::Select => app.select_square(),
+        Action::Deselect => app.deselect(),
+        Action::ToggleSidePanel => app.toggle_side_panel_tab(),
+        Action::NextGame => {
+            if !app.next_tab() {
+                app.toggle_side_panel_tab();
+            }
         }
-        KeyCode::Char('q') => {
-            app.quit();
+        Action::FlipBoard => app.toggle_flip(),
+        Action::CopyFen => app.copy_fen(),
+        Action::PasteFen => app.paste_fen(),
+        Action::CopyPgn => app.copy_pgn(),
+        Action::HistoryBack => app.step_history(-1),
+        Action::HistoryForward => app.step_history(1),
+        Action::HistoryPageBack => app.page_history(-1),
+        Action::HistoryPageForward => app.page_history(1),
+        Action::HistoryStart => app.review_start(),
+        Action::HistoryLive => app.return_to_live(),
+        Action::NextVariation => app.next_variation(),
+        Action::CycleNag => app.cycle_nag(),
+        Action::EditComment => app.open_comment_editor(),
+        Action::MarkSquare => app.mark_square(),
+        Action::DrawArrow => app.draw_arrow(),
+        Action::CommandLine => app.open_command_line(),
+        Action::Blindfold => app.cycle_blindfold(),
+        Action::Peek => app.peek(),
+        Action::TakeBack => app.take_back(),
+        Action::RetryPuzzle if app.session().mode == GameMode::Tutorial => app.retry_lesson(),
+        Action::RetryPuzzle if app.session().endgame.is_some() => app.retry_endgame(),
+        Action::RetryPuzzle => app.retry_puzzle(),
+        Action::NextPuzzle if app.session().mode == GameMode::Tutorial => app.next_lesson(),
+        Action::NextPuzzle => app.next_puzzle(),
+        Action::Resign if app.session().mode == GameMode::Online => app.resign_online_game(),
+        Action::Resign => {}
+        Action::ClaimDraw => {
+            if let Err(message) = app.claim_draw() {
+                app.notify(message);
+            }
         }
-        _ => {}
+        Action::AiDeeper => app.step_ai_depth(1),
+        Action::AiShallower => app.step_ai_depth(-1),
+        Action::ReturnToMenu => app.return_to_menu(),
+        Action::Quit => app.quit(),
     }
 }
 
 fn handle_game_over_input(app: &mut App, key: KeyEvent) {
+    if handle_restart_prompt(app, key) {
+        return;
+    }
+    if is_restart_key(key) {
+        app.request_restart();
+        return;
+    }
+    if is_export_key(key) {
+        app.export_json();
+        return;
+    }
+    if is_image_key(key) {
+        app.export_svg();
+        return;
+    }
+    if handle_file_keys(app, key) {
+        return;
+    }
+
     match key.code {
+        KeyCode::Tab if app.next_tab() => {}
+        KeyCode::Tab | KeyCode::Char('i') => {
+            app.toggle_side_panel_tab();
+        }
+        // No cursor here, so the arrows step through the game too
+        KeyCode::Char('[') | KeyCode::Left => app.step_history(-1),
+        KeyCode::Char(']') | KeyCode::Right => app.step_history(1),
+        KeyCode::Home => app.review_start(),
+        KeyCode::End => app.return_to_live(),
+        KeyCode::Char('n') => app.next_variation(),
+        KeyCode::Char('!') => app.cycle_nag(),
+        KeyCode::Char('e') => app.open_comment_editor(),
+        KeyCode::PageUp => app.page_history(-1),
+        KeyCode::PageDown => app.page_history(1),
+        KeyCode::Char('f') => app.toggle_flip(),
+        KeyCode::Char('c') => app.copy_fen(),
+        KeyCode::Char('v') => app.paste_fen(),
+        KeyCode::Char('p') => app.copy_pgn(),
+        KeyCode::Char('u') => {
+            app.take_back();
+        }
+        KeyCode::Char('r') => app.rematch(false),
+        KeyCode::Char('s') => app.rematch(true),
+        KeyCode::Enter | KeyCode::Char(' ') if app.is_archived_game() => app.open_archive(),
+        KeyCode::Enter | KeyCode::Char(' ') if app.is_tournament_game() => app.open_tournament(),
         KeyCode::Enter | KeyCode::Char(' ') | KeyCode::Char('m') => {
             app.return_to_menu();
         }
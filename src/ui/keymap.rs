@@ -0,0 +1,182 @@
+use std::collections::HashMap;
+
+use crossterm::event::{KeyCode, KeyEvent};
+
+use super::input::Action;
+
+// Config names of the rebindable actions
+const ACTION_NAMES: [(&str, Action); 35] = [
+    ("up", Action::MoveCursor(-1, 0)),
+    ("down", Action::MoveCursor(1, 0)),
+    ("left", Action::MoveCursor(0, -1)),
+    ("right", Action::MoveCursor(0, 1)),
+    ("select", Action::Select),
+    ("deselect", Action::Deselect),
+    ("side_panel", Action::ToggleSidePanel),
+    ("next_game", Action::NextGame),
+    ("flip", Action::FlipBoard),
+    ("copy_fen", Action::CopyFen),
+    ("paste_fen", Action::PasteFen),
+    ("copy_pgn", Action::CopyPgn),
+    ("history_back", Action::HistoryBack),
+    ("history_forward", Action::HistoryForward),
+    ("history_page_back", Action::HistoryPageBack),
+    ("history_page_forward", Action::HistoryPageForward),
+    ("history_start", Action::HistoryStart),
+    ("history_live", Action::HistoryLive),
+    ("next_variation", Action::NextVariation),
+    ("annotate", Action::CycleNag),
+    ("comment", Action::EditComment),
+    ("mark_square", Action::MarkSquare),
+    ("arrow", Action::DrawArrow),
+    ("command_line", Action::CommandLine),
+    ("blindfold", Action::Blindfold),
+    ("peek", Action::Peek),
+    ("take_back", Action::TakeBack),
+    ("retry_puzzle", Action::RetryPuzzle),
+    ("next_puzzle", Action::NextPuzzle),
+    ("resign", Action::Resign),
+    ("claim_draw", Action::ClaimDraw),
+    ("ai_deeper", Action::AiDeeper),
+    ("ai_shallower", Action::AiShallower),
+    ("menu", Action::ReturnToMenu),
+    ("quit", Action::Quit),
+];
+
+/// Which key does what during a game
+pub struct Keymap {
+    bindings: HashMap<KeyCode, Action>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let defaults = [
+            (KeyCode::Up, Action::MoveCursor(-1, 0)),
+            (KeyCode::Char('k'), Action::MoveCursor(-1, 0)),
+            (KeyCode::Down, Action::MoveCursor(1, 0)),
+            (KeyCode::Char('j'), Action::MoveCursor(1, 0)),
+            (KeyCode::Left, Action::MoveCursor(0, -1)),
+            (KeyCode::Char('h'), Action::MoveCursor(0, -1)),
+            (KeyCode::Right, Action::MoveCursor(0, 1)),
+            (KeyCode::Char('l'), Action::MoveCursor(0, 1)),
+            (KeyCode::Enter, Action::Select),
+            (KeyCode::Char(' '), Action::Select),
+            (KeyCode::Esc, Action::Deselect),
+            (KeyCode::Tab, Action::NextGame),
+            (KeyCode::Char('i'), Action::ToggleSidePanel),
+            (KeyCode::Char('f'), Action::FlipBoard),
+            (KeyCode::Char('c'), Action::CopyFen),
+            (KeyCode::Char('v'), Action::PasteFen),
+            (KeyCode::Char('p'), Action::CopyPgn),
+            (KeyCode::Char('['), Action::HistoryBack),
+            (KeyCode::Char(']'), Action::HistoryForward),
+            (KeyCode::PageUp, Action::HistoryPageBack),
+            (KeyCode::PageDown, Action::HistoryPageForward),
+            (KeyCode::Home, Action::HistoryStart),
+            (KeyCode::End, Action::HistoryLive),
+            (KeyCode::Char('n'), Action::NextVariation),
+            (KeyCode::Char('!'), Action::CycleNag),
+            (KeyCode::Char('e'), Action::EditComment),
+            (KeyCode::Char('x'), Action::MarkSquare),
+            (KeyCode::Char('a'), Action::DrawArrow),
+            (KeyCode::Char(':'), Action::CommandLine),
+            (KeyCode::Char('/'), Action::CommandLine),
+            (KeyCode::Char('b'), Action::Blindfold),
+            (KeyCode::Char('P'), Action::Peek),
+            (KeyCode::Char('u'), Action::TakeBack),
+            (KeyCode::Char('r'), Action::RetryPuzzle),
+            (KeyCode::Char('N'), Action::NextPuzzle),
+            (KeyCode::Char('R'), Action::Resign),
+            (KeyCode::Char('d'), Action::ClaimDraw),
+            (KeyCode::Char('+'), Action::AiDeeper),
+            (KeyCode::Char('-'), Action::AiShallower),
+            (KeyCode::Char('m'), Action::ReturnToMenu),
+            (KeyCode::Char('q'), Action::Quit),
+        ];
+        Keymap {
+            bindings: defaults.into_iter().collect(),
+        }
+    }
+}
+
+impl Keymap {
+    pub fn action(&self, key: KeyEvent) -> Option<Action> {
+        self.bindings.get(&key.code).copied()
+    }
+
+    /// Every rebindable action that has keys, in a fixed order, with its
+    /// keys: special keys first, then letters
+    pub fn bindings(&self) -> Vec<(Action, Vec<KeyCode>)> {
+        ACTION_NAMES
+            .iter()
+            .filter_map(|&(_, action)| {
+                let mut keys: Vec<KeyCode> = self
+                    .bindings
+                    .iter()
+                    .filter(|&(_, &bound)| bound == action)
+                    .map(|(&key, _)| key)
+                    .collect();
+                keys.sort_by_key(|&key| (matches!(key, KeyCode::Char(_)), key_name(key)));
+                (!keys.is_empty()).then_some((action, keys))
+            })
+            .collect()
+    }
+
+    /// Replaces the keys bound to `action`. Keys move over from whatever
+    /// they did before.
+    pub fn bind(&mut self, action: Action, keys: &[KeyCode]) {
+        self.bindings.retain(|_, bound| *bound != action);
+        for &key in keys {
+            self.bindings.insert(key, action);
+        }
+    }
+}
+
+/// The action with a config name such as "copy_fen"
+pub fn action_named(name: &str) -> Option<Action> {
+    ACTION_NAMES
+        .iter()
+        .find(|(action_name, _)| *action_name == name)
+        .map(|&(_, action)| action)
+}
+
+/// A key from its config name: a single character ("k", "R") or a key
+/// name such as "Up", "Enter" or "Space". Function keys are left to macros.
+pub fn parse_key(text: &str) -> Option<KeyCode> {
+    let mut chars = text.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(KeyCode::Char(c));
+    }
+    let key = match text.to_ascii_lowercase().as_str() {
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "enter" => KeyCode::Enter,
+        "space" => KeyCode::Char(' '),
+        "tab" => KeyCode::Tab,
+        "esc" | "escape" => KeyCode::Esc,
+        "backspace" => KeyCode::Backspace,
+        "delete" => KeyCode::Delete,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        _ => return None,
+    };
+    Some(key)
+}
+
+/// How a key is shown in the help
+pub fn key_name(key: KeyCode) -> String {
+    match key {
+        KeyCode::Char(' ') => "Space".to_string(),
+        KeyCode::Char(c) => c.to_string(),
+        KeyCode::Up => "↑".to_string(),
+        KeyCode::Down => "↓".to_string(),
+        KeyCode::Left => "←".to_string(),
+        KeyCode::Right => "→".to_string(),
+        KeyCode::F(number) => format!("F{}", number),
+        key => format!("{:?}", key),
+    }
+}
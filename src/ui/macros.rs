@@ -0,0 +1,50 @@
+use std::collections::HashMap;
+
+use super::input::Action;
+
+/// Recorded action sequences bound to function keys for the current session
+#[derive(Default)]
+pub struct Macros {
+    recording: Option<Vec<Action>>,
+    // A finished recording waiting for the user to pick a function key
+    pending: Option<Vec<Action>>,
+    bindings: HashMap<u8, Vec<Action>>,
+}
+
+impl Macros {
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    pub fn is_awaiting_binding(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    pub fn toggle_recording(&mut self) {
+        match self.recording.take() {
+            Some(actions) if !actions.is_empty() => self.pending = Some(actions),
+            Some(_) => {}
+            None => self.recording = Some(Vec::new()),
+        }
+    }
+
+    pub fn record(&mut self, action: Action) {
+        if let Some(actions) = self.recording.as_mut() {
+            actions.push(action);
+        }
+    }
+
+    pub fn bind(&mut self, function_key: u8) {
+        if let Some(actions) = self.pending.take() {
+            self.bindings.insert(function_key, actions);
+        }
+    }
+
+    pub fn discard_pending(&mut self) {
+        self.pending = None;
+    }
+
+    pub fn get(&self, function_key: u8) -> Option<Vec<Action>> {
+        self.bindings.get(&function_key).cloned()
+    }
+}
@@ -1,6 +1,14 @@
 pub mod app;
+pub mod bracket;
+pub mod commands;
+pub mod events;
+pub mod files;
 pub mod render;
 pub mod input;
+pub mod keymap;
+pub mod macros;
+pub mod puzzles;
+pub mod tutorial;
 
 pub use app::App;
 pub use render::render;
@@ -0,0 +1,271 @@
+use chess_core::ai::rng::Rng;
+use chess_core::game::puzzle::Puzzle;
+use chess_core::game::{Board, Move};
+use crate::profiles::rating_change;
+use crate::puzzle_db::PuzzleDatabase;
+use crate::saves::{PuzzleProgress, PuzzleReview};
+
+// How far from the player's rating puzzles are looked for at first; the
+// search widens until it finds one not seen before
+const RATING_WINDOW: i64 = 100;
+const MAX_RATING_WINDOW: i64 = 3200;
+// How many puzzles near the rating are read for one not seen before,
+// before the search widens
+const PICK_TRIES: usize = 16;
+// How quickly the rating moves, as in Elo
+const RATING_K: f64 = 32.0;
+// A failed puzzle comes back after this many others, then after twice as
+// many each time it's solved, until it has been solved with this long a gap
+const FIRST_REVIEW: u32 = 3;
+const LEARNED_REVIEW: u32 = 24;
+
+/// Where the puzzle on the board stands
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PuzzleStatus {
+    Solving,
+    Solved,
+    /// A move strayed from the solution
+    Failed,
+}
+
+/// What a move made of the puzzle
+pub enum Verdict {
+    /// On track; the opponent's reply from the solution comes next
+    Reply,
+    Solved,
+    /// Wrong; the solution's move was this
+    Failed(Move),
+}
+
+/// Where the puzzles come from
+pub enum PuzzleSource {
+    Builtin(Vec<Puzzle>),
+    Database(PuzzleDatabase),
+}
+
+impl PuzzleSource {
+    fn len(&self) -> usize {
+        match self {
+            PuzzleSource::Builtin(puzzles) => puzzles.len(),
+            PuzzleSource::Database(database) => database.len(),
+        }
+    }
+
+    fn get(&self, index: usize) -> Option<Puzzle> {
+        match self {
+            PuzzleSource::Builtin(puzzles) => puzzles.get(index).cloned(),
+            PuzzleSource::Database(database) => database.puzzle(index).ok(),
+        }
+    }
+
+    fn rating(&self, index: usize) -> Option<u32> {
+        match self {
+            PuzzleSource::Builtin(puzzles) => puzzles[index].rating,
+            PuzzleSource::Database(database) => database.rating(index),
+        }
+    }
+}
+
+/// The tactics trainer: puzzles picked near the player's rating, which
+/// moves with each one solved or failed, with failed ones coming back
+/// until they're learned. Counts of how many were solved and failed this
+/// session are kept alongside the progress kept between sessions.
+pub struct PuzzleRun {
+    source: PuzzleSource,
+    progress: PuzzleProgress,
+    rng: Rng,
+    puzzle: Puzzle,
+    /// Whether the puzzle on the board is a failed one come back
+    pub reviewing: bool,
+    // The move of the solution due next
+    ply: usize,
+    pub status: PuzzleStatus,
+    pub solved: u32,
+    pub failed: u32,
+    /// How the last puzzle finished moved the rating
+    pub rating_change: Option<i32>,
+    // Whether the puzzle on the board has been counted, so a retry
+    // doesn't count it again
+    counted: bool,
+}
+
+impl PuzzleRun {
+    /// Starts on a review that's due or a puzzle near the player's rating.
+    /// None if there's no puzzle that can be read.
+    pub fn new(source: PuzzleSource, progress: PuzzleProgress) -> Option<PuzzleRun> {
+        let mut rng = Rng::from_time();
+        let (puzzle, reviewing) = choose(&source, &progress, &mut rng, None)?;
+        Some(PuzzleRun {
+            source,
+            progress,
+            rng,
+            puzzle,
+            reviewing,
+            ply: 0,
+            status: PuzzleStatus::Solving,
+            solved: 0,
+            failed: 0,
+            rating_change: None,
+            counted: false,
+        })
+    }
+
+    pub fn puzzle(&self) -> &Puzzle {
+        &self.puzzle
+    }
+
+    pub fn progress(&self) -> &PuzzleProgress {
+        &self.progress
+    }
+
+    /// Starts the puzzle on the board over
+    pub fn retry(&mut self) {
+        self.ply = 0;
+        self.status = PuzzleStatus::Solving;
+    }
+
+    /// Moves on to the next puzzle: a failed one whose turn has come round
+    /// again, or else a new one near the player's rating
+    pub fn next(&mut self) {
+        for review in &mut self.progress.reviews {
+            review.due = review.due.saturating_sub(1);
+        }
+        if let Some((puzzle, reviewing)) = choose(&self.source, &self.progress, &mut self.rng, Some(&self.puzzle.id)) {
+            self.puzzle = puzzle;
+            self.reviewing = reviewing;
+        }
+        self.counted = false;
+        self.rating_change = None;
+        self.retry();
+    }
+
+    /// The opponent's answer to the solver's latest move, from the solution
+    pub fn reply(&self) -> Option<Move> {
+        let due = self.status == PuzzleStatus::Solving && self.ply > 0;
+        due.then(|| self.puzzle.solution[self.ply - 1])
+    }
+
+    /// Checks the solver's move `mv`, played in `board`
+    pub fn check(&mut self, board: &Board, mv: &Move) -> Verdict {
+        let puzzle = &self.puzzle;
+        let expected = puzzle.solution[self.ply];
+        let verdict = if !puzzle.accepts(self.ply, board, mv) {
+            Verdict::Failed(expected)
+        } else if self.ply + 1 < puzzle.solution.len() && expected.to_uci() == mv.to_uci() {
+            Verdict::Reply
+        } else {
+            // The last move, or a mate the solution takes longer to reach
+            Verdict::Solved
+        };
+
+        match verdict {
+            Verdict::Reply => self.ply += 2,
+            Verdict::Solved => self.finish(PuzzleStatus::Solved),
+            Verdict::Failed(_) => self.finish(PuzzleStatus::Failed),
+        }
+        verdict
+    }
+
+    fn finish(&mut self, status: PuzzleStatus) {
+        self.status = status;
+        if self.counted {
+            return;
+        }
+        self.counted = true;
+        let solved = status == PuzzleStatus::Solved;
+        if solved {
+            self.solved += 1;
+            self.progress.solved += 1;
+        } else {
+            self.failed += 1;
+            self.progress.failed += 1;
+        }
+
+        let id = &self.puzzle.id;
+        if self.reviewing {
+            // Only the first try at a puzzle is rated
+            let Some(index) = self.progress.reviews.iter().position(|review| &review.puzzle.id == id) else {
+                return;
+            };
+            let review = &mut self.progress.reviews[index];
+            review.interval = if solved { review.interval * 2 } else { FIRST_REVIEW };
+            review.due = review.interval;
+            if review.interval >= LEARNED_REVIEW {
+                self.progress.reviews.remove(index);
+                self.progress.done.insert(id.clone());
+            }
+            return;
+        }
+
+        if let Some(rating) = self.puzzle.rating {
+            let score = if solved { 1.0 } else { 0.0 };
+            let change = rating_change(self.progress.rating, rating as i32, score, RATING_K);
+            self.progress.rating += change;
+            self.rating_change = Some(change);
+        }
+        if solved {
+            self.progress.done.insert(id.clone());
+        } else {
+            self.progress.reviews.push(PuzzleReview {
+                puzzle: self.puzzle.clone(),
+                interval: FIRST_REVIEW,
+                due: FIRST_REVIEW,
+            });
+        }
+    }
+}
+
+/// A review that's due, or a new puzzle near the player's rating, other
+/// than `current`. The bool is whether it's a review.
+fn choose(
+    source: &PuzzleSource,
+    progress: &PuzzleProgress,
+    rng: &mut Rng,
+    current: Option<&str>,
+) -> Option<(Puzzle, bool)> {
+    let review = progress
+        .reviews
+        .iter()
+        .find(|review| review.due == 0 && Some(review.puzzle.id.as_str()) != current);
+    match review {
+        Some(review) => Some((review.puzzle.clone(), true)),
+        None => pick(source, progress, rng, current).map(|puzzle| (puzzle, false)),
+    }
+}
+
+/// A puzzle not seen before, as near the player's rating as can be found
+fn pick(source: &PuzzleSource, progress: &PuzzleProgress, rng: &mut Rng, current: Option<&str>) -> Option<Puzzle> {
+    let count = source.len();
+    if count == 0 {
+        return None;
+    }
+    let target = i64::from(progress.rating);
+    let unseen = |puzzle: &Puzzle| {
+        Some(puzzle.id.as_str()) != current
+            && !progress.done.contains(&puzzle.id)
+            && !progress.reviews.iter().any(|review| review.puzzle.id == puzzle.id)
+    };
+
+    let mut window = RATING_WINDOW;
+    while window <= MAX_RATING_WINDOW {
+        let mut near: Vec<usize> = (0..count)
+            .filter(|&index| source.rating(index).is_none_or(|rating| (i64::from(rating) - target).abs() <= window))
+            .collect();
+        for _ in 0..PICK_TRIES {
+            if near.is_empty() {
+                break;
+            }
+            let index = near.swap_remove((rng.next_u64() % near.len() as u64) as usize);
+            if let Some(puzzle) = source.get(index).filter(|puzzle| unseen(puzzle)) {
+                return Some(puzzle);
+            }
+        }
+        window *= 2;
+    }
+
+    // Everything has been seen: any other puzzle will do
+    let start = (rng.next_u64() % count as u64) as usize;
+    (0..count)
+        .filter_map(|step| source.get((start + step) % count))
+        .find(|puzzle| Some(puzzle.id.as_str()) != current)
+}
@@ -3,22 +3,828 @@ use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span, Text},
-    widgets::{Block, Borders, List, ListItem, Paragraph, Widget},
+    widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Tabs, Wrap},
     Frame,
 };
 
-use super::app::{App, GameMode, GameOverReason};
-use crate::game::{Color as PieceColor, MoveGenerator, Position};
+use super::commands::COMMANDS;
+use super::events::{self, EventKind};
+use super::bracket::{Bracket, BracketFormat};
+use super::files::FileBrowser;
+use super::input::Action;
+use super::keymap;
+use super::puzzles::{PuzzleRun, PuzzleStatus};
+use super::tutorial::{Goal, LessonStatus, Tutorial};
+use super::app::{
+    App, Blindfold, GameMode, HistoryRow, MenuItem, SearchInfo, SettingsRow, SidePanelTab, MENU_ITEMS,
+    PROMOTION_PIECES, SETTINGS_ROWS,
+};
+use chess_core::game::board::FIFTY_MOVE_LIMIT;
+use chess_core::game::clock::LOW_TIME_MS;
+use chess_core::game::draw::DrawClaim;
+use chess_core::game::endgame::{Endgame, EndgameGoal, ENDGAMES};
+use chess_core::game::pieces::Piece;
+use chess_core::game::pgn::{nag_symbol, MOVE_NAGS};
+use chess_core::game::{Color as PieceColor, GameOverReason, MoveGenerator, PieceType};
+use crate::analysis::format_score;
+use crate::config;
+use crate::painter::{piece_letter, BoardPainter};
+use crate::saves::{self, SavedColor, SavedMode};
+
+pub fn render(app: &App, frame: &mut Frame) {
+    let session = app.session();
+    match session.mode {
+        GameMode::Menu => render_menu(app, frame),
+        GameMode::TimeControlMenu => render_time_control_menu(app, frame),
+        GameMode::HandicapMenu => render_handicap_menu(app, frame),
+        GameMode::EndgameMenu => render_endgame_menu(app, frame),
+        GameMode::FenMenu => render_fen_menu(app, frame),
+        GameMode::TournamentMenu if app.tournament.is_some() => render_bracket(app, frame),
+        GameMode::TournamentMenu => render_tournament_setup(app, frame),
+        GameMode::SettingsMenu => render_settings_menu(app, frame),
+        GameMode::TwoPlayer if session.handoff_pending => render_handoff(app, frame),
+        GameMode::TwoPlayer | GameMode::VsAI | GameMode::Online | GameMode::Puzzle | GameMode::Tutorial => {
+            render_game(app, frame)
+        }
+        GameMode::SavedGames => render_saved_games(app, frame),
+        GameMode::Archive => render_archive(app, frame),
+        GameMode::Profiles => render_profiles(app, frame),
+        GameMode::Stats => render_stats(app, frame),
+        GameMode::OnlineLobby => render_online_lobby(app, frame),
+        GameMode::GameOver => render_game_over(app, frame),
+    }
+    if let Some(text) = &session.comment_editor {
+        render_comment_editor(app, frame, text);
+    }
+    if let Some(text) = &app.command_line {
+        render_command_line(app, frame, text);
+    }
+    if let Some(browser) = &app.file_browser {
+        render_file_browser(app, frame, browser);
+    }
+    if app.quit_pending {
+        render_quit_confirm(app, frame);
+    }
+    if app.show_help {
+        render_help(app, frame);
+    }
+    if app.ascii {
+        asciify(frame.buffer_mut());
+    }
+}
+
+/// Swaps the box drawing and symbols left in a finished frame for plain
+/// ASCII, for terminals without the glyphs
+fn asciify(buf: &mut Buffer) {
+    for cell in buf.content.iter_mut() {
+        // Blank halves of wide characters become real cells once those are narrow
+        let ch = cell.symbol().chars().next().unwrap_or(' ');
+        if !ch.is_ascii() || cell.symbol().is_empty() {
+            cell.set_char(ascii_fallback(ch));
+        }
+    }
+}
+
+fn ascii_fallback(ch: char) -> char {
+    match ch {
+        '─' | '━' | '═' => '-',
+        '│' | '┃' | '║' => '|',
+        '┌' | '┐' | '└' | '┘' | '├' | '┤' | '┬' | '┴' | '┼' | '╭' | '╮' | '╯' | '╰' => '+',
+        '♔' | '♚' => 'K',
+        '♕' | '♛' => 'Q',
+        '♖' | '♜' => 'R',
+        '♗' | '♝' => 'B',
+        '♘' | '♞' => 'N',
+        '♙' | '♟' => 'P',
+        '▶' | '→' => '>',
+        '◀' | '←' => '<',
+        '↑' => '^',
+        '↓' => 'v',
+        '●' | '•' | '★' | '✦' | '◆' => '*',
+        '…' => '.',
+        ch if ch.is_ascii() => ch,
+        _ => '?',
+    }
+}
+
+fn render_menu(app: &App, frame: &mut Frame) {
+    let session = app.session();
+    let area = frame.area();
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(2)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Min(10),
+            Constraint::Length(3),
+        ])
+        .split(area);
+
+    // Title
+    let title = Paragraph::new("♔ CHESS ♔")
+        .style(Style::default().fg(app.theme.accent).add_modifier(Modifier::BOLD))
+        .alignment(Alignment::Center)
+        .block(Block::default().borders(Borders::ALL).border_style(Style::default().fg(Color::White)));
+
+    frame.render_widget(title, chunks[0]);
+
+    // Menu options
+    let items: Vec<ListItem> = MENU_ITEMS
+        .iter()
+        .enumerate()
+        .map(|(i, item)| {
+            let style = if i == app.menu_selection {
+                Style::default().fg(Color::Black).bg(app.theme.accent).add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(Color::White)
+            };
+            let label = match item {
+                MenuItem::TimeControl => match session.time_control {
+                    Some(control) => format!("{}: {} {}", item.label(), control.category(), control),
+                    None => format!("{}: Untimed", item.label()),
+                },
+                MenuItem::Handicap => format!("{}: {}", item.label(), session.handicap.label()),
+                MenuItem::CustomBot => match &app.custom_bot {
+                    Some(bot) => format!("{}: {}", item.label(), bot.name),
+                    None => format!("{}: none loaded", item.label()),
+                },
+                MenuItem::HotSeat => {
+                    format!("{}: {}", item.label(), if app.hot_seat { "On" } else { "Off" })
+                }
+                MenuItem::Tournament => match &app.tournament {
+                    Some(bracket) if bracket.next_game().is_some() => {
+                        format!("{}: round {}", item.label(), bracket.round())
+                    }
+                    Some(_) => format!("{}: finished", item.label()),
+                    None => item.label().to_string(),
+                },
+                MenuItem::Profiles => match app.profiles.active() {
+                    Some(profile) => format!("{}: {} ({})", item.label(), profile.name, profile.rating),
+                    None => format!("{}: Guest", item.label()),
+                },
+                _ => item.label().to_string(),
+            };
+            ListItem::new(label).style(style)
+        })
+        .collect();
+
+    let menu = List::new(items)
+        .block(Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::White))
+            .title("Select Mode"));
+
+    frame.render_widget(menu, chunks[1]);
+
+    // Instructions, or why the settings weren't saved
+    let (text, color) = match &session.status_message {
+        Some(message) => (message.as_str(), Color::Red),
+        None if app.tab_count() > 1 => {
+            ("↑/↓: Navigate | Enter: Select | Tab: Open games | ?: Help | Q: Quit", app.theme.accent)
+        }
+        None => ("↑/↓: Navigate | Enter: Select | ?: Help | Q: Quit", app.theme.accent),
+    };
+    let instructions = Paragraph::new(text)
+        .style(Style::default().fg(color))
+        .alignment(Alignment::Center)
+        .block(Block::default().borders(Borders::ALL).border_style(Style::default().fg(Color::White)));
+
+    frame.render_widget(instructions, chunks[2]);
+}
+
+fn render_time_control_menu(app: &App, frame: &mut Frame) {
+    let area = frame.area();
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(2)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Min(10),
+            Constraint::Length(3),
+        ])
+        .split(area);
+
+    let title = Paragraph::new("Time Control")
+        .style(Style::default().fg(app.theme.accent).add_modifier(Modifier::BOLD))
+        .alignment(Alignment::Center)
+        .block(Block::default().borders(Borders::ALL).border_style(Style::default().fg(Color::White)));
+
+    frame.render_widget(title, chunks[0]);
+
+    let items: Vec<ListItem> = app
+        .time_control_items()
+        .into_iter()
+        .enumerate()
+        .map(|(i, label)| {
+            let style = if i == app.menu_selection {
+                Style::default().fg(Color::Black).bg(app.theme.accent).add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(Color::White)
+            };
+            ListItem::new(label).style(style)
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::White))
+            .title("Minutes + increment seconds, for new local games"));
+
+    frame.render_widget(list, chunks[1]);
+
+    let instructions = Paragraph::new("↑/↓: Navigate | Custom: ←/→ minutes, -/+ seconds, D: delay mode | Enter: Select | Esc: Back")
+        .style(Style::default().fg(app.theme.accent))
+        .alignment(Alignment::Center)
+        .block(Block::default().borders(Borders::ALL).border_style(Style::default().fg(Color::White)));
+
+    frame.render_widget(instructions, chunks[2]);
+}
+
+fn render_handicap_menu(app: &App, frame: &mut Frame) {
+    let session = app.session();
+    let area = frame.area();
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(2)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Min(10),
+            Constraint::Length(3),
+        ])
+        .split(area);
+
+    let title = Paragraph::new("Handicap")
+        .style(Style::default().fg(app.theme.accent).add_modifier(Modifier::BOLD))
+        .alignment(Alignment::Center)
+        .block(Block::default().borders(Borders::ALL).border_style(Style::default().fg(Color::White)));
+
+    frame.render_widget(title, chunks[0]);
+
+    let rows = [
+        format!("Computer plays without: ◀ {} ▶", session.handicap.material.label()),
+        format!("Your time: ◀ {}× ▶", session.handicap.time_factor),
+    ];
+    let items: Vec<ListItem> = rows
+        .into_iter()
+        .enumerate()
+        .map(|(i, label)| {
+            let style = if i == app.menu_selection {
+                Style::default().fg(Color::Black).bg(app.theme.accent).add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(Color::White)
+            };
+            ListItem::new(label).style(style)
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::White))
+            .title("Odds for Play vs AI (time odds need a time control)"));
+
+    frame.render_widget(list, chunks[1]);
+
+    let instructions = Paragraph::new("↑/↓: Navigate | ←/→: Change | Enter/Esc: Done")
+        .style(Style::default().fg(app.theme.accent))
+        .alignment(Alignment::Center)
+        .block(Block::default().borders(Borders::ALL).border_style(Style::default().fg(Color::White)));
+
+    frame.render_widget(instructions, chunks[2]);
+}
+
+fn render_endgame_menu(app: &App, frame: &mut Frame) {
+    let area = frame.area();
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(2)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Min(10),
+            Constraint::Length(3),
+        ])
+        .split(area);
+
+    let title = Paragraph::new("Endgame Practice")
+        .style(Style::default().fg(app.theme.accent).add_modifier(Modifier::BOLD))
+        .alignment(Alignment::Center)
+        .block(Block::default().borders(Borders::ALL).border_style(Style::default().fg(Color::White)));
+
+    frame.render_widget(title, chunks[0]);
+
+    let items: Vec<ListItem> = ENDGAMES
+        .iter()
+        .enumerate()
+        .map(|(i, endgame)| {
+            let style = if i == app.menu_selection {
+                Style::default().fg(Color::Black).bg(app.theme.accent).add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(Color::White)
+            };
+            ListItem::new(format!("{} ({})", endgame.name, endgame_task(endgame))).style(style)
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::White))
+            .title("Against the computer at full strength"));
+
+    frame.render_widget(list, chunks[1]);
+
+    let instructions = Paragraph::new("↑/↓: Navigate | Enter: Play | Esc: Back")
+        .style(Style::default().fg(app.theme.accent))
+        .alignment(Alignment::Center)
+        .block(Block::default().borders(Borders::ALL).border_style(Style::default().fg(Color::White)));
+
+    frame.render_widget(instructions, chunks[2]);
+}
+
+fn render_fen_menu(app: &App, frame: &mut Frame) {
+    let area = frame.area();
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(2)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Min(6),
+            Constraint::Length(3),
+            Constraint::Length(3),
+        ])
+        .split(area);
+
+    let title = Paragraph::new("Start from FEN")
+        .style(Style::default().fg(app.theme.accent).add_modifier(Modifier::BOLD))
+        .alignment(Alignment::Center)
+        .block(Block::default().borders(Borders::ALL).border_style(Style::default().fg(Color::White)));
+
+    frame.render_widget(title, chunks[0]);
+
+    let highlight = |i: usize| {
+        if i == app.menu_selection {
+            Style::default().fg(Color::Black).bg(app.theme.accent).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(Color::White)
+        }
+    };
+    let cursor = if app.menu_selection == 0 { "_" } else { "" };
+    let mut items = vec![ListItem::new(format!("FEN: {}{}", app.fen_input, cursor)).style(highlight(0))];
+    for (i, fen) in app.recent_positions.iter().enumerate() {
+        items.push(ListItem::new(format!("  {}", fen)).style(highlight(i + 1)));
+    }
+
+    let list = List::new(items)
+        .block(Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::White))
+            .title("Type or paste a FEN, or pick a recent position"));
+
+    frame.render_widget(list, chunks[1]);
+
+    let (text, color) = match &app.fen_error {
+        Some(error) => (error.clone(), Color::Red),
+        None if app.fen_vs_ai => ("Opponent: the computer, playing the side not to move".to_string(), Color::White),
+        None => ("Opponent: none, both sides played here".to_string(), Color::White),
+    };
+    let status = Paragraph::new(text)
+        .style(Style::default().fg(color))
+        .alignment(Alignment::Center)
+        .block(Block::default().borders(Borders::ALL).border_style(Style::default().fg(Color::White)));
+
+    frame.render_widget(status, chunks[2]);
+
+    let instructions = Paragraph::new("↑/↓: Recent positions | Tab: Opponent | Ctrl+V: Paste | Enter: Start | Esc: Back")
+        .style(Style::default().fg(app.theme.accent))
+        .alignment(Alignment::Center)
+        .block(Block::default().borders(Borders::ALL).border_style(Style::default().fg(Color::White)));
+
+    frame.render_widget(instructions, chunks[3]);
+}
+
+fn render_tournament_setup(app: &App, frame: &mut Frame) {
+    let area = frame.area();
+    let setup = &app.tournament_setup;
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(2)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Min(6),
+            Constraint::Length(3),
+            Constraint::Length(3),
+        ])
+        .split(area);
+
+    let title = Paragraph::new("Tournament")
+        .style(Style::default().fg(app.theme.accent).add_modifier(Modifier::BOLD))
+        .alignment(Alignment::Center)
+        .block(Block::default().borders(Borders::ALL).border_style(Style::default().fg(Color::White)));
+
+    frame.render_widget(title, chunks[0]);
+
+    let highlight = |i: usize| {
+        if i == app.menu_selection {
+            Style::default().fg(Color::Black).bg(app.theme.accent).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(Color::White)
+        }
+    };
+    let cursor = if app.menu_selection == 0 { "_" } else { "" };
+    let mut items = vec![ListItem::new(format!("Name: {}{}", setup.name, cursor)).style(highlight(0))];
+    for (i, player) in setup.players.iter().enumerate() {
+        items.push(ListItem::new(format!("  {}. {}", i + 1, player)).style(highlight(i + 1)));
+    }
+
+    let list = List::new(items)
+        .block(Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::White))
+            .title("Players, taking turns at this keyboard"));
+
+    frame.render_widget(list, chunks[1]);
+
+    let (text, color) = match &setup.error {
+        Some(error) => (error.clone(), Color::Red),
+        None => (format!("Format: {}", setup.format.label()), Color::White),
+    };
+    let status = Paragraph::new(text)
+        .style(Style::default().fg(color))
+        .alignment(Alignment::Center)
+        .block(Block::default().borders(Borders::ALL).border_style(Style::default().fg(Color::White)));
+
+    frame.render_widget(status, chunks[2]);
+
+    let instructions = Paragraph::new("Enter: Add player / Start | Delete: Remove | Tab: Format | Esc: Back")
+        .style(Style::default().fg(app.theme.accent))
+        .alignment(Alignment::Center)
+        .block(Block::default().borders(Borders::ALL).border_style(Style::default().fg(Color::White)));
+
+    frame.render_widget(instructions, chunks[3]);
+}
+
+fn render_bracket(app: &App, frame: &mut Frame) {
+    let Some(bracket) = &app.tournament else {
+        return;
+    };
+    let area = frame.area();
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(2)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Min(8),
+            Constraint::Length(3),
+            Constraint::Length(3),
+        ])
+        .split(area);
+
+    let title = Paragraph::new(format!("Tournament: {}, round {}", bracket.format.label(), bracket.round()))
+        .style(Style::default().fg(app.theme.accent).add_modifier(Modifier::BOLD))
+        .alignment(Alignment::Center)
+        .block(Block::default().borders(Borders::ALL).border_style(Style::default().fg(Color::White)));
+
+    frame.render_widget(title, chunks[0]);
+
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(chunks[1]);
+
+    let name_width = bracket.players.iter().map(|name| name.chars().count()).max().unwrap_or(0).max("Player".len());
+    let mut standings = vec![Line::from(Span::styled(
+        format!("{:>3}  {:<name_width$} {:>6} {:>5} {:>3} {:>3} {:>3}", "", "Player", "Points", "Games", "W", "D", "L"),
+        Style::default().fg(app.theme.accent).add_modifier(Modifier::BOLD),
+    ))];
+    for (rank, standing) in bracket.standings().iter().enumerate() {
+        let line = format!(
+            "{:>3}  {:<name_width$} {:>6} {:>5} {:>3} {:>3} {:>3}",
+            rank + 1,
+            bracket.players[standing.player],
+            standing.points(),
+            standing.games(),
+            standing.wins,
+            standing.draws,
+            standing.losses,
+        );
+        let style = if standing.eliminated { Style::default().fg(Color::DarkGray) } else { Style::default() };
+        standings.push(Line::from(Span::styled(line, style)));
+    }
+
+    let standings = Paragraph::new(standings)
+        .style(Style::default().fg(Color::White))
+        .block(Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::White))
+            .title("Standings"));
+
+    frame.render_widget(standings, columns[0]);
+
+    let next = bracket.next_game();
+    let mut games = Vec::new();
+    for round in 1..=bracket.round() {
+        games.push(Line::from(Span::styled(
+            format!("Round {}", round),
+            Style::default().fg(app.theme.accent).add_modifier(Modifier::BOLD),
+        )));
+        for (index, pairing) in bracket.pairings.iter().enumerate().filter(|(_, pairing)| pairing.round == round) {
+            let result = pairing.result.unwrap_or(if next == Some(index) { "next" } else { "" });
+            let line = format!(
+                "  {} - {}  {}",
+                bracket.players[pairing.white],
+                bracket.players[pairing.black],
+                result
+            );
+            let style = if next == Some(index) {
+                Style::default().fg(app.theme.accent).add_modifier(Modifier::BOLD)
+            } else {
+                Style::default()
+            };
+            games.push(Line::from(Span::styled(line, style)));
+        }
+        for &(_, player) in bracket.byes.iter().filter(|&&(bye_round, _)| bye_round == round) {
+            games.push(Line::from(format!("  {} sits this round out", bracket.players[player])));
+        }
+    }
+    // Keeps the round being played in view as the list grows
+    let scroll = games.len().saturating_sub(columns[1].height.saturating_sub(2) as usize);
+
+    let games = Paragraph::new(games)
+        .style(Style::default().fg(Color::White))
+        .scroll((scroll as u16, 0))
+        .block(Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::White))
+            .title("Games"));
+
+    frame.render_widget(games, columns[1]);
+
+    let status = Paragraph::new(bracket_status(bracket))
+        .style(Style::default().fg(Color::White))
+        .alignment(Alignment::Center)
+        .block(Block::default().borders(Borders::ALL).border_style(Style::default().fg(Color::White)));
+
+    frame.render_widget(status, chunks[2]);
+
+    let instructions = Paragraph::new("Enter: Play next game | n: New tournament | Esc: Menu")
+        .style(Style::default().fg(app.theme.accent))
+        .alignment(Alignment::Center)
+        .block(Block::default().borders(Borders::ALL).border_style(Style::default().fg(Color::White)));
+
+    frame.render_widget(instructions, chunks[3]);
+}
+
+/// Who plays next, or how the tournament ended
+fn bracket_status(bracket: &Bracket) -> String {
+    match (bracket.next_game(), bracket.winner()) {
+        (Some(next), _) => {
+            let (white, black) = bracket.names(next).unwrap_or_default();
+            let rematch = bracket.format == BracketFormat::Knockout
+                && bracket.pairings[..next].iter().any(|pairing| {
+                    pairing.round == bracket.pairings[next].round
+                        && pairing.white == bracket.pairings[next].black
+                        && pairing.black == bracket.pairings[next].white
+                });
+            let replay = if rematch { ", replaying their draw" } else { "" };
+            format!("Next: {} with White against {}{}", white, black, replay)
+        }
+        (None, Some(winner)) => format!("{} wins the tournament!", bracket.players[winner]),
+        (None, None) => "The tournament ends in a tie for first".to_string(),
+    }
+}
+
+/// What the player has to do, e.g. "win as White"
+fn endgame_task(endgame: &Endgame) -> String {
+    let side = match endgame.player {
+        PieceColor::White => "White",
+        PieceColor::Black => "Black",
+    };
+    match endgame.goal {
+        EndgameGoal::Win => format!("win as {}", side),
+        EndgameGoal::Draw => format!("draw as {}", side),
+    }
+}
+
+fn render_profiles(app: &App, frame: &mut Frame) {
+    let area = frame.area();
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(2)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Min(10),
+            Constraint::Length(3),
+        ])
+        .split(area);
+
+    let title = Paragraph::new("Player Profiles")
+        .style(Style::default().fg(app.theme.accent).add_modifier(Modifier::BOLD))
+        .alignment(Alignment::Center)
+        .block(Block::default().borders(Borders::ALL).border_style(Style::default().fg(Color::White)));
+
+    frame.render_widget(title, chunks[0]);
+
+    let highlight = |i: usize| {
+        if i == app.menu_selection {
+            Style::default().fg(Color::Black).bg(app.theme.accent).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(Color::White)
+        }
+    };
+    let mut items: Vec<ListItem> = app
+        .profiles
+        .profiles
+        .iter()
+        .enumerate()
+        .map(|(i, profile)| {
+            let active = if app.profiles.active == Some(i) { "▶" } else { " " };
+            let label = format!("{} {:<24} {:>5}  {} rated games", active, profile.name, profile.rating, profile.games);
+            ListItem::new(label).style(highlight(i))
+        })
+        .collect();
+    let new_row = match &app.profile_name {
+        Some(name) => format!("  Name: {}_", name),
+        None => "  + New profile".to_string(),
+    };
+    items.push(ListItem::new(new_row).style(highlight(app.profiles.profiles.len())));
+
+    let list = List::new(items)
+        .block(Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::White))
+            .title("Rated against the computer, whose levels have fixed ratings"));
+
+    frame.render_widget(list, chunks[1]);
+
+    let (text, color) = match (&app.session().status_message, &app.profile_name) {
+        (Some(message), _) => (message.as_str(), Color::Red),
+        (None, Some(_)) => ("Type a name | Enter: Create | Esc: Cancel", app.theme.accent),
+        (None, None) => ("↑/↓: Navigate | Enter: Play as | N: New | D: Delete | G: Guest | Esc: Back", app.theme.accent),
+    };
+    let instructions = Paragraph::new(text)
+        .style(Style::default().fg(color))
+        .alignment(Alignment::Center)
+        .block(Block::default().borders(Borders::ALL).border_style(Style::default().fg(Color::White)));
+
+    frame.render_widget(instructions, chunks[2]);
+}
+
+fn render_stats(app: &App, frame: &mut Frame) {
+    let area = frame.area();
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(2)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Min(10),
+            Constraint::Length(3),
+        ])
+        .split(area);
+
+    let title = Paragraph::new("Statistics")
+        .style(Style::default().fg(app.theme.accent).add_modifier(Modifier::BOLD))
+        .alignment(Alignment::Center)
+        .block(Block::default().borders(Borders::ALL).border_style(Style::default().fg(Color::White)));
+
+    frame.render_widget(title, chunks[0]);
+
+    let records = &app.stats.records;
+    let mut lines = Vec::new();
+    if records.is_empty() {
+        lines.push(Line::from("No finished games yet"));
+    } else {
+        lines.push(Line::from(Span::styled(
+            format!(
+                "{:<28} {:>5} {:>5} {:>5} {:>5} {:>6} {:>7} {:>5} {:>9}",
+                "Mode", "Games", "Won", "Lost", "Drawn", "Win %", "Streak", "Best", "Avg moves"
+            ),
+            Style::default().fg(app.theme.accent).add_modifier(Modifier::BOLD),
+        )));
+        for record in records {
+            let mode = match record.ai_depth {
+                Some(depth) => format!(
+                    "{}, {}",
+                    record.mode.label(),
+                    config::ai_level_label(record.ai_selection, depth).to_lowercase()
+                ),
+                None => record.mode.label().to_string(),
+            };
+            let streak = match record.streak {
+                0 => "-".to_string(),
+                won if won > 0 => format!("W{}", won),
+                lost => format!("L{}", -lost),
+            };
+            let win_rate = f64::from(record.wins) * 100.0 / f64::from(record.games().max(1));
+            lines.push(Line::from(format!(
+                "{:<28} {:>5} {:>5} {:>5} {:>5} {:>5.0}% {:>7} {:>5} {:>9.1}",
+                mode,
+                record.games(),
+                record.wins,
+                record.losses,
+                record.draws,
+                win_rate,
+                streak,
+                record.best_streak,
+                record.average_moves(),
+            )));
+        }
+    }
+
+    let table = Paragraph::new(lines)
+        .style(Style::default().fg(Color::White))
+        .block(Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::White))
+            .title("Finished games, from your side"));
+
+    frame.render_widget(table, chunks[1]);
+
+    let instructions = Paragraph::new("Enter/Esc: Back")
+        .style(Style::default().fg(app.theme.accent))
+        .alignment(Alignment::Center)
+        .block(Block::default().borders(Borders::ALL).border_style(Style::default().fg(Color::White)));
+
+    frame.render_widget(instructions, chunks[2]);
+}
+
+fn render_settings_menu(app: &App, frame: &mut Frame) {
+    let area = frame.area();
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(2)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Min(10),
+            Constraint::Length(3),
+        ])
+        .split(area);
 
-pub fn render(app: &App, frame: &mut Frame) {
-    match app.mode {
-        GameMode::Menu => render_menu(app, frame),
-        GameMode::TwoPlayer | GameMode::VsAI => render_game(app, frame),
-        GameMode::GameOver => render_game_over(app, frame),
-    }
+    let title = Paragraph::new("Settings")
+        .style(Style::default().fg(app.theme.accent).add_modifier(Modifier::BOLD))
+        .alignment(Alignment::Center)
+        .block(Block::default().borders(Borders::ALL).border_style(Style::default().fg(Color::White)));
+
+    frame.render_widget(title, chunks[0]);
+
+    let settings = &app.settings;
+    let on_off = |on: bool| if on { "On" } else { "Off" }.to_string();
+    let items: Vec<ListItem> = SETTINGS_ROWS
+        .iter()
+        .enumerate()
+        .map(|(i, row)| {
+            let value = match row {
+                SettingsRow::AiDepth => config::ai_level_label(settings.ai_selection, settings.ai_depth),
+                SettingsRow::AdaptiveAi => on_off(settings.adaptive_ai),
+                SettingsRow::AiDelay if settings.ai_delay_ms == 0 => "None".to_string(),
+                SettingsRow::AiDelay => format!("{:.1} s", settings.ai_delay_ms as f64 / 1000.0),
+                SettingsRow::Palette => settings.palette.label().to_string(),
+                SettingsRow::PieceArt => settings.piece_art.label().to_string(),
+                SettingsRow::AutoQueen => on_off(settings.auto_queen),
+                SettingsRow::Sound => on_off(settings.sound),
+                SettingsRow::AnimateMoves => on_off(settings.animate_moves),
+                SettingsRow::AutoFlip => on_off(settings.auto_flip),
+                SettingsRow::Coordinates => on_off(settings.coordinates),
+                SettingsRow::Blindfold => settings.blindfold.label().to_string(),
+                SettingsRow::ScreenReader => on_off(settings.screen_reader),
+            };
+            let style = if i == app.menu_selection {
+                Style::default().fg(Color::Black).bg(app.theme.accent).add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(Color::White)
+            };
+            ListItem::new(format!("{}: ◀ {} ▶", row.label(), value)).style(style)
+        })
+        .collect();
+
+    let path = crate::config::config_path().map_or(String::new(), |path| path.display().to_string());
+    let list = List::new(items)
+        .block(Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::White))
+            .title(format!("Saved to {}", path)));
+
+    frame.render_widget(list, chunks[1]);
+
+    let instructions = Paragraph::new("↑/↓: Navigate | ←/→: Change | Enter/Esc: Save and return")
+        .style(Style::default().fg(app.theme.accent))
+        .alignment(Alignment::Center)
+        .block(Block::default().borders(Borders::ALL).border_style(Style::default().fg(Color::White)));
+
+    frame.render_widget(instructions, chunks[2]);
 }
 
-fn render_menu(app: &App, frame: &mut Frame) {
+fn render_saved_games(app: &App, frame: &mut Frame) {
     let area = frame.area();
 
     let chunks = Layout::default()
@@ -31,45 +837,219 @@ fn render_menu(app: &App, frame: &mut Frame) {
         ])
         .split(area);
 
-    // Title
-    let title = Paragraph::new("♔ CHESS ♔")
-        .style(Style::default().fg(Color::Green).add_modifier(Modifier::BOLD))
+    let title = Paragraph::new("Games in Progress")
+        .style(Style::default().fg(app.theme.accent).add_modifier(Modifier::BOLD))
         .alignment(Alignment::Center)
         .block(Block::default().borders(Borders::ALL).border_style(Style::default().fg(Color::White)));
 
     frame.render_widget(title, chunks[0]);
 
-    // Menu options
-    let menu_items = vec![
-        "Two Player",
-        "Play vs AI",
-        "Quit",
-    ];
+    let now = saves::now();
+    let items: Vec<ListItem> = if app.saved_games.is_empty() {
+        vec![ListItem::new("No saved games. Games are saved automatically after every move.")
+            .style(Style::default().fg(Color::White))]
+    } else {
+        app.saved_games
+            .iter()
+            .enumerate()
+            .map(|(i, game)| {
+                let mode = match (game.mode, game.ai_color) {
+                    (SavedMode::VsAi, Some(SavedColor::White)) => "vs AI (you play Black)",
+                    (SavedMode::VsAi, _) => "vs AI (you play White)",
+                    (SavedMode::Kids, _) => "Kids Mode",
+                    (SavedMode::TwoPlayer, _) => "Two Player",
+                };
+                let mode = match &game.clock {
+                    Some(clock) => format!("{} {}", mode, clock.control),
+                    None => mode.to_string(),
+                };
+                let to_move = if game.fen.split_whitespace().nth(1) == Some("b") { "Black" } else { "White" };
+                let text = format!(
+                    "{} | {} moves | {} to move | {}",
+                    mode,
+                    game.moves.len(),
+                    to_move,
+                    format_age(now.saturating_sub(game.updated)),
+                );
+                let style = if i == app.menu_selection {
+                    Style::default().fg(Color::Black).bg(app.theme.accent).add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default().fg(Color::White)
+                };
+                ListItem::new(text).style(style)
+            })
+            .collect()
+    };
 
-    let items: Vec<ListItem> = menu_items
-        .iter()
+    let list = List::new(items)
+        .block(Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::White))
+            .title("Resume Game"));
+
+    frame.render_widget(list, chunks[1]);
+
+    let instructions = Paragraph::new("↑/↓: Navigate | Enter: Resume | D: Delete | Esc: Back")
+        .style(Style::default().fg(app.theme.accent))
+        .alignment(Alignment::Center)
+        .block(Block::default().borders(Borders::ALL).border_style(Style::default().fg(Color::White)));
+
+    frame.render_widget(instructions, chunks[2]);
+}
+
+fn render_archive(app: &App, frame: &mut Frame) {
+    let area = frame.area();
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(2)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Length(3),
+            Constraint::Min(10),
+            Constraint::Length(3),
+        ])
+        .split(area);
+
+    let title = Paragraph::new("Game Archive")
+        .style(Style::default().fg(app.theme.accent).add_modifier(Modifier::BOLD))
+        .alignment(Alignment::Center)
+        .block(Block::default().borders(Borders::ALL).border_style(Style::default().fg(Color::White)));
+
+    frame.render_widget(title, chunks[0]);
+
+    let search = if app.archive_searching {
+        format!("{}_", app.archive_query)
+    } else if app.archive_query.is_empty() {
+        "Press / to search by player, date, result or opening".to_string()
+    } else {
+        app.archive_query.clone()
+    };
+    let search_color = if app.archive_searching { Color::Yellow } else { Color::White };
+    let search = Paragraph::new(search)
+        .style(Style::default().fg(search_color))
+        .block(Block::default().borders(Borders::ALL).border_style(Style::default().fg(Color::White)).title("Search"));
+
+    frame.render_widget(search, chunks[1]);
+
+    let matches = app.archive_matches();
+    let items: Vec<ListItem> = if matches.is_empty() {
+        let text = if app.archive.is_empty() {
+            "No finished games yet. Every game is archived when it ends."
+        } else {
+            "No games match the search"
+        };
+        vec![ListItem::new(text).style(Style::default().fg(Color::White))]
+    } else {
+        matches
+            .iter()
+            .enumerate()
+            .map(|(i, game)| {
+                let tag = |name| game.tag(name).unwrap_or("?");
+                let opening = match (game.tag("ECO"), game.tag("Opening")) {
+                    (Some(eco), Some(name)) => format!("{}: {}", eco, name),
+                    _ => String::new(),
+                };
+                let text = format!(
+                    "{} | {} - {} | {:<7} | {} moves | {}",
+                    tag("Date"),
+                    tag("White"),
+                    tag("Black"),
+                    game.result,
+                    game.moves.len().div_ceil(2),
+                    opening,
+                );
+                let style = if i == app.menu_selection {
+                    Style::default().fg(Color::Black).bg(app.theme.accent).add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default().fg(Color::White)
+                };
+                ListItem::new(text).style(style)
+            })
+            .collect()
+    };
+
+    let list = List::new(items)
+        .block(Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::White))
+            .title(format!("{} of {} games", matches.len(), app.archive.len())));
+
+    frame.render_widget(list, chunks[2]);
+
+    let text = if app.archive_searching {
+        "Type to search | Enter: Done | Esc: Clear"
+    } else {
+        "↑/↓: Navigate | Enter: Review | /: Search | Esc: Back"
+    };
+    let instructions = Paragraph::new(text)
+        .style(Style::default().fg(app.theme.accent))
+        .alignment(Alignment::Center)
+        .block(Block::default().borders(Borders::ALL).border_style(Style::default().fg(Color::White)));
+
+    frame.render_widget(instructions, chunks[3]);
+}
+
+fn format_age(seconds: u64) -> String {
+    match seconds {
+        0..=59 => "just now".to_string(),
+        60..=3599 => format!("{} min ago", seconds / 60),
+        3600..=86399 => format!("{} h ago", seconds / 3600),
+        _ => format!("{} days ago", seconds / 86400),
+    }
+}
+
+fn render_online_lobby(app: &App, frame: &mut Frame) {
+    let area = frame.area();
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(2)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Min(10),
+            Constraint::Length(3),
+        ])
+        .split(area);
+
+    let status = match &app.online {
+        Some(session) => session.status.clone(),
+        None => "Set the LICHESS_TOKEN environment variable to a token with the board:play scope".to_string(),
+    };
+    let title = Paragraph::new(status)
+        .style(Style::default().fg(app.theme.accent).add_modifier(Modifier::BOLD))
+        .alignment(Alignment::Center)
+        .block(Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::White))
+            .title("Lichess"));
+
+    frame.render_widget(title, chunks[0]);
+
+    let items: Vec<ListItem> = app
+        .online_lobby_items()
+        .into_iter()
         .enumerate()
         .map(|(i, item)| {
             let style = if i == app.menu_selection {
-                Style::default().fg(Color::Black).bg(Color::Green).add_modifier(Modifier::BOLD)
+                Style::default().fg(Color::Black).bg(app.theme.accent).add_modifier(Modifier::BOLD)
             } else {
                 Style::default().fg(Color::White)
             };
-            ListItem::new(*item).style(style)
+            ListItem::new(item).style(style)
         })
         .collect();
 
-    let menu = List::new(items)
+    let lobby = List::new(items)
         .block(Block::default()
             .borders(Borders::ALL)
             .border_style(Style::default().fg(Color::White))
-            .title("Select Mode"));
+            .title("Online Play"));
 
-    frame.render_widget(menu, chunks[1]);
+    frame.render_widget(lobby, chunks[1]);
 
-    // Instructions
-    let instructions = Paragraph::new("↑/↓: Navigate | Enter: Select | Q: Quit")
-        .style(Style::default().fg(Color::Green))
+    let instructions = Paragraph::new("↑/↓: Navigate | Enter: Select | D: Decline challenge | Esc: Back")
+        .style(Style::default().fg(app.theme.accent))
         .alignment(Alignment::Center)
         .block(Block::default().borders(Borders::ALL).border_style(Style::default().fg(Color::White)));
 
@@ -77,7 +1057,18 @@ fn render_menu(app: &App, frame: &mut Frame) {
 }
 
 fn render_game(app: &App, frame: &mut Frame) {
-    let area = frame.area();
+    let session = app.session();
+    // A row of tabs across the top when several games are open
+    let area = if app.tab_count() > 1 {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(1), Constraint::Min(0)])
+            .split(frame.area());
+        render_game_tabs(app, frame, chunks[0]);
+        chunks[1]
+    } else {
+        frame.area()
+    };
 
     let main_chunks = Layout::default()
         .direction(Direction::Horizontal)
@@ -95,310 +1086,1136 @@ fn render_game(app: &App, frame: &mut Frame) {
     // Render status
     render_status(app, frame, left_chunks[1]);
 
-    // Right panel
+    // The lesson takes the whole right panel
+    if let (GameMode::Tutorial, Some(tutorial)) = (session.mode, &app.tutorial) {
+        render_lesson_panel(app, frame, main_chunks[1], tutorial);
+        if session.promotion_menu.is_some() {
+            render_promotion_menu(app, frame, area);
+        }
+        return;
+    }
+
+    // Right panel, between the clocks in timed games. Black's clock sits
+    // at the top, on Black's side of the board.
+    let clock_height = if session.game.clock().is_some() { 3 } else { 0 };
+    // The computer's search, in games against it
+    let search = session
+        .search_info
+        .as_ref()
+        .filter(|_| matches!(session.mode, GameMode::VsAI | GameMode::GameOver) && !session.kids_mode && !app.settings.screen_reader);
+    let search_height = if search.is_some() { 7 } else { 0 };
     let right_chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
+            Constraint::Length(clock_height),
             Constraint::Length(10),
+            Constraint::Length(search_height),
             Constraint::Min(10),
+            Constraint::Length(clock_height),
         ])
         .split(main_chunks[1]);
 
-    // Render captured pieces
-    render_captured(app, frame, right_chunks[0]);
+    if session.game.clock().is_some() {
+        render_clock(app, frame, right_chunks[0], PieceColor::Black);
+        render_clock(app, frame, right_chunks[4], PieceColor::White);
+    }
+
+    // Render captured pieces, or the points won in kids mode
+    if session.kids_mode {
+        render_kids_score(app, frame, right_chunks[1]);
+    } else {
+        render_captured(app, frame, right_chunks[1]);
+    }
+
+    if let Some(search) = search {
+        render_search_info(app, frame, right_chunks[2], search);
+    }
+
+    // Render move history or position info
+    render_side_panel(app, frame, right_chunks[3]);
+
+    // Render promotion menu if active
+    if session.promotion_menu.is_some() {
+        render_promotion_menu(app, frame, area);
+    }
+}
+
+/// How deep the computer has searched, how fast, and the move and score
+/// it has found so far; its last search's once it has moved
+fn render_search_info(app: &App, frame: &mut Frame, area: Rect, search: &SearchInfo) {
+    let label = Style::default().fg(app.theme.accent);
+    let speed = if search.nodes_per_second >= 1000.0 {
+        format!("{:.0}k nodes/s", search.nodes_per_second / 1000.0)
+    } else {
+        format!("{:.0} nodes/s", search.nodes_per_second)
+    };
+    let (best, score) = match &search.best {
+        Some((san, score)) => (app.language.localize(san), format_score(*score)),
+        None => ("-".to_string(), "-".to_string()),
+    };
+    let lines = vec![
+        Line::from(vec![Span::styled("Depth: ", label), Span::raw(format!("{}/{}", search.depth, search.max_depth))]),
+        Line::from(vec![Span::styled("Nodes: ", label), Span::raw(search.nodes.to_string())]),
+        Line::from(vec![Span::styled("Speed: ", label), Span::raw(speed)]),
+        Line::from(vec![Span::styled("Score: ", label), Span::raw(score)]),
+        Line::from(vec![Span::styled("Best: ", label), Span::raw(best)]),
+    ];
+
+    let title = if app.ai_thinking() { "Search" } else { "Last Search" };
+    let panel = Paragraph::new(lines)
+        .style(Style::default().fg(Color::White))
+        .block(Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::White))
+            .title(title));
+
+    frame.render_widget(panel, area);
+}
+
+fn render_game_tabs(app: &App, frame: &mut Frame, area: Rect) {
+    let (titles, active) = app.tab_titles();
+    let titles: Vec<String> = titles
+        .iter()
+        .enumerate()
+        .map(|(i, title)| format!("{}: {}", i + 1, title))
+        .collect();
+    let tabs = Tabs::new(titles)
+        .select(active)
+        .style(Style::default().fg(Color::White))
+        .highlight_style(Style::default().fg(Color::Black).bg(app.theme.accent).add_modifier(Modifier::BOLD))
+        .divider("|");
+
+    frame.render_widget(tabs, area);
+}
+
+/// The pause between hot-seat turns. The board stays hidden so the next
+/// player doesn't see what the last one was looking at.
+/// The keys for the current screen, as (keys, what they do), under a title
+fn help_entries(app: &App) -> (&'static str, Vec<(String, String)>) {
+    let session = app.session();
+    let entries = |rows: &[(&str, &str)]| -> Vec<(String, String)> {
+        rows.iter().map(|&(keys, text)| (keys.to_string(), text.to_string())).collect()
+    };
+    match session.mode {
+        GameMode::Menu => ("Main Menu", entries(&[
+            ("↑/↓, k/j", "Navigate"),
+            ("Enter, Space", "Select"),
+            ("Tab", "Back to the games open in other tabs"),
+            ("q, Esc", "Quit"),
+        ])),
+        GameMode::TimeControlMenu => ("Time Control", entries(&[
+            ("↑/↓, k/j", "Navigate"),
+            ("Enter", "Use the highlighted time control"),
+            ("←/→", "Custom: change the minutes"),
+            ("-/+", "Custom: change the increment or delay"),
+            ("d", "Custom: switch increment / delay / Bronstein"),
+            ("Esc", "Back to the menu"),
+        ])),
+        GameMode::HandicapMenu => ("Handicap", entries(&[
+            ("↑/↓, k/j", "Navigate"),
+            ("←/→, Space", "Change the setting"),
+            ("Enter, Esc", "Back to the menu"),
+        ])),
+        GameMode::EndgameMenu => ("Endgame Practice", entries(&[
+            ("↑/↓, k/j", "Navigate"),
+            ("Enter, Space", "Play the highlighted endgame"),
+            ("Esc, m", "Back to the menu"),
+        ])),
+        GameMode::FenMenu => ("Start from FEN", entries(&[
+            ("Type, Backspace", "Edit the FEN"),
+            ("Ctrl+V", "Paste a FEN from the clipboard"),
+            ("↑/↓", "Pick a recent position to start from or edit"),
+            ("Tab", "Play both sides, or against the computer"),
+            ("Enter", "Start the game"),
+            ("Esc", "Back to the menu"),
+        ])),
+        GameMode::TournamentMenu if app.tournament.is_some() => ("Tournament", entries(&[
+            ("Enter, Space", "Play the next game"),
+            ("n", "Drop this tournament and enter the players again"),
+            ("Esc, m", "Back to the menu; the tournament carries on"),
+        ])),
+        GameMode::TournamentMenu => ("Tournament", entries(&[
+            ("Type, Backspace", "Edit the player's name"),
+            ("Enter", "Add the player, or start once the name is empty"),
+            ("↑/↓", "Pick a player"),
+            ("Delete", "Remove the highlighted player"),
+            ("Tab", "Single elimination or round robin"),
+            ("Esc", "Back to the menu"),
+        ])),
+        GameMode::SettingsMenu => ("Settings", entries(&[
+            ("↑/↓, k/j", "Navigate"),
+            ("←/→, Space", "Change the setting"),
+            ("Enter, Esc", "Save and return"),
+        ])),
+        GameMode::Profiles => ("Player Profiles", entries(&[
+            ("↑/↓, k/j", "Navigate"),
+            ("Enter, Space", "Play as the highlighted profile"),
+            ("n", "Create a profile"),
+            ("d, Delete", "Delete the highlighted profile"),
+            ("g", "Play as a guest, unrated"),
+            ("Esc, m", "Back to the menu"),
+        ])),
+        GameMode::Stats => ("Statistics", entries(&[
+            ("Enter, Esc, m", "Back to the menu"),
+        ])),
+        GameMode::SavedGames => ("Resume Game", entries(&[
+            ("↑/↓, k/j", "Navigate"),
+            ("Enter, Space", "Resume the highlighted game"),
+            ("d, Delete", "Delete the highlighted game"),
+            ("Esc, m", "Back to the menu"),
+        ])),
+        GameMode::Archive => ("Game Archive", entries(&[
+            ("↑/↓, k/j", "Navigate"),
+            ("Enter, Space", "Review the highlighted game"),
+            ("/", "Search by player, date, result or opening"),
+            ("Esc", "Clear the search, then back to the menu"),
+        ])),
+        GameMode::OnlineLobby => ("Online Lobby", entries(&[
+            ("↑/↓, k/j", "Navigate"),
+            ("Enter, Space", "Seek / accept / resume"),
+            ("d", "Decline the highlighted challenge"),
+            ("Esc, m", "Back to the menu"),
+        ])),
+        GameMode::GameOver => ("Game Over", entries(&[
+            ("Enter, Space, m", "Back to the menu"),
+            ("Enter, Space", "Back to the list (archived games)"),
+            ("Ctrl+N", "New game, same settings"),
+            ("r", "Rematch, same settings (or try the endgame again)"),
+            ("s", "Rematch with colors switched (vs AI)"),
+            ("[ / ] or ← / →", "Step through the moves"),
+            ("PgUp / PgDn", "Page through the moves"),
+            ("Home / End", "Starting / final position"),
+            ("n", "Switch to the next variation"),
+            ("! / e", "Judge / comment on the reviewed move"),
+            ("Tab", "Next open game (moves / info with just one)"),
+            ("i", "Switch moves / position info / events"),
+            ("f", "Flip the board"),
+            ("c / p", "Copy FEN / PGN"),
+            ("v", "Set up a pasted FEN"),
+            ("u", "Take back (kids mode)"),
+            ("Ctrl+E / Ctrl+G", "Export JSON / SVG image"),
+            ("Ctrl+S / Ctrl+O", "Save the game to a file / open one"),
+            ("Ctrl+T / Ctrl+W", "Open another game in a new tab / close this one"),
+            ("q, Esc", "Quit"),
+        ])),
+        _ if session.promotion_menu.is_some() => ("Promotion", entries(&[
+            ("↑/↓", "Choose a piece"),
+            ("Enter", "Promote"),
+            ("q", "Queen"),
+            ("r", "Rook"),
+            ("b", "Bishop"),
+            ("n", "Knight"),
+            ("Esc", "Cancel"),
+        ])),
+        GameMode::TwoPlayer | GameMode::VsAI | GameMode::Online | GameMode::Puzzle | GameMode::Tutorial => {
+            // From the keymap, so keys rebound in the config file show up
+            let mut rows: Vec<(String, String)> = app
+                .keymap
+                .bindings()
+                .into_iter()
+                .map(|(action, keys)| {
+                    let keys: Vec<String> = keys.into_iter().map(keymap::key_name).collect();
+                    (keys.join(", "), action.description().to_string())
+                })
+                .collect();
+            rows.extend(entries(&[
+                ("Ctrl+N", "New game, same settings"),
+                ("Ctrl+E / Ctrl+G", "Export JSON / SVG image"),
+                ("Ctrl+S / Ctrl+O", "Save the game to a file / open one"),
+                ("Ctrl+T / Ctrl+W", "Open another game in a new tab / close this one"),
+                ("Ctrl+R", "Record a macro"),
+                ("F1-F12", "Replay a macro"),
+            ]));
+            rows.extend(COMMANDS.iter().map(|&(name, args, text)| {
+                (format!(":{} {}", name, args).trim_end().to_string(), text.to_string())
+            }));
+            ("Game", rows)
+        }
+    }
+}
+
+/// The ? overlay: the keys for whatever is on screen
+fn render_help(app: &App, frame: &mut Frame) {
+    let (title, entries) = help_entries(app);
+    let key_width = entries.iter().map(|(keys, _)| keys.chars().count()).max().unwrap_or(0);
+    let mut lines: Vec<Line> = entries
+        .iter()
+        .map(|(keys, text)| {
+            Line::from(vec![
+                Span::styled(format!("{:<width$}  ", keys, width = key_width), Style::default().fg(app.theme.accent)),
+                Span::raw(text.as_str()),
+            ])
+        })
+        .collect();
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled("? or any key: close", Style::default().fg(Color::DarkGray))));
+
+    let area = frame.area();
+    let width = (lines.iter().map(|line| line.width()).max().unwrap_or(0) as u16 + 4).min(area.width);
+    let height = (lines.len() as u16 + 2).min(area.height);
+    let popup_area = Rect {
+        x: area.width.saturating_sub(width) / 2,
+        y: area.height.saturating_sub(height) / 2,
+        width,
+        height,
+    };
+    let popup = Paragraph::new(lines)
+        .style(Style::default().fg(Color::White).bg(Color::Black))
+        .block(Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(app.theme.accent).add_modifier(Modifier::BOLD))
+            .title(format!("Help: {}", title)));
+
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(popup, popup_area);
+}
+
+fn render_handoff(app: &App, frame: &mut Frame) {
+    let area = frame.area();
+    let player = match app.session().game.board().current_player {
+        PieceColor::White => "White",
+        PieceColor::Black => "Black",
+    };
+    let text = format!(
+        "{} to move\n\nPass the keyboard to {}.\n\nPress any key when ready.",
+        player, player
+    );
+
+    let popup_area = Rect {
+        x: area.width.saturating_sub(40) / 2,
+        y: area.height.saturating_sub(7) / 2,
+        width: 40.min(area.width),
+        height: 7.min(area.height),
+    };
+    let popup = Paragraph::new(text)
+        .alignment(Alignment::Center)
+        .style(Style::default().fg(Color::White))
+        .block(Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(app.theme.accent).add_modifier(Modifier::BOLD))
+            .title("Next Turn"));
+
+    frame.render_widget(popup, popup_area);
+}
+
+fn render_board(app: &App, frame: &mut Frame, area: Rect) {
+    let session = app.session();
+    let review = app.review_node();
+    let history_rows = app.history_rows();
+    let reviewed_row = review.and_then(|node| history_rows.iter().find(|row| row.node == node));
+    let hidden = app.pieces_hidden();
+    let title = match (review, reviewed_row) {
+        (None, _) if hidden => match (app.settings.blindfold, blindfold_last_move(app)) {
+            (Blindfold::LastMove, Some(last)) => format!("Blindfold (last move: {})", last),
+            _ => "Blindfold".to_string(),
+        },
+        (None, _) => match (session.mode, &session.puzzles, &app.tutorial, &session.endgame) {
+            (GameMode::Tutorial, _, Some(tutorial), _) => {
+                let (number, count) = tutorial.position();
+                format!("Lesson {}/{}: {}", number, count, tutorial.lesson().title)
+            }
+            (GameMode::VsAI, _, _, Some(endgame)) => format!("Endgame: {}", endgame.name),
+            (GameMode::Puzzle, Some(run), _, _) => {
+                let kind = if run.reviewing { "Review" } else { "Puzzle" };
+                match run.puzzle().rating {
+                    Some(rating) => format!("{}: {} ({})", kind, run.puzzle().name, rating),
+                    None => format!("{}: {}", kind, run.puzzle().name),
+                }
+            }
+            _ => "Chess Board".to_string(),
+        },
+        (Some(_), None) => "Reviewing the starting position".to_string(),
+        (Some(_), Some(row)) if row.depth > 0 => format!("Reviewing a variation ({})", move_label(app, row)),
+        (Some(_), Some(row)) => format!("Reviewing move {} ({})", row.number, move_label(app, row)),
+    };
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(if review.is_some() { app.theme.accent } else { Color::White }))
+        .title(title);
+
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    // Calculate board dimensions - make it fill most of the space
+    // Use 90% of available space, ensuring square cells
+    let max_cell_width = inner.width / 8;
+    let max_cell_height = inner.height / 8;
+
+    // Make cells square-ish (terminal chars are taller than wide, so use width * 2)
+    let cell_height = max_cell_height.max(3);
+    let cell_width = (cell_height * 2).min(max_cell_width);
+
+    let board_width = cell_width * 8;
+    let board_height = cell_height * 8;
+
+    let board_area = Rect {
+        x: inner.x + (inner.width.saturating_sub(board_width)) / 2,
+        y: inner.y + (inner.height.saturating_sub(board_height)) / 2,
+        width: board_width,
+        height: board_height,
+    };
+
+    // Moves made in a reviewed position start variations
+    if let Some(node) = review {
+        let board = app.board_at(node);
+        BoardPainter::new(&board)
+            .cursor(session.cursor)
+            .selection(session.selected_piece, &session.legal_moves)
+            .last_move(session.move_tree.mv(node).filter(|_| !hidden))
+            .show_check(!hidden)
+            .marks(app.marks(), app.arrow_start())
+            .flip(session.flipped)
+            .ascii(app.ascii)
+            .art(app.settings.piece_art)
+            .custom_art(app.custom_art.as_ref())
+            .coordinates(app.settings.coordinates)
+            .hide_pieces(hidden)
+            .paint_buffer(board_area, frame.buffer_mut(), cell_width, cell_height, &app.theme);
+        return;
+    }
+
+    BoardPainter::new(session.game.board())
+        .cursor(session.cursor)
+        .selection(session.selected_piece, &session.legal_moves)
+        .last_move(app.last_move().filter(|_| !hidden || app.settings.blindfold == Blindfold::LastMove))
+        .show_check(!hidden)
+        .premove(session.premove)
+        .marks(app.marks(), app.arrow_start())
+        .animation(app.animation())
+        .big_hints(session.kids_mode)
+        .flip(session.flipped)
+        .ascii(app.ascii)
+        .art(app.settings.piece_art)
+        .custom_art(app.custom_art.as_ref())
+        .coordinates(app.settings.coordinates)
+        .hide_pieces(hidden)
+        .paint_buffer(board_area, frame.buffer_mut(), cell_width, cell_height, &app.theme);
+}
+
+/// The last move of the game, e.g. "14... Nc6", as blindfold mode shows it
+fn blindfold_last_move(app: &App) -> Option<String> {
+    let rows = app.history_rows();
+    rows.iter().rev().find(|row| row.depth == 0).map(|row| move_label(app, row))
+}
+
+// Spinner frames, a tenth of a second each
+const SPINNER: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+const ASCII_SPINNER: [char; 4] = ['|', '/', '-', '\\'];
+
+/// A spinner, then how deep the computer's search has got and how long
+/// it has taken, so a long think doesn't look like a hang
+fn thinking_text(app: &App) -> String {
+    // Nothing to time while waiting out the pause before a reply, and a
+    // line that changes ten times a second is noise to a screen reader
+    let Some(elapsed) = app.search_time().filter(|_| !app.settings.screen_reader) else {
+        return "Computer is thinking...".to_string();
+    };
+    let spinner: &[char] = if app.ascii { &ASCII_SPINNER } else { &SPINNER };
+    let frame = spinner[(elapsed.as_millis() / 100) as usize % spinner.len()];
+    match &app.session().search_info {
+        Some(search) => format!(
+            "{} Computer is thinking (depth {}/{}, {:.1} s)",
+            frame,
+            search.depth,
+            search.max_depth,
+            elapsed.as_secs_f64()
+        ),
+        None => format!("{} Computer is thinking ({:.1} s)", frame, elapsed.as_secs_f64()),
+    }
+}
+
+fn render_status(app: &App, frame: &mut Frame, area: Rect) {
+    let session = app.session();
+    let current_player = match session.game.board().current_player {
+        PieceColor::White => "White",
+        PieceColor::Black => "Black",
+    };
+
+    let in_check = MoveGenerator::is_in_check(session.game.board(), session.game.board().current_player);
+    let check_text = if in_check { " (CHECK!)" } else { "" };
+
+    let status_text = format!("Current Player: {}{}", current_player, check_text);
 
-    // Render move history
-    render_move_history(app, frame, right_chunks[1]);
+    let mut lines = vec![
+        Line::from(status_text),
+        Line::from(match app.opening() {
+            Some(opening) => format!("Move: {}  |  {}", session.game.board().fullmove_number, opening),
+            None => format!("Move: {}", session.game.board().fullmove_number),
+        }),
+    ];
+
+    if session.mode == GameMode::VsAI {
+        let ai_player = if session.ai_color == Some(PieceColor::Black) { "Black" } else { "White" };
+        match &session.endgame {
+            Some(endgame) => lines.push(Line::from(format!("AI: {} | Goal: {}", ai_player, endgame_task(endgame)))),
+            None => lines.push(Line::from(format!("AI: {}", ai_player))),
+        }
+    }
+
+    if app.macros.is_recording() {
+        lines.push(Line::from(Span::styled(
+            "● Recording macro (Ctrl+R to stop)",
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        )));
+    } else if app.macros.is_awaiting_binding() {
+        lines.push(Line::from(Span::styled(
+            "Press F1-F12 to bind the macro, Esc to discard",
+            Style::default().fg(app.theme.accent).add_modifier(Modifier::BOLD),
+        )));
+    }
+
+    if let (GameMode::Puzzle, Some(run)) = (session.mode, &session.puzzles) {
+        lines.extend(puzzle_lines(app, run));
+    } else if app.ai_thinking() {
+        lines.push(Line::from(thinking_text(app)));
+    } else if session.kids_mode && session.mode != GameMode::GameOver {
+        lines.push(Line::from("Press u to take back a move"));
+    }
+
+    if let Some(claim) = app.claimable_draw() {
+        let bindings = app.keymap.bindings();
+        let key = bindings
+            .iter()
+            .find(|&&(bound, _)| bound == Action::ClaimDraw)
+            .and_then(|(_, keys)| keys.first())
+            .map_or("?".to_string(), |&key| keymap::key_name(key));
+        lines.push(Line::from(Span::styled(
+            format!("Draw available by {} ({} to claim)", claim.label(), key),
+            Style::default().fg(app.theme.accent).add_modifier(Modifier::BOLD),
+        )));
+    }
+
+    if let Some(message) = &session.status_message {
+        lines.push(Line::from(Span::styled(message.as_str(), Style::default().fg(Color::Yellow))));
+    }
+
+    if session.restart_pending && session.mode != GameMode::GameOver {
+        lines.push(Line::from(Span::styled(
+            "Start a new game with the same settings? (y/n)",
+            Style::default().fg(app.theme.accent).add_modifier(Modifier::BOLD),
+        )));
+    }
 
-    // Render promotion menu if active
-    if app.promotion_menu.is_some() {
-        render_promotion_menu(app, frame, area);
+    if let Some(game) = app.online.as_ref().and_then(|online| online.game.as_ref()) {
+        if session.mode == GameMode::Online || matches!(session.game.result(), Some(GameOverReason::Remote { .. })) {
+            lines.push(Line::from(format!(
+                "{} game vs {} | White {} | Black {}",
+                if game.rated { "Rated" } else { "Casual" },
+                game.opponent_name(),
+                format_clock(game.white_time_ms),
+                format_clock(game.black_time_ms),
+            )));
+        }
     }
-}
 
-fn render_board(app: &App, frame: &mut Frame, area: Rect) {
-    let block = Block::default()
-        .borders(Borders::ALL)
-        .border_style(Style::default().fg(Color::White))
-        .title("Chess Board");
+    let status = Paragraph::new(lines)
+        .style(Style::default().fg(Color::White))
+        .block(Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(app.theme.accent))
+            .title("Status"));
 
-    let inner = block.inner(area);
-    frame.render_widget(block, area);
+    frame.render_widget(status, area);
+}
 
-    // Calculate board dimensions - make it fill most of the space
-    // Use 90% of available space, ensuring square cells
-    let max_cell_width = inner.width / 8;
-    let max_cell_height = inner.height / 8;
+/// The task, what's next once it's done, and the player's rating and tally
+fn puzzle_lines(app: &App, run: &PuzzleRun) -> Vec<Line<'static>> {
+    let puzzle = run.puzzle();
+    let side = match puzzle.solver() {
+        PieceColor::White => "White",
+        PieceColor::Black => "Black",
+    };
+    let moves = puzzle.moves_to_find();
+    let task = match (puzzle.mates, moves) {
+        (true, moves) => format!("{} to move: mate in {}", side, moves),
+        (false, 1) => format!("{} to move: find the best move", side),
+        (false, moves) => format!("{} to move: find the best {} moves", side, moves),
+    };
+    let (text, color) = match run.status {
+        PuzzleStatus::Solving => (task, Color::White),
+        PuzzleStatus::Solved => ("Solved! N: next puzzle".to_string(), Color::Green),
+        PuzzleStatus::Failed => ("Failed. r: try again, N: next puzzle".to_string(), Color::Red),
+    };
+    let progress = run.progress();
+    let rating = match run.rating_change {
+        Some(change) => format!("Puzzle rating {} ({:+})", progress.rating, change),
+        None => format!("Puzzle rating {}", progress.rating),
+    };
+    vec![
+        Line::from(Span::styled(text, Style::default().fg(color).add_modifier(Modifier::BOLD))),
+        Line::from(Span::styled(
+            format!("{} | Solved {}, failed {} this session", rating, run.solved, run.failed),
+            Style::default().fg(app.theme.accent),
+        )),
+    ]
+}
 
-    // Make cells square-ish (terminal chars are taller than wide, so use width * 2)
-    let cell_height = max_cell_height.max(3);
-    let cell_width = (cell_height * 2).min(max_cell_width);
+fn format_clock(millis: u64) -> String {
+    let seconds = millis / 1000;
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
 
-    let board_width = cell_width * 8;
-    let board_height = cell_height * 8;
+// Clocks below this show tenths of a second
+const TENTHS_BELOW_MS: u64 = 10_000;
 
-    let board_area = Rect {
-        x: inner.x + (inner.width.saturating_sub(board_width)) / 2,
-        y: inner.y + (inner.height.saturating_sub(board_height)) / 2,
-        width: board_width,
-        height: board_height,
+/// One side's clock: bold with a marker and a bright border while running
+fn render_clock(app: &App, frame: &mut Frame, area: Rect, color: PieceColor) {
+    let Some(clock) = app.session().game.clock() else {
+        return;
     };
+    let remaining = clock.remaining(color);
+    let running = app.running_clock() == Some(color);
 
-    let mut board_widget = BoardWidget {
-        app,
-        cell_width,
-        cell_height,
+    let mut style = Style::default().fg(if remaining < LOW_TIME_MS { Color::Red } else { Color::White });
+    if running {
+        style = style.add_modifier(Modifier::BOLD);
+    }
+    let marker = if running { "▶ " } else { "  " };
+    let name = match color {
+        PieceColor::White => "White",
+        PieceColor::Black => "Black",
     };
 
-    frame.render_widget(&mut board_widget, board_area);
+    let mut spans = vec![
+        Span::styled(marker, Style::default().fg(app.theme.accent)),
+        Span::styled(format_clock_tenths(remaining), style),
+    ];
+    // A delay clock counts down the delay before the main time
+    if running && clock.delay_remaining() > 0 {
+        let delay = clock.delay_remaining();
+        spans.push(Span::styled(
+            format!("  d{}.{}", delay / 1000, delay % 1000 / 100),
+            Style::default().fg(Color::DarkGray),
+        ));
+    }
+
+    let widget = Paragraph::new(Line::from(spans))
+    .alignment(Alignment::Center)
+    .block(Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(if running { app.theme.accent } else { Color::DarkGray }))
+        .title(format!("{} {}", name, clock.control)));
+
+    frame.render_widget(widget, area);
 }
 
-struct BoardWidget<'a> {
-    app: &'a App,
-    cell_width: u16,
-    cell_height: u16,
+/// Like `format_clock`, but with hours for long games and tenths of a
+/// second when time is short
+fn format_clock_tenths(millis: u64) -> String {
+    let seconds = millis / 1000;
+    if millis < TENTHS_BELOW_MS {
+        format!("0:{:02}.{}", seconds, millis % 1000 / 100)
+    } else if seconds >= 3600 {
+        format!("{}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60)
+    } else {
+        format_clock(millis)
+    }
 }
 
-impl<'a> Widget for &mut BoardWidget<'a> {
-    fn render(self, area: Rect, buf: &mut Buffer) {
-        for row in 0..8 {
-            for col in 0..8 {
-                let pos = Position::new(row, col);
-                let x = area.x + col as u16 * self.cell_width;
-                let y = area.y + row as u16 * self.cell_height;
-                let cell_area = Rect {
-                    x,
-                    y,
-                    width: self.cell_width,
-                    height: self.cell_height,
-                };
+fn render_captured(app: &App, frame: &mut Frame, area: Rect) {
+    let session = app.session();
+    let symbols = |captured: &[PieceType], color: PieceColor| -> String {
+        captured.iter()
+            .map(|&piece_type| {
+                let piece = Piece::new(piece_type, color);
+                if app.ascii { piece_letter(piece) } else { piece.symbol() }
+            })
+            .collect()
+    };
+    let white_captured = symbols(session.game.captured(PieceColor::White), PieceColor::White);
+    let black_captured = symbols(session.game.captured(PieceColor::Black), PieceColor::Black);
 
-                self.render_cell(pos, cell_area, buf);
-            }
-        }
-    }
+    let text = vec![
+        Line::from(vec![
+            Span::styled("White: ", Style::default().fg(Color::White)),
+            Span::styled(&white_captured, Style::default().fg(app.theme.white_piece)),
+        ]),
+        Line::from(vec![
+            Span::styled("Black: ", Style::default().fg(Color::White)),
+            Span::styled(&black_captured, Style::default().fg(app.theme.black_piece)),
+        ]),
+    ];
+
+    let captured = Paragraph::new(text)
+        .block(Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::White))
+            .title("Captured"));
+
+    frame.render_widget(captured, area);
 }
 
-impl<'a> BoardWidget<'a> {
-    fn render_cell(&self, pos: Position, area: Rect, buf: &mut Buffer) {
-        let is_light_square = (pos.row + pos.col) % 2 == 0;
-        let is_cursor = pos == self.app.cursor;
-        let is_selected = Some(pos) == self.app.selected_piece;
-        let is_legal_move = self.app.legal_moves.iter().any(|m| m.to == pos);
+/// Kids mode scoring: only captures count, in the usual 1/3/3/5/9 points
+fn render_kids_score(app: &App, frame: &mut Frame, area: Rect) {
+    let session = app.session();
+    let points = |captured: &[PieceType]| -> i32 { captured.iter().map(|piece| piece.value() / 100).sum() };
+    // The player scores the computer's pieces taken, and the other way round
+    let ai_color = session.ai_color.unwrap_or(PieceColor::Black);
+    let (you, computer) = (points(session.game.captured(ai_color)), points(session.game.captured(ai_color.opposite())));
 
-        // Determine background color
-        let bg_color = if is_cursor {
-            Color::Green
-        } else if is_selected {
-            Color::Rgb(0, 100, 0) // Dark green
-        } else if is_legal_move {
-            Color::Rgb(0, 150, 0) // Medium green for legal moves
-        } else if is_light_square {
-            Color::White
-        } else {
-            Color::Black
-        };
+    let text = vec![
+        Line::from(Span::styled(
+            format!("You: {} {}", you, "★".repeat(you.clamp(0, 10) as usize)),
+            Style::default().fg(Color::Rgb(255, 215, 0)).add_modifier(Modifier::BOLD),
+        )),
+        Line::from(Span::styled(
+            format!("Computer: {}", computer),
+            Style::default().fg(Color::Rgb(0, 180, 255)),
+        )),
+        Line::from(""),
+        Line::from("Capture pieces to score!"),
+        Line::from("Pawn 1, Knight 3, Bishop 3"),
+        Line::from("Rook 5, Queen 9"),
+    ];
 
-        // Determine foreground color
-        let fg_color = if is_light_square && !is_cursor && !is_selected && !is_legal_move {
-            Color::Black
-        } else {
-            Color::White
-        };
+    let score = Paragraph::new(text)
+        .block(Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::White))
+            .title("Score"));
 
-        // Fill cell background
-        for dy in 0..area.height {
-            for dx in 0..area.width {
-                let x = area.x + dx;
-                let y = area.y + dy;
-                if x < buf.area.width && y < buf.area.height {
-                    buf[(x, y)].set_bg(bg_color);
-                }
-            }
-        }
+    frame.render_widget(score, area);
+}
 
-        // Render piece
-        if let Some(piece) = self.app.board.get_piece(pos) {
-            let piece_fg = match piece.color {
-                PieceColor::White => {
-                    if is_cursor || is_selected {
-                        Color::White // Bright white on green for selected
-                    } else {
-                        Color::Rgb(255, 215, 0) // Gold color for white pieces
-                    }
-                }
-                PieceColor::Black => {
-                    if is_cursor || is_selected {
-                        Color::White // Bright white on green for selected
-                    } else {
-                        Color::Rgb(0, 180, 255) // Bright blue for black pieces
-                    }
-                }
-            };
+fn render_side_panel(app: &App, frame: &mut Frame, area: Rect) {
+    if app.settings.screen_reader {
+        render_event_log(app, frame, area, true);
+        return;
+    }
 
-            // Use ASCII art if cell is large enough (height >= 3)
-            if area.height >= 3 {
-                let art = piece.piece_type.ascii_art();
-                let art_height = art.len() as u16;
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(1), Constraint::Min(3)])
+        .split(area);
 
-                // Calculate consistent starting position for the whole piece
-                let max_line_width = art.iter().map(|s| s.len()).max().unwrap_or(0) as u16;
-                let start_x = area.x + (area.width.saturating_sub(max_line_width)) / 2;
-                let start_y = area.y + (area.height.saturating_sub(art_height)) / 2;
+    let selected = match app.side_panel_tab {
+        SidePanelTab::Moves => 0,
+        SidePanelTab::Info => 1,
+        SidePanelTab::Events => 2,
+    };
+    let tabs = Tabs::new(vec!["Moves", "Info", "Events"])
+        .select(selected)
+        .style(Style::default().fg(Color::White))
+        .highlight_style(Style::default().fg(Color::Black).bg(app.theme.accent).add_modifier(Modifier::BOLD))
+        .divider("|");
 
-                for (i, line) in art.iter().enumerate() {
-                    let y = start_y + i as u16;
-                    if y >= buf.area.height || y >= area.y + area.height {
-                        break;
-                    }
+    frame.render_widget(tabs, chunks[0]);
 
-                    // Render the line starting at consistent x position
-                    for (j, ch) in line.chars().enumerate() {
-                        let x = start_x + j as u16;
-                        if x < buf.area.width && x < area.x + area.width && y < buf.area.height {
-                            buf[(x, y)]
-                                .set_char(ch)
-                                .set_fg(piece_fg)
-                                .set_bg(bg_color)
-                                .set_style(Style::default().add_modifier(Modifier::BOLD));
-                        }
-                    }
-                }
-            } else {
-                // Fall back to single character for small cells
-                let symbol = piece.symbol();
-                let center_x = area.x + area.width / 2;
-                let center_y = area.y + area.height / 2;
-
-                if center_x < buf.area.width && center_y < buf.area.height {
-                    buf[(center_x, center_y)]
-                        .set_char(symbol)
-                        .set_fg(piece_fg)
-                        .set_bg(bg_color)
-                        .set_style(Style::default().add_modifier(Modifier::BOLD));
-                }
-            }
-        } else if is_legal_move {
-            // Show dot for legal move squares
-            let center_x = area.x + area.width / 2;
-            let center_y = area.y + area.height / 2;
-
-            if center_x < buf.area.width && center_y < buf.area.height {
-                buf[(center_x, center_y)]
-                    .set_char('●')
-                    .set_fg(Color::White)
-                    .set_bg(bg_color);
-            }
-        }
+    // Every tab gives the position away
+    if app.pieces_hidden() {
+        render_blindfold_panel(app, frame, chunks[1]);
+        return;
+    }
 
-        // Add file/rank labels on edges
-        if pos.row == 7 && area.y + area.height < buf.area.height {
-            let file_label = (b'a' + pos.col as u8) as char;
-            buf[(area.x + area.width / 2, area.y + area.height - 1)]
-                .set_char(file_label)
-                .set_fg(fg_color)
-                .set_bg(bg_color);
-        }
+    match app.side_panel_tab {
+        SidePanelTab::Moves => render_move_history(app, frame, chunks[1]),
+        SidePanelTab::Info => render_position_info(app, frame, chunks[1]),
+        SidePanelTab::Events => render_event_log(app, frame, chunks[1], false),
+    }
+}
 
-        if pos.col == 0 && area.x > 0 {
-            let rank_label = (b'8' - pos.row as u8) as char;
-            buf[(area.x, area.y)]
-                .set_char(rank_label)
-                .set_fg(fg_color)
-                .set_bg(bg_color);
+/// The event log, latest at the bottom. Screen reader mode shows it in
+/// place of the side panel as plain text: no times or engine details, and
+/// the last message at the end.
+fn render_event_log(app: &App, frame: &mut Frame, area: Rect, plain: bool) {
+    let height = area.height.saturating_sub(2) as usize;
+    let width = area.width.saturating_sub(2).max(1) as usize;
+    let mut lines: Vec<(String, &str)> = app
+        .session()
+        .events
+        .events()
+        .filter(|event| !plain || event.kind != EventKind::Search)
+        .map(|event| {
+            let time = if plain { String::new() } else { format!("{} ", events::format_time(event.at)) };
+            (time, event.text.as_str())
+        })
+        .collect();
+    if plain {
+        if lines.is_empty() {
+            lines.push((String::new(), "Type a move after : to play it"));
+        }
+        let last = lines.last().map(|&(_, text)| text);
+        if let Some(message) = app.session().status_message.as_deref().filter(|&message| last != Some(message)) {
+            lines.push((String::new(), message));
         }
     }
+    // As many of the latest lines as fit, once wrapped
+    let mut rows = 0;
+    let skip = lines
+        .iter()
+        .rposition(|(time, text)| {
+            rows += (time.chars().count() + text.chars().count()).div_ceil(width).max(1);
+            rows > height
+        })
+        .map_or(0, |index| index + 1);
+    let text: Vec<Line> = lines
+        .drain(skip..)
+        .map(|(time, text)| Line::from(vec![Span::styled(time, Style::default().fg(Color::DarkGray)), Span::raw(text)]))
+        .collect();
+    let mut block = Block::default().borders(Borders::ALL);
+    if plain {
+        block = block.title("Events");
+    }
+    frame.render_widget(Paragraph::new(text).wrap(Wrap { trim: true }).block(block), area);
 }
 
-fn render_status(app: &App, frame: &mut Frame, area: Rect) {
-    let current_player = match app.board.current_player {
-        PieceColor::White => "White",
-        PieceColor::Black => "Black",
+/// Stands in for the side panel while blindfolded: the last move, if
+/// that's shown, and how to play
+fn render_blindfold_panel(app: &App, frame: &mut Frame, area: Rect) {
+    let mut lines = Vec::new();
+    if app.settings.blindfold == Blindfold::LastMove {
+        let last = blindfold_last_move(app).unwrap_or_else(|| "None yet".to_string());
+        lines.push(Line::from(vec![Span::styled("Last move: ", Style::default().fg(app.theme.accent)), Span::raw(last)]));
+        lines.push(Line::from(""));
+    }
+    let bindings = app.keymap.bindings();
+    let key = |action| {
+        let keys = bindings.iter().find(|&&(bound, _)| bound == action).map(|(_, keys)| keys.as_slice());
+        keys.and_then(|keys| keys.first()).map_or("?".to_string(), |&key| keymap::key_name(key))
     };
+    lines.push(Line::from(format!("Type moves after {}", key(Action::CommandLine))));
+    lines.push(Line::from(format!("Peek with {}", key(Action::Peek))));
 
-    let in_check = MoveGenerator::is_in_check(&app.board, app.board.current_player);
-    let check_text = if in_check { " (CHECK!)" } else { "" };
+    let panel = Paragraph::new(lines)
+        .style(Style::default().fg(Color::White))
+        .wrap(Wrap { trim: false })
+        .block(Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::White))
+            .title("Blindfold"));
 
-    let status_text = format!("Current Player: {}{}", current_player, check_text);
+    frame.render_widget(panel, area);
+}
 
+/// The lesson's explanation and task, how it's going, and the keys to
+/// go on with
+fn render_lesson_panel(app: &App, frame: &mut Frame, area: Rect, tutorial: &Tutorial) {
+    let lesson = tutorial.lesson();
     let mut lines = vec![
-        Line::from(status_text),
-        Line::from(format!("Move: {}", app.board.fullmove_number)),
+        Line::from(Span::styled(lesson.title, Style::default().fg(app.theme.accent).add_modifier(Modifier::BOLD))),
+        Line::from(""),
+        Line::from(lesson.text),
+        Line::from(""),
     ];
+    let (text, color) = match tutorial.status {
+        LessonStatus::Trying => match lesson.goal {
+            Goal::Visit(_) => (format!("Squares left: {}", tutorial.squares_left()), Color::White),
+            _ => ("Your move".to_string(), Color::White),
+        },
+        LessonStatus::Passed => ("Well done!".to_string(), Color::Green),
+        LessonStatus::Failed => ("Not quite.".to_string(), Color::Red),
+    };
+    lines.push(Line::from(Span::styled(text, Style::default().fg(color).add_modifier(Modifier::BOLD))));
 
-    if app.mode == GameMode::VsAI {
-        let ai_player = if app.ai_color == Some(PieceColor::Black) { "Black" } else { "White" };
-        lines.push(Line::from(format!("AI: {}", ai_player)));
-    }
+    let bindings = app.keymap.bindings();
+    let key = |action| {
+        let keys = bindings.iter().find(|&&(bound, _)| bound == action).map(|(_, keys)| keys.as_slice());
+        keys.and_then(|keys| keys.first()).map_or("?".to_string(), |&key| keymap::key_name(key))
+    };
+    lines.push(Line::from(""));
+    lines.push(Line::from(format!("{}: start the lesson over", key(Action::RetryPuzzle))));
+    lines.push(Line::from(format!("{}: next lesson", key(Action::NextPuzzle))));
+    lines.push(Line::from(format!("{}: back to the menu", key(Action::ReturnToMenu))));
 
-    let status = Paragraph::new(lines)
+    let panel = Paragraph::new(lines)
         .style(Style::default().fg(Color::White))
+        .wrap(Wrap { trim: false })
         .block(Block::default()
             .borders(Borders::ALL)
-            .border_style(Style::default().fg(Color::Green))
-            .title("Status"));
+            .border_style(Style::default().fg(app.theme.accent))
+            .title("Learn Chess"));
 
-    frame.render_widget(status, area);
+    frame.render_widget(panel, area);
 }
 
-fn render_captured(app: &App, frame: &mut Frame, area: Rect) {
-    let white_captured: String = app.captured_white.iter()
-        .map(|p| p.symbol())
-        .collect();
-    let black_captured: String = app.captured_black.iter()
-        .map(|p| p.symbol())
-        .collect();
+fn render_position_info(app: &App, frame: &mut Frame, area: Rect) {
+    let board = app.session().game.board();
+    let fen = board.to_fen();
+    let fields: Vec<&str> = fen.split_whitespace().collect();
 
-    let text = vec![
+    let side_to_move = match board.current_player {
+        PieceColor::White => "White",
+        PieceColor::Black => "Black",
+    };
+
+    let label = Style::default().fg(app.theme.accent);
+    let lines = vec![
+        Line::from(vec![Span::styled("Side to move: ", label), Span::raw(side_to_move)]),
+        Line::from(vec![Span::styled("Castling: ", label), Span::raw(fields[2])]),
+        Line::from(vec![Span::styled("En passant: ", label), Span::raw(fields[3])]),
         Line::from(vec![
-            Span::styled("White: ", Style::default().fg(Color::White)),
-            Span::raw(&white_captured),
+            Span::styled("Halfmove clock: ", label),
+            Span::raw(format!("{}/{}", board.halfmove_clock, FIFTY_MOVE_LIMIT)),
         ]),
+        Line::from(vec![Span::styled("Fullmove: ", label), Span::raw(board.fullmove_number.to_string())]),
         Line::from(vec![
-            Span::styled("Black: ", Style::default().fg(Color::White)),
-            Span::raw(&black_captured),
+            Span::styled("Zobrist: ", label),
+            Span::raw(format!("{:016x}", board.zobrist_hash())),
         ]),
+        Line::from(""),
+        Line::from(Span::styled("FEN:", label)),
+        Line::from(fen.clone()),
     ];
 
-    let captured = Paragraph::new(text)
+    let info = Paragraph::new(lines)
+        .style(Style::default().fg(Color::White))
+        .wrap(Wrap { trim: false })
         .block(Block::default()
             .borders(Borders::ALL)
             .border_style(Style::default().fg(Color::White))
-            .title("Captured"));
+            .title("Position Info"));
 
-    frame.render_widget(captured, area);
+    frame.render_widget(info, area);
 }
 
 fn render_move_history(app: &App, frame: &mut Frame, area: Rect) {
-    let items: Vec<ListItem> = app.move_history
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::White))
+        .title("Move History");
+    let inner = block.inner(area);
+    app.history_area.set(inner);
+
+    let rows = inner.height as usize;
+    let selected = app.selected_history();
+    let history_rows = app.history_rows();
+    let items: Vec<ListItem> = history_rows
         .iter()
-        .enumerate()
-        .map(|(i, mv)| {
-            let move_num = (i / 2) + 1;
-            let move_text = if i % 2 == 0 {
-                format!("{}. {}", move_num, mv.to_algebraic())
+        .map(|row| {
+            let san = app.language.localize(&row.san) + &nag_suffix(app, row);
+            let mut spans = if row.depth > 0 {
+                // Variations are indented under the move they replace
+                let indent = "  ".repeat(row.depth);
+                vec![Span::styled(format!("{}{}", indent, move_label(app, row)), Style::default().fg(Color::Gray))]
             } else {
-                format!("   {}..{}", move_num, mv.to_algebraic())
+                vec![Span::raw(match row.color {
+                    PieceColor::White => format!("{}. {}", row.number, san),
+                    PieceColor::Black => format!("   {}..{}", row.number, san),
+                })]
             };
-            ListItem::new(move_text).style(Style::default().fg(Color::White))
+            if let Some(comment) = &app.session().move_tree.annotation(row.node).comment {
+                spans.push(Span::styled(format!(" {}", comment), Style::default().fg(Color::DarkGray)));
+            }
+            ListItem::new(Line::from(spans))
         })
-        .rev()
-        .take(area.height as usize - 2)
-        .collect::<Vec<_>>()
-        .into_iter()
-        .rev()
         .collect();
 
     let history = List::new(items)
+        .block(block)
+        .style(Style::default().fg(Color::White))
+        .highlight_style(Style::default().fg(Color::Black).bg(app.theme.accent));
+    let mut state = ListState::default()
+        .with_offset(app.history_first_visible(rows))
+        .with_selected(selected);
+
+    frame.render_stateful_widget(history, area, &mut state);
+    app.remember_history_offset(state.offset());
+
+    if let Some(index) = selected {
+        render_history_tooltip(app, frame, inner, index, &history_rows[index]);
+    }
+}
+
+/// A move with its number and any judgement, e.g. "14. Nf3" or "14... Nc6?!"
+fn move_label(app: &App, row: &HistoryRow) -> String {
+    let dots = match row.color {
+        PieceColor::White => ".",
+        PieceColor::Black => "...",
+    };
+    format!("{}{} {}{}", row.number, dots, app.language.localize(&row.san), nag_suffix(app, row))
+}
+
+/// The NAG on a row's move as written after it: "!?" right after the move,
+/// others such as " $14" spaced off
+fn nag_suffix(app: &App, row: &HistoryRow) -> String {
+    match app.session().move_tree.annotation(row.node).nag {
+        Some(nag) if MOVE_NAGS.iter().any(|&(number, _)| number == nag) => nag_symbol(nag),
+        Some(nag) => format!(" {}", nag_symbol(nag)),
+        None => String::new(),
+    }
+}
+
+/// The position after a history move, drawn beside its row in the list
+fn render_history_tooltip(app: &App, frame: &mut Frame, list: Rect, index: usize, history_row: &HistoryRow) {
+    const WIDTH: u16 = 40;
+    const HEIGHT: u16 = 7;
+
+    let screen = frame.area();
+    let board = app.board_at(history_row.node);
+    let comment = &app.session().move_tree.annotation(history_row.node).comment;
+    // Comments get a few lines of their own
+    let height = HEIGHT + comment.as_ref().map_or(0, |comment| (comment.len() as u16 + 9) / (WIDTH - 2) + 1).min(4);
+    let row = list.y + (index - app.history_first_visible(list.height as usize)) as u16;
+    let area = Rect {
+        x: list.x.saturating_sub(WIDTH + 1),
+        y: row.min(screen.height.saturating_sub(height)),
+        width: WIDTH.min(screen.width),
+        height: height.min(screen.height),
+    };
+
+    let title = format!("After {}", move_label(app, history_row));
+
+    let label = Style::default().fg(app.theme.accent);
+    let eval = app
+        .position_evals
+        .get(&board.zobrist_hash())
+        .map_or("not analyzed".to_string(), |&score| format_score(score));
+    let mut lines = vec![
+        Line::from(board.to_fen()),
+        Line::from(""),
+        Line::from(vec![Span::styled("Eval: ", label), Span::raw(eval)]),
+    ];
+    if let Some(comment) = comment {
+        lines.push(Line::from(vec![Span::styled("Comment: ", label), Span::raw(comment.clone())]));
+    }
+
+    let tooltip = Paragraph::new(lines)
+        .style(Style::default().fg(Color::White).bg(Color::Black))
+        .wrap(Wrap { trim: false })
         .block(Block::default()
             .borders(Borders::ALL)
-            .border_style(Style::default().fg(Color::White))
-            .title("Move History"));
+            .border_style(Style::default().fg(app.theme.accent))
+            .title(title));
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(tooltip, area);
+}
+
+fn render_comment_editor(app: &App, frame: &mut Frame, text: &str) {
+    let area = frame.area();
+    let popup_area = Rect {
+        x: area.width.saturating_sub(50) / 2,
+        y: area.height.saturating_sub(8) / 2,
+        width: 50.min(area.width),
+        height: 8.min(area.height),
+    };
+    let title = match app.selected_history() {
+        Some(index) => format!("Comment on {}", move_label(app, &app.history_rows()[index])),
+        None => "Comment".to_string(),
+    };
+    let lines = vec![
+        Line::from(format!("{}_", text)),
+        Line::from(""),
+        Line::from("Enter: Save | Esc: Cancel").style(Style::default().fg(Color::DarkGray)),
+    ];
+    let popup = Paragraph::new(lines)
+        .style(Style::default().fg(Color::White).bg(Color::Black))
+        .wrap(Wrap { trim: false })
+        .block(Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(app.theme.accent))
+            .title(title));
+
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(popup, popup_area);
+}
+
+/// The Ctrl+O popup listing the game files of a directory
+fn render_file_browser(app: &App, frame: &mut Frame, browser: &FileBrowser) {
+    let area = frame.area();
+    let popup_area = Rect {
+        x: area.width.saturating_sub(60) / 2,
+        y: area.height.saturating_sub(20) / 2,
+        width: 60.min(area.width),
+        height: 20.min(area.height),
+    };
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(3), Constraint::Length(1)])
+        .split(popup_area);
+
+    let items: Vec<ListItem> = if browser.entries.is_empty() {
+        vec![ListItem::new("No PGN or JSON files here")]
+    } else {
+        browser
+            .entries
+            .iter()
+            .map(|entry| {
+                let (name, color) = if entry.is_dir {
+                    (format!("{}/", entry.name), app.theme.accent)
+                } else {
+                    (entry.name.clone(), Color::White)
+                };
+                ListItem::new(name).style(Style::default().fg(color))
+            })
+            .collect()
+    };
+    let list = List::new(items)
+        .style(Style::default().bg(Color::Black))
+        .highlight_style(Style::default().fg(Color::Black).bg(app.theme.accent).add_modifier(Modifier::BOLD))
+        .block(Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(app.theme.accent))
+            .title(format!("Open: {}", browser.dir.display())));
+    let mut state = ListState::default().with_selected(Some(browser.selection));
+
+    let keys = Paragraph::new("Enter: Open | Backspace: Up | Esc: Cancel")
+        .style(Style::default().fg(Color::DarkGray).bg(Color::Black))
+        .alignment(Alignment::Center);
+
+    frame.render_widget(Clear, popup_area);
+    frame.render_stateful_widget(list, chunks[0], &mut state);
+    frame.render_widget(keys, chunks[1]);
+}
+
+/// Asks before quitting with a game unfinished
+fn render_quit_confirm(app: &App, frame: &mut Frame) {
+    let area = frame.area();
+    let popup_area = Rect {
+        x: area.width.saturating_sub(50) / 2,
+        y: area.height.saturating_sub(10) / 2,
+        width: 50.min(area.width),
+        height: 10.min(area.height),
+    };
+
+    let mut lines = vec![
+        Line::from("Quit with a game unfinished?").style(Style::default().add_modifier(Modifier::BOLD)),
+        Line::from(""),
+        Line::from(if app.session().mode == GameMode::Online {
+            "Y: quit; the clock keeps running on Lichess"
+        } else {
+            "Y: quit; Resume Game picks it up again"
+        }),
+    ];
+    if app.can_save_and_quit() {
+        lines.push(Line::from(format!("S: save to {} and quit", app.quit_save_name())));
+    }
+    lines.push(Line::from("N, Esc: keep playing"));
+    let popup = Paragraph::new(lines)
+        .style(Style::default().fg(Color::White).bg(Color::Black))
+        .alignment(Alignment::Center)
+        .wrap(Wrap { trim: true })
+        .block(Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(app.theme.accent))
+            .title("Quit"));
+
+    frame.render_widget(Clear, popup_area);
+    frame.render_widget(popup, popup_area);
+}
 
-    frame.render_widget(history, area);
+/// The command line along the bottom of the screen, vim style, with the
+/// last message or completions above it
+fn render_command_line(app: &App, frame: &mut Frame, text: &str) {
+    let area = frame.area();
+    let height = 2.min(area.height);
+    let bar = Rect {
+        x: area.x,
+        y: area.bottom() - height,
+        width: area.width,
+        height,
+    };
+    let lines = vec![
+        Line::from(app.session().status_message.clone().unwrap_or_default()).style(Style::default().fg(Color::Yellow)),
+        Line::from(format!(":{}_", text)),
+    ];
+    let bar_widget = Paragraph::new(lines).style(Style::default().fg(Color::White).bg(Color::Black));
+
+    frame.render_widget(Clear, bar);
+    frame.render_widget(bar_widget, bar);
 }
 
-fn render_promotion_menu(_app: &App, frame: &mut Frame, area: Rect) {
+fn render_promotion_menu(app: &App, frame: &mut Frame, area: Rect) {
     let popup_area = Rect {
         x: area.width / 2 - 15,
         y: area.height / 2 - 5,
@@ -406,26 +2223,37 @@ fn render_promotion_menu(_app: &App, frame: &mut Frame, area: Rect) {
         height: 10,
     };
 
-    let text = vec![
+    let mut text = vec![
         Line::from("Promote pawn to:").alignment(Alignment::Center),
         Line::from(""),
-        Line::from("Q - Queen").alignment(Alignment::Center),
-        Line::from("R - Rook").alignment(Alignment::Center),
-        Line::from("B - Bishop").alignment(Alignment::Center),
-        Line::from("N - Knight").alignment(Alignment::Center),
     ];
+    for (i, piece_type) in PROMOTION_PIECES.into_iter().enumerate() {
+        let (letter, name) = match piece_type {
+            PieceType::Queen => ('Q', "Queen"),
+            PieceType::Rook => ('R', "Rook"),
+            PieceType::Bishop => ('B', "Bishop"),
+            _ => ('N', "Knight"),
+        };
+        let style = if i == app.session().promotion_selection {
+            Style::default().fg(Color::Black).bg(app.theme.accent).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(Color::White)
+        };
+        text.push(Line::from(Span::styled(format!(" {} - {:<6} ", letter, name), style)).alignment(Alignment::Center));
+    }
 
     let popup = Paragraph::new(text)
         .style(Style::default().fg(Color::White).bg(Color::Black))
         .block(Block::default()
             .borders(Borders::ALL)
-            .border_style(Style::default().fg(Color::Green))
+            .border_style(Style::default().fg(app.theme.accent))
             .title("Promotion"));
 
     frame.render_widget(popup, popup_area);
 }
 
 fn render_game_over(app: &App, frame: &mut Frame) {
+    let session = app.session();
     // First render the game board in the background
     render_game(app, frame);
 
@@ -438,7 +2266,15 @@ fn render_game_over(app: &App, frame: &mut Frame) {
         height: 12,
     };
 
-    let message = match app.game_over_reason {
+    let kids_won = session.kids_mode
+        && matches!(session.game.result(), Some(GameOverReason::Checkmate(winner)) if Some(winner) != session.ai_color);
+    if kids_won {
+        render_confetti(frame, popup_area);
+    }
+
+    let message = match session.game.result() {
+        Some(GameOverReason::Checkmate(_)) if kids_won => "★ CHECKMATE! ★\n\nYou won! Amazing!".to_string(),
+        Some(GameOverReason::Checkmate(_)) if session.kids_mode => "Checkmate!\n\nGood try! Press U to take back.".to_string(),
         Some(GameOverReason::Checkmate(winner)) => {
             let winner_str = match winner {
                 PieceColor::White => "White",
@@ -446,21 +2282,116 @@ fn render_game_over(app: &App, frame: &mut Frame) {
             };
             format!("Checkmate!\n\n{} wins!", winner_str)
         }
+        Some(GameOverReason::Resignation(loser)) => match loser {
+            PieceColor::White => "White resigns.\n\nBlack wins!".to_string(),
+            PieceColor::Black => "Black resigns.\n\nWhite wins!".to_string(),
+        },
         Some(GameOverReason::Stalemate) => "Stalemate!\n\nGame is a draw.".to_string(),
         Some(GameOverReason::InsufficientMaterial) => "Insufficient Material!\n\nGame is a draw.".to_string(),
-        Some(GameOverReason::FiftyMoveRule) => "Fifty Move Rule!\n\nGame is a draw.".to_string(),
+        Some(GameOverReason::DeadPosition) => "Dead Position!\n\nNeither side can ever mate.".to_string(),
+        Some(GameOverReason::DrawClaimed(DrawClaim::ThreefoldRepetition)) => {
+            "Threefold Repetition!\n\nDraw claimed.".to_string()
+        }
+        Some(GameOverReason::DrawClaimed(DrawClaim::FiftyMoves)) => "Fifty Move Rule!\n\nDraw claimed.".to_string(),
+        Some(GameOverReason::DrawAgreed) => "Draw Agreed!\n\nGame is a draw.".to_string(),
+        Some(GameOverReason::FivefoldRepetition) => "Fivefold Repetition!\n\nGame is a draw.".to_string(),
+        Some(GameOverReason::SeventyFiveMoveRule) => "Seventy-Five Move Rule!\n\nGame is a draw.".to_string(),
+        Some(GameOverReason::Timeout { flagged, winner }) => {
+            let (loser, other) = match flagged {
+                PieceColor::White => ("White", "Black"),
+                PieceColor::Black => ("Black", "White"),
+            };
+            match winner {
+                Some(_) => format!("Time!\n\n{} ran out of time.\n{} wins!", loser, other),
+                None => format!("Time!\n\n{} ran out of time, but\n{} can't mate. Draw.", loser, other),
+            }
+        }
+        Some(GameOverReason::Review { winner, archived }) => {
+            let from = if archived { "From the archive" } else { "Game review" };
+            match winner {
+                Some(PieceColor::White) => format!("{}\n\nWhite won.", from),
+                Some(PieceColor::Black) => format!("{}\n\nBlack won.", from),
+                None => format!("{}\n\nThe game was drawn.", from),
+            }
+        }
+        Some(GameOverReason::Remote { winner, reason }) => {
+            match winner {
+                Some(PieceColor::White) => format!("{}\n\nWhite wins!", reason),
+                Some(PieceColor::Black) => format!("{}\n\nBlack wins!", reason),
+                None => format!("{}\n\nNo winner.", reason),
+            }
+        }
         None => "Game Over".to_string(),
     };
+    let message = match app.endgame_passed() {
+        Some(true) => format!("Endgame passed!\n{}", message),
+        Some(false) => format!("Endgame failed.\n{}", message),
+        None => message,
+    };
 
-    let text = Text::from(format!("{}\n\nPress Enter to return to menu\nPress Q to quit", message));
+    let footer = if session.restart_pending {
+        "New game, same settings? (y/n)"
+    } else if app.is_archived_game() {
+        "Press Enter to return to the archive\nM: menu\nP: copy PGN\nPress Q to quit"
+    } else if session.endgame.is_some() {
+        "Press Enter to return to menu\nR: try the endgame again\nP: copy PGN\nPress Q to quit"
+    } else if session.kids_mode {
+        "Press Enter to return to menu\nR: play again\nU: take back\nP: copy PGN"
+    } else if app.can_restart() && session.ai.is_some() {
+        "Press Enter to return to menu\nR: rematch | S: switch colors\nP: copy PGN\nPress Q to quit"
+    } else if app.can_restart() {
+        "Press Enter to return to menu\nR: rematch\nP: copy PGN\nPress Q to quit"
+    } else {
+        "Press Enter to return to menu\nP: copy PGN\nPress Q to quit"
+    };
+    let text = Text::from(format!("{}\n\n{}", message, footer));
+
+    let border_color = if kids_won { Color::Rgb(255, 215, 0) } else { app.theme.accent };
 
     let popup = Paragraph::new(text)
         .alignment(Alignment::Center)
         .style(Style::default().fg(Color::White).bg(Color::Black))
         .block(Block::default()
             .borders(Borders::ALL)
-            .border_style(Style::default().fg(Color::Green).add_modifier(Modifier::BOLD))
+            .border_style(Style::default().fg(border_color).add_modifier(Modifier::BOLD))
             .title("Game Over"));
 
     frame.render_widget(popup, popup_area);
 }
+
+/// Twinkling sparkles over the whole screen, except where `keep_clear` is
+fn render_confetti(frame: &mut Frame, keep_clear: Rect) {
+    const SPARKLES: [char; 4] = ['*', '+', '✦', '•'];
+    const COLORS: [Color; 5] = [
+        Color::Rgb(255, 215, 0),
+        Color::Rgb(255, 80, 80),
+        Color::Rgb(0, 180, 255),
+        Color::Rgb(80, 220, 80),
+        Color::Rgb(255, 140, 0),
+    ];
+
+    // The pattern changes every 150ms; the main loop redraws often enough to animate it
+    let tick = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis() / 150) as u64;
+
+    let area = frame.area();
+    let buf = frame.buffer_mut();
+    for y in area.top()..area.bottom() {
+        for x in area.left()..area.right() {
+            if keep_clear.contains((x, y).into()) {
+                continue;
+            }
+            let hash = (x as u64)
+                .wrapping_mul(0x9E37_79B9_7F4A_7C15)
+                ^ (y as u64).wrapping_mul(0xBF58_476D_1CE4_E5B9)
+                ^ tick.wrapping_mul(0x94D0_49BB_1331_11EB);
+            let hash = hash ^ (hash >> 29);
+            if hash.is_multiple_of(17) {
+                buf[(x, y)]
+                    .set_char(SPARKLES[(hash >> 8) as usize % SPARKLES.len()])
+                    .set_fg(COLORS[(hash >> 16) as usize % COLORS.len()]);
+            }
+        }
+    }
+}
@@ -0,0 +1,258 @@
+use chess_core::game::board::CastlingRights;
+use chess_core::game::marks::{MarkColor, Marks};
+use chess_core::game::pieces::Piece;
+use chess_core::game::{Board, Color, Move, MoveGenerator, PieceType, Position};
+
+/// What a lesson asks the player to do
+pub enum Goal {
+    /// Move to each of these squares, in any order. Black doesn't move.
+    Visit(&'static [&'static str]),
+    /// Capture every black piece. Black doesn't move.
+    CaptureAll,
+    /// Play one of these moves, in UCI
+    Play(&'static [&'static str]),
+    /// Checkmate Black in one move
+    Mate,
+}
+
+impl Goal {
+    /// Whether White keeps the move, so a lesson can take several moves
+    /// with nothing moving back
+    pub fn keeps_turn(&self) -> bool {
+        matches!(self, Goal::Visit(_) | Goal::CaptureAll)
+    }
+}
+
+/// One step of the tutorial: an explanation and something to try on the
+/// board, always as White
+pub struct Lesson {
+    pub title: &'static str,
+    pub text: &'static str,
+    /// The pieces on the board: a piece letter and a square each, upper
+    /// case for White. A side can only move with its king on the board.
+    pieces: &'static str,
+    /// Black's move that sets the lesson up, played first
+    setup: Option<&'static str>,
+    pub goal: Goal,
+}
+
+const LESSONS: &[Lesson] = &[
+    Lesson {
+        title: "The rook",
+        text: "The rook moves any number of squares along a rank or a file, but can't jump over other pieces. \
+               Move the rook to each marked square.",
+        pieces: "Rd4 Kh8",
+        setup: None,
+        goal: Goal::Visit(&["d7", "a7", "a2", "g2"]),
+    },
+    Lesson {
+        title: "The bishop",
+        text: "The bishop moves any number of squares diagonally, so it stays on squares of one color all game. \
+               Move the bishop to each marked square.",
+        pieces: "Bc1 Kh8",
+        setup: None,
+        goal: Goal::Visit(&["a3", "d6", "h2"]),
+    },
+    Lesson {
+        title: "The queen",
+        text: "The queen moves like a rook and a bishop together: any number of squares along a rank, a file or \
+               a diagonal. Move the queen to each marked square.",
+        pieces: "Qd1 Kb1",
+        setup: None,
+        goal: Goal::Visit(&["d5", "a8", "h8", "h1"]),
+    },
+    Lesson {
+        title: "The king",
+        text: "The king moves one square in any direction, but never onto a square an enemy piece attacks. \
+               Walk the king to each marked square, keeping off the rook's lines.",
+        pieces: "Ke1 rd8",
+        setup: None,
+        goal: Goal::Visit(&["f2", "e3", "e4"]),
+    },
+    Lesson {
+        title: "The knight",
+        text: "The knight moves in an L: two squares along a rank or file, then one to the side. It's the only \
+               piece that jumps over others. Capture the black pawns.",
+        pieces: "Nb1 Kh1 pc3 pe4 pg5",
+        setup: None,
+        goal: Goal::CaptureAll,
+    },
+    Lesson {
+        title: "The pawn",
+        text: "Pawns move straight forward one square, or two from their starting square, but capture one square \
+               diagonally forward. Capture the black pieces; if the pawn gets stuck, start over.",
+        pieces: "Pe2 Kh1 nf4 be5",
+        setup: None,
+        goal: Goal::CaptureAll,
+    },
+    Lesson {
+        title: "Promotion",
+        text: "A pawn that reaches the far rank becomes a queen, rook, bishop or knight, whichever you choose. \
+               Nearly always it's a queen. Promote the pawn.",
+        pieces: "Pe7 Ka1",
+        setup: None,
+        goal: Goal::Visit(&["e8"]),
+    },
+    Lesson {
+        title: "Castling",
+        text: "Once a game, the king can move two squares toward a rook, which jumps to the square it crossed. \
+               Neither may have moved, the squares between them must be empty, and the king can't be in check \
+               or pass an attacked square. Castle to either side.",
+        pieces: "Ke1 Ra1 Rh1",
+        setup: None,
+        goal: Goal::Play(&["e1g1", "e1c1"]),
+    },
+    Lesson {
+        title: "En passant",
+        text: "A pawn that moves two squares past an enemy pawn beside it can be taken as if it had moved one, \
+               but only straight after. Black has just played d7-d5: take it en passant.",
+        pieces: "Pe5 Ka1 pd7 kh8",
+        setup: Some("d7d5"),
+        goal: Goal::Play(&["e5d6"]),
+    },
+    Lesson {
+        title: "Back-rank mate",
+        text: "Checkmate attacks the king so that it has no escape, which wins the game. A king behind its own \
+               pawns is trapped on the back rank. Checkmate in one move.",
+        pieces: "Kg1 Rd1 Pf2 Pg2 Ph2 kg8 pf7 pg7 ph7",
+        setup: None,
+        goal: Goal::Mate,
+    },
+    Lesson {
+        title: "Queen and king",
+        text: "A queen and king mate a lone king on the edge of the board: the king guards the queen, and the \
+               queen takes every escape. Checkmate in one move.",
+        pieces: "Ke6 Qa7 ke8",
+        setup: None,
+        goal: Goal::Mate,
+    },
+    Lesson {
+        title: "Ladder mate",
+        text: "Two rooks walk a king to the edge, one cutting it off a rank at a time while the other checks. \
+               Checkmate in one move.",
+        pieces: "Kg1 Ra7 Rb1 ke8",
+        setup: None,
+        goal: Goal::Mate,
+    },
+];
+
+/// How the lesson on the board is going
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LessonStatus {
+    Trying,
+    Passed,
+    Failed,
+}
+
+/// Working through the lessons in order, remembering where the player got
+/// to for the next time the tutorial is opened
+pub struct Tutorial {
+    index: usize,
+    pub status: LessonStatus,
+    // The squares still to visit, marked on the board
+    marks: Marks,
+}
+
+impl Tutorial {
+    pub fn new() -> Tutorial {
+        let mut tutorial = Tutorial { index: 0, status: LessonStatus::Trying, marks: Marks::default() };
+        tutorial.restart();
+        tutorial
+    }
+
+    pub fn lesson(&self) -> &'static Lesson {
+        &LESSONS[self.index]
+    }
+
+    /// The lesson's number, from 1, and how many there are
+    pub fn position(&self) -> (usize, usize) {
+        (self.index + 1, LESSONS.len())
+    }
+
+    pub fn marks(&self) -> &Marks {
+        &self.marks
+    }
+
+    /// The squares still to visit
+    pub fn squares_left(&self) -> usize {
+        self.marks.squares.len()
+    }
+
+    /// Starts the lesson on the board over
+    pub fn restart(&mut self) {
+        self.status = LessonStatus::Trying;
+        self.marks = Marks::default();
+        if let Goal::Visit(squares) = self.lesson().goal {
+            for square in squares {
+                let pos = Position::from_algebraic(square).expect("lesson squares are valid");
+                self.marks.squares.push((pos, MarkColor::Green));
+            }
+        }
+    }
+
+    /// Moves on to the next lesson, back to the first after the last
+    pub fn next(&mut self) {
+        self.index = (self.index + 1) % LESSONS.len();
+        self.restart();
+    }
+
+    /// The lesson's starting position, and Black's move that sets it up
+    pub fn board(&self) -> (Board, Option<Move>) {
+        let lesson = self.lesson();
+        let mut board = Board::empty();
+        for token in lesson.pieces.split_whitespace() {
+            let (letter, square) = token.split_at(1);
+            let piece = piece_from_letter(letter).expect("lesson pieces are valid");
+            let pos = Position::from_algebraic(square).expect("lesson squares are valid");
+            board.set_piece(pos, Some(piece));
+        }
+        // Castling is allowed wherever the king and rook are where they start
+        let white = |square: &str, piece_type| {
+            let pos = Position::from_algebraic(square).expect("valid square");
+            board.get_piece(pos) == Some(Piece::new(piece_type, Color::White))
+        };
+        let king_home = white("e1", PieceType::King);
+        board.castling_rights = CastlingRights {
+            white_kingside: king_home && white("h1", PieceType::Rook),
+            white_queenside: king_home && white("a1", PieceType::Rook),
+            ..CastlingRights::none()
+        };
+        board.current_player = if lesson.setup.is_some() { Color::Black } else { Color::White };
+
+        let setup = lesson.setup.map(|uci| MoveGenerator::find_uci_move(&board, uci).expect("lesson setups are legal"));
+        (board, setup)
+    }
+
+    /// Checks the player's move `mv`, which led to `board`
+    pub fn check(&mut self, board: &Board, mv: &Move) -> LessonStatus {
+        let passed = match self.lesson().goal {
+            Goal::Visit(_) => {
+                self.marks.squares.retain(|&(square, _)| square != mv.to);
+                self.marks.squares.is_empty()
+            }
+            Goal::CaptureAll => board.get_all_pieces(Color::Black).is_empty(),
+            Goal::Play(moves) => moves.contains(&mv.to_uci().as_str()),
+            Goal::Mate => MoveGenerator::is_checkmate(board, Color::Black),
+        };
+        self.status = match (passed, self.lesson().goal.keeps_turn()) {
+            (true, _) => LessonStatus::Passed,
+            (false, true) => LessonStatus::Trying,
+            (false, false) => LessonStatus::Failed,
+        };
+        self.status
+    }
+}
+
+fn piece_from_letter(letter: &str) -> Option<Piece> {
+    let color = if letter.chars().all(|c| c.is_ascii_uppercase()) { Color::White } else { Color::Black };
+    let piece_type = match letter.to_ascii_lowercase().as_str() {
+        "p" => PieceType::Pawn,
+        "n" => PieceType::Knight,
+        "b" => PieceType::Bishop,
+        "r" => PieceType::Rook,
+        "q" => PieceType::Queen,
+        "k" => PieceType::King,
+        _ => return None,
+    };
+    Some(Piece::new(piece_type, color))
+}